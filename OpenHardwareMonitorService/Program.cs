@@ -30,6 +30,8 @@ namespace OpenHardwareMonitorService
 
     public class HardwareInfo
     {
+        public string name = "";
+        public string identifier = "";
         public readonly List<float> fans = new List<float>();
         public readonly List<float> temperatures = new List<float>();
         public readonly List<float> loads = new List<float>();
@@ -112,6 +114,8 @@ namespace OpenHardwareMonitorService
                 foreach (var hardware in computer.Hardware)
                 {
                     var hardware_info = new HardwareInfo();
+                    hardware_info.name = hardware.Name;
+                    hardware_info.identifier = hardware.Identifier.ToString();
 
                     foreach (var sensor in hardware.Sensors)
                     {
@@ -0,0 +1,148 @@
+use unicode_bidi::{get_base_direction, Direction, ParagraphBidiInfo};
+
+// 阿拉伯字母和独立形式(Arabic Presentation Forms-B)的编码，true为双向连接(有首、中、尾、独立4种形式)，false只和前一个字母连接
+const ARABIC_FORMS: [(char, u32, bool); 35] = [
+    ('\u{0622}', 0xFE81, false), // آ
+    ('\u{0623}', 0xFE83, false), // أ
+    ('\u{0624}', 0xFE85, false), // ؤ
+    ('\u{0625}', 0xFE87, false), // إ
+    ('\u{0626}', 0xFE89, true),  // ئ
+    ('\u{0627}', 0xFE8D, false), // ا
+    ('\u{0628}', 0xFE8F, true),  // ب
+    ('\u{0629}', 0xFE93, false), // ة
+    ('\u{062A}', 0xFE95, true),  // ت
+    ('\u{062B}', 0xFE99, true),  // ث
+    ('\u{062C}', 0xFE9D, true),  // ج
+    ('\u{062D}', 0xFEA1, true),  // ح
+    ('\u{062E}', 0xFEA5, true),  // خ
+    ('\u{062F}', 0xFEA9, false), // د
+    ('\u{0630}', 0xFEAB, false), // ذ
+    ('\u{0631}', 0xFEAD, false), // ر
+    ('\u{0632}', 0xFEAF, false), // ز
+    ('\u{0633}', 0xFEB1, true),  // س
+    ('\u{0634}', 0xFEB5, true),  // ش
+    ('\u{0635}', 0xFEB9, true),  // ص
+    ('\u{0636}', 0xFEBD, true),  // ض
+    ('\u{0637}', 0xFEC1, true),  // ط
+    ('\u{0638}', 0xFEC5, true),  // ظ
+    ('\u{0639}', 0xFEC9, true),  // ع
+    ('\u{063A}', 0xFECD, true),  // غ
+    ('\u{0641}', 0xFED1, true),  // ف
+    ('\u{0642}', 0xFED5, true),  // ق
+    ('\u{0643}', 0xFED9, true),  // ك
+    ('\u{0644}', 0xFEDD, true),  // ل
+    ('\u{0645}', 0xFEE1, true),  // م
+    ('\u{0646}', 0xFEE5, true),  // ن
+    ('\u{0647}', 0xFEE9, true),  // ه
+    ('\u{0648}', 0xFEED, false), // و
+    ('\u{0649}', 0xFEEF, false), // ى
+    ('\u{064A}', 0xFEF1, true),  // ي
+];
+const TATWEEL: char = '\u{0640}';
+const LAM: char = '\u{0644}';
+// 拉姆和阿里夫的连写(独立形式)，尾形为+1
+const LAM_ALEF: [(char, u32); 4] = [('\u{0622}', 0xFEF5), ('\u{0623}', 0xFEF7), ('\u{0625}', 0xFEF9), ('\u{0627}', 0xFEFB)];
+
+fn arabic_form(c: char) -> Option<(u32, bool)> {
+    ARABIC_FORMS.iter().find(|(ch, _, _)| *ch == c).map(|(_, code, dual)| (*code, *dual))
+}
+
+// 元音符号不参与连接
+fn is_transparent(c: char) -> bool {
+    ('\u{064B}'..='\u{065F}').contains(&c) || c == '\u{0670}'
+}
+
+// 可以和后一个字母连接
+fn joins_next(c: char) -> bool {
+    c == TATWEEL || arabic_form(c).map(|(_, dual)| dual).unwrap_or(false)
+}
+
+// 可以和前一个字母连接
+fn joins_prev(c: char) -> bool {
+    c == TATWEEL || arabic_form(c).is_some()
+}
+
+fn to_char(code: u32) -> char {
+    char::from_u32(code).unwrap_or(' ')
+}
+
+// 阿拉伯文按前后字母替换为首、中、尾、独立形式，字体需要包含这些字形
+pub fn shape_arabic(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let prev_of = |i: usize| chars[..i].iter().rev().find(|c| !is_transparent(**c)).copied();
+    let next_of = |i: usize| chars[i + 1..].iter().find(|c| !is_transparent(**c)).copied();
+    let mut shaped = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let (code, dual) = match arabic_form(c) {
+            None => {
+                shaped.push(c);
+                i += 1;
+                continue;
+            }
+            Some(form) => form,
+        };
+        let prev = prev_of(i).map(joins_next).unwrap_or(false);
+        //拉姆后面是阿里夫时写成一个字
+        if c == LAM {
+            if let Some((next_idx, ligature)) = chars[i + 1..]
+                .iter()
+                .position(|c| !is_transparent(*c))
+                .and_then(|p| LAM_ALEF.iter().find(|(alef, _)| *alef == chars[i + 1 + p]).map(|(_, l)| (i + 1 + p, *l)))
+            {
+                shaped.push(to_char(if prev { ligature + 1 } else { ligature }));
+                shaped.extend(&chars[i + 1..next_idx]);
+                i = next_idx + 1;
+                continue;
+            }
+        }
+        let next = dual && next_of(i).map(joins_prev).unwrap_or(false);
+        let offset = match (prev, next) {
+            (true, true) => 3,
+            (false, true) => 2,
+            (true, false) => 1,
+            (false, false) => 0,
+        };
+        shaped.push(to_char(code + offset));
+        i += 1;
+    }
+    shaped
+}
+
+// 第一个强方向字符是从右到左的文字(阿拉伯文、希伯来文等)
+pub fn is_rtl(text: &str) -> bool {
+    get_base_direction(text) == Direction::Rtl
+}
+
+// 转换为从左到右绘制的顺序，每行单独处理
+pub fn visual_text(text: &str) -> String {
+    if text.is_ascii() {
+        return text.to_string();
+    }
+    text.split('\n')
+        .map(|line| {
+            let line = shape_arabic(line);
+            let info = ParagraphBidiInfo::new(&line, None);
+            if !info.has_rtl() {
+                return line;
+            }
+            info.reorder_line(0..line.len()).into_owned()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn test_visual_text() {
+    //希伯来文反转，数字保持顺序
+    assert_eq!(visual_text("שלום 123"), "123 םולש");
+    assert_eq!(visual_text("CPU: עברית"), "CPU: תירבע");
+    //مرحبا: م首 ر尾 ح首 ب中 ا尾
+    assert_eq!(shape_arabic("مرحبا"), "\u{FEE3}\u{FEAE}\u{FEA3}\u{FE92}\u{FE8E}");
+    //لا 连写
+    assert_eq!(shape_arabic("لا"), "\u{FEFB}");
+    assert_eq!(shape_arabic("سلام"), "\u{FEB3}\u{FEFC}\u{FEE1}");
+    assert!(is_rtl("مرحبا"));
+    assert!(!is_rtl("温度 25℃"));
+}
@@ -0,0 +1,210 @@
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use log::warn;
+use reqwest::header::USER_AGENT;
+
+// 日历中的一个事件，全天事件的开始时间为当天0点
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEvent {
+    pub title: String,
+    pub start: DateTime<Local>,
+    pub all_day: bool,
+}
+
+impl CalendarEvent {
+    // 全天事件当天结束前都算作还没有过去
+    pub fn is_upcoming(&self, now: DateTime<Local>) -> bool {
+        if self.all_day {
+            self.start + Duration::days(1) > now
+        } else {
+            self.start > now
+        }
+    }
+}
+
+// 下载iCal订阅，返回还没有过去的事件
+pub fn query_events(url: &str) -> Result<Vec<CalendarEvent>> {
+    let client = reqwest::blocking::Client::new();
+    let res = client.get(url)
+        .header(USER_AGENT, "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36 Edg/126.0.0.0")
+        .send()?
+        .error_for_status()?;
+    Ok(parse_events(&res.text()?, Local::now()))
+}
+
+// 按开始时间排序，重复事件按下一次发生的时间计算
+pub fn parse_events(text: &str, now: DateTime<Local>) -> Vec<CalendarEvent> {
+    let mut events = vec![];
+    for calendar in ical::IcalParser::new(text.as_bytes()) {
+        let calendar = match calendar {
+            Ok(calendar) => calendar,
+            Err(err) => {
+                warn!("日历解析失败:{err:?}");
+                break;
+            }
+        };
+        for event in calendar.events {
+            let mut title = None;
+            let mut start = None;
+            let mut rule = None;
+            for property in event.properties {
+                match property.name.as_str() {
+                    "SUMMARY" => title = property.value.map(|v| unescape(&v)),
+                    "DTSTART" => start = property.value.as_deref().and_then(parse_time),
+                    "RRULE" => rule = property.value.as_deref().and_then(RepeatRule::parse),
+                    _ => (),
+                }
+            }
+            if let (Some(title), Some((start, all_day))) = (title, start) {
+                let mut event = CalendarEvent { title, start, all_day };
+                let upcoming = match rule {
+                    Some(rule) => rule.next_start(&event, now).map(|start| event.start = start).is_some(),
+                    None => event.is_upcoming(now),
+                };
+                if upcoming {
+                    events.push(event);
+                }
+            }
+        }
+    }
+    events.sort_by_key(|e| e.start);
+    events
+}
+
+// 重复次数的上限，避免很早开始的每天重复事件计算太久
+const MAX_REPEATS: u32 = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+// RRULE中的FREQ/INTERVAL/UNTIL/COUNT，BYDAY等其他规则不支持，按FREQ的间隔重复
+#[derive(Debug, Clone, PartialEq)]
+struct RepeatRule {
+    frequency: Frequency,
+    interval: u32,
+    until: Option<DateTime<Local>>,
+    count: Option<u32>,
+}
+
+impl RepeatRule {
+    fn parse(value: &str) -> Option<RepeatRule> {
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut until = None;
+        let mut count = None;
+        for part in value.split(';') {
+            let (key, value) = part.split_once('=')?;
+            match key.trim() {
+                "FREQ" => {
+                    frequency = match value.trim() {
+                        "DAILY" => Some(Frequency::Daily),
+                        "WEEKLY" => Some(Frequency::Weekly),
+                        "MONTHLY" => Some(Frequency::Monthly),
+                        "YEARLY" => Some(Frequency::Yearly),
+                        _ => None,
+                    }
+                }
+                "INTERVAL" => interval = value.trim().parse::<u32>().ok().filter(|i| *i > 0)?,
+                "UNTIL" => until = parse_time(value).map(|(time, _)| time),
+                "COUNT" => count = value.trim().parse::<u32>().ok(),
+                _ => (),
+            }
+        }
+        Some(RepeatRule { frequency: frequency?, interval, until, count })
+    }
+
+    // 第n次重复的开始时间，按本地时间计算，月末等不存在的日期跳过
+    fn nth_start(&self, start: DateTime<Local>, n: u32) -> Option<DateTime<Local>> {
+        let first = start.naive_local();
+        let steps = n.checked_mul(self.interval)?;
+        let time = match self.frequency {
+            Frequency::Daily => first.checked_add_signed(Duration::days(steps as i64))?,
+            Frequency::Weekly => first.checked_add_signed(Duration::weeks(steps as i64))?,
+            Frequency::Monthly => first.checked_add_months(Months::new(steps)).filter(|t| t.day() == first.day())?,
+            Frequency::Yearly => first
+                .checked_add_months(Months::new(steps.checked_mul(12)?))
+                .filter(|t| t.day() == first.day())?,
+        };
+        Local.from_local_datetime(&time).earliest()
+    }
+
+    // 还没有过去的第一次重复，已经结束时返回None
+    fn next_start(&self, event: &CalendarEvent, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        let count = self.count.unwrap_or(MAX_REPEATS).min(MAX_REPEATS);
+        for n in 0..count {
+            let Some(start) = self.nth_start(event.start, n) else {
+                continue;
+            };
+            if self.until.map(|until| start > until).unwrap_or(false) {
+                return None;
+            }
+            let repeat = CalendarEvent { start, ..event.clone() };
+            if repeat.is_upcoming(now) {
+                return Some(start);
+            }
+        }
+        None
+    }
+}
+
+// 支持UTC时间(Z结尾)、本地时间和全天日期，TZID按本地时区处理
+fn parse_time(value: &str) -> Option<(DateTime<Local>, bool)> {
+    let value = value.trim();
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((Utc.from_utc_datetime(&time).with_timezone(&Local), false));
+    }
+    if let Ok(time) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        return Some((Local.from_local_datetime(&time).earliest()?, false));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+    Some((Local.from_local_datetime(&date.and_hms_opt(0, 0, 0)?).earliest()?, true))
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+#[test]
+fn test_parse_events() {
+    let ics = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+BEGIN:VEVENT\r\nSUMMARY:周会\\, 第二季度\r\nDTSTART:20300102T020000Z\r\nEND:VEVENT\r\n\
+BEGIN:VEVENT\r\nSUMMARY:已经过去\r\nDTSTART:20000101T090000\r\nEND:VEVENT\r\n\
+BEGIN:VEVENT\r\nSUMMARY:假期\r\nDTSTART;VALUE=DATE:20300101\r\nEND:VEVENT\r\n\
+END:VCALENDAR\r\n";
+    let now = Local.with_ymd_and_hms(2029, 12, 31, 12, 0, 0).unwrap();
+    let events = parse_events(ics, now);
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].title, "假期");
+    assert!(events[0].all_day);
+    assert_eq!(events[1].title, "周会, 第二季度");
+    assert!(!events[1].all_day);
+}
+
+#[test]
+fn test_repeat_events() {
+    let ics = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+BEGIN:VEVENT\r\nSUMMARY:周会\r\nDTSTART:20291203T090000\r\nRRULE:FREQ=WEEKLY;INTERVAL=2\r\nEND:VEVENT\r\n\
+BEGIN:VEVENT\r\nSUMMARY:已结束\r\nDTSTART:20291201T090000\r\nRRULE:FREQ=DAILY;COUNT=3\r\nEND:VEVENT\r\n\
+BEGIN:VEVENT\r\nSUMMARY:截止\r\nDTSTART:20291201T090000\r\nRRULE:FREQ=DAILY;UNTIL=20291230T000000\r\nEND:VEVENT\r\n\
+BEGIN:VEVENT\r\nSUMMARY:月底\r\nDTSTART;VALUE=DATE:20290131\r\nRRULE:FREQ=MONTHLY\r\nEND:VEVENT\r\n\
+END:VCALENDAR\r\n";
+    let now = Local.with_ymd_and_hms(2029, 12, 31, 12, 0, 0).unwrap();
+    let events = parse_events(ics, now);
+    assert_eq!(events.len(), 2);
+    //12月31日全天事件当天还没有过去
+    assert_eq!(events[0].title, "月底");
+    assert_eq!(events[0].start, Local.with_ymd_and_hms(2029, 12, 31, 0, 0, 0).unwrap());
+    //12月3日开始每两周一次，下一次是1月14日
+    assert_eq!(events[1].title, "周会");
+    assert_eq!(events[1].start, Local.with_ymd_and_hms(2030, 1, 14, 9, 0, 0).unwrap());
+}
@@ -0,0 +1,280 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use anyhow::{anyhow, Result};
+use log::error;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+// 程序配置保存在当前目录，与.screen文件无关的设置放在这里
+const CONFIG_FILE_NAME: &str = "usb-screen.json";
+// 最多保存的最近使用颜色数量
+const MAX_RECENT_COLORS: usize = 8;
+
+static CONFIG: Lazy<RwLock<Config>> = Lazy::new(|| {
+    let config = match load() {
+        Ok(config) => config,
+        Err(err) => {
+            if std::path::Path::new(CONFIG_FILE_NAME).exists() {
+                error!("配置文件读取失败:{err:?}");
+            }
+            Config::default()
+        }
+    };
+    RwLock::new(config)
+});
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Config {
+    //按照设备串号保存的颜色校准
+    #[serde(default)]
+    pub color_profiles: HashMap<String, ColorProfile>,
+    //切换布局的全局快捷键，例如 "ctrl+alt+1": "game.screen"
+    #[serde(default)]
+    pub layout_hotkeys: HashMap<String, String>,
+    //按时间段自动切换布局
+    #[serde(default)]
+    pub layout_schedule: Option<LayoutSchedule>,
+    //编辑器不自动连接设备，只连接下拉框中选择的设备
+    #[serde(default)]
+    pub manual_connect: bool,
+    //保存screen文件时使用zstd高压缩率
+    #[serde(default)]
+    pub high_compression: bool,
+    //编辑器中最近使用的颜色，最新的在前面
+    #[serde(default)]
+    pub recent_colors: Vec<[u8; 3]>,
+    //按照设备串号保存的测速帧率，作为编辑器刷新率的上限
+    #[serde(default)]
+    pub benchmark_fps: HashMap<String, f32>,
+    //编辑器最后连接的设备串号，启动时优先连接
+    #[serde(default)]
+    pub last_device: Option<String>,
+    //前台有全屏程序(游戏)时的刷新率，0暂停刷新，不设置时不检测
+    #[serde(default)]
+    pub fullscreen_fps: Option<f32>,
+    //布局文件在磁盘上修改后自动重新加载，用于外部程序生成布局
+    #[serde(default)]
+    pub watch_layout_file: bool,
+    //手机电池组件使用的adb程序路径，不设置时使用PATH中的adb
+    #[serde(default)]
+    pub adb_path: Option<String>,
+}
+
+// 时间段和对应的布局文件，不在任何时间段内时使用default
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LayoutSchedule {
+    #[serde(default)]
+    pub ranges: Vec<ScheduleRange>,
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+// 时间格式为 HH:MM，结束时间早于开始时间时跨过午夜，例如 22:00-07:00
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleRange {
+    pub start: String,
+    pub end: String,
+    pub file: String,
+}
+
+// HH:MM 转为当天的分钟数
+fn parse_time(time: &str) -> Option<u32> {
+    let (hour, minute) = time.trim().split_once(':')?;
+    let (hour, minute) = (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?);
+    if hour > 24 || minute > 59 || hour * 60 + minute > 24 * 60 {
+        return None;
+    }
+    Some(hour * 60 + minute)
+}
+
+impl ScheduleRange {
+    // 包含开始时间，不包含结束时间
+    pub fn contains(&self, minutes: u32) -> bool {
+        let (start, end) = match (parse_time(&self.start), parse_time(&self.end)) {
+            (Some(start), Some(end)) => (start, end),
+            _ => {
+                error!("时间段格式错误:{}-{}", self.start, self.end);
+                return false;
+            }
+        };
+        if start <= end {
+            minutes >= start && minutes < end
+        } else {
+            minutes >= start || minutes < end
+        }
+    }
+}
+
+impl LayoutSchedule {
+    // minutes为当天的分钟数，多个时间段重叠时使用第一个
+    pub fn layout_at(&self, minutes: u32) -> Option<&str> {
+        self.ranges
+            .iter()
+            .find(|range| range.contains(minutes))
+            .map(|range| range.file.as_str())
+            .or(self.default.as_deref())
+    }
+}
+
+// 屏幕的颜色校准，RGB各通道的增益
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ColorProfile {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+impl Default for ColorProfile {
+    fn default() -> Self {
+        Self { r: 1., g: 1., b: 1. }
+    }
+}
+
+impl ColorProfile {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // channels为3(RGB)或者4(RGBA)，透明通道不变
+    pub fn apply(&self, pixels: &mut [u8], channels: usize) {
+        if self.is_identity() {
+            return;
+        }
+        let gains = [self.r, self.g, self.b];
+        for pixel in pixels.chunks_exact_mut(channels) {
+            for (v, gain) in pixel.iter_mut().zip(gains) {
+                *v = (*v as f32 * gain).round().clamp(0., 255.) as u8;
+            }
+        }
+    }
+}
+
+fn load() -> Result<Config> {
+    let json = std::fs::read_to_string(CONFIG_FILE_NAME)?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+fn save(config: &Config) -> Result<()> {
+    std::fs::write(CONFIG_FILE_NAME, serde_json::to_string_pretty(config)?)?;
+    Ok(())
+}
+
+pub fn color_profile(serial: &str) -> Option<ColorProfile> {
+    CONFIG.read().ok()?.color_profiles.get(serial).cloned()
+}
+
+// 保存设备的颜色校准，没有调整时删除
+pub fn set_color_profile(serial: &str, profile: ColorProfile) -> Result<()> {
+    let mut config = CONFIG.write().map_err(|err| anyhow!("{:?}", err))?;
+    if profile.is_identity() {
+        config.color_profiles.remove(serial);
+    } else {
+        config.color_profiles.insert(serial.to_string(), profile);
+    }
+    save(&config)
+}
+
+#[cfg(feature = "tray")]
+pub fn layout_hotkeys() -> Vec<(String, String)> {
+    CONFIG
+        .read()
+        .map(|config| config.layout_hotkeys.clone().into_iter().collect())
+        .unwrap_or_default()
+}
+
+pub fn manual_connect() -> bool {
+    CONFIG.read().map(|config| config.manual_connect).unwrap_or(false)
+}
+
+pub fn set_manual_connect(manual_connect: bool) -> Result<()> {
+    let mut config = CONFIG.write().map_err(|err| anyhow!("{:?}", err))?;
+    config.manual_connect = manual_connect;
+    save(&config)
+}
+
+pub fn high_compression() -> bool {
+    CONFIG.read().map(|config| config.high_compression).unwrap_or(false)
+}
+
+pub fn set_high_compression(high_compression: bool) -> Result<()> {
+    let mut config = CONFIG.write().map_err(|err| anyhow!("{:?}", err))?;
+    config.high_compression = high_compression;
+    save(&config)
+}
+
+pub fn recent_colors() -> Vec<[u8; 3]> {
+    CONFIG.read().map(|config| config.recent_colors.clone()).unwrap_or_default()
+}
+
+// 颜色移到最前面，超出数量时删除最早的
+pub fn add_recent_color(color: [u8; 3]) -> Result<()> {
+    let mut config = CONFIG.write().map_err(|err| anyhow!("{:?}", err))?;
+    config.recent_colors.retain(|c| *c != color);
+    config.recent_colors.insert(0, color);
+    config.recent_colors.truncate(MAX_RECENT_COLORS);
+    save(&config)
+}
+
+pub fn benchmark_fps(serial: &str) -> Option<f32> {
+    CONFIG.read().ok()?.benchmark_fps.get(serial).copied()
+}
+
+pub fn set_benchmark_fps(serial: &str, fps: f32) -> Result<()> {
+    let mut config = CONFIG.write().map_err(|err| anyhow!("{:?}", err))?;
+    config.benchmark_fps.insert(serial.to_string(), fps);
+    save(&config)
+}
+
+pub fn last_device() -> Option<String> {
+    CONFIG.read().ok()?.last_device.clone()
+}
+
+pub fn set_last_device(serial: &str) -> Result<()> {
+    let mut config = CONFIG.write().map_err(|err| anyhow!("{:?}", err))?;
+    config.last_device = Some(serial.to_string());
+    save(&config)
+}
+
+pub fn fullscreen_fps() -> Option<f32> {
+    CONFIG.read().ok()?.fullscreen_fps
+}
+
+pub fn adb_path() -> Option<String> {
+    CONFIG.read().ok()?.adb_path.clone().filter(|path| !path.trim().is_empty())
+}
+
+pub fn watch_layout_file() -> bool {
+    CONFIG.read().map(|config| config.watch_layout_file).unwrap_or(false)
+}
+
+pub fn layout_schedule() -> Option<LayoutSchedule> {
+    CONFIG.read().ok()?.layout_schedule.clone()
+}
+
+#[test]
+fn test_color_profile() -> Result<()> {
+    let profile = ColorProfile { r: 0.5, g: 1., b: 2. };
+    let mut pixels = [200, 100, 100, 255, 10, 20, 200, 128];
+    profile.apply(&mut pixels, 4);
+    assert_eq!(pixels, [100, 100, 200, 255, 5, 20, 255, 128]);
+    Ok(())
+}
+
+#[test]
+fn test_layout_schedule() {
+    let range = |start: &str, end: &str, file: &str| ScheduleRange {
+        start: start.to_string(),
+        end: end.to_string(),
+        file: file.to_string(),
+    };
+    let schedule = LayoutSchedule {
+        ranges: vec![range("09:00", "18:00", "work.screen"), range("22:00", "07:00", "clock.screen")],
+        default: Some("main.screen".to_string()),
+    };
+    assert_eq!(schedule.layout_at(9 * 60), Some("work.screen"));
+    assert_eq!(schedule.layout_at(18 * 60), Some("main.screen"));
+    assert_eq!(schedule.layout_at(23 * 60), Some("clock.screen"));
+    assert_eq!(schedule.layout_at(3 * 60), Some("clock.screen"));
+    assert_eq!(schedule.layout_at(7 * 60), Some("main.screen"));
+    assert!(!range("25:00", "07:00", "x").contains(60));
+}
@@ -1,9 +1,9 @@
 use anyhow::Result;
 use hex_color::HexColor;
 use image::buffer::ConvertBuffer;
-use image::RgbaImage;
+use image::{Rgba, RgbaImage};
 use image::{imageops::resize, RgbImage};
-use log::{error, info};
+use log::{error, info, warn};
 use offscreen_canvas::{OffscreenCanvas, BLUE, WHITE};
 use rfd::{FileDialog, MessageDialog};
 use slint::private_unstable_api::re_exports::KeyEvent;
@@ -12,22 +12,29 @@ use slint::{
 };
 use std::time::Instant;
 use once_cell::sync::Lazy;
+use uuid::Uuid;
 use std::{
     cell::RefCell,
     fs::File,
-    io::{Read, Write},
+    io::Read,
+    path::{Path, PathBuf},
     rc::Rc,
     str::FromStr,
-    sync::{Arc, Mutex},
+    sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex},
 };
 
 use crate::{monitor, utils};
+use crate::config::{self, ColorProfile};
+use crate::monitor::Correction;
+use crate::rgb565::{self, ByteOrder};
+use crate::lut::Lut;
+use crate::transition::{Direction, Easing, TransitionKind};
 use crate::usb_screen::{self, UsbScreen, UsbScreenInfo};
 use crate::{
-    nmc::CITIES,
-    screen::{ScreenRender, ScreenSize, DEFAULT_FONT},
+    nmc::{self, CITIES},
+    screen::{self, rotate_frame, Compression, LayoutPreset, SaveableScreen, ScreenRender, ScreenSize, ShutdownAction, DEFAULT_FONT, MAX_SUPERSAMPLE},
     utils::get_font_name,
-    widgets::{ImageData, ImageWidget, TextWidget, Widget},
+    widgets::{self, Condition, Corner, Fit, ImageData, ImageSource, ImageWidget, Mask, Rect, Role, CpuCoresWidget, SummaryLayout, SummaryWidget, TextWidget, ValueFormat, Watermark, Widget, MIN_FONT_SIZE, PLACEHOLDERS, split_cities, resize_filter, snap},
 };
 
 struct CurrentUsbScreen{
@@ -35,6 +42,12 @@ struct CurrentUsbScreen{
     screen: UsbScreen
 }
 
+// 复制的组件样式，只保存格式相关的字段
+enum StyleClipboard {
+    Text(TextWidget),
+    Image(ImageWidget),
+}
+
 // 当前打开的屏幕
 static SCREEN: Lazy<Mutex<Option<CurrentUsbScreen>>> = Lazy::new(|| {
     Mutex::new(None)
@@ -42,8 +55,24 @@ static SCREEN: Lazy<Mutex<Option<CurrentUsbScreen>>> = Lazy::new(|| {
 // 所有屏幕列表
 static ALL_SCREENS: Lazy<Mutex<Vec<UsbScreenInfo>>> = Lazy::new(|| Mutex::new(vec![]) );
 
+//画面压缩后的大小，用于提示复杂度
+static FRAME_COMPLEXITY: Lazy<Mutex<usb_screen::FrameComplexity>> = Lazy::new(|| {
+    Mutex::new(usb_screen::FrameComplexity::default())
+});
+
+// 取色器中的预设颜色，包括常用的硬件品牌色
+const PRESET_COLORS: &[[u8; 3]] = &[
+    [255, 255, 255], [0, 0, 0], [255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 0], [0, 255, 255], [255, 128, 0],
+    [118, 185, 0], [237, 28, 36], [0, 113, 197],
+];
+
+//正在测速，测速时编辑器不向屏幕发送画面
+static BENCHMARKING: AtomicBool = AtomicBool::new(false);
+//每个复杂度等级的测速时长
+const BENCHMARK_LEVEL_DURATION: std::time::Duration = std::time::Duration::from_millis(1500);
+
 //解压好的屏幕数据
-static UNCOMPRESSED_SCREEN: Lazy<Mutex<Option<Vec<u8>>>> = Lazy::new(|| {
+static UNCOMPRESSED_SCREEN: Lazy<Mutex<Option<(Vec<u8>, PathBuf)>>> = Lazy::new(|| {
     Mutex::new(None)
 });
 
@@ -53,17 +82,38 @@ slint::include_modules!();
 struct CanvasEditorContext {
     app: Weak<CanvasEditor>,
     screen: ScreenRender,
-    temp_image: Arc<Mutex<Option<ImageData>>>,
+    temp_image: Arc<Mutex<Option<(ImageData, PathBuf)>>>,
     screens: Vec<ScreenSize>,
     list_model: Rc<VecModel<WidgetObject>>,
     active_id: Option<String>,
+    //多选的组件
+    selected_ids: Vec<String>,
+    //等待点击画布插入的组合
+    pending_component: Option<Vec<u8>>,
+    //选择了预设，点击画布时添加
+    pending_preset: Option<&'static str>,
     is_drag: bool,
     start_drag_dx: i32,
     start_drag_dy: i32,
     picker_img: RgbImage,
+    //取色器打开期间最后选择的颜色，关闭时加入最近使用的颜色
+    picked_color: Option<[u8; 3]>,
     fps: f32,
     last_frame_time: Option<Instant>,
-    devices: Vec<UsbScreenInfo>
+    //最后发送到设备的一帧(旋转前)，用于设备截屏
+    device_frame: Option<RgbaImage>,
+    //当前设备的串号和颜色校准
+    device_serial: Option<String>,
+    color_profile: ColorProfile,
+    //实际的渲染帧率(滑动平均)
+    render_fps: f32,
+    last_render_time: Option<Instant>,
+    devices: Vec<UsbScreenInfo>,
+    style_clipboard: Option<StyleClipboard>,
+    //已经提示过大小不一致的设备和布局大小，避免重复提示
+    size_checked: Option<(String, u32, u32)>,
+    //当前打开或者保存过的文件，直接保存时覆盖此文件
+    screen_file: Arc<Mutex<Option<PathBuf>>>,
 }
 
 impl CanvasEditorContext {
@@ -107,6 +157,12 @@ impl CanvasEditorContext {
         ));
 
         win.set_screen_names(screen_names.into());
+        win.set_correction_types(Rc::new(VecModel::from(
+            monitor::CORRECTION_TYPES
+                .iter()
+                .map(|t| SharedString::from(*t))
+                .collect::<Vec<SharedString>>(),
+        )).into());
         win.set_screen_name(format!(
             "{ } {}x{}",
             screens[0].name, screens[0].width, screens[0].height
@@ -114,6 +170,10 @@ impl CanvasEditorContext {
         .into());
         win.set_screen_width(screens[0].width as f32);
         win.set_screen_height(screens[0].height as f32);
+        win.set_picker_preset_colors(Rc::new(VecModel::from(
+            PRESET_COLORS.iter().map(|c| Color::from_rgb_u8(c[0], c[1], c[2])).collect::<Vec<Color>>(),
+        )).into());
+        Self::update_recent_colors(&win);
 
         CanvasEditorContext {
             app,
@@ -127,15 +187,27 @@ impl CanvasEditorContext {
             .unwrap(),
             temp_image: Arc::new(Mutex::new(None)),
             active_id: None,
+            selected_ids: vec![],
+            pending_component: None,
+            pending_preset: None,
             is_drag: false,
             start_drag_dx: 0,
             start_drag_dy: 0,
             list_model,
             screens,
             picker_img,
+            picked_color: None,
             fps: 10.,
             last_frame_time: None,
+            device_frame: None,
+            device_serial: None,
+            color_profile: ColorProfile::default(),
+            render_fps: 0.,
+            last_render_time: None,
             devices: vec![],
+            style_clipboard: None,
+            size_checked: None,
+            screen_file: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -166,13 +238,32 @@ impl CanvasEditorContext {
         let mut dev_index:i32 = -1;
         let current_name = app.get_device_name().to_string();
         for (idx, dev) in self.devices.iter().enumerate(){
-            if dev.label == current_name{
+            if current_name == format!("{} {}x{}", dev.label, dev.width, dev.height){
                 dev_index = idx as i32;
                 break;
             }
         }
 
-        if dev_index == -1 && self.devices.len()>0{
+        //优先选择上次使用的设备
+        let preferred = config::last_device();
+        let preferred_index = preferred
+            .as_ref()
+            .and_then(|serial| self.devices.iter().position(|dev| dev.serial == *serial));
+        if dev_index == -1 {
+            if let Some(idx) = preferred_index {
+                let dev = &self.devices[idx];
+                dev_index = idx as i32;
+                app.set_device_name(format!("{} {}x{}", dev.label, dev.width, dev.height).into());
+            }
+        }
+
+        //手动连接时不自动选择其他设备，只重连下拉框中选择的设备
+        let manual = app.get_manual_connect();
+        if dev_index == -1 && self.devices.len()>0 && manual{
+            app.set_device_name("未选择".into());
+        }
+
+        if dev_index == -1 && self.devices.len()>0 && !manual{
             let dev = &self.devices[0];
             dev_index = 0;
             app.set_device_name(format!("{} {}x{}", dev.label, dev.width, dev.height).into());
@@ -185,6 +276,7 @@ impl CanvasEditorContext {
         //连接当前设备
         if dev_index >= 0{
             let dev = self.devices[dev_index as usize].clone();
+            self.check_device_size(&dev);
             std::thread::spawn(move ||{
                 if let Ok(mut screen) = SCREEN.lock(){
                     if screen.is_some() && screen.as_ref().unwrap().info.label == dev.label{
@@ -193,6 +285,10 @@ impl CanvasEditorContext {
     
                     match UsbScreen::open(dev.clone()){
                         Ok(s) => {
+                            //还没有记住的设备时，记住自动连接的设备
+                            if preferred.is_none() {
+                                remember_device(&dev);
+                            }
                             screen.replace(CurrentUsbScreen { info: dev.clone(), screen: s });
                         }
                         Err(err) => {
@@ -204,8 +300,86 @@ impl CanvasEditorContext {
         }
     }
 
+    // 布局和连接的设备大小不一致时提示缩放布局，同一设备和布局大小只提示一次
+    fn check_device_size(&mut self, dev: &UsbScreenInfo) {
+        //竖屏布局的宽高和设备相反
+        let (width, height) = if self.screen.is_vertical() {
+            (dev.height as u32, dev.width as u32)
+        } else {
+            (dev.width as u32, dev.height as u32)
+        };
+        if width == 0 || height == 0 || (width, height) == (self.screen.width, self.screen.height) {
+            return;
+        }
+        let key = (dev.label.clone(), self.screen.width, self.screen.height);
+        if self.size_checked.as_ref() == Some(&key) {
+            return;
+        }
+        self.size_checked = Some(key);
+        warn!("布局大小{}x{}与屏幕{}大小{width}x{height}不一致", self.screen.width, self.screen.height, dev.label);
+        let description = format!(
+            "当前布局为{}x{}，连接的屏幕\"{}\"为{width}x{height}，画面无法完整显示。\n是否把布局缩放到屏幕大小？",
+            self.screen.width, self.screen.height, dev.label
+        );
+        //在单独的线程中显示对话框，不阻塞界面刷新
+        let app = self.app.clone();
+        std::thread::spawn(move || {
+            let ret = MessageDialog::new()
+                .set_title("屏幕大小不一致")
+                .set_description(description)
+                .set_buttons(rfd::MessageButtons::YesNo)
+                .show();
+            if let rfd::MessageDialogResult::Yes = ret {
+                let _ = app.upgrade_in_event_loop(move |app| {
+                    app.invoke_scale_to_device(width as i32, height as i32);
+                });
+            } else {
+                toast(app, "布局和屏幕大小不一致，画面可能显示不全");
+            }
+        });
+    }
+
+    //缩放布局到设备大小，有同样大小的预设时使用预设的名称
+    fn on_scale_to_device(&mut self, width: i32, height: i32) {
+        let (width, height) = (width.max(1) as u32, height.max(1) as u32);
+        let screen = self
+            .screens
+            .iter()
+            .find(|s| (s.width, s.height) == (width, height) || (s.height, s.width) == (width, height))
+            .map(|s| ScreenSize { name: s.name.clone(), width, height })
+            .unwrap_or(ScreenSize { name: "自定义".to_string(), width, height });
+        self.resize_layout(&screen);
+        self.size_checked = None;
+    }
+
     pub fn render_screen(&mut self) {
+        if let Some(last) = self.last_render_time.as_ref() {
+            let ms = last.elapsed().as_millis().max(1) as f32;
+            self.render_fps = self.render_fps * 0.8 + 1000. / ms * 0.2;
+        }
+        self.last_render_time = Some(Instant::now());
+        let page_index = self.screen.page_index();
         self.screen.render();
+        //预览轮播时切换了页面
+        if page_index != self.screen.page_index() {
+            self.on_page_switched();
+        }
+        self.update_profile();
+        if self.app.unwrap().get_layout_guides() {
+            self.draw_layout_guides();
+        }
+        //绘制多选的框
+        for widget in &self.screen.widgets {
+            if Some(widget.id()) != self.active_id.as_deref()
+                && self.selected_ids.iter().any(|id| id == widget.id())
+            {
+                let rect = widget.position();
+                self.screen.canvas.stroke_rect(
+                    offscreen_canvas::Rect::new(rect.left, rect.top, rect.right, rect.bottom),
+                    BLUE,
+                );
+            }
+        }
         //绘制选中的框
         if let Some(active_id) = self.active_id.as_ref() {
             for widget in &mut self.screen.widgets {
@@ -218,18 +392,20 @@ impl CanvasEditorContext {
                         bottom: rect.bottom,
                     };
 
-                    //进度条按照tag2为宽度
+                    //进度条按照tag2为宽度，圆环按照tag2为直径
                     if let Some(widget) = widget.as_any_mut().downcast_mut::<TextWidget>() {
-                        if widget.type_name != "weather" && widget.type_name != "uptime" && widget.tag1 == "1" {
-                            let width = widget
-                                .tag2
-                                .parse::<i32>()
-                                .unwrap_or(widget.font_size as i32 * 5);
+                        if widget.is_bar() || widget.is_ring() {
+                            let (width, height) = if widget.is_ring() {
+                                let width = widget.ring_size(self.screen.width.max(self.screen.height) as i32);
+                                (width, width)
+                            } else {
+                                widget.bar_size(self.screen.width.max(self.screen.height) as i32)
+                            };
                             rect = offscreen_canvas::Rect::from(
                                 rect.left,
                                 rect.top,
                                 width,
-                                rect.height(),
+                                height,
                             );
                         }
                     }
@@ -256,9 +432,26 @@ impl CanvasEditorContext {
                 }
             }
         }
+        if self.app.unwrap().get_debug_overlay() {
+            self.draw_debug_overlay();
+        }
+        self.update_color_profile();
         let image_data = self.screen.canvas.image_data();
+        let mut rgba = image_data.clone();
+        //预览页面切换的过渡效果
+        if self.screen.transition != TransitionKind::None {
+            let mut frame: RgbImage = rgba.convert();
+            self.screen.apply_transition(&mut frame);
+            rgba = frame.convert();
+        }
+        self.screen.apply_color_filter(&mut rgba, 4);
+        self.color_profile.apply(&mut rgba, 4);
+        //按照屏幕的RGB565色深预览
+        if self.app.unwrap().get_preview_rgb565() {
+            rgb565::reduce_rgba_to_rgb565(&mut rgba);
+        }
         let buf = SharedPixelBuffer::clone_from_slice(
-            &image_data,
+            &rgba,
             self.screen.width(),
             self.screen.height(),
         );
@@ -272,32 +465,257 @@ impl CanvasEditorContext {
             }
         }
 
-        let _ = slint::spawn_local(Self::draw_image_to_usb_screen(self.app.clone(), self.screen.canvas.image_data().clone(), self.screen.rotate_degree));
+        if BENCHMARKING.load(Ordering::SeqCst){
+            return;
+        }
+        self.device_frame = Some(rgba.clone());
+        let _ = slint::spawn_local(Self::draw_image_to_usb_screen(self.app.clone(), rgba, self.screen.rotate_degree, self.screen.byte_order));
         //更新最后时间
         self.last_frame_time = Some(Instant::now());
     }
 
-    async fn draw_image_to_usb_screen(app_clone: Weak<CanvasEditor>, img: RgbaImage, rotate_degree: i32){
+    // 切换了设备时读取设备的颜色校准
+    fn update_color_profile(&mut self) {
+        //渲染线程正在发送时下次再检查
+        let serial = match SCREEN.try_lock() {
+            Ok(screen) => screen.as_ref().map(|s| s.info.serial.clone()),
+            Err(_) => return,
+        };
+        if serial == self.device_serial {
+            return;
+        }
+        self.color_profile = serial
+            .as_deref()
+            .and_then(config::color_profile)
+            .unwrap_or_default();
+        self.device_serial = serial;
+        let app = self.app.unwrap();
+        app.set_calibration_r(self.color_profile.r * 100.);
+        app.set_calibration_g(self.color_profile.g * 100.);
+        app.set_calibration_b(self.color_profile.b * 100.);
+    }
+
+    fn on_change_calibration(&mut self) {
+        let app = self.app.unwrap();
+        self.color_profile = ColorProfile {
+            r: app.get_calibration_r().round() / 100.,
+            g: app.get_calibration_g().round() / 100.,
+            b: app.get_calibration_b().round() / 100.,
+        };
+    }
+
+    // 在当前连接的屏幕上测速，帧率最低的等级作为可以持续的最高帧率
+    fn on_benchmark(&mut self) {
+        if BENCHMARKING.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let app = self.app.clone();
+        self.app.unwrap().set_benchmark_result("测速中...".into());
+        std::thread::spawn(move || {
+            let result = match SCREEN.lock() {
+                Err(err) => Err(anyhow::anyhow!("{err:?}")),
+                Ok(mut screen) => match screen.as_mut() {
+                    None => Err(anyhow::anyhow!("没有连接屏幕")),
+                    Some(device) => device
+                        .screen
+                        .benchmark(BENCHMARK_LEVEL_DURATION)
+                        .map(|results| (device.info.clone(), results))
+                        .map_err(anyhow::Error::from),
+                },
+            };
+            BENCHMARKING.store(false, Ordering::SeqCst);
+            let text = match result {
+                Ok((info, results)) => {
+                    for r in &results {
+                        info!("测速 {} {}x{}: {r} ({}帧)", info.label, info.width, info.height, r.frames);
+                    }
+                    let max_fps = results.iter().map(|r| r.fps).reduce(f32::min);
+                    if let (Some(fps), false) = (max_fps, info.serial.is_empty()) {
+                        if let Err(err) = config::set_benchmark_fps(&info.serial, fps) {
+                            error!("配置保存失败:{err:?}");
+                        }
+                    }
+                    let details = results.iter().map(|r| r.to_string()).collect::<Vec<_>>().join(" | ");
+                    match max_fps {
+                        Some(fps) => format!("最高{fps:.1}帧: {details}"),
+                        None => "画面超过设备缓冲区，无法测速".to_string(),
+                    }
+                }
+                Err(err) => {
+                    error!("测速失败:{err:?}");
+                    format!("测速失败:{err}")
+                }
+            };
+            let _ = app.upgrade_in_event_loop(move |app| {
+                app.set_benchmark_result(text.into());
+            });
+        });
+    }
+
+    fn on_change_manual_connect(&mut self) {
+        let manual = self.app.unwrap().get_manual_connect();
+        if let Err(err) = config::set_manual_connect(manual) {
+            error!("配置保存失败:{err:?}");
+            toast(self.app.clone(), &format!("{:?}", err));
+        }
+    }
+
+    fn on_change_high_compression(&mut self) {
+        let high_compression = self.app.unwrap().get_high_compression();
+        if let Err(err) = config::set_high_compression(high_compression) {
+            error!("配置保存失败:{err:?}");
+            toast(self.app.clone(), &format!("{:?}", err));
+        }
+    }
+
+    fn on_save_calibration(&mut self) {
+        let serial = match self.device_serial.as_ref().filter(|s| !s.is_empty()) {
+            None => {
+                toast(self.app.clone(), "没有连接屏幕");
+                return;
+            }
+            Some(s) => s,
+        };
+        match config::set_color_profile(serial, self.color_profile) {
+            Ok(()) => toast(self.app.clone(), "颜色校准已保存"),
+            Err(err) => {
+                error!("颜色校准保存失败:{err:?}");
+                toast(self.app.clone(), &format!("{:?}", err));
+            }
+        }
+    }
+
+    // 性能分析：显示每帧耗时和最慢的组件
+    fn update_profile(&mut self) {
+        let app = self.app.unwrap();
+        if !app.get_profiling() {
+            self.screen.profile = None;
+            return;
+        }
+        let profile = match self.screen.profile.as_ref() {
+            None => {
+                self.screen.profile = Some(Default::default());
+                return;
+            }
+            Some(p) => p,
+        };
+        let mut info = format!("每帧:{:.1}ms", profile.render_ms);
+        for (id, ms) in profile.slowest(3) {
+            if let Some((idx, widget)) = self.screen.widgets.iter().enumerate().find(|(_, w)| w.id() == id) {
+                info.push_str(&format!("  {}#{}:{:.1}ms", widget.type_name(), idx, ms));
+            }
+        }
+        app.set_profile_info(info.into());
+    }
+
+    // 布局辅助线: 所有组件的边框、画布边缘的标尺、选中组件的坐标和大小
+    fn draw_layout_guides(&mut self) {
+        let guide_color = Rgba([255, 0, 255, 255]);
+        for widget in &self.screen.widgets {
+            if !widget.is_visible(self.screen.tick) {
+                continue;
+            }
+            let rect = widget.position();
+            self.screen.canvas.stroke_rect(
+                offscreen_canvas::Rect::new(rect.left, rect.top, rect.right, rect.bottom),
+                guide_color,
+            );
+        }
+        //每10像素一个刻度，每50像素长刻度并标注坐标
+        let (width, height) = (self.screen.width as i32, self.screen.height as i32);
+        let ruler_color = Rgba([255, 255, 0, 255]);
+        let font_size = 8.;
+        for x in (0..width).step_by(10) {
+            let len = if x % 50 == 0 { 6 } else { 3 };
+            self.screen.canvas.fill_rect(offscreen_canvas::Rect::from(x, 0, 1, len), ruler_color);
+            if x % 50 == 0 && x > 0 {
+                self.screen.canvas.draw_text(&format!("{x}"), ruler_color, font_size, x + 2, 0);
+            }
+        }
+        for y in (0..height).step_by(10) {
+            let len = if y % 50 == 0 { 6 } else { 3 };
+            self.screen.canvas.fill_rect(offscreen_canvas::Rect::from(0, y, len, 1), ruler_color);
+            if y % 50 == 0 && y > 0 {
+                self.screen.canvas.draw_text(&format!("{y}"), ruler_color, font_size, 1, y + 1);
+            }
+        }
+        //选中组件的坐标和大小显示在组件下方，超出画布时显示在上方
+        let rect = match self.active_id.clone().and_then(|id| self.screen.find_widget(&id)) {
+            Some((_, widget)) => widget.position().clone(),
+            None => return,
+        };
+        let label = format!("{},{} {}x{}", rect.left, rect.top, rect.width(), rect.height());
+        let font_size = 10.;
+        let size = self.screen.canvas.measure_text(&label, font_size);
+        let (label_width, label_height) = (size.width() + 4, size.height() + 2);
+        let x = rect.left.clamp(0, (width - label_width).max(0));
+        let y = if rect.bottom + 2 + label_height <= height { rect.bottom + 2 } else { (rect.top - 2 - label_height).max(0) };
+        self.screen.canvas.fill_rect(offscreen_canvas::Rect::from(x, y, label_width, label_height), Rgba([0, 0, 0, 200]));
+        self.screen.canvas.draw_text(&label, WHITE, font_size, x + 2, y + 1);
+    }
+
+    // 调试信息绘制在画面上，同时发送到屏幕
+    fn draw_debug_overlay(&mut self) {
+        let (size, percent) = FRAME_COMPLEXITY
+            .lock()
+            .map(|c| (c.size(), c.percent()))
+            .unwrap_or((0, 0));
+        //渲染线程正在发送时不等待
+        let device = match SCREEN.try_lock() {
+            Ok(screen) => screen.as_ref().map(|s| s.info.label.clone()).unwrap_or("未连接".to_string()),
+            Err(_) => "发送中".to_string(),
+        };
+        let lines = [
+            format!("FPS:{:.1}", self.render_fps),
+            format!("组件:{}", self.screen.widgets.len()),
+            format!("大小:{:.1}k {}%", size as f32 / 1024., percent),
+            format!("设备:{device}"),
+        ];
+        let font_size = 12.;
+        let width = lines
+            .iter()
+            .map(|l| self.screen.canvas.measure_text(l, font_size).width())
+            .max()
+            .unwrap_or(0);
+        let line_height = font_size as i32 + 2;
+        self.screen.canvas.fill_rect(
+            offscreen_canvas::Rect::from(0, 0, width + 4, line_height * lines.len() as i32 + 2),
+            Rgba([0, 0, 0, 200]),
+        );
+        for (i, line) in lines.iter().enumerate() {
+            self.screen.canvas.draw_text(line, Rgba([0, 255, 0, 255]), font_size, 2, 1 + line_height * i as i32);
+        }
+    }
+
+    async fn draw_image_to_usb_screen(app_clone: Weak<CanvasEditor>, img: RgbaImage, rotate_degree: i32, byte_order: Option<ByteOrder>){
         async_std::task::spawn_blocking(move ||{
             //发送到USB屏幕
-            let frame: RgbImage = img.convert();
-            let frame = if rotate_degree == 90 {
-                image::imageops::rotate90(&frame)
-            }else if rotate_degree == 180{
-                image::imageops::rotate180(&frame)
-            }else if rotate_degree == 270{
-                image::imageops::rotate270(&frame)
-            }else{
-                frame
-            };
+            let frame = rotate_frame(img.convert(), rotate_degree);
+            //没有连接屏幕时也提示复杂度，按照当前设备的大小上限计算
+            let max_frame_size = SCREEN.lock().ok()
+                .and_then(|screen| screen.as_ref().and_then(|device| device.screen.frame_size_limit()))
+                .unwrap_or(usb_screen::DEFAULT_MAX_FRAME_SIZE);
+            if let Ok(mut complexity) = FRAME_COMPLEXITY.lock(){
+                complexity.set_max_size(max_frame_size);
+                complexity.update(usb_screen::compressed_frame_size(&frame));
+                let (percent, warning) = (complexity.percent() as i32, complexity.is_high());
+                let _ = app_clone.upgrade_in_event_loop(move |app|{
+                    app.set_complexity_percent(percent);
+                    app.set_complexity_warning(warning);
+                });
+            }
             if let Ok(mut screen) = SCREEN.lock(){
                 let mut image_too_complete = false;
-                if let Some(device) = screen.as_mut(){
-                    if let Err(err) = device.screen.draw_rgb_image(0,0,&frame){
-                        let err_msg = format!("{err:?}");
-                        image_too_complete =  err_msg.contains("图像太大了");
-                        error!("绘制失败:{err:?}");
+                //设备断开时从SCREEN中移除，由定时器重新打开
+                let result = usb_screen::draw_or_release(&mut screen, |device|{
+                    if let Some(byte_order) = byte_order{
+                        device.screen.set_byte_order(byte_order);
                     }
+                    Ok(device.screen.draw_rgb_image(0,0,&frame)?)
+                });
+                if let Err(err) = result{
+                    image_too_complete = usb_screen::is_image_too_large(&err);
+                    error!("绘制失败:{err:?}");
                 }
                 let _ = app_clone.upgrade_in_event_loop(move |app|{
                     app.set_image_too_complex(image_too_complete);
@@ -306,7 +724,7 @@ impl CanvasEditorContext {
         }).await
     }
 
-    fn on_mouse_click(&mut self, mouse_x: f32, mouse_y: f32, image_width: f32, image_height: f32) {
+    fn on_mouse_click(&mut self, mouse_x: f32, mouse_y: f32, image_width: f32, image_height: f32, multi_select: bool) {
         // info!("on_mouse_click 鼠标位置:{mouse_x}x{mouse_y}");
         let app = self.app.unwrap();
 
@@ -318,8 +736,23 @@ impl CanvasEditorContext {
         let index = app.get_widget_type_index();
         let (x, y) = Self::get_real_pos(&self.screen, mouse_x, mouse_y, image_width, image_height);
 
+        if let Some(component) = self.pending_component.take() {
+            self.insert_component(&component, x, y);
+            return;
+        }
+        if let Some(preset) = self.pending_preset.take() {
+            self.insert_preset(preset, x, y);
+            return;
+        }
+
         if index == 0 {
-            self.set_active_widget(x, y);
+            if multi_select {
+                self.toggle_select_widget(x, y);
+            } else {
+                self.selected_ids.clear();
+                self.set_active_widget(x, y);
+                self.select_group();
+            }
         } else {
             self.add_widget(x, y);
         }
@@ -343,10 +776,17 @@ impl CanvasEditorContext {
                     None => return,
                     Some(v) => v,
                 };
+                //锁定的背景和锁定位置的组件不能拖动
+                if active_widget.locked() || (active_widget.is_background() && app.get_background_locked()) {
+                    return;
+                }
 
+                let (old_x, old_y) = active_widget.position().center();
                 active_widget.position_mut().set_center(x, y);
                 app.set_active_widget_x(format!("{x}").into());
                 app.set_active_widget_y(format!("{y}").into());
+                //组合中的其他组件跟随移动
+                self.offset_group(x - old_x, y - old_y);
             } else {
                 self.is_drag = true;
                 let active_widget = match self.active_widget() {
@@ -403,11 +843,31 @@ impl CanvasEditorContext {
         app.set_active_widget_height(format!("{nh}").into());
         widget.position_mut().set_center(x, y);
 
+        if let Some(widget) = widget.as_any_mut().downcast_mut::<TextWidget>() {
+            if widget.auto_size || widget.scroll_speed > 0. {
+                widget.position_mut().set_size(nw, nh);
+            }
+            widget.rotation = rotate_str.parse().unwrap_or(widget.rotation);
+            app.set_active_widget_rotation(format!("{}", widget.rotation as i32).into());
+        }
         if let Some(widget) = widget.as_any_mut().downcast_mut::<ImageWidget>() {
+            //锁定宽高比时，修改了宽度就按宽度计算高度，否则按高度计算宽度
+            if app.get_lock_aspect_ratio() {
+                let keep_width = nw != widget.position.width() || nh == widget.position.height();
+                (nw, nh) = widget.fit_aspect(nw, nh, keep_width);
+                app.set_active_widget_width(format!("{nw}").into());
+                app.set_active_widget_height(format!("{nh}").into());
+            }
             widget.position_mut().set_size(nw, nh);
             widget.rotation = rotate_str.parse().unwrap_or(widget.rotation);
             app.set_active_widget_rotation(format!("{}", widget.rotation as i32).into());
         }
+        if let Some(widget) = widget.as_any_mut().downcast_mut::<SummaryWidget>() {
+            widget.position.set_size(nw, nh);
+        }
+        if let Some(widget) = widget.as_any_mut().downcast_mut::<CpuCoresWidget>() {
+            widget.position.set_size(nw, nh);
+        }
     }
 
     fn on_update_widget_text(&mut self) {
@@ -434,7 +894,8 @@ impl CanvasEditorContext {
         if widget.font_size < 5. {
             widget.font_size = 5.;
         }
-        widget.text = text.to_string();
+        //输入框中用\n表示换行
+        widget.text = text.replace("\\n", "\n");
         widget.prefix = prefix.to_string();
         if let Ok(color) = HexColor::from_str(&color.to_string()) {
             widget.color[0] = color.r;
@@ -485,141 +946,514 @@ impl CanvasEditorContext {
         }
     }
 
-    fn on_update_widget_text_color(&mut self) {
-        let color_str = self.app.unwrap().get_active_widget_color_str().to_string();
-        let mut color = None;
-        if color_str.len() > 0 {
-            if let Ok(c) = HexColor::from_str(&color_str) {
-                color = Some([c.r, c.g, c.b, c.a]);
-            }
-        }
+    //着色颜色字符串为空时不着色
+    fn on_update_widget_tint(&mut self) {
+        let tint_str = self.app.unwrap().get_active_widget_tint_str().to_string();
+        let tint = match tint_str.trim() {
+            "" => None,
+            tint_str => match HexColor::from_str(tint_str) {
+                Ok(c) => Some([c.r, c.g, c.b]),
+                Err(_) => return,
+            },
+        };
+        self.update_widget_tint(tint);
+    }
 
-        if let (Some(color), Some(widget)) = (color, self
+    fn on_update_widget_image_fit(&mut self) {
+        let fit = Fit::from_index(self.app.unwrap().get_active_widget_image_fit());
+        if let Some(widget) = self
             .active_widget()
-            .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>()))
+            .and_then(|w| w.as_any_mut().downcast_mut::<ImageWidget>())
         {
-            widget.color = color;
+            widget.fit = fit;
         }
     }
 
-    fn on_update_widget_image(&mut self) {
-        let temp_image_clone = self.temp_image.clone();
-        let (screen_width, screen_height) = (self.screen.width, self.screen.height);
-        let app_clone = self.app.clone();
-        std::thread::spawn(move || {
-            let file = match FileDialog::new()
-                .add_filter("图像", &["png", "bmp", "jpg", "jpeg", "gif"])
-                .pick_file()
-            {
-                None => return,
-                Some(path) => path,
-            };
-            let mut file_data = vec![];
-            let result = File::open(file).map(|mut f| f.read_to_end(&mut file_data));
-            if let (Ok(Ok(img)), Ok(mut tmp)) = (
-                result.map(|_| ImageData::load(&file_data, (screen_width, screen_height))),
-                temp_image_clone.lock(),
-            ) {
-                info!(
-                    "选择了图片，最终大小:{}x{} 帧数:{} 帧大小:{}",
-                    img.width,
-                    img.height,
-                    img.frames.len(),
-                    img.frames[0].len()
-                );
-                tmp.replace(img);
-                let _ = app_clone.upgrade_in_event_loop(move |app| {
-                    app.invoke_new_image_ready();
-                });
-            }
-        });
+    fn on_update_widget_image_mask(&mut self) {
+        let app = self.app.unwrap();
+        let radius = app.get_active_widget_image_mask_radius().parse::<i32>().unwrap_or(10);
+        let mask = Mask::from_index(app.get_active_widget_image_mask(), radius);
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<ImageWidget>())
+        {
+            widget.mask = mask;
+        }
     }
 
-    fn on_update_widget_tags(&mut self) {
+    fn on_update_widget_watermark(&mut self) {
         let app = self.app.unwrap();
-        let tag1 = app.get_active_widget_tag1();
-        let tag2 = app.get_active_widget_tag2();
-        
-        if let Some(widget) = self.active_widget()
+        let watermark = if app.get_active_widget_watermark() {
+            let color = match HexColor::from_str(app.get_active_widget_watermark_color().trim()) {
+                Ok(c) => [c.r, c.g, c.b, c.a],
+                // 颜色还没输入完整时保持原样
+                Err(_) => return,
+            };
+            Some(Watermark {
+                corner: Corner::from_index(app.get_active_widget_watermark_corner()),
+                color,
+                font_size: app.get_active_widget_watermark_size().parse::<f32>().unwrap_or(12.).max(MIN_FONT_SIZE),
+                label: app.get_active_widget_watermark_label().trim().to_string(),
+            })
+        } else {
+            None
+        };
+        if let Some(widget) = self
+            .active_widget()
             .and_then(|w| w.as_any_mut().downcast_mut::<ImageWidget>())
         {
-            if widget.is_webcam(){
-                //更新摄像头
-                widget.tag1 = Some(tag1.to_string());
-                let _ = self.screen.setup_monitor();
-            }
+            widget.watermark = watermark;
         }
+    }
 
+    fn show_widget_watermark(app: &CanvasEditor, watermark: Option<&Watermark>) {
+        app.set_active_widget_watermark(watermark.is_some());
+        let watermark = watermark.cloned().unwrap_or_default();
+        let [r, g, b, a] = watermark.color;
+        app.set_active_widget_watermark_corner(watermark.corner.index());
+        app.set_active_widget_watermark_color(HexColor::rgba(r, g, b, a).display_rgba().to_string().into());
+        app.set_active_widget_watermark_size(format!("{}", watermark.font_size).into());
+        app.set_active_widget_watermark_label(watermark.label.into());
+    }
+
+    fn on_update_widget_text_auto_size(&mut self) {
+        let app = self.app.unwrap();
+        let auto_size = app.get_active_widget_text_auto_size();
         if let Some(widget) = self
             .active_widget()
             .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>())
         {
-            widget.tag1 = tag1.to_string();
+            widget.auto_size = auto_size;
+            app.set_active_widget_width(format!("{}", widget.position.width()).into());
+            app.set_active_widget_height(format!("{}", widget.position.height()).into());
+        }
+    }
 
-            //更新天气
-            if widget.type_name == "weather" {
-                let city_name = tag2.to_string();
-                //查询城市名称
-                if city_name.trim().len() > 0 {
-                    let mut find_city = None;
-                    for city in CITIES.iter() {
-                        if city.city.contains(city_name.as_str()) {
-                            find_city = Some(city.clone());
-                            break;
-                        }
-                    }
-    
-                    if let Some(city) = find_city {
-                        widget.tag2 = city.city.clone();
-                        self.app
-                            .unwrap()
-                            .set_active_widget_tag2(city.city.clone().into());
-                        let _ = self.screen.setup_monitor();
-                        //更新所有日期组件的tag2
-                        for w in self.screen.widgets.iter_mut() {
-                            if let Some(widget) = w.as_any_mut().downcast_mut::<TextWidget>() {
-                                widget.tag2 = city.city.clone();
-                            }
-                        }
-                        //刷新ui
-                        self.refresh_model_text();
-                        self.app.unwrap().set_active_widget_tag2(city.city.into());
-                    }
+    fn on_update_widget_bar_gradient(&mut self) {
+        let app = self.app.unwrap();
+        let low = HexColor::from_str(&app.get_active_widget_bar_low_str().to_string());
+        let high = HexColor::from_str(&app.get_active_widget_bar_high_str().to_string());
+        let enabled = app.get_active_widget_bar_gradient();
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>())
+        {
+            match (enabled, low, high) {
+                (false, _, _) => widget.bar_gradient = None,
+                (true, Ok(low), Ok(high)) => {
+                    widget.bar_gradient = Some(([low.r, low.g, low.b, low.a], [high.r, high.g, high.b, high.a]));
                 }
-    
-                self.app.unwrap().set_active_widget_tag1(tag1);
-            } else {
-                //更新文字、进度条类型
-                widget.tag2 = tag2.to_string();
-                self.app.unwrap().set_active_widget_tag2(tag2);
+                // 颜色还没输入完整时保持原样
+                _ => (),
             }
         }
     }
 
-    fn on_new_image_ready(&mut self) {
-        let image = self.temp_image.lock();
-        if image.is_err() {
-            return;
+    //最大值不大于最小值时按0~100处理
+    fn on_update_widget_value_range(&mut self) {
+        let app = self.app.unwrap();
+        let (min, max) = match (
+            app.get_active_widget_value_min().trim().parse::<f32>(),
+            app.get_active_widget_value_max().trim().parse::<f32>(),
+        ) {
+            (Ok(min), Ok(max)) if max > min => (min, max),
+            _ => (0., 100.),
+        };
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>())
+        {
+            widget.value_min = min;
+            widget.value_max = max;
         }
-        let image = image.unwrap().take();
-        if image.is_none() {
-            return;
+    }
+
+    fn on_update_widget_track_color(&mut self) {
+        let color_str = self.app.unwrap().get_active_widget_track_color_str().to_string();
+        let color = if color_str.trim().is_empty() {
+            None
+        } else {
+            match HexColor::from_str(color_str.trim()) {
+                Ok(c) => Some([c.r, c.g, c.b, c.a]),
+                // 颜色还没输入完整时保持原样
+                Err(_) => return,
+            }
+        };
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>())
+        {
+            widget.track_color = color;
         }
-        let tmp_img = image.unwrap();
-        let (w, h) = (tmp_img.width, tmp_img.height);
+    }
 
-        let (image, width, height) = match self
+    fn on_update_widget_bar_shape(&mut self) {
+        let app = self.app.unwrap();
+        if let Some(widget) = self
             .active_widget()
-            .and_then(|w| w.as_any_mut().downcast_mut::<ImageWidget>())
+            .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>())
         {
-            None => return,
-            Some(widget) => {
-                widget.position_mut().set_size(w as i32, h as i32);
-                widget.image_data = tmp_img;
-                (
-                    Image::from_rgba8(SharedPixelBuffer::clone_from_slice(
-                        &widget.image_data.frames[0],
-                        widget.image_data.width,
+            widget.bar_vertical = app.get_active_widget_bar_vertical();
+            widget.bar_rounded = app.get_active_widget_bar_rounded();
+            widget.bar_peak_hold = app.get_active_widget_bar_peak_hold();
+        }
+    }
+
+    fn on_update_widget_text_background(&mut self) {
+        let app = self.app.unwrap();
+        let color_str = app.get_active_widget_background_str().to_string();
+        let padding = app.get_active_widget_padding().parse::<i32>().unwrap_or(0);
+        let max_padding = self.screen.width.max(self.screen.height) as i32;
+        let background = if color_str.trim().is_empty() {
+            None
+        } else {
+            match HexColor::from_str(color_str.trim()) {
+                Ok(c) => Some([c.r, c.g, c.b, c.a]),
+                // 颜色还没输入完整时保持原样
+                Err(_) => return,
+            }
+        };
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>())
+        {
+            widget.background = background;
+            widget.padding = padding.clamp(0, max_padding);
+        }
+        app.set_active_widget_background(background_color(background));
+    }
+
+    fn on_update_widget_text_spacing(&mut self) {
+        let app = self.app.unwrap();
+        let letter_spacing = app.get_active_widget_letter_spacing().parse::<f32>();
+        let line_height = app.get_active_widget_line_height().parse::<f32>();
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>())
+        {
+            widget.letter_spacing = letter_spacing.unwrap_or(widget.letter_spacing);
+            widget.line_height = line_height.unwrap_or(widget.line_height).max(0.);
+        }
+    }
+
+    fn on_update_widget_placeholder(&mut self) {
+        let app = self.app.unwrap();
+        let placeholder = PLACEHOLDERS
+            .get(app.get_active_widget_placeholder() as usize)
+            .copied()
+            .flatten();
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>())
+        {
+            widget.placeholder = placeholder.map(|p| p.to_string());
+            widget.keep_last = app.get_active_widget_keep_last();
+        }
+    }
+
+    // 数值变化幅度，0或者无法解析时关闭
+    fn on_update_widget_pulse(&mut self) {
+        let app = self.app.unwrap();
+        let delta = app.get_active_widget_pulse_delta().trim().parse::<f32>().unwrap_or(0.).max(0.);
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>())
+        {
+            widget.pulse_delta = delta;
+        }
+    }
+
+    // 都没有填写时显示原始值
+    fn on_update_widget_value_format(&mut self) {
+        let app = self.app.unwrap();
+        let (multiplier, decimals) = (app.get_active_widget_format_multiplier(), app.get_active_widget_format_decimals());
+        let (prefix, suffix) = (app.get_active_widget_format_prefix(), app.get_active_widget_format_suffix());
+        let format = if [&multiplier, &decimals, &prefix, &suffix].iter().all(|s| s.is_empty()) {
+            None
+        } else {
+            Some(ValueFormat {
+                multiplier: multiplier.trim().parse::<f64>().unwrap_or(1.),
+                decimals: decimals.trim().parse::<u8>().ok().map(|d| d.min(10)),
+                prefix: prefix.to_string(),
+                suffix: suffix.to_string(),
+            })
+        };
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>())
+        {
+            widget.value_format = format;
+        }
+    }
+
+    fn on_update_widget_preview_value(&mut self) {
+        let app = self.app.unwrap();
+        if let Some(id) = self.active_id.clone() {
+            widgets::set_preview_value(&id, app.get_active_widget_preview_value().trim());
+        }
+    }
+
+    fn on_update_widget_text_scroll(&mut self) {
+        let app = self.app.unwrap();
+        let speed = app.get_active_widget_text_scroll_speed().parse::<f32>().unwrap_or(0.);
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>())
+        {
+            widget.scroll_speed = speed.max(0.);
+            app.set_active_widget_width(format!("{}", widget.position.width()).into());
+            app.set_active_widget_height(format!("{}", widget.position.height()).into());
+        }
+    }
+
+    fn on_update_widget_animation(&mut self) {
+        let app = self.app.unwrap();
+        let pause = app.get_active_widget_pause().trim().parse::<f32>().unwrap_or(0.);
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>())
+        {
+            widget.animation.direction = Direction::from_index(app.get_active_widget_scroll_direction());
+            widget.animation.easing = Easing::from_index(app.get_active_widget_easing());
+            widget.animation.pause = pause.max(0.);
+        }
+    }
+
+    fn on_update_widget_text_color(&mut self) {
+        let color_str = self.app.unwrap().get_active_widget_color_str().to_string();
+        let mut color = None;
+        if color_str.len() > 0 {
+            if let Ok(c) = HexColor::from_str(&color_str) {
+                color = Some([c.r, c.g, c.b, c.a]);
+            }
+        }
+
+        if let (Some(color), Some(widget)) = (color, self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>()))
+        {
+            widget.color = color;
+        }
+        if let (Some(color), Some(widget)) = (color, self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<CpuCoresWidget>()))
+        {
+            widget.color = color;
+        }
+    }
+
+    fn on_update_widget_image(&mut self) {
+        let temp_image_clone = self.temp_image.clone();
+        let (screen_width, screen_height) = (self.screen.width, self.screen.height);
+        let app_clone = self.app.clone();
+        std::thread::spawn(move || {
+            let file = match FileDialog::new()
+                .add_filter("图像", &["png", "bmp", "jpg", "jpeg", "gif"])
+                .pick_file()
+            {
+                None => return,
+                Some(path) => path,
+            };
+            let mut file_data = vec![];
+            let result = File::open(&file)
+                .and_then(|mut f| f.read_to_end(&mut file_data))
+                .map_err(anyhow::Error::from)
+                .and_then(|_| ImageData::load(&file_data, (screen_width, screen_height)));
+            let img = match result {
+                Ok(img) => img,
+                Err(err) => {
+                    error!("图片加载失败:{err:?}");
+                    toast(app_clone, &format!("图片加载失败:{err}"));
+                    return;
+                }
+            };
+            if let Ok(mut tmp) = temp_image_clone.lock() {
+                info!(
+                    "选择了图片，最终大小:{}x{} 帧数:{} 帧大小:{}",
+                    img.width,
+                    img.height,
+                    img.frames.len(),
+                    img.frames[0].len()
+                );
+                tmp.replace((img, file));
+                let _ = app_clone.upgrade_in_event_loop(move |app| {
+                    app.invoke_new_image_ready();
+                });
+            }
+        });
+    }
+
+    // 勾选背景时图像铺满屏幕，作为背景图层最先绘制
+    fn on_update_widget_image_background(&mut self) {
+        let is_background = self.app.unwrap().get_active_widget_image_background();
+        let uuid = match self.active_id.clone() {
+            None => return,
+            Some(v) => v,
+        };
+        if self.screen.set_background(&uuid, is_background) {
+            self.show_active_widget();
+        }
+    }
+
+    //把选中的组件对齐到屏幕边缘、居中或者铺满
+    fn on_align_widget(&mut self, action: SharedString) {
+        let (width, height) = (self.screen.width as i32, self.screen.height as i32);
+        let widget = match self.active_widget() {
+            None => return,
+            Some(v) => v,
+        };
+        let (old_x, old_y) = widget.position().center();
+        if action.as_str() == "fit" {
+            //只有图像可以拉伸
+            if let Some(widget) = widget.as_any_mut().downcast_mut::<ImageWidget>() {
+                widget.position = Rect::new(0, 0, width, height);
+                self.show_active_widget();
+            }
+            return;
+        }
+        let position = widget.position_mut();
+        match action.as_str() {
+            "left" => position.offset(-position.left, 0),
+            "right" => position.offset(width - position.right, 0),
+            "top" => position.offset(0, -position.top),
+            "bottom" => position.offset(0, height - position.bottom),
+            "center_h" => position.set_center(width / 2, old_y),
+            "center_v" => position.set_center(old_x, height / 2),
+            _ => return,
+        }
+        let (x, y) = position.center();
+        //组合中的其他组件跟随移动
+        self.offset_group(x - old_x, y - old_y);
+        self.show_active_widget();
+    }
+
+    // 勾选链接时选择图像文件，取消勾选时将已加载的图像嵌入
+    fn on_update_widget_image_link(&mut self) {
+        let link = self.app.unwrap().get_active_widget_image_link();
+        let mut pick_file = false;
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<ImageWidget>())
+        {
+            match (&widget.source, link) {
+                (ImageSource::Path(_), false) => widget.source = ImageSource::Embedded,
+                (ImageSource::Embedded, true) => pick_file = true,
+                _ => (),
+            }
+        }
+        if pick_file {
+            self.on_update_widget_image();
+        }
+    }
+
+    fn on_update_widget_tags(&mut self) {
+        let app = self.app.unwrap();
+        let tag1 = app.get_active_widget_tag1();
+        let tag2 = app.get_active_widget_tag2();
+
+        //系统概要 tag1为排列方式
+        if let Some(widget) = self.active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<SummaryWidget>())
+        {
+            widget.layout = SummaryLayout::from_index(tag1.parse().unwrap_or(0));
+            return;
+        }
+
+        //多核使用率 tag1为列数，tag2为间距
+        if let Some(widget) = self.active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<CpuCoresWidget>())
+        {
+            widget.columns = tag1.trim().parse().unwrap_or(0);
+            widget.gap = tag2.trim().parse().unwrap_or(widget.gap).max(0);
+            return;
+        }
+        
+        if let Some(widget) = self.active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<ImageWidget>())
+        {
+            if widget.is_webcam() || widget.is_desktop() {
+                //更新摄像头编号或截图区域，以及帧率
+                widget.tag1 = Some(tag1.trim().to_string());
+                widget.tag2 = Some(tag2.trim().to_string()).filter(|fps| fps.parse::<u32>().is_ok());
+                let _ = self.screen.setup_monitor();
+            }
+        }
+
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>())
+        {
+            widget.tag1 = tag1.to_string();
+
+            //更新天气
+            if widget.type_name == "weather" {
+                //查询城市名称，多个城市用逗号分隔，轮流显示，"自动"按公网IP定位
+                let cities: Vec<String> = split_cities(tag2.as_str())
+                    .into_iter()
+                    .filter_map(|name| {
+                        if name == nmc::AUTO_CITY {
+                            return Some(name.to_string());
+                        }
+                        CITIES.iter().find(|city| city.city.contains(name)).map(|city| city.city.clone())
+                    })
+                    .collect();
+
+                if cities.len() > 0 {
+                    widget.tag2 = cities.join(",");
+                    let tag2 = widget.tag2.clone();
+                    let _ = self.screen.setup_monitor();
+                    //刷新ui
+                    self.refresh_model_text();
+                    self.app.unwrap().set_active_widget_tag2(tag2.into());
+                }
+    
+                self.app.unwrap().set_active_widget_tag1(tag1);
+            } else {
+                //更新文字、进度条类型
+                widget.tag2 = tag2.to_string();
+                //更新订阅和检测的主机
+                if widget.type_name == "rss" || widget.type_name == "ping" || widget.type_name == "ticker"
+                    || widget.type_name == "phone_battery" || widget.type_name == "phone_status"
+                    || widget.type_name == "next_event_title" || widget.type_name == "next_event_time"
+                {
+                    let _ = self.screen.setup_monitor();
+                }
+                self.app.unwrap().set_active_widget_tag2(tag2);
+            }
+        }
+    }
+
+    fn on_new_image_ready(&mut self) {
+        let image = self.temp_image.lock();
+        if image.is_err() {
+            return;
+        }
+        let image = image.unwrap().take();
+        if image.is_none() {
+            return;
+        }
+        let (tmp_img, file) = image.unwrap();
+        let (w, h) = (tmp_img.width, tmp_img.height);
+        let link = self.app.unwrap().get_active_widget_image_link();
+
+        let (image, width, height) = match self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<ImageWidget>())
+        {
+            None => return,
+            Some(widget) => {
+                widget.position_mut().set_size(w as i32, h as i32);
+                widget.image_data = tmp_img;
+                widget.frame_index = 0;
+                widget.source = if link {
+                    ImageSource::Path(file.to_string_lossy().to_string())
+                } else {
+                    ImageSource::Embedded
+                };
+                (
+                    Image::from_rgba8(SharedPixelBuffer::clone_from_slice(
+                        &widget.image_data.frames[0],
+                        widget.image_data.width,
                         widget.image_data.height,
                     )),
                     widget.position.width(),
@@ -642,6 +1476,34 @@ impl CanvasEditorContext {
     fn show_active_widget(&mut self) {
         let app = self.app.unwrap();
 
+        app.set_can_group_widgets(self.selected_ids.len() > 1);
+        app.set_fan_count(monitor::fan_count() as i32);
+        if let Some(widget) = self.active_widget() {
+            app.set_active_widget_grouped(widget.group().is_some());
+            app.set_active_widget_locked(widget.locked());
+        }
+        self.copy_widget_geometry();
+        if let Some(widget) = self.active_widget() {
+            let opacity = widget
+                .as_any_mut()
+                .downcast_mut::<ImageWidget>()
+                .map(|w| w.opacity)
+                .unwrap_or(widget.opacity());
+            app.set_active_widget_opacity(opacity * 100.);
+            app.set_active_widget_min_refresh(format!("{}", widget.min_refresh_ms()).into());
+            app.set_active_widget_z_index(format!("{}", widget.z_index()).into());
+            app.set_active_widget_color_role(Role::index(widget.color_role()));
+        }
+        if let Some(widget) = self.active_widget() {
+            let condition = widget.show_when().cloned();
+            app.set_active_widget_show_when(Condition::index(condition.as_ref()));
+            app.set_active_widget_show_when_blink(condition.as_ref().map(|c| c.blink).unwrap_or(false));
+            if let Some(condition) = condition {
+                app.set_active_widget_show_when_type(condition.type_name.into());
+                app.set_active_widget_show_when_value(format!("{}", condition.value).into());
+            }
+        }
+
         if let Some(widget) = self
             .active_widget()
             .and_then(|w| w.as_any_mut().downcast_mut::<ImageWidget>())
@@ -649,6 +1511,22 @@ impl CanvasEditorContext {
             // info!("当前选中了图像:{}", widget.id);
             app.set_active_widget_type_name(widget.type_name.as_str().into());
             app.set_active_widget_rotation(format!("{}", widget.rotation as i32).into());
+            app.set_active_widget_image_fit(widget.fit.index());
+            app.set_active_widget_image_mask(Mask::index(widget.mask.as_ref()));
+            app.set_active_widget_image_link(widget.source != ImageSource::Embedded);
+            app.set_active_widget_image_background(widget.is_background);
+            Self::show_widget_tint(&app, widget.tint);
+            app.set_active_widget_lut_name(widget.lut.as_ref().map(|lut| lut.name.as_str()).unwrap_or("").into());
+            Self::show_widget_watermark(&app, widget.watermark.as_ref());
+            Self::show_widget_chroma_key(&app, widget.chroma_key);
+            app.set_active_widget_image_fade(format!("{}", widget.fade).into());
+            if widget.is_webcam() || widget.is_desktop() {
+                app.set_active_widget_tag1(widget.tag1.clone().unwrap_or_default().into());
+                app.set_active_widget_tag2(widget.tag2.clone().unwrap_or_default().into());
+            }
+            if let Some(Mask::RoundedRect(radius)) = widget.mask {
+                app.set_active_widget_image_mask_radius(format!("{radius}").into());
+            }
             app.set_active_widget_width(format!("{}", widget.position().width()).into());
             app.set_active_widget_height(format!("{}", widget.position().height()).into());
             app.set_active_widget_image(Image::from_rgba8(SharedPixelBuffer::clone_from_slice(
@@ -662,11 +1540,94 @@ impl CanvasEditorContext {
             return;
         }
 
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<SummaryWidget>())
+        {
+            app.set_active_widget_type_name(SummaryWidget::TYPE_NAME.into());
+            app.set_active_widget_tag1(format!("{}", widget.layout.index()).into());
+            app.set_active_widget_width(format!("{}", widget.position.width()).into());
+            app.set_active_widget_height(format!("{}", widget.position.height()).into());
+            app.set_active_widget_uuid(SharedString::from(widget.id.as_str()));
+            app.set_active_widget_x(format!("{}", widget.position.center().0).into());
+            app.set_active_widget_y(format!("{}", widget.position.center().1).into());
+            return;
+        }
+
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<CpuCoresWidget>())
+        {
+            let [r, g, b, a] = widget.color;
+            app.set_active_widget_type_name(CpuCoresWidget::TYPE_NAME.into());
+            app.set_active_widget_tag1(format!("{}", widget.columns).into());
+            app.set_active_widget_tag2(format!("{}", widget.gap).into());
+            app.set_active_widget_color(Color::from_argb_u8(a, r, g, b));
+            app.set_active_widget_color_str(HexColor::rgba(r, g, b, a).display_rgba().to_string().into());
+            app.set_active_widget_width(format!("{}", widget.position.width()).into());
+            app.set_active_widget_height(format!("{}", widget.position.height()).into());
+            app.set_active_widget_uuid(SharedString::from(widget.id.as_str()));
+            app.set_active_widget_x(format!("{}", widget.position.center().0).into());
+            app.set_active_widget_y(format!("{}", widget.position.center().1).into());
+            return;
+        }
+
         if let Some(widget) = self
             .active_widget()
             .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>())
         {
             app.set_active_widget_type_name(widget.type_name.as_str().into());
+            app.set_active_widget_rotation(format!("{}", widget.rotation as i32).into());
+            app.set_active_widget_text_auto_size(widget.auto_size);
+            app.set_active_widget_text_scroll_speed(format!("{}", widget.scroll_speed).into());
+            app.set_active_widget_scroll_direction(widget.animation.direction.index());
+            app.set_active_widget_easing(widget.animation.easing.index());
+            app.set_active_widget_pause(format!("{}", widget.animation.pause).into());
+            app.set_active_widget_bar_gradient(widget.bar_gradient.is_some());
+            app.set_active_widget_background_str(
+                widget
+                    .background
+                    .map(|c| HexColor::rgba(c[0], c[1], c[2], c[3]).display_rgba().to_string())
+                    .unwrap_or_default()
+                    .into(),
+            );
+            app.set_active_widget_background(background_color(widget.background));
+            app.set_active_widget_padding(format!("{}", widget.padding).into());
+            app.set_active_widget_letter_spacing(format!("{}", widget.letter_spacing).into());
+            app.set_active_widget_line_height(format!("{}", widget.line_height).into());
+            app.set_active_widget_placeholder(
+                PLACEHOLDERS
+                    .iter()
+                    .position(|p| *p == widget.placeholder.as_deref())
+                    .unwrap_or(0) as i32,
+            );
+            app.set_active_widget_keep_last(widget.keep_last);
+            app.set_active_widget_pulse_delta(format!("{}", widget.pulse_delta).into());
+            let format = widget.value_format.as_ref();
+            app.set_active_widget_format_multiplier(format.map(|f| f.multiplier.to_string()).unwrap_or_default().into());
+            app.set_active_widget_format_decimals(format.and_then(|f| f.decimals).map(|d| d.to_string()).unwrap_or_default().into());
+            app.set_active_widget_format_prefix(format.map(|f| f.prefix.clone()).unwrap_or_default().into());
+            app.set_active_widget_format_suffix(format.map(|f| f.suffix.clone()).unwrap_or_default().into());
+            app.set_active_widget_preview_value(widgets::preview_value_of(&widget.id).unwrap_or_default().into());
+            Self::show_widget_gpu(&app, widget.gpu.as_deref());
+            app.set_active_widget_bar_vertical(widget.bar_vertical);
+            app.set_active_widget_bar_rounded(widget.bar_rounded);
+            app.set_active_widget_bar_peak_hold(widget.bar_peak_hold);
+            app.set_active_widget_value_min(format!("{}", widget.value_min).into());
+            app.set_active_widget_value_max(format!("{}", widget.value_max).into());
+            app.set_active_widget_track_color_str(
+                widget
+                    .track_color
+                    .map(|c| HexColor::rgba(c[0], c[1], c[2], c[3]).display_rgba().to_string())
+                    .unwrap_or_default()
+                    .into(),
+            );
+            if let Some((low, high)) = widget.bar_gradient {
+                app.set_active_widget_bar_low_str(HexColor::rgba(low[0], low[1], low[2], low[3]).display_rgba().to_string().into());
+                app.set_active_widget_bar_high_str(HexColor::rgba(high[0], high[1], high[2], high[3]).display_rgba().to_string().into());
+            }
+            app.set_active_widget_width(format!("{}", widget.position().width()).into());
+            app.set_active_widget_height(format!("{}", widget.position().height()).into());
             // info!("当前选中了文本:{}", widget.id);
             app.set_active_widget_uuid(SharedString::from(widget.id()));
             app.set_active_widget_x(format!("{}", widget.position().left).into());
@@ -693,7 +1654,7 @@ impl CanvasEditorContext {
                 .widgets
                 .iter()
                 .filter_map(|v| {
-                    if v.position().contain(x, y) {
+                    if self.can_pick(v.as_ref(), x, y) {
                         Some(v.id().to_string())
                     } else {
                         None
@@ -733,7 +1694,7 @@ impl CanvasEditorContext {
         } else {
             //如果没有选中的，那么按顺序选择第一个
             for w in &self.screen.widgets {
-                if w.position().contain(x, y) {
+                if self.can_pick(w.as_ref(), x, y) {
                     self.active_id = Some(w.id().to_string());
                     break;
                 }
@@ -743,6 +1704,281 @@ impl CanvasEditorContext {
         self.show_active_widget();
     }
 
+    // 背景锁定时点击不会选中背景图片
+    fn can_pick(&self, widget: &dyn Widget, x: i32, y: i32) -> bool {
+        if widget.is_background() && self.app.unwrap().get_background_locked() {
+            return false;
+        }
+        widget.contains_point(x, y)
+    }
+
+    // 当前组件所在组合的所有组件，不在组合中时返回空
+    fn group_members(&self, uuid: &str) -> Vec<String> {
+        let group = match self
+            .screen
+            .widgets
+            .iter()
+            .find(|w| w.id() == uuid)
+            .and_then(|w| w.group())
+        {
+            None => return vec![],
+            Some(g) => g.to_string(),
+        };
+        self.screen
+            .widgets
+            .iter()
+            .filter(|w| w.group() == Some(group.as_str()))
+            .map(|w| w.id().to_string())
+            .collect()
+    }
+
+    // 点击组合中的组件时选中整个组合
+    fn select_group(&mut self) {
+        if let Some(active_id) = self.active_id.clone() {
+            let members = self.group_members(&active_id);
+            if members.len() > 1 {
+                self.selected_ids = members;
+                self.show_active_widget();
+            }
+        }
+    }
+
+    // 移动当前组件所在组合中的其他组件
+    fn offset_group(&mut self, dx: i32, dy: i32) {
+        let active_id = match self.active_id.clone() {
+            None => return,
+            Some(id) => id,
+        };
+        if dx == 0 && dy == 0 {
+            return;
+        }
+        let members = self.group_members(&active_id);
+        for w in self.screen.widgets.iter_mut() {
+            if w.id() != active_id && members.iter().any(|id| id == w.id()) {
+                w.position_mut().offset(dx, dy);
+            }
+        }
+    }
+
+    // 以组合的左上角为基准整体缩放，返回false表示当前组件不在组合中
+    fn scale_group(&mut self, scale: f32) -> bool {
+        let active_id = match self.active_id.clone() {
+            None => return false,
+            Some(id) => id,
+        };
+        let members = self.group_members(&active_id);
+        if members.len() <= 1 {
+            return false;
+        }
+        let in_group = |w: &Box<dyn Widget>| members.iter().any(|id| id == w.id());
+        let left = self.screen.widgets.iter().filter(|w| in_group(w)).map(|w| w.position().left).min().unwrap_or(0);
+        let top = self.screen.widgets.iter().filter(|w| in_group(w)).map(|w| w.position().top).min().unwrap_or(0);
+        for w in self.screen.widgets.iter_mut().filter(|w| in_group(w)) {
+            let pos = w.position().clone();
+            let new_left = left + ((pos.left - left) as f32 * scale).round() as i32;
+            let new_top = top + ((pos.top - top) as f32 * scale).round() as i32;
+            let new_width = ((pos.width() as f32 * scale).round() as i32).max(1);
+            let new_height = ((pos.height() as f32 * scale).round() as i32).max(1);
+            if let Some(widget) = w.as_any_mut().downcast_mut::<TextWidget>() {
+                widget.font_size = snap((widget.font_size * scale).max(MIN_FONT_SIZE));
+                //进度条和圆环的宽度保存在tag2中
+                if widget.is_bar() || widget.is_ring() {
+                    if let Ok(width) = widget.tag2.parse::<f32>() {
+                        widget.tag2 = format!("{}", (width * scale) as i32);
+                    }
+                }
+            }
+            *w.position_mut() = Rect::new(new_left, new_top, new_left + new_width, new_top + new_height);
+        }
+        true
+    }
+
+    fn on_group_widgets(&mut self) {
+        let ids = self.selection();
+        if ids.len() < 2 {
+            return;
+        }
+        let group = Uuid::new_v4().to_string();
+        for w in self.screen.widgets.iter_mut() {
+            if ids.iter().any(|id| id == w.id()) {
+                w.set_group(Some(group.clone()));
+            }
+        }
+        self.show_active_widget();
+    }
+
+    fn on_ungroup_widgets(&mut self) {
+        let active_id = match self.active_id.clone() {
+            None => return,
+            Some(id) => id,
+        };
+        let members = self.group_members(&active_id);
+        for w in self.screen.widgets.iter_mut() {
+            if members.iter().any(|id| id == w.id()) {
+                w.set_group(None);
+            }
+        }
+        self.show_active_widget();
+    }
+
+    //按住Ctrl点击，添加或者取消选中一个组件
+    fn toggle_select_widget(&mut self, x: i32, y: i32) {
+        let clicked_uuid = match self
+            .screen
+            .widgets
+            .iter()
+            .rev()
+            .find(|w| self.can_pick(w.as_ref(), x, y))
+        {
+            None => return,
+            Some(w) => w.id().to_string(),
+        };
+        if let Some(active_id) = self.active_id.clone() {
+            if !self.selected_ids.contains(&active_id) {
+                self.selected_ids.push(active_id);
+            }
+        }
+        if let Some(pos) = self.selected_ids.iter().position(|id| *id == clicked_uuid) {
+            self.selected_ids.remove(pos);
+            self.active_id = self.selected_ids.last().cloned();
+        } else {
+            self.selected_ids.push(clicked_uuid.clone());
+            self.active_id = Some(clicked_uuid);
+        }
+        if self.active_id.is_none() {
+            let app = self.app.unwrap();
+            app.set_active_widget_type_name(SharedString::from(""));
+            app.set_active_widget_uuid(SharedString::from(""));
+            return;
+        }
+        self.show_active_widget();
+    }
+
+    //当前选中的组件，没有多选时为当前组件
+    fn selection(&self) -> Vec<String> {
+        if self.selected_ids.len() > 0 {
+            self.selected_ids.clone()
+        } else {
+            self.active_id.iter().cloned().collect()
+        }
+    }
+
+    fn on_save_component(&mut self) {
+        let ids = self.selection();
+        let app_clone = self.app.clone();
+        match self.screen.to_component(&ids) {
+            Ok(file_data) => {
+                std::thread::spawn(move || {
+                    let dlg = rfd::FileDialog::new()
+                        .add_filter("component", &["component"])
+                        .set_file_name("widgets.component");
+                    if let Some(file) = dlg.save_file() {
+                        match std::fs::write(file, &file_data) {
+                            Ok(()) => toast(app_clone, "保存成功"),
+                            Err(err) => toast(app_clone, &format!("{:?}", err)),
+                        }
+                    }
+                });
+            }
+            Err(err) => {
+                error!("{:?}", err);
+                toast(app_clone, &format!("{:?}", err));
+            }
+        }
+    }
+
+    fn on_insert_component(&mut self) {
+        let dlg = rfd::FileDialog::new().add_filter("component", &["component"]);
+        if let Some(file) = dlg.pick_file() {
+            match std::fs::read(file) {
+                Ok(data) => {
+                    self.pending_component = Some(data);
+                    toast(self.app.clone(), "点击画布插入组合");
+                }
+                Err(err) => toast(self.app.clone(), &format!("{:?}", err)),
+            }
+        }
+    }
+
+    fn on_select_preset(&mut self, index: i32) {
+        if let Some(preset) = screen::METRIC_PRESETS.get(index.max(0) as usize) {
+            self.pending_preset = Some(preset.name);
+            toast(self.app.clone(), &format!("点击画布添加预设:{}", preset.label));
+        }
+    }
+
+    fn on_generate_layout(&mut self, index: i32) {
+        let preset = match LayoutPreset::from_index(index) {
+            Some(preset) => preset,
+            None => return,
+        };
+        let ids = self.screen.generate_default_layout(preset);
+        info!("生成了布局:{:?}", preset);
+        //生成的组件全部选中，不需要时可以直接删除
+        self.on_widgets_inserted(ids);
+    }
+
+    fn insert_component(&mut self, component: &[u8], x: i32, y: i32) {
+        let ids = match self.screen.add_component(component, x, y) {
+            Ok(ids) => ids,
+            Err(err) => {
+                error!("{:?}", err);
+                toast(self.app.clone(), &format!("{:?}", err));
+                return;
+            }
+        };
+        info!("插入了组合:{}个组件", ids.len());
+        self.on_widgets_inserted(ids);
+    }
+
+    fn insert_preset(&mut self, name: &str, x: i32, y: i32) {
+        let ids = match self.screen.apply_preset(name, (x, y)) {
+            Ok(ids) => ids,
+            Err(err) => {
+                error!("{:?}", err);
+                toast(self.app.clone(), &format!("{:?}", err));
+                return;
+            }
+        };
+        info!("添加了预设{name}:{}个组件", ids.len());
+        self.on_widgets_inserted(ids);
+    }
+
+    // 把新插入的组件加入列表并全部选中
+    fn on_widgets_inserted(&mut self, ids: Vec<String>) {
+        for uuid in &ids {
+            if let Some((idx, w)) = self.screen.find_widget(uuid) {
+                let mut text = "".to_string();
+                let mut prefix = "".to_string();
+                let mut tag1 = "".to_string();
+                let mut tag2 = "".to_string();
+                if let Some(widget) = w.as_any_mut().downcast_mut::<TextWidget>() {
+                    text = widget.text.to_string();
+                    prefix = widget.prefix.to_string();
+                    tag1 = widget.tag1.to_string();
+                    tag2 = widget.tag2.to_string();
+                }
+                self.list_model.push(WidgetObject {
+                    index: idx as i32,
+                    name: SharedString::from(w.get_label()),
+                    type_name: SharedString::from(w.type_name()),
+                    uuid: SharedString::from(w.id()),
+                    text: SharedString::from(&text),
+                    prefix: SharedString::from(&prefix),
+                    tag1: SharedString::from(&tag1),
+                    tag2: SharedString::from(&tag2),
+                    visible: w.visible(),
+                });
+            }
+        }
+        let ret = self.screen.setup_monitor();
+        info!("更新监视器:{:?}", ret);
+        self.refresh_model_text();
+        self.active_id = ids.last().cloned();
+        self.selected_ids = ids;
+        self.show_active_widget();
+    }
+
     fn update_widget_edit_text(&mut self) {
         let app = self.app.unwrap();
         let widget = match self
@@ -752,11 +1988,16 @@ impl CanvasEditorContext {
             None => return,
             Some(v) => v,
         };
-        app.set_active_widget_text(SharedString::from(&widget.text));
+        app.set_active_widget_text(widget.text.replace('\n', "\\n").into());
         app.set_active_widget_tag1(SharedString::from(&widget.tag1));
+        //下拉框显示当前的tag1，统计值的tag1为0、2、3、4
+        let display_index = widget.tag1.parse::<i32>().unwrap_or(0).clamp(0, 6);
+        app.set_active_widget_display_index(display_index);
+        app.set_active_widget_stat_index(if (2..=4).contains(&display_index) { display_index - 1 } else { 0 });
         app.set_active_widget_tag2(SharedString::from(&widget.tag2));
         app.set_active_widget_font_size(format!("{}", widget.font_size as i32).into());
         app.set_active_widget_prefix(SharedString::from(&widget.prefix));
+        Self::show_widget_tint(&app, widget.tint);
         app.set_active_widget_color(Color::from_argb_u8(
             widget.color[3],
             widget.color[0],
@@ -779,7 +2020,7 @@ impl CanvasEditorContext {
     fn add_widget(&mut self, x: i32, y: i32) {
         let app = self.app.unwrap();
         let widget_type_name = app.get_widget_type_name();
-        let widget_type_label = if widget_type_name.as_str() == "weather" {
+        let widget_type_label = if widget_type_name.as_str() == "weather" || widget_type_name.as_str() == "rss" {
             SharedString::new()
         } else {
             app.get_widget_type_label()
@@ -813,6 +2054,7 @@ impl CanvasEditorContext {
                 prefix: SharedString::from(&prefix),
                 tag1: SharedString::from(""),
                 tag2: SharedString::from(""),
+                visible: w.visible(),
             };
             info!("添加了一个:{:?}", model);
 
@@ -867,6 +2109,7 @@ impl CanvasEditorContext {
                 prefix: SharedString::from(&prefix),
                 tag1,
                 tag2,
+                visible: w.visible(),
             };
 
             self.list_model.set_row_data(idx, model);
@@ -892,7 +2135,7 @@ impl CanvasEditorContext {
             return;
         }
         //下一个索引
-        self.screen.widgets.swap(widget_index, widget_index + 1);
+        self.swap_widgets(widget_index, widget_index + 1);
         self.refresh_model_text();
     }
 
@@ -915,10 +2158,146 @@ impl CanvasEditorContext {
             return;
         }
         //下一个索引
-        self.screen.widgets.swap(widget_index - 1, widget_index);
+        self.swap_widgets(widget_index - 1, widget_index);
         self.refresh_model_text();
     }
 
+    // 交换相邻组件的位置和层级，数组保持按层级排序
+    fn swap_widgets(&mut self, a: usize, b: usize) {
+        let (z_a, z_b) = (self.screen.widgets[a].z_index(), self.screen.widgets[b].z_index());
+        self.screen.widgets[a].set_z_index(z_b);
+        self.screen.widgets[b].set_z_index(z_a);
+        self.screen.widgets.swap(a, b);
+        if let Some(widget) = self.active_widget() {
+            let z_index = widget.z_index();
+            self.app.unwrap().set_active_widget_z_index(format!("{z_index}").into());
+        }
+    }
+
+    fn toggle_widget_visible(&mut self, uuid: &str) {
+        let (idx, w) = match self.screen.find_widget(uuid) {
+            None => return,
+            Some(v) => v,
+        };
+        let visible = !w.visible();
+        w.set_visible(visible);
+        if let Some((idx, mut model)) = self.find_widget_model(uuid) {
+            model.visible = visible;
+            self.list_model.set_row_data(idx, model);
+        }
+        info!("组件{idx} visible={visible}");
+    }
+
+    fn on_toggle_widget_locked(&mut self) {
+        let locked = self.app.unwrap().get_active_widget_locked();
+        if let Some(widget) = self.active_widget() {
+            widget.set_locked(locked);
+        }
+    }
+
+    // 位置和大小的文本格式: 左,上,宽,高,旋转角度
+    fn copy_widget_geometry(&mut self) {
+        let geometry = match self.active_widget() {
+            None => return,
+            Some(widget) => {
+                let rotation = if let Some(w) = widget.as_any_mut().downcast_mut::<TextWidget>() {
+                    w.rotation
+                } else if let Some(w) = widget.as_any_mut().downcast_mut::<ImageWidget>() {
+                    w.rotation
+                } else {
+                    0.
+                };
+                let pos = widget.position();
+                format!("{},{},{},{},{}", pos.left, pos.top, pos.width(), pos.height(), rotation)
+            }
+        };
+        self.app.unwrap().set_active_widget_geometry(geometry.into());
+    }
+
+    fn paste_widget_geometry(&mut self, geometry: SharedString) {
+        let values: Vec<f32> = geometry
+            .split(',')
+            .filter_map(|v| v.trim().parse().ok())
+            .collect();
+        if values.len() < 4 {
+            toast(self.app.clone(), &format!("格式错误:{geometry}"));
+            return;
+        }
+        let (left, top, width, height) = (values[0] as i32, values[1] as i32, values[2] as i32, values[3] as i32);
+        let rotation = values.get(4).cloned();
+        let group_offset = match self.active_widget() {
+            None => return,
+            Some(widget) => {
+                let (old_x, old_y) = widget.position().center();
+                if let Some(w) = widget.as_any_mut().downcast_mut::<TextWidget>() {
+                    // 自动字号和滚动时才使用区域大小
+                    if w.auto_size || w.scroll_speed > 0. {
+                        w.position = Rect::new(left, top, left + width.max(2), top + height.max(2));
+                    } else {
+                        w.position.set_position(left, top);
+                    }
+                    w.rotation = rotation.unwrap_or(w.rotation);
+                } else if let Some(w) = widget.as_any_mut().downcast_mut::<ImageWidget>() {
+                    w.position = Rect::new(left, top, left + width.max(2), top + height.max(2));
+                    w.rotation = rotation.unwrap_or(w.rotation);
+                }
+                let (x, y) = widget.position().center();
+                (x - old_x, y - old_y)
+            }
+        };
+        self.offset_group(group_offset.0, group_offset.1);
+        self.show_active_widget();
+    }
+
+    fn on_update_widget_opacity(&mut self) {
+        let opacity = self.app.unwrap().get_active_widget_opacity() / 100.;
+        if let Some(widget) = self.active_widget() {
+            widget.set_opacity(opacity);
+        }
+    }
+
+    fn on_update_widget_min_refresh(&mut self) {
+        let ms = self.app.unwrap().get_active_widget_min_refresh().parse::<u32>().unwrap_or(0);
+        if let Some(widget) = self.active_widget() {
+            widget.set_min_refresh_ms(ms);
+        }
+    }
+
+    //修改层级后重新排序，列表和绘制顺序保持一致
+    fn on_update_widget_z_index(&mut self) {
+        let z_index = match self.app.unwrap().get_active_widget_z_index().trim().parse::<i32>() {
+            Ok(z_index) => z_index,
+            Err(_) => return,
+        };
+        if let Some(widget) = self.active_widget() {
+            widget.set_z_index(z_index);
+        }
+        self.screen.sort_by_z_index();
+        self.refresh_model_text();
+    }
+
+    fn on_update_widget_color_role(&mut self) {
+        let role = Role::from_index(self.app.unwrap().get_active_widget_color_role());
+        if let Some(widget) = self.active_widget() {
+            widget.set_color_role(role);
+        }
+    }
+
+    fn on_update_widget_show_when(&mut self) {
+        let app = self.app.unwrap();
+        let condition = Condition::from_index(
+            app.get_active_widget_show_when(),
+            app.get_active_widget_show_when_type().as_str(),
+            app.get_active_widget_show_when_value().as_str(),
+            app.get_active_widget_show_when_blink(),
+        );
+        if let Some(widget) = self.active_widget() {
+            widget.set_show_when(condition);
+        }
+        //开启条件中用到的监听
+        let _ = self.screen.setup_monitor();
+    }
+
     fn delete_widget(&mut self, uuid: &str) {
         let widget_index = match self
             .screen
@@ -932,6 +2311,7 @@ impl CanvasEditorContext {
 
         self.screen.widgets.remove(widget_index);
         self.list_model.remove(widget_index);
+        self.selected_ids.retain(|id| id != uuid);
         self.refresh_model_text();
         if let Some(active_uuid) = self.active_id.as_ref() {
             if active_uuid == uuid {
@@ -957,7 +2337,7 @@ impl CanvasEditorContext {
         let app = self.app.unwrap();
 
         let widget_type_name:SharedString = self.screen.widgets[widget_index].type_name().into();
-        let widget_type_label = if widget_type_name.as_str() == "weather" {
+        let widget_type_label = if widget_type_name.as_str() == "weather" || widget_type_name.as_str() == "rss" {
             SharedString::new()
         } else {
             app.get_widget_type_label()
@@ -990,6 +2370,7 @@ impl CanvasEditorContext {
 
             if let Some(text_widget) = w.as_any_mut().downcast_mut::<TextWidget>() {
                 *text_widget = text_widget_clone.unwrap();
+                text_widget.group = None;
                 text = text_widget.text.to_string();
                 prefix = text_widget.prefix.to_string();
                 tag1 = text_widget.tag1.to_string();
@@ -999,6 +2380,7 @@ impl CanvasEditorContext {
             if let Some(image_widget) = w.as_any_mut().downcast_mut::<ImageWidget>() {
                 *image_widget = image_widget_clone.unwrap();
                 image_widget.id = uuid.clone();
+                image_widget.group = None;
             }
 
             w.position_mut().offset(5, 5);
@@ -1012,6 +2394,7 @@ impl CanvasEditorContext {
                 prefix: SharedString::from(&prefix),
                 tag1: SharedString::from(&tag1),
                 tag2: SharedString::from(&tag2),
+                visible: w.visible(),
             };
             info!("添加了一个:{:?}", model);
 
@@ -1025,19 +2408,108 @@ impl CanvasEditorContext {
         }
     }
 
+    fn copy_widget_style(&mut self) {
+        let clipboard = match self.active_widget() {
+            None => return,
+            Some(w) => {
+                if let Some(widget) = w.as_any_mut().downcast_mut::<TextWidget>() {
+                    StyleClipboard::Text(widget.clone())
+                } else if let Some(widget) = w.as_any_mut().downcast_mut::<ImageWidget>() {
+                    // 不需要复制图片数据
+                    let image_data = std::mem::take(&mut widget.image_data);
+                    let style = widget.clone();
+                    widget.image_data = image_data;
+                    StyleClipboard::Image(style)
+                } else {
+                    return;
+                }
+            }
+        };
+        self.style_clipboard = Some(clipboard);
+        self.app.unwrap().set_style_copied(true);
+    }
+
+    // 粘贴样式，不修改位置和文字
+    fn paste_widget_style(&mut self) {
+        let clipboard = match self.style_clipboard.take() {
+            None => return,
+            Some(c) => c,
+        };
+        if let Some(w) = self.active_widget() {
+            match &clipboard {
+                StyleClipboard::Text(src) => {
+                    if let Some(widget) = w.as_any_mut().downcast_mut::<TextWidget>() {
+                        widget.color = src.color;
+                        widget.color_role = src.color_role;
+                        widget.font_size = src.font_size;
+                        widget.auto_size = src.auto_size;
+                        widget.scroll_speed = src.scroll_speed;
+                        widget.animation = src.animation;
+                        widget.bar_gradient = src.bar_gradient;
+                        widget.track_color = src.track_color;
+                        widget.bar_vertical = src.bar_vertical;
+                        widget.bar_rounded = src.bar_rounded;
+                        widget.bar_peak_hold = src.bar_peak_hold;
+                        widget.value_min = src.value_min;
+                        widget.value_max = src.value_max;
+                        widget.background = src.background;
+                        widget.padding = src.padding;
+                        widget.opacity = src.opacity;
+                        widget.letter_spacing = src.letter_spacing;
+                        widget.line_height = src.line_height;
+                        widget.placeholder = src.placeholder.clone();
+                        widget.keep_last = src.keep_last;
+                        widget.pulse_delta = src.pulse_delta;
+                        // 自动字号和滚动依赖区域大小
+                        if src.auto_size || src.scroll_speed > 0. {
+                            let (left, top) = (widget.position.left, widget.position.top);
+                            widget.position = Rect::new(left, top, left + src.position.width(), top + src.position.height());
+                        }
+                        // 不同类型的组件tag含义不同
+                        if widget.type_name == src.type_name {
+                            widget.tag1 = src.tag1.clone();
+                            widget.tag2 = src.tag2.clone();
+                        }
+                    }
+                }
+                StyleClipboard::Image(src) => {
+                    if let Some(widget) = w.as_any_mut().downcast_mut::<ImageWidget>() {
+                        widget.rotation = src.rotation;
+                        widget.fit = src.fit;
+                        widget.mask = src.mask;
+                        widget.opacity = src.opacity;
+                        // 只有纯色组件才复制颜色
+                        if widget.color.is_some() && src.color.is_some() {
+                            widget.color = src.color;
+                        }
+                    }
+                }
+            }
+        }
+        self.style_clipboard = Some(clipboard);
+        let ret = self.screen.setup_monitor();
+        info!("更新监视器:{:?}", ret);
+        self.show_active_widget();
+    }
+
     // 鼠标缩放
     fn on_screen_mouse_scroll(&mut self, _dx: f32, dy: f32) {
         let app = self.app.unwrap();
 
-        //往下滑动dy>0否则dy<0
+        //往下滑动dy>0否则dy<0，组合整体缩放
+        if self.scale_group(if dy > 0. { 1.05 } else { 0.95 }) {
+            return;
+        }
+        //像素模式下按整数调整字号
+        let step = if self.screen.pixel_perfect { 1. } else { 0.5 };
         if let Some(widget) = self
             .active_widget()
             .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>())
         {
             if dy > 0. {
-                widget.font_size += 0.5;
+                widget.font_size += step;
             } else {
-                widget.font_size -= 0.5;
+                widget.font_size -= step;
             }
             app.set_active_widget_font_size(format!("{}", widget.font_size as i32).into());
         }
@@ -1048,7 +2520,13 @@ impl CanvasEditorContext {
             if widget.position.width() <= 1 || widget.position.height() <= 1 {
                 return;
             }
-            if dy > 0. {
+            if app.get_lock_aspect_ratio() {
+                let width = widget.position.width() + if dy > 0. { 4 } else { -4 };
+                if width > 4 {
+                    let (width, height) = widget.fit_aspect(width, 0, true);
+                    widget.position.set_size(width, height);
+                }
+            } else if dy > 0. {
                 widget.position.inflate(2, 2);
             } else {
                 if widget.position.width() > 4 && widget.position.height() > 4 {
@@ -1064,28 +2542,71 @@ impl CanvasEditorContext {
     fn on_screen_key_event(&mut self, event: KeyEvent) {
         let app = self.app.unwrap();
 
+        //Ctrl+S 直接保存
+        if (event.modifiers.control || event.modifiers.meta) && event.text.eq_ignore_ascii_case("s") {
+            self.on_save_screen(false);
+            return;
+        }
+
+        //图像: Ctrl+方向键调整宽高，+/-同时调整宽高，按住Shift每次10像素
+        if let Some(widget) = self
+            .active_widget()
+            .filter(|w| !w.locked())
+            .and_then(|w| w.as_any_mut().downcast_mut::<ImageWidget>())
+        {
+            let char = event.text.chars().next().unwrap_or(' ');
+            let step = if event.modifiers.shift { 10 } else { 1 };
+            let size_delta = match char {
+                '\u{f700}' if event.modifiers.control => Some((0, -step)),
+                '\u{f701}' if event.modifiers.control => Some((0, step)),
+                '\u{f702}' if event.modifiers.control => Some((-step, 0)),
+                '\u{f703}' if event.modifiers.control => Some((step, 0)),
+                '+' | '=' => Some((step, step)),
+                '-' | '_' => Some((-step, -step)),
+                _ => None,
+            };
+            if let Some((dw, dh)) = size_delta {
+                if app.get_lock_aspect_ratio() {
+                    let (width, height) = (widget.position.width(), widget.position.height());
+                    let (new_width, new_height) = widget.fit_aspect(width + dw, height + dh, dw != 0);
+                    widget.position.resize(new_width - width, new_height - height);
+                } else {
+                    widget.position.resize(dw, dh);
+                }
+                app.set_active_widget_width(format!("{}", widget.position.width()).into());
+                app.set_active_widget_height(format!("{}", widget.position.height()).into());
+                return;
+            }
+        }
+
         let mut delete_uuid = String::new();
+        let mut group_offset = (0, 0);
         if let Some(widget) = self.active_widget() {
+            let (old_x, old_y) = widget.position().center();
             let char = event.text.chars().next().unwrap_or(' ');
             if char == '\u{7f}' {
                 delete_uuid = widget.id().to_string();
             }
+            //锁定位置的组件不能用方向键移动
+            let step = if widget.locked() { 0 } else { 1 };
             if char == 'w' || char == '\u{f700}' {
-                widget.position_mut().offset(0, -1);
+                widget.position_mut().offset(0, -step);
             }
             if char == 's' || char == '\u{f701}' {
-                widget.position_mut().offset(0, 1);
+                widget.position_mut().offset(0, step);
             }
             if char == 'a' || char == '\u{f702}' {
-                widget.position_mut().offset(-1, 0);
+                widget.position_mut().offset(-step, 0);
             }
             if char == 'd' || char == '\u{f703}' {
-                widget.position_mut().offset(1, 0);
+                widget.position_mut().offset(step, 0);
             }
             let (x, y) = widget.position().center();
             app.set_active_widget_x(format!("{x}").into());
             app.set_active_widget_y(format!("{y}").into());
+            group_offset = (x - old_x, y - old_y);
         }
+        self.offset_group(group_offset.0, group_offset.1);
 
         if delete_uuid.len() > 0 {
             self.delete_widget(&delete_uuid);
@@ -1126,8 +2647,12 @@ impl CanvasEditorContext {
     }
 
     fn on_change_screen(&mut self, index: i32) {
-        let screen = &self.screens[index as usize];
-        
+        let screen = self.screens[index as usize].clone();
+        self.resize_layout(&screen);
+    }
+
+    // 修改布局大小，所有页面的组件按比例缩放
+    fn resize_layout(&mut self, screen: &ScreenSize) {
         let width_scale = screen.width as f32 / self.screen.width as f32;
         let height_scale = screen.height as f32 / self.screen.height as f32;
 
@@ -1143,46 +2668,53 @@ impl CanvasEditorContext {
             self.screen.canvas.font().clone(),
         );
 
-        //修改元素大小
-        for idx in 0..self.screen.widgets.len() {
-            if self.screen.widgets[idx].is_text() {
-                if let Some(widget) = self.screen.widgets[idx]
-                    .as_any_mut()
-                    .downcast_mut::<TextWidget>()
-                {
-                    //重新设置进度条设置宽度
-                    if widget.type_name != "weather" && widget.type_name != "uptime" && widget.tag1 == "1"{
-                        let tag2 = widget.tag2.clone();
-                        let width = tag2.parse::<f32>().unwrap_or(widget.font_size * 5.);
-                        widget.tag2 = format!("{}", (width_scale * width) as i32);
-                        let new_left = widget.position().left as f32 * width_scale;
-                        let new_top = widget.position().top as f32 * height_scale;
-                        widget.position_mut().set_position(new_left as i32, new_top as i32);
-                        widget.font_size = height_scale * widget.font_size as f32;
-                    }else{
+        //修改所有页面的元素大小
+        let current_page = self.screen.page_index();
+        for page in 0..self.screen.page_count() {
+            self.screen.switch_page(page);
+            for idx in 0..self.screen.widgets.len() {
+                if self.screen.widgets[idx].is_text() {
+                    if let Some(widget) = self.screen.widgets[idx]
+                        .as_any_mut()
+                        .downcast_mut::<TextWidget>()
+                    {
+                        //重新设置进度条设置宽度(圆环直径)
+                        if widget.is_bar() || widget.is_ring() {
+                            let tag2 = widget.tag2.clone();
+                            let width = tag2.parse::<f32>().unwrap_or(widget.font_size * 5.);
+                            //竖向进度条tag2为高度
+                            let scale = if widget.bar_vertical && widget.is_bar() { height_scale } else { width_scale };
+                            widget.tag2 = format!("{}", (scale * width) as i32);
+                            let new_left = widget.position().left as f32 * width_scale;
+                            let new_top = widget.position().top as f32 * height_scale;
+                            widget.position_mut().set_position(new_left as i32, new_top as i32);
+                            widget.font_size = height_scale * widget.font_size as f32;
+                        }else{
+                            let pos = widget.position_mut();
+                            let (x, y) = pos.center();
+                            pos.set_center((x as f32 * width_scale) as i32, (y as f32 * height_scale) as i32);
+                            widget.font_size = height_scale * widget.font_size as f32;
+                        }
+                    }
+                }
+                if !self.screen.widgets[idx].is_text() {
+                    if let Some(widget) = self.screen.widgets[idx]
+                        .as_any_mut()
+                        .downcast_mut::<ImageWidget>()
+                    {
                         let pos = widget.position_mut();
                         let (x, y) = pos.center();
+                        let new_width = pos.width() as f32 * width_scale;
+                        let new_height = pos.height() as f32 * height_scale;
+                        let dw = (new_width - pos.width() as f32) /2.;
+                        let dh = (new_height - pos.height() as f32) /2.;
+                        pos.inflate(dw as i32, dh as i32);
                         pos.set_center((x as f32 * width_scale) as i32, (y as f32 * height_scale) as i32);
-                        widget.font_size = height_scale * widget.font_size as f32;
                     }
                 }
             }
-            if !self.screen.widgets[idx].is_text() {
-                if let Some(widget) = self.screen.widgets[idx]
-                    .as_any_mut()
-                    .downcast_mut::<ImageWidget>()
-                {
-                    let pos = widget.position_mut();
-                    let (x, y) = pos.center();
-                    let new_width = pos.width() as f32 * width_scale;
-                    let new_height = pos.height() as f32 * height_scale;
-                    let dw = (new_width - pos.width() as f32) /2.;
-                    let dh = (new_height - pos.height() as f32) /2.;
-                    pos.inflate(dw as i32, dh as i32);
-                    pos.set_center((x as f32 * width_scale) as i32, (y as f32 * height_scale) as i32);
-                }
-            }
         }
+        self.screen.switch_page(current_page);
 
         let app = self.app.unwrap();
         app.set_screen_name(format!(
@@ -1196,13 +2728,15 @@ impl CanvasEditorContext {
         let _ = self.screen.setup_monitor();
     }
 
-    fn on_save_screen(&mut self) {
+    // save_as为false时直接覆盖当前打开的文件，还没有文件时和另存为一样选择文件
+    fn on_save_screen(&mut self, save_as: bool) {
         //检查是否有打开的屏幕，并且跟当前屏幕大小一致，保存至配置文件中
         let mut size_fit = false;
         if let Ok(current_device) = SCREEN.lock(){
             if let Some(screen) = current_device.as_ref(){
                 if screen.info.width == self.screen.width as u16 && screen.info.height == self.screen.height as u16{
                     self.screen.device_address = Some(screen.info.address.clone());
+                    self.screen.device_serial = Some(screen.info.serial.clone()).filter(|s| !s.is_empty());
                     size_fit = true;
                 }
             }
@@ -1211,32 +2745,50 @@ impl CanvasEditorContext {
         //错误的屏幕大小要清空
         if !size_fit{
             self.screen.device_address = None;
+            self.screen.device_serial = None;
         }
 
         toast_loading(self.app.clone(), "正在保存...");
         let app_clone = self.app.clone();
+        let current_file = self.screen_file.lock().ok().and_then(|file| file.clone());
         
         match self.screen.to_savable() {
-            Ok(saveable) => {
-                let file_name = format!("{}x{}.screen", self.screen.width, self.screen.height);
+            Ok(mut saveable) => {
+                let file_name = current_file
+                    .as_ref()
+                    .and_then(|file| file.file_name())
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or(format!("{}x{}.screen", self.screen.width, self.screen.height));
+                let screen_file = self.screen_file.clone();
                 std::thread::spawn(move || {
-                    let file_data = match ScreenRender::saveable_to_compressed_json(&saveable){
-                        Err(err) => {
-                            error!("{:?}", err);
-                            toast(app_clone, &format!("{:?}", err));
-                            return;
+                    let file = match current_file.filter(|_| !save_as) {
+                        Some(file) => file,
+                        None => {
+                            hide_loading(app_clone.clone());
+                            let mut dlg = rfd::FileDialog::new()
+                                .add_filter("screen", &["screen"])
+                                .add_filter("json", &["json"])
+                                .set_file_name(file_name);
+                            if let Some(dir) = screen_file.lock().ok().and_then(|file| file.as_ref()?.parent().map(|p| p.to_path_buf())) {
+                                dlg = dlg.set_directory(dir);
+                            }
+                            match dlg.save_file() {
+                                Some(file) => file,
+                                None => return,
+                            }
                         }
-                        Ok(v) => v
                     };
-                    hide_loading(app_clone.clone());
-                    let dlg = rfd::FileDialog::new()
-                        .add_filter("screen", &["screen"])
-                        .set_file_name(file_name);
-                    if let Some(file) = dlg.save_file() {
-                        if let Ok(mut f) = std::fs::File::create(file) {
-                            if let Ok(()) = f.write_all(&file_data){
-                                toast(app_clone, "保存成功");
+                    match write_screen_file(&mut saveable, &file) {
+                        Ok(()) => {
+                            show_screen_file(app_clone.clone(), &file);
+                            if let Ok(mut screen_file) = screen_file.lock() {
+                                screen_file.replace(file);
                             }
+                            toast(app_clone, "保存成功");
+                        }
+                        Err(err) => {
+                            error!("{:?}", err);
+                            toast(app_clone, &format!("{:?}", err));
                         }
                     }
                 });
@@ -1248,10 +2800,39 @@ impl CanvasEditorContext {
         }
     }
 
+    // 导出分享用的布局包，链接的图像嵌入到文件中，打开时校验
+    fn on_share_screen(&mut self) {
+        self.screen.fps = self.fps;
+        let mut saveable = match self.screen.to_bundle() {
+            Ok(saveable) => saveable,
+            Err(err) => {
+                error!("{:?}", err);
+                toast(self.app.clone(), &format!("导出失败:{:?}", err));
+                return;
+            }
+        };
+        //分享的布局不绑定设备
+        saveable.device_address = None;
+        saveable.device_serial = None;
+        let file_name = format!("{}x{}-share.screen", self.screen.width, self.screen.height);
+        let dlg = rfd::FileDialog::new()
+            .add_filter("screen", &["screen"])
+            .set_file_name(file_name);
+        if let Some(file) = dlg.save_file() {
+            match write_screen_file(&mut saveable, &file) {
+                Ok(()) => toast(self.app.clone(), "已导出布局包"),
+                Err(err) => {
+                    error!("{:?}", err);
+                    toast(self.app.clone(), &format!("{:?}", err));
+                }
+            }
+        }
+    }
+
     /// 从线程中解压数据后，通过 app传递事件来调用此方法加载屏幕
     fn load_screen_from_uncompressed(&mut self){
         let app_clone = self.app.clone();
-        let file = match UNCOMPRESSED_SCREEN.lock(){
+        let (file, path) = match UNCOMPRESSED_SCREEN.lock(){
             Ok(mut v) => {
                 let v = v.take();
                 if v.is_none(){
@@ -1261,8 +2842,21 @@ impl CanvasEditorContext {
             }
             Err(_) => return
         };
-        match self.screen.load_from_file(file) {
+        self.screen.base_dir = path.parent().map(|p| p.to_path_buf());
+        let result = if screen::is_json_file(&path) {
+            self.screen.load_from_file_v2(&file)
+        } else {
+            self.screen.load_from_file(file)
+        };
+        match result {
             Ok(()) => {
+                //直接保存时覆盖打开的文件
+                show_screen_file(self.app.clone(), &path);
+                if let Ok(mut screen_file) = self.screen_file.lock() {
+                    screen_file.replace(path.clone());
+                }
+                self.screen.absolute_image_paths();
+                self.screen.load_image_sources();
                 //更新帧率
                 let fps_str = format!("{}", self.screen.fps);
                 self.on_change_fps(SharedString::from(&fps_str));
@@ -1290,43 +2884,43 @@ impl CanvasEditorContext {
                     }
                 }
                 //更新显示列表
-                self.list_model = Rc::new(VecModel::from(vec![]));
-                for idx in 0..self.screen.widgets.len() {
-                    let mut text = "".to_string();
-                    let mut prefix = "".to_string();
-                    if self.screen.widgets[idx].is_text() {
-                        if let Some(widget) = self.screen.widgets[idx]
-                            .as_any_mut()
-                            .downcast_mut::<TextWidget>()
-                        {
-                            text = widget.text.to_string();
-                            prefix = widget.prefix.to_string();
-                        }
-                    }
-
-                    let model = WidgetObject {
-                        index: idx as i32,
-                        name: SharedString::from(self.screen.widgets[idx].get_label()),
-                        type_name: SharedString::from(self.screen.widgets[idx].type_name()),
-                        uuid: SharedString::from(self.screen.widgets[idx].id()),
-                        text: SharedString::from(&text),
-                        prefix: SharedString::from(&prefix),
-                        tag1: SharedString::from(""),
-                        tag2: SharedString::from(""),
-                    };
-                    info!("添加了一个:{:?}", model);
-
-                    self.list_model.push(model);
-                }
+                self.reload_list_model();
+                self.update_page_info();
                 //刷新监听器
                 let _ = self.screen.setup_monitor();
+                self.show_correction();
                 //清空选中的widget
                 let app = self.app.unwrap();
+                app.set_shutdown_action(self.screen.shutdown.index());
+                app.set_night_mode(self.screen.night_mode);
+                app.set_brightness(self.screen.brightness * 100.);
+                app.set_lut_name(self.screen.lut.as_ref().map(|lut| lut.name.as_str()).unwrap_or("").into());
+                self.show_theme();
+                app.set_disk_max_speed(format!("{}", self.screen.disk_max_speed).into());
+                app.set_network_max_speed(format!("{}", self.screen.network_max_speed).into());
+                app.set_supersample(self.screen.supersample as i32 - 1);
+                app.set_pixel_perfect(self.screen.pixel_perfect);
+                app.set_overscan(format!("{}", self.screen.overscan).into());
+                app.set_font_scale(self.screen.font_scale * 100.);
+                app.set_time_24h(self.screen.time_24h);
+                app.set_mirror(self.screen.mirror);
+                app.set_mirror_fit(self.screen.mirror_fit.index());
+                app.set_sync_source(self.screen.sync_source);
+                app.set_weather_icon_set(
+                    nmc::WEATHER_ICON_SETS
+                        .iter()
+                        .position(|(name, _)| *name == self.screen.weather_icon_set)
+                        .unwrap_or(0) as i32,
+                );
                 app.set_font_name(self.screen.font_name.clone().into());
-                app.set_object_list(self.list_model.clone().into());
+                app.set_icon_font_name(self.screen.icon_font_name.clone().into());
                 app.set_active_widget_type_name("".into());
                 app.set_active_widget_uuid("".into());
                 hide_loading(self.app.clone());
+                let unsupported = self.screen.unsupported_widgets();
+                if !unsupported.is_empty() {
+                    toast(self.app.clone(), &format!("当前系统没有这些组件的数据:{}", unsupported.join(",")));
+                }
             }
             Err(err) => {
                 error!("{:?}", err);
@@ -1336,16 +2930,18 @@ impl CanvasEditorContext {
     }
 
     fn on_open_screen(&mut self) {
-        let dlg = rfd::FileDialog::new().add_filter("screen", &["screen"]);
+        let dlg = rfd::FileDialog::new()
+            .add_filter("screen", &["screen"])
+            .add_filter("json", &["json"]);
         toast_loading(self.app.clone(), "加载中...");
         let app_clone = self.app.clone();
         if let Some(file) = dlg.pick_file() {
             std::thread::spawn(move ||{
-                match ScreenRender::decompress_screen_file(file){
+                match ScreenRender::decompress_screen_file(file.clone()){
                     Ok(uncompressed_sceen) => {
                         hide_loading(app_clone.clone());
                         if let Ok(mut us) = UNCOMPRESSED_SCREEN.lock(){
-                            us.replace(uncompressed_sceen);
+                            us.replace((uncompressed_sceen, file));
                             let _ = app_clone.upgrade_in_event_loop(move |app| {
                                 app.invoke_screen_uncompress_ready();
                             });
@@ -1391,17 +2987,7 @@ impl CanvasEditorContext {
         }
         let pixel = self.picker_img.get_pixel(x as u32, y as u32).clone();
 
-        let type_name = self
-            .active_widget()
-            .and_then(|w| Some(w.type_name()))
-            .unwrap_or("");
-
-        if type_name == "images" {
-            self.update_image_widget_color(Some([pixel[0], pixel[1], pixel[2], 255]));
-        } else {
-            //设置当前文本的字体颜色字符串
-            self.update_text_widget_color(pixel[0], pixel[1], pixel[2]);
-        }
+        self.apply_picker_color(pixel[0], pixel[1], pixel[2]);
         Brush::SolidColor(Color::from_rgb_u8(pixel[0], pixel[1], pixel[2]))
     }
 
@@ -1418,6 +3004,40 @@ impl CanvasEditorContext {
         let g = ((color.color().green() as f32 / 255.0) * brightness_factor * 255.0) as u8;
         let b = ((color.color().blue() as f32 / 255.0) * brightness_factor * 255.0) as u8;
 
+        self.apply_picker_color(r, g, b);
+    }
+
+    //点击预设或最近使用的颜色
+    fn on_color_picker_choose_swatch(&mut self, color: Color) {
+        self.apply_picker_color(color.red(), color.green(), color.blue());
+    }
+
+    //取色器关闭时记录最后选择的颜色，拖动取色过程中的颜色不记录
+    fn on_color_picker_closed(&mut self) {
+        if let Some(color) = self.picked_color.take() {
+            if let Err(err) = config::add_recent_color(color) {
+                error!("最近使用的颜色保存失败:{err:?}");
+            }
+            Self::update_recent_colors(&self.app.unwrap());
+        }
+    }
+
+    fn update_recent_colors(app: &CanvasEditor) {
+        app.set_picker_recent_colors(Rc::new(VecModel::from(
+            config::recent_colors().iter().map(|c| Color::from_rgb_u8(c[0], c[1], c[2])).collect::<Vec<Color>>(),
+        )).into());
+    }
+
+    //取色器选择的颜色设置到当前组件
+    fn apply_picker_color(&mut self, r: u8, g: u8, b: u8) {
+        if self.app.unwrap().get_picker_tint() {
+            self.update_widget_tint(Some([r, g, b]));
+            return;
+        }
+        if self.app.unwrap().get_picker_chroma_key() {
+            self.update_widget_chroma_key(Some([r, g, b]));
+            return;
+        }
         let type_name = self
             .active_widget()
             .and_then(|w| Some(w.type_name()))
@@ -1433,11 +3053,18 @@ impl CanvasEditorContext {
 
     //设置当前文本的字体颜色字符串
     fn update_text_widget_color(&mut self, r: u8, g: u8, b: u8) {
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<CpuCoresWidget>())
+        {
+            widget.color = [r, g, b, 255];
+        }
         if let Some(widget) = self
             .active_widget()
             .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>())
         {
             widget.color = [r, g, b, 255];
+            self.picked_color = Some([r, g, b]);
             let app = self.app.unwrap();
             app.set_active_widget_color(Color::from_argb_u8(255, r, g, b));
             app.set_active_widget_color_str(SharedString::from(
@@ -1452,6 +3079,9 @@ impl CanvasEditorContext {
             .and_then(|w| w.as_any_mut().downcast_mut::<ImageWidget>())
         {
             widget.color = color.clone();
+            if let Some(color) = color {
+                self.picked_color = Some([color[0], color[1], color[2]]);
+            }
         }
         let app = self.app.unwrap();
         if let Some(color) = color {
@@ -1465,6 +3095,118 @@ impl CanvasEditorContext {
         }
     }
 
+    //设置当前图片或天气图标的着色颜色
+    fn update_widget_tint(&mut self, tint: Option<[u8; 3]>) {
+        if let Some(widget) = self.active_widget() {
+            if let Some(image) = widget.as_any_mut().downcast_mut::<ImageWidget>() {
+                image.tint = tint;
+            } else if let Some(text) = widget.as_any_mut().downcast_mut::<TextWidget>() {
+                text.tint = tint;
+            }
+        }
+        if let Some(tint) = tint {
+            self.picked_color = Some(tint);
+        }
+        Self::show_widget_tint(&self.app.unwrap(), tint);
+    }
+
+    // 显卡选择框: 第一项为自动，选择的显卡没有连接时保留在最后
+    fn gpu_choices(gpu: Option<&str>) -> Vec<(Option<String>, String)> {
+        let mut choices = vec![(None, "自动".to_string())];
+        choices.extend(monitor::gpu_devices().into_iter().map(|(id, name)| (Some(id), name)));
+        if let Some(gpu) = gpu.filter(|gpu| !choices.iter().any(|(id, _)| id.as_deref() == Some(*gpu))) {
+            choices.push((Some(gpu.to_string()), format!("未连接:{gpu}")));
+        }
+        choices
+    }
+
+    fn show_widget_gpu(app: &CanvasEditor, gpu: Option<&str>) {
+        let choices = Self::gpu_choices(gpu);
+        let names: Vec<SharedString> = choices.iter().map(|(_, name)| SharedString::from(name.as_str())).collect();
+        app.set_gpu_names(Rc::new(VecModel::from(names)).into());
+        app.set_active_widget_gpu(choices.iter().position(|(id, _)| id.as_deref() == gpu).unwrap_or(0) as i32);
+    }
+
+    fn on_update_widget_gpu(&mut self) {
+        let app = self.app.unwrap();
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<TextWidget>())
+        {
+            let choices = Self::gpu_choices(widget.gpu.as_deref());
+            if let Some((id, _)) = choices.get(app.get_active_widget_gpu() as usize) {
+                widget.gpu = id.clone();
+            }
+        }
+    }
+
+    //图像切换时的淡入淡出时长，0直接切换
+    fn on_update_widget_image_fade(&mut self) {
+        let fade = self.app.unwrap().get_active_widget_image_fade().trim().parse::<f32>().unwrap_or(0.).max(0.);
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<ImageWidget>())
+        {
+            widget.fade = fade;
+        }
+    }
+
+    //抠色颜色字符串为空时不抠色
+    fn on_update_widget_chroma_key(&mut self) {
+        let key_str = self.app.unwrap().get_active_widget_chroma_key_str().to_string();
+        let key = match key_str.trim() {
+            "" => None,
+            key_str => match HexColor::from_str(key_str) {
+                Ok(c) => Some([c.r, c.g, c.b]),
+                Err(_) => return,
+            },
+        };
+        self.update_widget_chroma_key(key);
+    }
+
+    fn update_widget_chroma_key(&mut self, key: Option<[u8; 3]>) {
+        let app = self.app.unwrap();
+        let tolerance = app.get_active_widget_chroma_tolerance().round().clamp(0., 255.) as u8;
+        let chroma_key = key.map(|key| (key, tolerance));
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<ImageWidget>())
+        {
+            widget.chroma_key = chroma_key;
+        }
+        if let Some(key) = key {
+            self.picked_color = Some(key);
+        }
+        Self::show_widget_chroma_key(&app, chroma_key);
+    }
+
+    fn show_widget_chroma_key(app: &CanvasEditor, chroma_key: Option<([u8; 3], u8)>) {
+        match chroma_key {
+            Some(([r, g, b], tolerance)) => {
+                app.set_active_widget_chroma_key(Color::from_rgb_u8(r, g, b));
+                app.set_active_widget_chroma_key_str(HexColor::rgb(r, g, b).display_rgb().to_string().into());
+                app.set_active_widget_chroma_tolerance(tolerance as f32);
+            }
+            None => {
+                app.set_active_widget_chroma_key(Color::from_rgb_u8(0, 255, 0));
+                app.set_active_widget_chroma_key_str("".into());
+            }
+        }
+    }
+
+    fn show_widget_tint(app: &CanvasEditor, tint: Option<[u8; 3]>) {
+        match tint {
+            Some([r, g, b]) => {
+                app.set_active_widget_tint(Color::from_rgb_u8(r, g, b));
+                app.set_active_widget_tint_str(HexColor::rgb(r, g, b).display_rgb().to_string().into());
+            }
+            None => {
+                app.set_active_widget_tint(Color::from_rgb_u8(255, 255, 255));
+                app.set_active_widget_tint_str("".into());
+            }
+        }
+    }
+
     fn get_real_pos(
         screen: &ScreenRender,
         mouse_x: f32,
@@ -1493,6 +3235,30 @@ impl CanvasEditorContext {
         });
     }
 
+    // 保存设备上实际显示的画面：包含颜色调整和旋转，开启RGB565预览时也包含色深损失
+    fn on_save_device_capture(&mut self) {
+        let image = match self.device_frame.clone() {
+            None => {
+                toast(self.app.clone(), "还没有发送画面");
+                return;
+            }
+            Some(image) => image,
+        };
+        let frame = rotate_frame(image.convert(), self.screen.rotate_degree);
+        let file_name = format!("device_{}x{}.png", frame.width(), frame.height());
+        let app = self.app.clone();
+        std::thread::spawn(move || {
+            let dlg = rfd::FileDialog::new()
+                .add_filter("png", &["png"])
+                .set_file_name(file_name);
+            if let Some(file) = dlg.save_file() {
+                if let Err(err) = frame.save(file) {
+                    toast(app, &format!("保存失败:{err}"));
+                }
+            }
+        });
+    }
+
     fn on_change_device(&mut self, device: SharedString) {
         info!("on_change_device: {}", device.as_str());
         let devices = self.devices.clone();
@@ -1506,6 +3272,7 @@ impl CanvasEditorContext {
                         }
                         match UsbScreen::open(dev.clone()){
                             Ok(s) => {
+                                remember_device(&dev);
                                 screen.replace(CurrentUsbScreen { info: dev.clone(), screen: s });
                             }
                             Err(err) => {
@@ -1519,11 +3286,363 @@ impl CanvasEditorContext {
         });
     }
 
+    //显示当前选择的传感器的校准值
+    fn show_correction(&mut self) {
+        let app = self.app.unwrap();
+        let type_name = app.get_correction_type().to_string();
+        let (scale, offset) = self
+            .screen
+            .corrections
+            .iter()
+            .find(|c| c.type_name == type_name)
+            .map(|c| (c.scale, c.offset))
+            .unwrap_or((1., 0.));
+        app.set_correction_scale(format!("{scale}").into());
+        app.set_correction_offset(format!("{offset}").into());
+    }
+
+    fn on_update_correction(&mut self) {
+        let app = self.app.unwrap();
+        let type_name = app.get_correction_type().to_string();
+        if type_name.len() == 0 {
+            return;
+        }
+        let scale = app.get_correction_scale().parse::<f32>().unwrap_or(1.);
+        let offset = app.get_correction_offset().parse::<f32>().unwrap_or(0.);
+        self.screen.corrections.retain(|c| c.type_name != type_name);
+        //系数1偏移0不需要保存
+        if scale != 1. || offset != 0. {
+            self.screen.corrections.push(Correction { type_name, scale, offset });
+        }
+        self.show_correction();
+        let _ = self.screen.setup_monitor();
+    }
+
+    //选择待机图像时打开图片
+    fn on_change_shutdown_action(&mut self) {
+        let app = self.app.unwrap();
+        self.screen.shutdown = match app.get_shutdown_action() {
+            1 => ShutdownAction::Clear,
+            2 => {
+                let image = FileDialog::new()
+                    .add_filter("图像", &["png", "bmp", "jpg", "jpeg"])
+                    .pick_file()
+                    .and_then(|file| std::fs::read(file).ok())
+                    .and_then(|data| ImageData::load(&data, (self.screen.width, self.screen.height)).ok());
+                match image {
+                    Some(image) => ShutdownAction::Image(image),
+                    //没有选择图片时保持原来的设置
+                    None => self.screen.shutdown.clone(),
+                }
+            }
+            _ => ShutdownAction::Keep,
+        };
+        app.set_shutdown_action(self.screen.shutdown.index());
+    }
+
+    //当前画面保存为设备的开机画面
+    fn on_set_boot_splash(&mut self) {
+        let mut frame: RgbImage = self.screen.canvas.image_data().convert();
+        self.screen.apply_color_filter(&mut frame, 3);
+        let frame = rotate_frame(frame, self.screen.rotate_degree);
+        let result = match SCREEN.lock() {
+            Err(err) => Err(anyhow::anyhow!("{err:?}")),
+            Ok(mut screen) => match screen.as_mut() {
+                None => Err(anyhow::anyhow!("没有连接屏幕")),
+                Some(device) => {
+                    let (width, height) = (device.info.width as u32, device.info.height as u32);
+                    let frame = if frame.width() == width && frame.height() == height {
+                        frame
+                    } else {
+                        resize(&frame, width, height, resize_filter())
+                    };
+                    device.screen.set_boot_splash(&frame).map_err(|err| anyhow::anyhow!("{err:?}"))
+                }
+            },
+        };
+        match result {
+            Ok(()) => toast(self.app.clone(), "开机画面已发送"),
+            Err(err) => {
+                error!("开机画面设置失败:{err:?}");
+                toast(self.app.clone(), &format!("开机画面设置失败:{err}"));
+            }
+        }
+    }
+
+    // 选择.cube文件，取消或解析失败时返回None
+    fn pick_lut(&self) -> Option<Lut> {
+        let file = FileDialog::new().add_filter("LUT", &["cube"]).pick_file()?;
+        let name = file.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let lut = std::fs::read_to_string(&file)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| Lut::parse(&text, &name));
+        match lut {
+            Ok(lut) => Some(lut),
+            Err(err) => {
+                error!("LUT加载失败:{err:?}");
+                toast(self.app.clone(), &format!("LUT加载失败:{err}"));
+                None
+            }
+        }
+    }
+
+    fn on_change_lut(&mut self, load: bool) {
+        if !load {
+            self.screen.lut = None;
+        } else if let Some(lut) = self.pick_lut() {
+            self.screen.lut = Some(lut);
+        }
+        let name = self.screen.lut.as_ref().map(|lut| lut.name.as_str()).unwrap_or("");
+        self.app.unwrap().set_lut_name(name.into());
+    }
+
+    fn on_update_widget_lut(&mut self, load: bool) {
+        let lut = if load {
+            match self.pick_lut() {
+                Some(lut) => Some(lut),
+                None => return,
+            }
+        } else {
+            None
+        };
+        if let Some(widget) = self
+            .active_widget()
+            .and_then(|w| w.as_any_mut().downcast_mut::<ImageWidget>())
+        {
+            let name = lut.as_ref().map(|lut| lut.name.clone()).unwrap_or_default();
+            widget.lut = lut;
+            self.app.unwrap().set_active_widget_lut_name(name.into());
+        }
+    }
+
+    // 颜色格式错误时保持原来的颜色
+    fn on_change_theme(&mut self) {
+        let app = self.app.unwrap();
+        let parse = |s: SharedString, old: [u8; 3]| {
+            HexColor::from_str(s.trim()).map(|c| [c.r, c.g, c.b]).unwrap_or(old)
+        };
+        let theme = &mut self.screen.theme;
+        theme.accent = parse(app.get_theme_accent(), theme.accent);
+        theme.text = parse(app.get_theme_text(), theme.text);
+        theme.background = parse(app.get_theme_background(), theme.background);
+        theme.warning = parse(app.get_theme_warning(), theme.warning);
+    }
+
+    fn show_theme(&self) {
+        let app = self.app.unwrap();
+        let hex = |[r, g, b]: [u8; 3]| SharedString::from(HexColor::rgb(r, g, b).display_rgb().to_string());
+        app.set_theme_accent(hex(self.screen.theme.accent));
+        app.set_theme_text(hex(self.screen.theme.text));
+        app.set_theme_background(hex(self.screen.theme.background));
+        app.set_theme_warning(hex(self.screen.theme.warning));
+    }
+
+    fn on_change_color_filter(&mut self) {
+        let app = self.app.unwrap();
+        self.screen.night_mode = app.get_night_mode();
+        self.screen.brightness = app.get_brightness() / 100.;
+    }
+
+    fn on_change_activity_limits(&mut self) {
+        let app = self.app.unwrap();
+        if let Ok(v) = app.get_disk_max_speed().parse::<f32>() {
+            self.screen.disk_max_speed = v.max(0.1);
+        }
+        if let Ok(v) = app.get_network_max_speed().parse::<f32>() {
+            self.screen.network_max_speed = v.max(0.1);
+        }
+        let _ = monitor::set_activity_limits(self.screen.disk_max_speed, self.screen.network_max_speed);
+    }
+
+    fn on_change_overscan(&mut self) {
+        let app = self.app.unwrap();
+        self.screen.overscan = app.get_overscan().trim().parse::<u32>().unwrap_or(0);
+    }
+
+    fn on_change_font_scale(&mut self) {
+        let app = self.app.unwrap();
+        self.screen.font_scale = (app.get_font_scale() / 100.).clamp(0.5, 2.);
+    }
+
+    fn on_change_time_24h(&mut self) {
+        let app = self.app.unwrap();
+        self.screen.time_24h = app.get_time_24h();
+    }
+
+    fn on_change_supersample(&mut self) {
+        let app = self.app.unwrap();
+        self.screen.supersample = (app.get_supersample() + 1).clamp(1, MAX_SUPERSAMPLE as i32) as u8;
+    }
+
+    fn on_change_design_preview(&mut self) {
+        widgets::set_design_preview(self.app.unwrap().get_design_preview());
+    }
+
+    fn on_change_pixel_perfect(&mut self) {
+        let app = self.app.unwrap();
+        self.screen.pixel_perfect = app.get_pixel_perfect();
+        //开启时把现有文字组件的字号取整
+        if self.screen.pixel_perfect {
+            for widget in self.screen.widgets.iter_mut() {
+                if let Some(widget) = widget.as_any_mut().downcast_mut::<TextWidget>() {
+                    widget.font_size = widget.font_size.round();
+                }
+            }
+        }
+    }
+
+    //当前页面的组件列表重新生成显示列表
+    fn reload_list_model(&mut self) {
+        self.list_model = Rc::new(VecModel::from(vec![]));
+        for idx in 0..self.screen.widgets.len() {
+            let mut text = "".to_string();
+            let mut prefix = "".to_string();
+            if self.screen.widgets[idx].is_text() {
+                if let Some(widget) = self.screen.widgets[idx]
+                    .as_any_mut()
+                    .downcast_mut::<TextWidget>()
+                {
+                    text = widget.text.to_string();
+                    prefix = widget.prefix.to_string();
+                }
+            }
+
+            let model = WidgetObject {
+                index: idx as i32,
+                name: SharedString::from(self.screen.widgets[idx].get_label()),
+                type_name: SharedString::from(self.screen.widgets[idx].type_name()),
+                uuid: SharedString::from(self.screen.widgets[idx].id()),
+                text: SharedString::from(&text),
+                prefix: SharedString::from(&prefix),
+                tag1: SharedString::from(""),
+                tag2: SharedString::from(""),
+                visible: self.screen.widgets[idx].visible(),
+            };
+            info!("添加了一个:{:?}", model);
+
+            self.list_model.push(model);
+        }
+        self.app.unwrap().set_object_list(self.list_model.clone().into());
+    }
+
+    //同步页面选择框
+    fn update_page_info(&mut self) {
+        let app = self.app.unwrap();
+        let names: Vec<SharedString> = (1..=self.screen.page_count())
+            .map(|i| SharedString::from(format!("{i}")))
+            .collect();
+        app.set_page_names(Rc::new(VecModel::from(names)).into());
+        app.set_page_index(self.screen.page_index() as i32);
+        app.set_page_interval(format!("{}", self.screen.page_interval).into());
+        app.set_page_auto(self.screen.auto_page);
+        app.set_transition_index(self.screen.transition.index());
+        app.set_transition_duration(format!("{}", self.screen.transition_duration).into());
+        app.set_transition_direction(self.screen.animation.direction.index());
+        app.set_transition_easing(self.screen.animation.easing.index());
+    }
+
+    //切换页面后刷新列表，清空选中
+    fn on_page_switched(&mut self) {
+        self.selected_ids.clear();
+        self.active_id = None;
+        self.reload_list_model();
+        self.update_page_info();
+        let app = self.app.unwrap();
+        app.set_active_widget_type_name("".into());
+        app.set_active_widget_uuid("".into());
+    }
+
+    fn on_change_page(&mut self, index: i32) {
+        self.screen.switch_page(index.max(0) as usize);
+        self.on_page_switched();
+    }
+
+    fn on_add_page(&mut self) {
+        self.screen.add_page();
+        self.on_page_switched();
+    }
+
+    fn on_remove_page(&mut self) {
+        self.screen.remove_page();
+        let _ = self.screen.setup_monitor();
+        self.on_page_switched();
+    }
+
+    fn on_change_page_interval(&mut self, interval: SharedString) {
+        if let Ok(interval) = interval.trim().parse::<f32>() {
+            self.screen.page_interval = interval.max(0.);
+        }
+    }
+
+    fn on_change_page_auto(&mut self) {
+        self.screen.auto_page = self.app.unwrap().get_page_auto();
+    }
+
+    fn on_change_transition(&mut self) {
+        let app = self.app.unwrap();
+        self.screen.transition = TransitionKind::from_index(app.get_transition_index());
+        if let Ok(duration) = app.get_transition_duration().trim().parse::<f32>() {
+            self.screen.transition_duration = duration.clamp(0., 5.);
+        }
+        self.screen.animation.direction = Direction::from_index(app.get_transition_direction());
+        self.screen.animation.easing = Easing::from_index(app.get_transition_easing());
+    }
+
+    fn on_change_mirror(&mut self) {
+        let app = self.app.unwrap();
+        self.screen.mirror = app.get_mirror();
+        self.screen.mirror_fit = Fit::from_index(app.get_mirror_fit());
+    }
+
+    fn on_change_sync_source(&mut self) {
+        self.screen.sync_source = self.app.unwrap().get_sync_source();
+    }
+
+    fn on_change_weather_icon_set(&mut self) {
+        let app = self.app.unwrap();
+        let index = app.get_weather_icon_set().max(0) as usize;
+        if let Some((name, _)) = nmc::WEATHER_ICON_SETS.get(index) {
+            //选择字体图标时还没有加载字体，先选择字体文件
+            if *name == "font" && self.screen.icon_font.is_none() {
+                self.on_change_icon_font();
+            }
+            self.screen.weather_icon_set = name.to_string();
+            nmc::set_weather_icon_set(name);
+        }
+    }
+
+    // 选择天气图标使用的字体，例如 Weather Icons
+    fn on_change_icon_font(&mut self) {
+        let file = match FileDialog::new().add_filter("字体文件", &["ttf", "otf"]).pick_file() {
+            Some(file) => file,
+            None => return,
+        };
+        let result = std::fs::read(&file).map_err(anyhow::Error::from).and_then(|font| {
+            nmc::set_icon_font(Some(&font))?;
+            Ok(font)
+        });
+        match result {
+            Ok(font) => {
+                self.screen.icon_font = Some(font);
+                self.screen.icon_font_name = file.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                self.app.unwrap().set_icon_font_name(self.screen.icon_font_name.clone().into());
+            }
+            Err(err) => {
+                error!("图标字体加载失败:{err:?}");
+                toast(self.app.clone(), &format!("图标字体加载失败:{err}"));
+            }
+        }
+    }
+
     fn on_change_fps(&mut self, fps: SharedString) {
         info!("on_change_fps {fps}");
         let fps = fps.to_string().replace("刷新率:", "").replace("帧", "");
         let mut fps = fps.parse::<f32>().unwrap_or(10.);
-        if self.screen.width > 160 && self.screen.height > 128{
+        //测速过的设备使用测出的帧率作为上限
+        let benchmark_fps = self.device_serial.as_deref().and_then(config::benchmark_fps);
+        if let Some(max_fps) = benchmark_fps {
+            fps = fps.min(max_fps.floor().max(1.));
+        } else if self.screen.width > 160 && self.screen.height > 128{
             //320x240屏幕最高不超过12帧
             if fps > 12.{
                 fps = 12.;
@@ -1540,6 +3659,8 @@ impl CanvasEditorContext {
 
 pub fn run() -> Result<()> {
     let app = CanvasEditor::new().unwrap();
+    app.set_manual_connect(config::manual_connect());
+    app.set_high_compression(config::high_compression());
     let mut context = CanvasEditorContext::new(app.as_weak());
 
     context.render_screen();
@@ -1608,10 +3729,10 @@ pub fn run() -> Result<()> {
         });
     }
     let context_clone = context.clone();
-    app.on_mouse_click(move |mouse_x, mouse_y, image_width, image_height| {
+    app.on_mouse_click(move |mouse_x, mouse_y, image_width, image_height, multi_select| {
         context_clone
             .borrow_mut()
-            .on_mouse_click(mouse_x, mouse_y, image_width, image_height);
+            .on_mouse_click(mouse_x, mouse_y, image_width, image_height, multi_select);
     });
 
     let context_clone = context.clone();
@@ -1647,11 +3768,119 @@ pub fn run() -> Result<()> {
         context_clone.borrow_mut().on_update_widget_image_color();
     });
 
+    let context_clone = context.clone();
+    app.on_update_widget_tint(move || {
+        context_clone.borrow_mut().on_update_widget_tint();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_image_fit(move || {
+        context_clone.borrow_mut().on_update_widget_image_fit();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_image_mask(move || {
+        context_clone.borrow_mut().on_update_widget_image_mask();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_text_auto_size(move || {
+        context_clone.borrow_mut().on_update_widget_text_auto_size();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_bar_gradient(move || {
+        context_clone.borrow_mut().on_update_widget_bar_gradient();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_track_color(move || {
+        context_clone.borrow_mut().on_update_widget_track_color();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_value_range(move || {
+        context_clone.borrow_mut().on_update_widget_value_range();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_text_background(move || {
+        context_clone.borrow_mut().on_update_widget_text_background();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_text_spacing(move || {
+        context_clone.borrow_mut().on_update_widget_text_spacing();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_bar_shape(move || {
+        context_clone.borrow_mut().on_update_widget_bar_shape();
+    });
+    let context_clone = context.clone();
+    app.on_update_widget_placeholder(move || {
+        context_clone.borrow_mut().on_update_widget_placeholder();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_pulse(move || {
+        context_clone.borrow_mut().on_update_widget_pulse();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_value_format(move || {
+        context_clone.borrow_mut().on_update_widget_value_format();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_chroma_key(move || {
+        context_clone.borrow_mut().on_update_widget_chroma_key();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_image_fade(move || {
+        context_clone.borrow_mut().on_update_widget_image_fade();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_watermark(move || {
+        context_clone.borrow_mut().on_update_widget_watermark();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_gpu(move || {
+        context_clone.borrow_mut().on_update_widget_gpu();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_preview_value(move || {
+        context_clone.borrow_mut().on_update_widget_preview_value();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_text_scroll(move || {
+        context_clone.borrow_mut().on_update_widget_text_scroll();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_animation(move || {
+        context_clone.borrow_mut().on_update_widget_animation();
+    });
+
     let context_clone = context.clone();
     app.on_update_widget_text_color(move || {
         context_clone.borrow_mut().on_update_widget_text_color();
     });
 
+    let context_clone = context.clone();
+    app.on_update_widget_image_background(move || {
+        context_clone.borrow_mut().on_update_widget_image_background();
+    });
+    let context_clone = context.clone();
+    app.on_update_widget_image_link(move || {
+        context_clone.borrow_mut().on_update_widget_image_link();
+    });
+
     let context_clone = context.clone();
     app.on_update_widget_tags(move || {
         context_clone.borrow_mut().on_update_widget_tags();
@@ -1689,6 +3918,213 @@ pub fn run() -> Result<()> {
         context_clone.borrow_mut().delete_widget(uuid.as_str());
     });
 
+    let context_clone = context.clone();
+    app.on_benchmark(move || {
+        context_clone.borrow_mut().on_benchmark();
+    });
+
+    let context_clone = context.clone();
+    app.on_scale_to_device(move |width, height| {
+        context_clone.borrow_mut().on_scale_to_device(width, height);
+    });
+
+    let context_clone = context.clone();
+    app.on_change_theme(move || {
+        context_clone.borrow_mut().on_change_theme();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_color_role(move || {
+        context_clone.borrow_mut().on_update_widget_color_role();
+    });
+
+    let context_clone = context.clone();
+    app.on_change_lut(move |load| {
+        context_clone.borrow_mut().on_change_lut(load);
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_lut(move |load| {
+        context_clone.borrow_mut().on_update_widget_lut(load);
+    });
+
+    let context_clone = context.clone();
+    app.on_change_color_filter(move || {
+        context_clone.borrow_mut().on_change_color_filter();
+    });
+
+    let context_clone = context.clone();
+    app.on_change_activity_limits(move || {
+        context_clone.borrow_mut().on_change_activity_limits();
+    });
+    let context_clone = context.clone();
+    app.on_change_overscan(move || {
+        context_clone.borrow_mut().on_change_overscan();
+    });
+    let context_clone = context.clone();
+    app.on_change_font_scale(move || {
+        context_clone.borrow_mut().on_change_font_scale();
+    });
+    let context_clone = context.clone();
+    app.on_change_time_24h(move || {
+        context_clone.borrow_mut().on_change_time_24h();
+    });
+
+    let context_clone = context.clone();
+    app.on_change_supersample(move || {
+        context_clone.borrow_mut().on_change_supersample();
+    });
+
+    let context_clone = context.clone();
+    app.on_change_design_preview(move || {
+        context_clone.borrow_mut().on_change_design_preview();
+    });
+    let context_clone = context.clone();
+    app.on_change_pixel_perfect(move || {
+        context_clone.borrow_mut().on_change_pixel_perfect();
+    });
+
+    let context_clone = context.clone();
+    app.on_change_mirror(move || {
+        context_clone.borrow_mut().on_change_mirror();
+    });
+
+    let context_clone = context.clone();
+    app.on_change_sync_source(move || {
+        context_clone.borrow_mut().on_change_sync_source();
+    });
+
+    let context_clone = context.clone();
+    app.on_set_boot_splash(move || {
+        context_clone.borrow_mut().on_set_boot_splash();
+    });
+
+    let context_clone = context.clone();
+    app.on_change_weather_icon_set(move || {
+        context_clone.borrow_mut().on_change_weather_icon_set();
+    });
+    let context_clone = context.clone();
+    app.on_change_icon_font(move || {
+        context_clone.borrow_mut().on_change_icon_font();
+    });
+
+    let context_clone = context.clone();
+    app.on_align_widget(move |action| {
+        context_clone.borrow_mut().on_align_widget(action);
+    });
+
+    let context_clone = context.clone();
+    app.on_change_page(move |index| {
+        context_clone.borrow_mut().on_change_page(index);
+    });
+
+    let context_clone = context.clone();
+    app.on_add_page(move || {
+        context_clone.borrow_mut().on_add_page();
+    });
+
+    let context_clone = context.clone();
+    app.on_remove_page(move || {
+        context_clone.borrow_mut().on_remove_page();
+    });
+
+    let context_clone = context.clone();
+    app.on_change_page_interval(move |interval| {
+        context_clone.borrow_mut().on_change_page_interval(interval);
+    });
+
+    let context_clone = context.clone();
+    app.on_change_page_auto(move || {
+        context_clone.borrow_mut().on_change_page_auto();
+    });
+
+    let context_clone = context.clone();
+    app.on_change_transition(move || {
+        context_clone.borrow_mut().on_change_transition();
+    });
+
+    let context_clone = context.clone();
+    app.on_change_calibration(move || {
+        context_clone.borrow_mut().on_change_calibration();
+    });
+
+    let context_clone = context.clone();
+    app.on_save_calibration(move || {
+        context_clone.borrow_mut().on_save_calibration();
+    });
+
+    let context_clone = context.clone();
+    app.on_change_shutdown_action(move || {
+        context_clone.borrow_mut().on_change_shutdown_action();
+    });
+
+    let context_clone = context.clone();
+    app.on_select_correction(move || {
+        context_clone.borrow_mut().show_correction();
+    });
+
+    let context_clone = context.clone();
+    app.on_update_correction(move || {
+        context_clone.borrow_mut().on_update_correction();
+    });
+
+    let context_clone = context.clone();
+    app.on_toggle_widget_visible(move |uuid| {
+        context_clone.borrow_mut().toggle_widget_visible(uuid.as_str());
+    });
+
+    let context_clone = context.clone();
+    app.on_update_widget_opacity(move || {
+        context_clone.borrow_mut().on_update_widget_opacity();
+    });
+    let context_clone = context.clone();
+    app.on_update_widget_min_refresh(move || {
+        context_clone.borrow_mut().on_update_widget_min_refresh();
+    });
+    let context_clone = context.clone();
+    app.on_update_widget_z_index(move || {
+        context_clone.borrow_mut().on_update_widget_z_index();
+    });
+    let context_clone = context.clone();
+    app.on_update_widget_show_when(move || {
+        context_clone.borrow_mut().on_update_widget_show_when();
+    });
+
+    let context_clone = context.clone();
+    app.on_group_widgets(move || {
+        context_clone.borrow_mut().on_group_widgets();
+    });
+
+    let context_clone = context.clone();
+    app.on_ungroup_widgets(move || {
+        context_clone.borrow_mut().on_ungroup_widgets();
+    });
+
+    let context_clone = context.clone();
+    app.on_toggle_widget_locked(move || {
+        context_clone.borrow_mut().on_toggle_widget_locked();
+    });
+
+    let context_clone = context.clone();
+    app.on_copy_widget_geometry(move || {
+        context_clone.borrow_mut().copy_widget_geometry();
+    });
+
+    let context_clone = context.clone();
+    app.on_paste_widget_geometry(move |geometry| {
+        context_clone.borrow_mut().paste_widget_geometry(geometry);
+    });
+
+    let context_clone = context.clone();
+    app.on_copy_widget_style(move || {
+        context_clone.borrow_mut().copy_widget_style();
+    });
+
+    let context_clone = context.clone();
+    app.on_paste_widget_style(move || {
+        context_clone.borrow_mut().paste_widget_style();
+    });
+
     let context_clone = context.clone();
     app.on_clone_widget(move |uuid| {
         context_clone.borrow_mut().clone_widget(uuid.as_str());
@@ -1715,8 +4151,8 @@ pub fn run() -> Result<()> {
     });
 
     let context_clone = context.clone();
-    app.on_save_screen(move || {
-        context_clone.borrow_mut().on_save_screen();
+    app.on_save_screen(move |save_as| {
+        context_clone.borrow_mut().on_save_screen(save_as);
     });
 
     let context_clone = context.clone();
@@ -1724,11 +4160,51 @@ pub fn run() -> Result<()> {
         context_clone.borrow_mut().on_save_capture();
     });
 
+    let context_clone = context.clone();
+    app.on_share_screen(move || {
+        context_clone.borrow_mut().on_share_screen();
+    });
+
     let context_clone = context.clone();
     app.on_open_screen(move || {
         context_clone.borrow_mut().on_open_screen();
     });
 
+    let context_clone = context.clone();
+    app.on_change_manual_connect(move || {
+        context_clone.borrow_mut().on_change_manual_connect();
+    });
+
+    let context_clone = context.clone();
+    app.on_change_high_compression(move || {
+        context_clone.borrow_mut().on_change_high_compression();
+    });
+
+    let context_clone = context.clone();
+    app.on_save_device_capture(move || {
+        context_clone.borrow_mut().on_save_device_capture();
+    });
+
+    let context_clone = context.clone();
+    app.on_save_component(move || {
+        context_clone.borrow_mut().on_save_component();
+    });
+
+    let context_clone = context.clone();
+    app.on_insert_component(move || {
+        context_clone.borrow_mut().on_insert_component();
+    });
+
+    let context_clone = context.clone();
+    app.on_select_preset(move |index| {
+        context_clone.borrow_mut().on_select_preset(index);
+    });
+
+    let context_clone = context.clone();
+    app.on_generate_layout(move |index| {
+        context_clone.borrow_mut().on_generate_layout(index);
+    });
+
     let context_clone = context.clone();
     app.on_open_font(move || {
         context_clone.borrow_mut().on_open_font();
@@ -1749,11 +4225,41 @@ pub fn run() -> Result<()> {
             .on_color_picker_brightness_change();
     });
 
+    let context_clone = context.clone();
+    app.on_color_picker_choose_swatch(move |color| {
+        context_clone
+            .borrow_mut()
+            .on_color_picker_choose_swatch(color);
+    });
+
+    let context_clone = context.clone();
+    app.on_color_picker_closed(move || {
+        context_clone.borrow_mut().on_color_picker_closed();
+    });
+
     let context_clone = context.clone();
     app.on_change_device(move |device| {
         context_clone.borrow_mut().on_change_device(device);
     });
 
+    app.on_reset_stats(move || {
+        if let Err(err) = monitor::reset_stats() {
+            error!("{:?}", err);
+        }
+    });
+
+    app.on_reset_network_total(move || {
+        if let Err(err) = monitor::reset_network_total() {
+            error!("{:?}", err);
+        }
+    });
+
+    app.on_reset_energy(move || {
+        if let Err(err) = monitor::reset_energy() {
+            error!("{:?}", err);
+        }
+    });
+
     let context_clone = context.clone();
     app.on_change_fps(move |fps| {
         context_clone.borrow_mut().on_change_fps(fps);
@@ -1768,6 +4274,16 @@ pub fn run() -> Result<()> {
 }
 
 
+// 记住最后连接的设备，下次启动编辑器时优先连接
+fn remember_device(dev: &UsbScreenInfo) {
+    if dev.serial.is_empty() || config::last_device().as_deref() == Some(dev.serial.as_str()) {
+        return;
+    }
+    if let Err(err) = config::set_last_device(&dev.serial) {
+        error!("设备保存失败:{err:?}");
+    }
+}
+
 pub fn toast(app: Weak<CanvasEditor>, msg:&str){
     let msg = msg.to_string();
     let _ = app.upgrade_in_event_loop(move |app|{
@@ -1779,6 +4295,29 @@ pub fn toast(app: Weak<CanvasEditor>, msg:&str){
     });
 }
 
+// 保存布局文件，选择json时导出未压缩的格式
+fn write_screen_file(saveable: &mut SaveableScreen, file: &Path) -> Result<()> {
+    //链接的图像保存为相对于.screen文件的路径
+    if let Some(dir) = file.parent() {
+        ScreenRender::relative_image_paths(saveable, dir);
+    }
+    let file_data = if screen::is_json_file(file) {
+        ScreenRender::saveable_to_pretty_json(saveable)?
+    } else {
+        ScreenRender::saveable_to_compressed_json(saveable, Compression::from_config())?
+    };
+    std::fs::write(file, file_data)?;
+    Ok(())
+}
+
+// 显示当前打开的文件名
+fn show_screen_file(app: Weak<CanvasEditor>, file: &Path) {
+    let name = file.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let _ = app.upgrade_in_event_loop(move |app| {
+        app.set_screen_file_name(name.into());
+    });
+}
+
 pub fn toast_loading(app: Weak<CanvasEditor>, msg:&str){
     let msg = msg.to_string();
     let _ = app.upgrade_in_event_loop(move |app|{
@@ -1794,4 +4333,11 @@ pub fn hide_loading(app: Weak<CanvasEditor>){
     let _ = app.upgrade_in_event_loop(|app|{
         app.set_toast_message("".into());
     });
-}
\ No newline at end of file
+}
+// 背景色预览，没有背景时透明
+fn background_color(color: Option<[u8; 4]>) -> Color {
+    match color {
+        Some(c) => Color::from_argb_u8(c[3], c[0], c[1], c[2]),
+        None => Color::from_argb_u8(0, 0, 0, 0),
+    }
+}
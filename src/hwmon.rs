@@ -0,0 +1,277 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use log::info;
+
+use crate::monitor::{HardwareData, HardwareInfo};
+
+const HWMON_DIR: &str = "/sys/class/hwmon";
+// nvidia-smi启动较慢，刷新间隔跟随帧率时最快每秒调用一次
+const NVIDIA_SMI_INTERVAL: Duration = Duration::from_secs(1);
+const THERMAL_DIR: &str = "/sys/class/thermal";
+// RAPL能量计数器，封装和核心
+const RAPL_PACKAGE: &str = "/sys/class/powercap/intel-rapl:0/energy_uj";
+const RAPL_CORES: &str = "/sys/class/powercap/intel-rapl:0/intel-rapl:0:0/energy_uj";
+// CPU温度传感器驱动
+const CPU_SENSORS: [&str; 6] = [
+    "coretemp",
+    "k10temp",
+    "zenpower",
+    "cpu_thermal",
+    "cpu-thermal",
+    "soc_thermal",
+];
+// 显卡驱动和对应的厂商
+const GPU_SENSORS: [(&str, &str); 5] = [
+    ("amdgpu", "AMD"),
+    ("radeon", "AMD"),
+    ("nouveau", "NVIDIA"),
+    ("i915", "Intel"),
+    ("xe", "Intel"),
+];
+
+fn read_string(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+fn read_number(path: &Path) -> Option<f32> {
+    read_string(path)?.parse().ok()
+}
+
+// 读取hwmon目录中的传感器数值，例如 temp1_input temp2_input ...，返回(标签, 数值)
+fn read_inputs(dir: &Path, prefix: &str) -> Vec<(String, f32)> {
+    let mut inputs = vec![];
+    for i in 1..=32 {
+        if let Some(value) = read_number(&dir.join(format!("{prefix}{i}_input"))) {
+            let label = read_string(&dir.join(format!("{prefix}{i}_label"))).unwrap_or_default();
+            inputs.push((label, value));
+        }
+    }
+    inputs
+}
+
+// 没有hwmon温度传感器时(例如树莓派)，读取thermal_zone0
+fn read_thermal_zone() -> Option<f32> {
+    let temp = read_number(&Path::new(THERMAL_DIR).join("thermal_zone0").join("temp"))?;
+    Some(temp / 1000.)
+}
+
+// PCI地址统一为 0000:01:00.0 的格式，nvidia-smi的域为8位
+fn normalize_pci_address(address: &str) -> String {
+    let address = address.trim().to_lowercase();
+    match address.split_once(':') {
+        Some((domain, rest)) if domain.len() > 4 => format!("{}:{rest}", &domain[domain.len() - 4..]),
+        _ => address,
+    }
+}
+
+// amdgpu/radeon/nouveau/i915 显卡，标识为device链接指向的PCI地址
+fn read_hwmon_gpu(dir: &Path, driver: &str, vendor: &str) -> HardwareInfo {
+    let temperatures: Vec<f32> = read_inputs(dir, "temp").into_iter().map(|(_, v)| v / 1000.).collect();
+    let device = dir.join("device");
+    let identifier = fs::canonicalize(&device)
+        .ok()
+        .and_then(|path| path.file_name().map(|name| normalize_pci_address(&name.to_string_lossy())))
+        .unwrap_or_default();
+    let name = read_string(&device.join("product_name"))
+        .filter(|name| !name.is_empty())
+        .unwrap_or(format!("{vendor} GPU ({driver})"));
+    let mut gpu = HardwareInfo {
+        name,
+        identifier,
+        total_temperature: temperatures.first().cloned().unwrap_or(0.),
+        temperatures,
+        fans: read_inputs(dir, "fan").into_iter().map(|(_, v)| v).collect(),
+        //频率单位为Hz
+        clocks: read_inputs(dir, "freq").into_iter().map(|(_, v)| v / 1_000_000.).collect(),
+        ..Default::default()
+    };
+    //功耗单位为微瓦
+    if let Some(power) = read_number(&dir.join("power1_average")).or(read_number(&dir.join("power1_input"))) {
+        gpu.package_power = power / 1_000_000.;
+        gpu.cores_power = gpu.package_power;
+    }
+    if let Some(load) = read_number(&device.join("gpu_busy_percent")) {
+        gpu.total_load = load;
+        gpu.loads.push(load);
+    }
+    let vram_used = read_number(&device.join("mem_info_vram_used"));
+    let vram_total = read_number(&device.join("mem_info_vram_total"));
+    if let (Some(used), Some(total)) = (vram_used, vram_total) {
+        if total > 0. {
+            gpu.memory_load = used / total * 100.;
+            gpu.memory_total = total / 1024. / 1024.;
+        }
+    }
+    gpu
+}
+
+// NVIDIA显卡通过nvidia-smi读取
+fn read_nvidia_smi() -> Result<Vec<HardwareInfo>> {
+    let output = Command::new("nvidia-smi")
+        .args([
+            "--query-gpu=temperature.gpu,utilization.gpu,clocks.gr,power.draw,memory.used,memory.total,name,pci.bus_id",
+            "--format=csv,noheader,nounits",
+        ])
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!("nvidia-smi 执行失败:{:?}", output.status));
+    }
+    let mut gpus = vec![];
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() < 8 {
+            continue;
+        }
+        let values: Vec<f32> = fields[..6].iter().map(|s| s.parse().unwrap_or(0.)).collect();
+        gpus.push(HardwareInfo {
+            name: fields[6].to_string(),
+            identifier: normalize_pci_address(fields[7]),
+            temperatures: vec![values[0]],
+            total_temperature: values[0],
+            loads: vec![values[1]],
+            total_load: values[1],
+            clocks: vec![values[2]],
+            package_power: values[3],
+            cores_power: values[3],
+            memory_load: if values[5] > 0. { values[4] / values[5] * 100. } else { 0. },
+            memory_total: values[5],
+            ..Default::default()
+        });
+    }
+    Ok(gpus)
+}
+
+// 根据两次读取的能量差计算功耗
+struct RaplCounter {
+    path: PathBuf,
+    last: Option<(u64, Instant)>,
+}
+
+impl RaplCounter {
+    fn new(path: &str) -> Self {
+        Self { path: PathBuf::from(path), last: None }
+    }
+
+    fn read_power(&mut self) -> Option<f32> {
+        let energy = read_string(&self.path)?.parse::<u64>().ok()?;
+        let now = Instant::now();
+        let power = match self.last {
+            Some((last_energy, last_time)) if energy >= last_energy => {
+                let secs = now.duration_since(last_time).as_secs_f32();
+                if secs > 0. {
+                    Some((energy - last_energy) as f32 / 1_000_000. / secs)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+        self.last = Some((energy, now));
+        power
+    }
+}
+
+// Linux传感器，读取 /sys/class/hwmon 和 /sys/class/thermal
+pub struct LinuxSensors {
+    // nvidia-smi不可用时不再调用
+    nvidia_smi: bool,
+    //上一次nvidia-smi的结果和读取时间
+    nvidia_gpus: Vec<HardwareInfo>,
+    nvidia_time: Option<Instant>,
+    package_power: RaplCounter,
+    cores_power: RaplCounter,
+}
+
+// 是否可以读取CPU功耗
+pub fn rapl_available() -> bool {
+    Path::new(RAPL_PACKAGE).exists()
+}
+
+impl LinuxSensors {
+    pub fn new() -> Self {
+        Self {
+            nvidia_smi: true,
+            nvidia_gpus: vec![],
+            nvidia_time: None,
+            package_power: RaplCounter::new(RAPL_PACKAGE),
+            cores_power: RaplCounter::new(RAPL_CORES),
+        }
+    }
+
+    pub fn read(&mut self, read_gpu: bool) -> HardwareData {
+        let mut cpu = HardwareInfo::default();
+        let mut gpu_infos = vec![];
+        let mut has_cpu_sensor = false;
+
+        let mut dirs: Vec<PathBuf> = match fs::read_dir(HWMON_DIR) {
+            Ok(entries) => entries.filter_map(|e| e.ok().map(|e| e.path())).collect(),
+            Err(_) => vec![],
+        };
+        dirs.sort();
+        for dir in dirs {
+            let name = read_string(&dir.join("name")).unwrap_or_default();
+            if CPU_SENSORS.contains(&name.as_str()) {
+                has_cpu_sensor = true;
+                let temps = read_inputs(&dir, "temp");
+                //Intel为Package温度，AMD为Tctl/Tdie
+                if let Some((_, total)) = temps
+                    .iter()
+                    .find(|(label, _)| label.starts_with("Package") || label == "Tctl" || label == "Tdie")
+                    .or(temps.first())
+                {
+                    cpu.total_temperature = total / 1000.;
+                }
+                //每个核心的温度，不包含封装温度
+                cpu.temperatures.extend(
+                    temps
+                        .iter()
+                        .filter(|(label, _)| !label.starts_with("Package"))
+                        .map(|(_, v)| v / 1000.),
+                );
+            } else if let Some((driver, vendor)) = GPU_SENSORS.iter().find(|(driver, _)| *driver == name) {
+                if read_gpu {
+                    gpu_infos.push(read_hwmon_gpu(&dir, driver, vendor));
+                }
+            } else {
+                //主板传感器芯片上的风扇
+                cpu.fans.extend(read_inputs(&dir, "fan").into_iter().map(|(_, v)| v));
+            }
+        }
+
+        if !has_cpu_sensor {
+            if let Some(temp) = read_thermal_zone() {
+                cpu.temperatures.push(temp);
+                cpu.total_temperature = temp;
+            }
+        }
+
+        cpu.package_power = self.package_power.read_power().unwrap_or(0.);
+        cpu.cores_power = self.cores_power.read_power().unwrap_or(0.);
+
+        if read_gpu && self.nvidia_smi {
+            if self.nvidia_time.map_or(true, |t| t.elapsed() >= NVIDIA_SMI_INTERVAL) {
+                self.nvidia_time = Some(Instant::now());
+                match read_nvidia_smi() {
+                    Ok(gpus) => self.nvidia_gpus = gpus,
+                    Err(err) => {
+                        info!("nvidia-smi 不可用:{:?}", err);
+                        self.nvidia_smi = false;
+                        self.nvidia_gpus.clear();
+                    }
+                }
+            }
+            gpu_infos.extend(self.nvidia_gpus.iter().cloned());
+        }
+
+        HardwareData {
+            cpu_infos: vec![cpu],
+            gpu_infos,
+        }
+    }
+}
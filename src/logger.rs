@@ -0,0 +1,98 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+use anyhow::Result;
+
+// 单个日志文件最大字节数
+const MAX_LOG_SIZE: u64 = 2 * 1024 * 1024;
+// 保留的历史日志个数
+const MAX_LOG_FILES: usize = 3;
+const LOG_FILE_NAME: &str = "usb-screen.log";
+// 环境变量 USB_SCREEN_LOG=file 或者命令行参数 --log-file 开启文件日志
+pub const LOG_ENV: &str = "USB_SCREEN_LOG";
+pub const LOG_ARG: &str = "--log-file";
+
+// 滚动写入的日志文件，超过大小后 usb-screen.log -> usb-screen.log.1 -> usb-screen.log.2 ...
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path, file, size })
+    }
+
+    fn backup_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{index}"));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        let _ = std::fs::remove_file(self.backup_path(MAX_LOG_FILES));
+        for i in (1..MAX_LOG_FILES).rev() {
+            let _ = std::fs::rename(self.backup_path(i), self.backup_path(i + 1));
+        }
+        std::fs::rename(&self.path, self.backup_path(1))?;
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.size + buf.len() as u64 > MAX_LOG_SIZE {
+            self.rotate()?;
+        }
+        // debug模式同时输出到控制台
+        #[cfg(debug_assertions)]
+        let _ = std::io::stderr().write_all(buf);
+        let n = self.file.write(buf)?;
+        self.size += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+// 日志文件放在exe所在目录
+fn log_file_path() -> Result<PathBuf> {
+    let exe_path = std::env::current_exe()?;
+    let dir = exe_path
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_default();
+    Ok(dir.join(LOG_FILE_NAME))
+}
+
+pub fn file_log_enabled(args: &[String]) -> bool {
+    args.iter().any(|a| a == LOG_ARG)
+        || std::env::var(LOG_ENV)
+            .map(|v| v.eq_ignore_ascii_case("file"))
+            .unwrap_or(false)
+}
+
+pub fn init(log_to_file: bool) {
+    let mut builder = env_logger::builder();
+    builder.filter_level(log::LevelFilter::Info);
+    if log_to_file {
+        match log_file_path().and_then(RotatingFile::open) {
+            Ok(file) => {
+                builder.target(env_logger::Target::Pipe(Box::new(file)));
+            }
+            Err(err) => eprintln!("日志文件打开失败:{:?}", err),
+        }
+    }
+    let _ = builder.try_init();
+}
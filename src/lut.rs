@@ -0,0 +1,124 @@
+use anyhow::{anyhow, Result};
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+// 最大支持的3D LUT边长，常见的为17、33、65
+const MAX_LUT_SIZE: usize = 65;
+
+// .cube格式的3D颜色查找表，table按R变化最快、B变化最慢的顺序排列
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Deserialize, Serialize)]
+pub struct Lut {
+    pub name: String,
+    size: usize,
+    table: Vec<[f32; 3]>,
+}
+
+impl Lut {
+    // 解析.cube文件，name在文件没有TITLE时使用
+    pub fn parse(text: &str, name: &str) -> Result<Self> {
+        let mut title = None;
+        let mut size = 0;
+        let (mut domain_min, mut domain_max) = ([0f32; 3], [1f32; 3]);
+        let mut table = vec![];
+        for line in text.trim_start_matches('\u{feff}').lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut arr = line.split_whitespace();
+            let key = arr.next().unwrap_or("");
+            match key {
+                "TITLE" => title = Some(line[5..].trim().trim_matches('"').to_string()),
+                "LUT_3D_SIZE" => size = arr.next().and_then(|v| v.parse().ok()).unwrap_or(0),
+                "LUT_1D_SIZE" => return Err(anyhow!("不支持1D LUT")),
+                "DOMAIN_MIN" => domain_min = parse_rgb(arr)?,
+                "DOMAIN_MAX" => domain_max = parse_rgb(arr)?,
+                _ if key.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.') => {
+                    table.push(parse_rgb(line.split_whitespace())?);
+                }
+                //忽略其他关键字
+                _ => (),
+            }
+        }
+        if !(2..=MAX_LUT_SIZE).contains(&size) {
+            return Err(anyhow!("LUT大小错误:{size}"));
+        }
+        if table.len() != size * size * size {
+            return Err(anyhow!("LUT数据数量错误:{}，应为{}", table.len(), size * size * size));
+        }
+        //输出值换算到0~1
+        for rgb in table.iter_mut() {
+            for (i, v) in rgb.iter_mut().enumerate() {
+                let range = (domain_max[i] - domain_min[i]).max(f32::EPSILON);
+                *v = ((*v - domain_min[i]) / range).clamp(0., 1.);
+            }
+        }
+        Ok(Self { name: title.filter(|t| !t.is_empty()).unwrap_or(name.to_string()), size, table })
+    }
+
+    fn get(&self, r: usize, g: usize, b: usize) -> [f32; 3] {
+        self.table[r + g * self.size + b * self.size * self.size]
+    }
+
+    // 三线性插值查表
+    fn lookup(&self, rgb: [u8; 3]) -> [u8; 3] {
+        let max = (self.size - 1) as f32;
+        //格点坐标和到下一格点的比例
+        let axis = |v: u8| {
+            let v = v as f32 / 255. * max;
+            let base = (v.floor() as usize).min(self.size - 2);
+            (base, v - base as f32)
+        };
+        let ((r, fr), (g, fg), (b, fb)) = (axis(rgb[0]), axis(rgb[1]), axis(rgb[2]));
+        let mut out = [0u8; 3];
+        for (i, v) in out.iter_mut().enumerate() {
+            let lerp = |from: f32, to: f32, t: f32| from + (to - from) * t;
+            let c00 = lerp(self.get(r, g, b)[i], self.get(r + 1, g, b)[i], fr);
+            let c10 = lerp(self.get(r, g + 1, b)[i], self.get(r + 1, g + 1, b)[i], fr);
+            let c01 = lerp(self.get(r, g, b + 1)[i], self.get(r + 1, g, b + 1)[i], fr);
+            let c11 = lerp(self.get(r, g + 1, b + 1)[i], self.get(r + 1, g + 1, b + 1)[i], fr);
+            let c = lerp(lerp(c00, c10, fg), lerp(c01, c11, fg), fb);
+            *v = (c * 255.).round().clamp(0., 255.) as u8;
+        }
+        out
+    }
+
+    // channels为3(RGB)或者4(RGBA)，透明通道不变
+    pub fn apply(&self, pixels: &mut [u8], channels: usize) {
+        for pixel in pixels.chunks_exact_mut(channels) {
+            let rgb = self.lookup([pixel[0], pixel[1], pixel[2]]);
+            pixel[..3].copy_from_slice(&rgb);
+        }
+    }
+}
+
+fn parse_rgb<'a>(mut arr: impl Iterator<Item = &'a str>) -> Result<[f32; 3]> {
+    let mut rgb = [0f32; 3];
+    for v in rgb.iter_mut() {
+        *v = arr
+            .next()
+            .and_then(|s| s.parse().ok())
+            .ok_or(anyhow!("LUT数据格式错误"))?;
+    }
+    Ok(rgb)
+}
+
+#[test]
+fn test_lut() {
+    //2x2x2的单位LUT，输出与输入相同
+    let identity = "TITLE \"identity\"\nLUT_3D_SIZE 2\n0 0 0\n1 0 0\n0 1 0\n1 1 0\n0 0 1\n1 0 1\n0 1 1\n1 1 1\n";
+    let lut = Lut::parse(identity, "file").unwrap();
+    assert_eq!(lut.name, "identity");
+    let mut pixels = [10u8, 128, 250, 255];
+    lut.apply(&mut pixels, 4);
+    assert_eq!(pixels, [10, 128, 250, 255]);
+
+    //反色LUT
+    let invert = "# invert\nLUT_3D_SIZE 2\n1 1 1\n0 1 1\n1 0 1\n0 0 1\n1 1 0\n0 1 0\n1 0 0\n0 0 0\n";
+    let lut = Lut::parse(invert, "invert").unwrap();
+    let mut pixels = [0u8, 255, 100];
+    lut.apply(&mut pixels, 3);
+    assert_eq!(pixels, [255, 0, 155]);
+
+    assert!(Lut::parse("LUT_3D_SIZE 2\n0 0 0\n", "bad").is_err());
+}
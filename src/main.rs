@@ -1,33 +1,130 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::{path::Path, process::Command, time::{Duration, Instant}};
+use std::{collections::HashMap, path::Path, process::Command, sync::{atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering}, Arc, Mutex}, time::{Duration, Instant, SystemTime}};
 
 use anyhow::{anyhow, Result};
-use image::{buffer::ConvertBuffer, RgbImage};
-use log::{error, info};
+use chrono::Timelike;
+use image::{buffer::ConvertBuffer, RgbImage, Rgba, RgbaImage};
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
 #[cfg(feature = "tray")]
 use tao::event_loop::ControlFlow;
 
-use usb_screen::find_and_open_a_screen;
+use usb_screen::{find_and_open_screen, ScreenError, UsbScreen};
+use utils::LatestSlot;
+use widgets::{Fit, ImageData};
 
-use crate::screen::ScreenRender;
+use crate::{screen::{rotate_frame, scale_frame, ScreenRender, TestPattern}, sensor_spec::SensorSpec};
+mod bidi;
+mod calendar;
+mod config;
 #[cfg(feature = "editor")]
 mod editor;
+#[cfg(target_os = "linux")]
+mod hwmon;
+mod logger;
+mod lut;
 mod monitor;
+mod mono;
 mod nmc;
+mod phone;
+mod ping;
+mod pipe;
 mod rgb565;
+mod rss;
 mod screen;
+mod sensor_spec;
+mod ticker;
+mod transition;
 mod usb_screen;
 mod utils;
 mod widgets;
 #[cfg(all(not(windows),feature = "v4l-webcam"))]
 mod yuv422;
 
+// 当前显示的screen文件，托盘菜单切换布局时修改
+static CURRENT_SCREEN_FILE: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+// 请求退出，发送线程处理完退出时的屏幕显示后设置SHUTDOWN_DONE
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+static SHUTDOWN_DONE: AtomicBool = AtomicBool::new(false);
+// 渲染线程和发送线程最后一次心跳的时间(毫秒)，看门狗据此判断是否卡住
+static LAST_HEARTBEAT: AtomicU64 = AtomicU64::new(0);
+static LAST_TRANSMIT_HEARTBEAT: AtomicU64 = AtomicU64::new(0);
+// 发送线程统计的设备渲染耗时(毫秒)和降帧倍数，渲染线程据此控制帧率
+static DEVICE_RENDER_TIME: AtomicU64 = AtomicU64::new(0);
+static DEVICE_SLOWDOWN: AtomicU64 = AtomicU64::new(1);
+// 看门狗每重启一次渲染循环加1，卡住的旧循环恢复后发现不一致就退出
+static LOOP_GENERATION: AtomicUsize = AtomicUsize::new(0);
+// 发送线程持有的屏幕数量，线程退出并释放所有屏幕后为0，看门狗等待释放后再启动新的循环
+static HELD_SCREENS: AtomicUsize = AtomicUsize::new(0);
+// 环境变量 USB_SCREEN_WATCHDOG 设置看门狗超时秒数，0关闭看门狗
+const WATCHDOG_ENV: &str = "USB_SCREEN_WATCHDOG";
+const DEFAULT_WATCHDOG_SECS: u64 = 10;
+// 看门狗等待旧的发送线程释放屏幕的最长时间，超过后仍然启动新的循环，打开失败时按SCREEN_REOPEN_INTERVAL重试
+const SCREEN_RELEASE_TIMEOUT: Duration = Duration::from_secs(3);
+// 命令行 --image <文件> [--device <串号>] 直接将图片发送到屏幕
+const IMAGE_ARG: &str = "--image";
+const DEVICE_ARG: &str = "--device";
+// 命令行 --pipe [管道路径] 从标准输入或命名管道读取图片和布局，收到后立即显示
+const PIPE_ARG: &str = "--pipe";
+// 命令行 --info 显示所有屏幕的固件版本和功能
+const INFO_ARG: &str = "--info";
+// 命令行 --test-pattern <red|green|blue|white|black|bars|gradient|grid> [--device <串号>] 显示测试图案
+const TEST_PATTERN_ARG: &str = "--test-pattern";
+// 命令行 --import-sensors <CSV或JSON> [--output <文件>] 按AIDA64/Rainmeter的传感器名称生成布局
+const IMPORT_SENSORS_ARG: &str = "--import-sensors";
+const OUTPUT_ARG: &str = "--output";
+// 命令行 --export-frames <screen或图片> [--frames <帧数>] [--size <宽x高>] [--device <串号或地址>] [--output <文件>] 导出设备可以直接回放的帧数据
+// 指定设备时按照设备的像素格式和字节序编码，否则按照RGB565 Big-Endian编码
+const EXPORT_FRAMES_ARG: &str = "--export-frames";
+const FRAMES_ARG: &str = "--frames";
+const SIZE_ARG: &str = "--size";
+const DEFAULT_EXPORT_FRAMES: usize = 30;
+// 命令行 --render-frame <screen文件> [输出png] 渲染一帧保存为图片，不指定输出文件时和布局同名
+const RENDER_FRAME_ARG: &str = "--render-frame";
+// 镜像输出时，每隔一段时间检查设备列表，有屏幕插拔时才打开新连接的屏幕
+const MIRROR_REOPEN_INTERVAL: Duration = Duration::from_secs(2);
+// 没有打开的屏幕时，重新查找的间隔
+const SCREEN_REOPEN_INTERVAL: Duration = Duration::from_secs(2);
+// 按时间段切换布局时检查的间隔
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(20);
+// 检查前台是否有全屏程序的间隔
+const FULLSCREEN_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// 跟随画面源刷新时，没有新的相机或截图帧时其他组件的最长刷新间隔
+const SOURCE_FRAME_TIMEOUT: Duration = Duration::from_secs(1);
+// 检查布局文件是否修改的间隔，修改后等待一段时间没有再变化才重新加载
+const LAYOUT_WATCH_INTERVAL: Duration = Duration::from_millis(500);
+const LAYOUT_RELOAD_DEBOUNCE: Duration = Duration::from_millis(800);
+
 fn main() -> Result<()> {
-    // env_logger::init();
-    let _ = env_logger::builder()
-        .filter_level(log::LevelFilter::Info)
-        .try_init();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    logger::init(logger::file_log_enabled(&args));
+
+    //在切换工作目录之前处理，图片路径相对于启动时的目录
+    if let Some(file) = arg_value(&args, IMAGE_ARG) {
+        return push_image(&file, arg_value(&args, DEVICE_ARG).as_deref());
+    }
+    if let Some(name) = arg_value(&args, TEST_PATTERN_ARG) {
+        return push_test_pattern(&name, arg_value(&args, DEVICE_ARG).as_deref());
+    }
+    if args.iter().any(|a| a == INFO_ARG) {
+        return print_device_info();
+    }
+    if let Some(file) = arg_value(&args, IMPORT_SENSORS_ARG) {
+        return import_sensors(&file, arg_value(&args, OUTPUT_ARG));
+    }
+    if let Some(file) = arg_value(&args, EXPORT_FRAMES_ARG) {
+        return export_frames(&file, &args);
+    }
+    if let Some(pos) = args.iter().position(|a| a == RENDER_FRAME_ARG) {
+        let file = args.get(pos + 1).ok_or(anyhow!("缺少布局文件"))?;
+        let output = args.get(pos + 2).filter(|a| !a.starts_with("--")).cloned();
+        return render_frame(file, output);
+    }
+    if let Some(pos) = args.iter().position(|a| a == PIPE_ARG) {
+        let source = args.get(pos + 1).filter(|a| !a.starts_with("--")).cloned();
+        return run_pipe(source, arg_value(&args, DEVICE_ARG).as_deref());
+    }
 
     #[cfg(windows)]
     {
@@ -38,7 +135,7 @@ fn main() -> Result<()> {
         }
     }
 
-    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<String> = args.into_iter().filter(|a| a != logger::LOG_ARG).collect();
 
     let screen_file = match args.len() {
         0 => read_screen_file(),
@@ -69,55 +166,751 @@ fn main() -> Result<()> {
 }
 
 fn open_usb_screen(file: String) -> Result<()>{
-    let f = std::fs::read(file)?;
-    let mut render = ScreenRender::new_from_file(&f)?;
+    if let Ok(mut f) = CURRENT_SCREEN_FILE.lock(){
+        f.replace(file.clone());
+    }
+    //Ctrl-C或结束进程时，先处理屏幕显示再退出
+    if let Err(err) = ctrlc::set_handler(|| {
+        request_shutdown();
+        std::process::exit(0);
+    }) {
+        error!("退出信号注册失败:{err:?}");
+    }
+    if let Some(schedule) = config::layout_schedule() {
+        std::thread::spawn(move || schedule_layouts(schedule));
+    }
 
+    let timeout = match watchdog_timeout() {
+        None => return render_loop(file, 0),
+        Some(t) => t,
+    };
+    //看门狗：渲染循环超时没有心跳时，放弃卡住的线程，重新打开屏幕
+    heartbeat();
+    transmit_heartbeat();
+    let mut handle = std::thread::spawn(move || render_loop(file, 0));
+    loop {
+        std::thread::sleep(Duration::from_millis(500));
+        if SHUTDOWN_DONE.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        if handle.is_finished() {
+            return handle.join().map_err(|err| anyhow!("{err:?}"))?;
+        }
+        let last_heartbeat = LAST_HEARTBEAT.load(Ordering::SeqCst).min(LAST_TRANSMIT_HEARTBEAT.load(Ordering::SeqCst));
+        let elapsed = (monitor::current_timestamp() as u64).saturating_sub(last_heartbeat);
+        if elapsed > timeout.as_millis() as u64 {
+            let generation = LOOP_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+            error!("渲染循环{elapsed}ms没有响应，重新启动:{generation}");
+            //旧的发送线程发现循环被替换后释放屏幕，传输都有超时，卡住的传输也会返回
+            //释放之前打开会因为设备被占用而失败
+            let start = Instant::now();
+            while HELD_SCREENS.load(Ordering::SeqCst) > 0 && start.elapsed() < SCREEN_RELEASE_TIMEOUT {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            if HELD_SCREENS.load(Ordering::SeqCst) > 0 {
+                warn!("旧的发送线程没有释放屏幕，继续重启");
+            }
+            let file = match CURRENT_SCREEN_FILE.lock().ok().and_then(|f| f.clone()) {
+                Some(file) => file,
+                None => return Err(anyhow!("没有布局文件")),
+            };
+            heartbeat();
+            transmit_heartbeat();
+            handle = std::thread::spawn(move || render_loop(file, generation));
+        }
+    }
+}
+
+fn watchdog_timeout() -> Option<Duration> {
+    let secs = std::env::var(WATCHDOG_ENV)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_WATCHDOG_SECS);
+    if secs == 0 {
+        None
+    } else {
+        Some(Duration::from_secs(secs))
+    }
+}
+
+fn heartbeat() {
+    LAST_HEARTBEAT.store(monitor::current_timestamp() as u64, Ordering::SeqCst);
+}
+
+fn transmit_heartbeat() {
+    LAST_TRANSMIT_HEARTBEAT.store(monitor::current_timestamp() as u64, Ordering::SeqCst);
+}
+
+// 渲染线程交给发送线程的数据，发送线程只处理最新的一帧
+enum Transmit {
+    Frame(RgbImage, ScreenTarget),
+    //退出时显示的画面，发送完设置SHUTDOWN_DONE
+    Shutdown(Option<RgbImage>),
+}
+
+// 布局中打开屏幕需要的设置
+#[derive(Clone)]
+struct ScreenTarget {
+    mirror: bool,
+    mirror_fit: Fit,
+    device_serial: Option<String>,
+    device_address: Option<String>,
+    byte_order: Option<rgb565::ByteOrder>,
+}
+
+impl ScreenTarget {
+    fn new(render: &ScreenRender) -> Self {
+        Self {
+            mirror: render.mirror,
+            mirror_fit: render.mirror_fit,
+            device_serial: render.device_serial.clone(),
+            device_address: render.device_address.clone(),
+            byte_order: render.byte_order,
+        }
+    }
+}
+
+fn render_loop(file: String, generation: usize) -> Result<()>{
+    //渲染和发送分开两个线程，设备卡住时不影响渲染节奏
+    let slot = Arc::new(LatestSlot::new());
+    let transmit = {
+        let slot = slot.clone();
+        std::thread::spawn(move || transmit_loop(slot, generation))
+    };
+    let ret = render_frames(file, generation, &slot);
+    slot.close();
+    //退出时等待发送线程显示完退出画面
+    if SHUTDOWN_REQUESTED.load(Ordering::SeqCst) {
+        let _ = transmit.join();
+    }
+    ret
+}
+
+// 读取布局文件并开始监听组件的数据
+fn load_render(file: &str) -> Result<ScreenRender>{
+    let f = std::fs::read(file)?;
+    let mut render = ScreenRender::from_bytes(&f)?;
+    render.base_dir = Path::new(file).parent().map(|p| p.to_path_buf());
+    render.auto_page = true;
+    render.enable_raster_cache();
     render.setup_monitor()?;
-    let mut usb_screen = usb_screen::find_and_open_a_screen();
-    info!("USB Screen是否已打开: {}", usb_screen.is_some());
+    Ok(render)
+}
+
+// 文件的修改时间和大小，用于判断布局文件是否被修改
+fn file_stamp(file: &str) -> Option<(SystemTime, u64)>{
+    let metadata = std::fs::metadata(file).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn render_frames(file: String, generation: usize, slot: &LatestSlot<Transmit>) -> Result<()>{
+    let mut render = load_render(&file)?;
+    let mut current_file = file;
+    //布局文件修改后自动重新加载
+    let watch_file = config::watch_layout_file();
+    let mut current_stamp = file_stamp(&current_file);
+    let mut last_file_check = Instant::now();
+    let mut pending_reload: Option<Instant> = None;
+
+    let unsupported = render.unsupported_widgets();
+    if !unsupported.is_empty() {
+        warn!("当前系统没有这些组件的数据:{}", unsupported.join(","));
+    }
     let mut last_draw_time = Instant::now();
-    let frame_duration = (1000./render.fps) as u128;
+    let mut frame_duration = (1000./render.fps) as u128;
     info!("帧时间:{}ms", frame_duration);
     //设置系统信息更新延迟
     let _ = monitor::set_update_delay(frame_duration);
+    //全屏程序运行时的刷新率
+    let fullscreen_fps = config::fullscreen_fps();
+    let mut fullscreen = false;
+    let mut last_fullscreen_check = Instant::now();
+    let mut last_source_frame = 0;
     loop {
-        if last_draw_time.elapsed().as_millis() < frame_duration{
+        //看门狗已经启动了新的循环，退出
+        if LOOP_GENERATION.load(Ordering::SeqCst) != generation {
+            info!("渲染循环已被替换，退出:{generation}");
+            return Ok(());
+        }
+        heartbeat();
+
+        //退出时清屏或显示待机图像
+        if SHUTDOWN_REQUESTED.load(Ordering::SeqCst) {
+            let frame = render.shutdown_frame().map(|frame| rotate_frame(frame, render.rotate_degree));
+            slot.put(Transmit::Shutdown(frame));
+            return Ok(());
+        }
+
+        //切换了布局文件，重新加载
+        let new_file = CURRENT_SCREEN_FILE.lock().ok().and_then(|f| f.clone());
+        if let Some(new_file) = new_file.filter(|f| *f != current_file){
+            info!("切换布局:{new_file}");
+            match load_render(&new_file){
+                Ok(mut new_render) => {
+                    //从旧布局的最后一帧过渡
+                    new_render.start_transition(render.take_last_frame());
+                    render = new_render;
+                    frame_duration = (1000./render.fps) as u128;
+                    let _ = monitor::set_update_delay(frame_duration);
+                }
+                Err(err) => error!("布局加载失败:{err:?}"),
+            }
+            current_stamp = file_stamp(&new_file);
+            pending_reload = None;
+            current_file = new_file;
+        }
+
+        //外部程序写入文件时可能连续修改多次，等待文件不再变化后再加载
+        if watch_file && last_file_check.elapsed() >= LAYOUT_WATCH_INTERVAL {
+            last_file_check = Instant::now();
+            let stamp = file_stamp(&current_file);
+            if stamp != current_stamp {
+                current_stamp = stamp;
+                pending_reload = Some(Instant::now());
+            } else if pending_reload.is_some_and(|t| t.elapsed() >= LAYOUT_RELOAD_DEBOUNCE) {
+                pending_reload = None;
+                info!("布局文件已修改，重新加载:{current_file}");
+                //文件写了一半等加载失败时保留当前布局
+                match load_render(&current_file){
+                    Ok(new_render) => {
+                        render = new_render;
+                        frame_duration = (1000./render.fps) as u128;
+                        let _ = monitor::set_update_delay(frame_duration);
+                    }
+                    Err(err) => error!("布局加载失败:{err:?}"),
+                }
+            }
+        }
+
+        //前台有全屏程序时降低帧率，帧率为0时暂停刷新
+        if let Some(fps) = fullscreen_fps {
+            if last_fullscreen_check.elapsed() >= FULLSCREEN_CHECK_INTERVAL {
+                last_fullscreen_check = Instant::now();
+                let active = monitor::is_fullscreen_app_active();
+                if active != fullscreen {
+                    fullscreen = active;
+                    if fullscreen {
+                        info!("检测到全屏程序，刷新率:{fps}");
+                    } else {
+                        info!("全屏程序已退出，恢复刷新");
+                    }
+                }
+            }
+            if fullscreen && fps <= 0. {
+                std::thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+        }
+        let frame_duration = match fullscreen_fps {
+            Some(fps) if fullscreen => frame_duration.max((1000. / fps) as u128),
+            _ => frame_duration,
+        };
+
+        //设备渲染较慢或者画面持续接近大小上限时，按照发送线程统计的结果降低帧率
+        let device_duration = DEVICE_RENDER_TIME.load(Ordering::SeqCst) as u128;
+        let slowdown = DEVICE_SLOWDOWN.load(Ordering::SeqCst).max(1) as u128;
+        if last_draw_time.elapsed().as_millis() < frame_duration.max(device_duration) * slowdown{
             std::thread::sleep(Duration::from_millis(5));
             continue;
         }
+        //跟随画面源刷新，收到相机或截图的新帧时才绘制，帧率作为上限
+        if render.sync_source && render.has_frame_source() {
+            let count = monitor::wait_source_frame(last_source_frame, Duration::from_millis(100));
+            if count == last_source_frame && last_draw_time.elapsed() < SOURCE_FRAME_TIMEOUT {
+                continue;
+            }
+            last_source_frame = count;
+        }
         last_draw_time = Instant::now();
         render.render();
-        let frame: RgbImage = render.canvas.image_data().convert();
-        //旋转
-        let frame = if render.rotate_degree == 90 {
-            image::imageops::rotate90(&frame)
-        }else if render.rotate_degree == 180{
-            image::imageops::rotate180(&frame)
-        }else if render.rotate_degree == 270{
-            image::imageops::rotate270(&frame)
-        }else{
-            frame
-        };
+        let mut frame: RgbImage = render.canvas.image_data().convert();
+        render.apply_transition(&mut frame);
+        render.apply_color_filter(&mut frame, 3);
+        let frame = rotate_frame(frame, render.rotate_degree);
         // let rgb565 = rgb888_to_rgb565_u16(&frame, frame.width() as usize, frame.height() as usize);
-        if usb_screen.is_none() {
-            std::thread::sleep(Duration::from_millis(2000));
+        slot.put(Transmit::Frame(frame, ScreenTarget::new(&render)));
+    }
+}
+
+// 线程退出(包括panic)时，屏幕已经释放，清零HELD_SCREENS
+struct ReleaseScreens;
+
+impl Drop for ReleaseScreens {
+    fn drop(&mut self) {
+        HELD_SCREENS.store(0, Ordering::SeqCst);
+    }
+}
+
+// 发送线程，只发送最新渲染的一帧，来不及发送的旧帧直接丢弃
+fn transmit_loop(slot: Arc<LatestSlot<Transmit>>, generation: usize) {
+    //先声明，在screens之后析构
+    let _release = ReleaseScreens;
+    //每个屏幕单独统计画面复杂度
+    let mut screens: Vec<(UsbScreen, usb_screen::FrameComplexity)> = vec![];
+    let mut last_open_time: Option<Instant> = None;
+    //上次打开屏幕时的设备列表，镜像输出时列表不变就不重新打开，避免重复打开失败的设备和输出日志
+    let mut known_devices: Vec<String> = vec![];
+    //退出画面使用最后一帧的缩放方式
+    let mut last_fit = Fit::Stretch;
+    loop {
+        //看门狗已经启动了新的循环，释放屏幕后退出
+        if LOOP_GENERATION.load(Ordering::SeqCst) != generation {
+            info!("发送线程已被替换，退出:{generation}");
+            return;
+        }
+        transmit_heartbeat();
+        let message = match slot.take(Duration::from_millis(100)) {
+            Some(message) => message,
+            None if slot.is_closed() => return,
+            None => continue,
+        };
+        let (frame, target) = match message {
+            Transmit::Shutdown(frame) => {
+                if let Some(frame) = frame {
+                    let mut scaled = ScaledFrames::default();
+                    for (screen, _) in screens.iter_mut() {
+                        if let Err(err) = screen.draw_rgb_image(0, 0, scaled.get(&frame, screen.info(), last_fit)) {
+                            error!("退出时屏幕绘制失败:{err:?}");
+                        }
+                    }
+                }
+                SHUTDOWN_DONE.store(true, Ordering::SeqCst);
+                return;
+            }
+            Transmit::Frame(frame, target) => (frame, target),
+        };
+        if screens.is_empty() {
+            //没有屏幕时每隔一段时间重新查找，期间的帧直接丢弃
+            if last_open_time.is_some_and(|t| t.elapsed() < SCREEN_REOPEN_INTERVAL) {
+                continue;
+            }
             info!("open USB Screen...");
-            usb_screen = find_and_open_a_screen();
+            known_devices = usb_screen::device_addresses();
+            screens = open_screens(&target, &[]).into_iter().map(|s| (s, Default::default())).collect();
+            info!("已打开的USB Screen数量: {}", screens.len());
+            last_open_time = Some(Instant::now());
+            if screens.is_empty() {
+                continue;
+            }
+        }
+        //镜像输出时打开新连接的屏幕
+        if target.mirror && last_open_time.is_some_and(|t| t.elapsed() > MIRROR_REOPEN_INTERVAL) {
+            let devices = usb_screen::device_addresses();
+            if devices != known_devices {
+                let opened: Vec<String> = screens.iter().map(|(s, _)| s.info().address.clone()).collect();
+                screens.extend(open_screens(&target, &opened).into_iter().map(|s| (s, Default::default())));
+                known_devices = devices;
+            }
+            last_open_time = Some(Instant::now());
+        }
+        last_fit = target.mirror_fit;
+        //同样大小的屏幕只缩放一次
+        let mut scaled = ScaledFrames::default();
+        let held = screens.len();
+        screens.retain_mut(|(screen, complexity)| {
+            let mut frame = scaled.get(&frame, screen.info(), target.mirror_fit).clone();
+            //设备的颜色校准
+            if let Some(profile) = config::color_profile(&screen.info().serial) {
+                profile.apply(&mut frame, 3);
+            }
+            //超过上限的帧拆分成多个条带发送，帧率会降低
+            if let Some(max_frame_size) = screen.frame_size_limit() {
+                complexity.set_max_size(max_frame_size);
+                complexity.update(usb_screen::compressed_frame_size(&frame));
+            }
+            match screen.draw_rgb_image(0, 0, &frame) {
+                Ok(()) => true,
+                //单个条带仍然超过上限时跳过这一帧，设备没有问题，不需要重新打开
+                Err(ScreenError::ImageTooLarge(size)) => {
+                    warn!("画面太复杂，跳过这一帧:{}k", size / 1024);
+                    true
+                }
+                Err(err) => {
+                    error!("屏幕绘制失败:{err:?}");
+                    false
+                }
+            }
+        });
+        //有屏幕绘制失败被释放时，下次检查重新打开
+        if screens.len() < held {
+            known_devices.clear();
+        }
+        //设备渲染较慢时，按照设备回传的耗时降低帧率
+        let device_duration = screens
+            .iter()
+            .filter_map(|(s, _)| s.device_render_time())
+            .map(|ms| ms as u64)
+            .max()
+            .unwrap_or(0);
+        DEVICE_RENDER_TIME.store(device_duration, Ordering::SeqCst);
+        //画面持续接近大小上限时帧率减半
+        let slowdown = if screens.iter().any(|(_, c)| c.is_high()) { 2 } else { 1 };
+        DEVICE_SLOWDOWN.store(slowdown, Ordering::SeqCst);
+        HELD_SCREENS.store(screens.len(), Ordering::SeqCst);
+    }
+}
+
+// 打开布局对应的屏幕，镜像输出时打开除opened之外的所有屏幕
+fn open_screens(target: &ScreenTarget, opened: &[String]) -> Vec<UsbScreen> {
+    let mut screens: Vec<UsbScreen> = if target.mirror {
+        usb_screen::open_all_screens(opened)
+    } else {
+        find_and_open_screen(target.device_serial.as_deref(), target.device_address.as_deref())
+            .into_iter()
+            .collect()
+    };
+    if let Some(byte_order) = target.byte_order {
+        screens.iter_mut().for_each(|s| s.set_byte_order(byte_order));
+    }
+    screens
+}
+
+// 屏幕大小和布局不同时缩放画面，按屏幕大小缓存缩放结果，一个布局同时输出到多个大小不同的屏幕
+#[derive(Default)]
+struct ScaledFrames {
+    frames: HashMap<(u32, u32), RgbImage>,
+}
+
+impl ScaledFrames {
+    fn get(&mut self, frame: &RgbImage, info: &usb_screen::UsbScreenInfo, fit: Fit) -> &RgbImage {
+        let (width, height) = (info.width as u32, info.height as u32);
+        self.frames
+            .entry((width, height))
+            .or_insert_with(|| scale_frame(frame, width, height, fit))
+    }
+}
+
+fn arg_value(args: &[String], name: &str) -> Option<String> {
+    let pos = args.iter().position(|a| a == name)?;
+    args.get(pos + 1).cloned()
+}
+
+// 按串号或地址打开屏幕，没有指定时打开第一个
+fn open_device(device: Option<&str>) -> Result<UsbScreen> {
+    let devices = usb_screen::find_all_device();
+    let info = match device {
+        Some(device) => devices.into_iter().find(|d| d.serial == device || d.address == device),
+        None => devices.into_iter().next(),
+    }
+    .ok_or(anyhow!("没有找到屏幕"))?;
+    Ok(UsbScreen::open(info)?)
+}
+
+// 读取传感器列表生成screen文件，不指定输出文件时和列表同名
+fn import_sensors(file: &str, output: Option<String>) -> Result<()> {
+    let spec = SensorSpec::parse(&std::fs::read_to_string(file)?)?;
+    let mut render = ScreenRender::from_sensor_spec(&spec)?;
+    let output = output.unwrap_or(Path::new(file).with_extension("screen").to_string_lossy().to_string());
+    std::fs::write(&output, render.to_json()?)?;
+    println!("{} 个组件已保存到 {output}", render.widgets.len());
+    Ok(())
+}
+
+// 按照传输协议的格式(IMAGE_AA帧头 + LZ4压缩的像素 + IMAGE_BB)依次写入文件，设备从SD卡读取后可以脱离电脑循环播放
+// screen文件按帧率渲染指定帧数，图片导出所有帧
+fn export_frames(file: &str, args: &[String]) -> Result<()> {
+    let data = std::fs::read(file)?;
+    let is_layout = Path::new(file).extension().map(|e| e == "screen" || e == "json").unwrap_or(false);
+    let frames = if is_layout {
+        let count = match arg_value(args, FRAMES_ARG) {
+            Some(n) => n.parse::<usize>().map_err(|_| anyhow!("帧数错误:{n}"))?,
+            None => DEFAULT_EXPORT_FRAMES,
+        };
+        render_layout_frames(file, &data, count)?
+    } else {
+        let (width, height) = match arg_value(args, SIZE_ARG) {
+            Some(size) => parse_size(&size).ok_or(anyhow!("屏幕大小错误:{size}"))?,
+            None => (160, 128),
+        };
+        let image = ImageData::load(&data, (width, height))?;
+        centered_frames(&image, width, height)?
+    };
+    let (color_mode, byte_order) = match arg_value(args, DEVICE_ARG) {
+        Some(device) => {
+            let info = open_device(Some(&device))?.info().clone();
+            (info.color_mode, info.byte_order)
+        }
+        None => (usb_screen::ColorMode::Rgb565, rgb565::ByteOrder::BigEndian),
+    };
+    let output = arg_value(args, OUTPUT_ARG).unwrap_or(Path::new(file).with_extension("bin").to_string_lossy().to_string());
+    let mut bytes = vec![];
+    for frame in &frames {
+        bytes.extend_from_slice(&usb_screen::encode_wire_frame(frame, color_mode, byte_order));
+    }
+    std::fs::write(&output, &bytes)?;
+    println!("{} 帧({} 字节)已保存到 {output}", frames.len(), bytes.len());
+    Ok(())
+}
+
+// 渲染布局的一帧保存为png，和发送到屏幕的画面一样包含颜色调整和旋转
+fn render_frame(file: &str, output: Option<String>) -> Result<()> {
+    let data = std::fs::read(file)?;
+    let frame = screen::render_single_frame(&data, Path::new(file).parent())?;
+    let output = output.unwrap_or(Path::new(file).with_extension("png").to_string_lossy().to_string());
+    frame.save(&output)?;
+    println!("{}x{} 已保存到 {output}", frame.width(), frame.height());
+    Ok(())
+}
+
+// 按布局的帧率渲染，和发送到屏幕的画面一样包含颜色调整和旋转
+fn render_layout_frames(file: &str, data: &[u8], count: usize) -> Result<Vec<RgbImage>> {
+    let mut render = ScreenRender::from_bytes(data)?;
+    render.base_dir = Path::new(file).parent().map(|p| p.to_path_buf());
+    render.auto_page = true;
+    render.setup_monitor()?;
+    let frame_duration = Duration::from_millis((1000. / render.fps) as u64);
+    let mut frames = vec![];
+    for _ in 0..count {
+        let start = Instant::now();
+        render.render();
+        let mut frame: RgbImage = render.canvas.image_data().convert();
+        render.apply_color_filter(&mut frame, 3);
+        frames.push(rotate_frame(frame, render.rotate_degree));
+        if let Some(rest) = frame_duration.checked_sub(start.elapsed()) {
+            std::thread::sleep(rest);
+        }
+    }
+    monitor::clean();
+    Ok(frames)
+}
+
+// 解析 160x128 格式的屏幕大小
+fn parse_size(size: &str) -> Option<(u32, u32)> {
+    let (w, h) = size.split_once(['x', 'X'])?;
+    let (w, h) = (w.trim().parse().ok()?, h.trim().parse().ok()?);
+    if w == 0 || h == 0 {
+        None
+    } else {
+        Some((w, h))
+    }
+}
+
+// 打开每个屏幕查询固件信息
+fn print_device_info() -> Result<()> {
+    let devices = usb_screen::find_all_device();
+    if devices.is_empty() {
+        println!("没有找到屏幕");
+    }
+    for info in devices {
+        let label = info.label.clone();
+        match UsbScreen::open(info) {
+            Ok(screen) => {
+                let info = screen.info();
+                let firmware = info.firmware.as_ref().map(|f| f.to_string()).unwrap_or("未知(旧固件)".to_string());
+                println!("{} 串号:{} {}x{} 固件:{firmware}", info.label, info.serial, info.width, info.height);
+            }
+            Err(err) => println!("{label} 打开失败:{err}"),
+        }
+    }
+    Ok(())
+}
+
+// 图片的每一帧缩放到屏幕大小，居中放在黑色背景上
+fn centered_frames(image: &ImageData, width: u32, height: u32) -> Result<Vec<RgbImage>> {
+    let mut frames = vec![];
+    for frame in &image.frames {
+        let img = RgbaImage::from_raw(image.width, image.height, frame.clone())
+            .ok_or(anyhow!("图像数据错误"))?;
+        let mut background = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255]));
+        let x = (width as i64 - img.width() as i64) / 2;
+        let y = (height as i64 - img.height() as i64) / 2;
+        image::imageops::overlay(&mut background, &img, x, y);
+        let frame: RgbImage = background.convert();
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+// 图片缩放到屏幕大小居中显示，GIF按照帧延迟循环播放
+fn push_image(file: &str, device: Option<&str>) -> Result<()> {
+    let mut screen = open_device(device)?;
+    let (width, height) = (screen.info().width as u32, screen.info().height as u32);
+
+    let image = ImageData::load(&std::fs::read(file)?, (width, height))?;
+    let frames = centered_frames(&image, width, height)?;
+
+    if frames.len() <= 1 {
+        if let Some(frame) = frames.first() {
+            screen.draw_rgb_image(0, 0, frame)?;
+        }
+        return Ok(());
+    }
+    loop {
+        for (i, frame) in frames.iter().enumerate() {
+            let start = Instant::now();
+            screen.draw_rgb_image(0, 0, frame)?;
+            let delay = image.delays.get(i).cloned().unwrap_or(100) as u64;
+            if let Some(rest) = Duration::from_millis(delay).checked_sub(start.elapsed()) {
+                std::thread::sleep(rest);
+            }
+        }
+    }
+}
+
+// 按屏幕分辨率生成测试图案发送到屏幕，用于检查坏点和颜色
+fn push_test_pattern(name: &str, device: Option<&str>) -> Result<()> {
+    let kind = TestPattern::from_name(name)
+        .ok_or(anyhow!("未知的测试图案:{name}，可选:{}", TestPattern::NAMES.join("|")))?;
+    let mut screen = open_device(device)?;
+    let (width, height) = (screen.info().width as u32, screen.info().height as u32);
+    screen.draw_rgb_image(0, 0, &ScreenRender::test_pattern(kind, width, height))?;
+    Ok(())
+}
+
+// 读取管道中的消息: 图片直接显示，布局按照帧率持续渲染，直到收到下一条消息
+fn run_pipe(source: Option<String>, device: Option<&str>) -> Result<()> {
+    let first_screen = open_device(device)?;
+    let (width, height) = (first_screen.info().width as u32, first_screen.info().height as u32);
+    let mut screen = PipeScreen { screen: Some(first_screen), device: device.map(|d| d.to_string()), last_open_time: Instant::now() };
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut reader: Box<dyn std::io::Read> = match source.as_ref() {
+            Some(path) => match std::fs::File::open(path) {
+                Ok(f) => Box::new(f),
+                Err(err) => {
+                    error!("管道打开失败:{path} {err:?}");
+                    return;
+                }
+            },
+            None => Box::new(std::io::stdin()),
+        };
+        loop {
+            match pipe::read_message(&mut reader) {
+                Ok(Some(message)) => {
+                    if sender.send(message).is_err() {
+                        return;
+                    }
+                }
+                Ok(None) => return,
+                Err(err) => {
+                    error!("管道读取失败:{err:?}");
+                    return;
+                }
+            }
+        }
+    });
+
+    let mut render: Option<ScreenRender> = None;
+    let mut last_draw_time: Option<Instant> = None;
+    let mut closed = false;
+    loop {
+        let message = if closed {
+            //输入结束后继续显示最后的布局
+            if render.is_none() {
+                return Ok(());
+            }
+            std::thread::sleep(Duration::from_millis(5));
+            None
         } else {
-            let screen = usb_screen.as_mut().unwrap();
-            if let Err(err) = screen.draw_rgb_image(
-                0,
-                0,
-                &frame
-            )
-            {
-                error!("屏幕绘制失败:{err:?}");
-                usb_screen = None;
+            match receiver.recv_timeout(Duration::from_millis(5)) {
+                Ok(message) => Some(message),
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => None,
+                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                    closed = true;
+                    None
+                }
+            }
+        };
+        match message {
+            Some(pipe::Message::Image(data)) => {
+                render = None;
+                let image = match ImageData::load(&data, (width, height)) {
+                    Ok(image) => image,
+                    Err(err) => {
+                        error!("图片解码失败:{err:?}");
+                        continue;
+                    }
+                };
+                match centered_frames(&image, width, height) {
+                    Ok(frames) => if let Some(frame) = frames.first() {
+                        screen.draw(frame, Fit::Contain);
+                    },
+                    Err(err) => error!("图片绘制失败:{err:?}"),
+                }
+            }
+            Some(pipe::Message::Layout(data)) => {
+                match ScreenRender::from_bytes(&data) {
+                    Ok(mut new_render) => {
+                        new_render.auto_page = true;
+                        new_render.enable_raster_cache();
+                        if let Err(err) = new_render.setup_monitor() {
+                            error!("监控启动失败:{err:?}");
+                        }
+                        new_render.start_transition(render.as_mut().and_then(|r| r.take_last_frame()));
+                        render = Some(new_render);
+                        last_draw_time = None;
+                    }
+                    Err(err) => error!("布局加载失败:{err:?}"),
+                }
+            }
+            None => (),
+        }
+        let render = match render.as_mut() {
+            Some(render) => render,
+            None => continue,
+        };
+        let frame_duration = (1000. / render.fps) as u128;
+        if last_draw_time.map(|t| t.elapsed().as_millis() < frame_duration).unwrap_or(false) {
+            continue;
+        }
+        last_draw_time = Some(Instant::now());
+        render.render();
+        let mut frame: RgbImage = render.canvas.image_data().convert();
+        render.apply_transition(&mut frame);
+        render.apply_color_filter(&mut frame, 3);
+        let frame = rotate_frame(frame, render.rotate_degree);
+        screen.draw(&frame, render.mirror_fit);
+    }
+}
+
+// 管道模式的屏幕，绘制失败时只记录日志并释放设备，之后每隔一段时间重新打开，不影响继续读取输入
+struct PipeScreen {
+    screen: Option<UsbScreen>,
+    device: Option<String>,
+    last_open_time: Instant,
+}
+
+impl PipeScreen {
+    fn draw(&mut self, frame: &RgbImage, fit: Fit) {
+        if self.screen.is_none() {
+            if self.last_open_time.elapsed() < SCREEN_REOPEN_INTERVAL {
+                return;
+            }
+            self.last_open_time = Instant::now();
+            match open_device(self.device.as_deref()) {
+                Ok(screen) => self.screen = Some(screen),
+                Err(err) => {
+                    error!("屏幕打开失败:{err:?}");
+                    return;
+                }
+            }
+        }
+        if let Some(screen) = self.screen.as_mut() {
+            let (width, height) = (screen.info().width as u32, screen.info().height as u32);
+            match screen.draw_rgb_image(0, 0, &scale_frame(frame, width, height, fit)) {
+                Ok(()) => (),
+                //画面太复杂时跳过这一帧，设备没有问题
+                Err(ScreenError::ImageTooLarge(size)) => warn!("画面太复杂，跳过这一帧:{}k", size / 1024),
+                Err(err) => {
+                    error!("屏幕绘制失败，稍后重新打开:{err:?}");
+                    self.screen = None;
+                    self.last_open_time = Instant::now();
+                }
             }
         }
     }
 }
 
+// 通知渲染线程退出，最多等待3秒
+fn request_shutdown() {
+    SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+    let start = Instant::now();
+    while !SHUTDOWN_DONE.load(Ordering::SeqCst) && start.elapsed() < Duration::from_secs(3) {
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
 fn create_tray_icon(file: String) -> Result<()> {
 
     #[cfg(not(feature = "editor"))]
@@ -140,10 +933,30 @@ fn create_tray_icon(file: String) -> Result<()> {
         let tray_menu = Box::new(tray_icon::menu::Menu::new());
         let quit_i = tray_icon::menu::MenuItem::new("退出", true, None);
         let editor_i = tray_icon::menu::MenuItem::new("编辑器", true, None);
+        //当前目录下所有的布局文件
+        let layout_menu = tray_icon::menu::Submenu::new("切换布局", true);
+        let layout_items: Vec<(tray_icon::menu::MenuItem, String)> = list_screen_files()
+            .into_iter()
+            .map(|file| {
+                let name = Path::new(&file).file_stem().and_then(|s| s.to_str()).unwrap_or(&file).to_string();
+                (tray_icon::menu::MenuItem::new(name, true, None), file)
+            })
+            .collect();
+        for (item, _) in &layout_items {
+            let _ = layout_menu.append(item);
+        }
+        if layout_items.len() > 1 {
+            let _ = tray_menu.append(&layout_menu);
+        }
         let _ = tray_menu.append(&quit_i);
         let _ = tray_menu.append(&editor_i);
         let mut tray_icon = None;
         let mut menu_channel = None;
+        //全局快捷键必须在UI线程上注册，event_loop.run不会返回，manager一直有效
+        let hotkey_manager = global_hotkey::GlobalHotKeyManager::new()
+            .map_err(|err| error!("全局快捷键初始化失败:{err:?}"))
+            .ok();
+        let hotkey_layouts = hotkey_manager.as_ref().map(register_layout_hotkeys).unwrap_or_default();
     
         event_loop.run(move |event, _, control_flow| {
             // We add delay of 16 ms (60fps) to event_loop to reduce cpu load.
@@ -185,6 +998,7 @@ fn create_tray_icon(file: String) -> Result<()> {
             if let (Some(_tray_icon), Some(menu_channel)) = (tray_icon.as_mut(), menu_channel.as_mut()){
                 if let Ok(event) = menu_channel.try_recv() {
                     if event.id == quit_i.id() {
+                        request_shutdown();
                         *control_flow = ControlFlow::Exit;
                     }else if event.id == editor_i.id() {
                         //启动自身
@@ -192,6 +1006,17 @@ fn create_tray_icon(file: String) -> Result<()> {
                             //退出托盘
                             *control_flow = ControlFlow::Exit;
                         }
+                    }else if let Some((_, file)) = layout_items.iter().find(|(item, _)| event.id == item.id()) {
+                        switch_layout(file);
+                    }
+                }
+            }
+
+            if let Ok(event) = global_hotkey::GlobalHotKeyEvent::receiver().try_recv() {
+                if event.state == global_hotkey::HotKeyState::Pressed {
+                    if let Some((_, file)) = hotkey_layouts.iter().find(|(id, _)| *id == event.id) {
+                        info!("快捷键切换布局:{file}");
+                        switch_layout(file);
                     }
                 }
             }
@@ -200,12 +1025,70 @@ fn create_tray_icon(file: String) -> Result<()> {
     Ok(())
 }
 
+// 渲染循环检测到文件变化后重新加载布局
+fn switch_layout(file: &str) {
+    if let Ok(mut current) = CURRENT_SCREEN_FILE.lock(){
+        current.replace(file.to_string());
+    }
+}
+
+// 时间段变化时切换布局，时间段内手动切换的布局保持到下一个时间段
+fn schedule_layouts(schedule: config::LayoutSchedule) {
+    let mut last: Option<String> = None;
+    loop {
+        let now = chrono::Local::now();
+        let minutes = now.hour() * 60 + now.minute();
+        let file = schedule.layout_at(minutes).map(|f| f.to_string());
+        if file != last {
+            if let Some(file) = file.as_deref() {
+                if Path::new(file).exists() {
+                    info!("定时切换布局:{file}");
+                    switch_layout(file);
+                } else {
+                    error!("定时切换的布局文件不存在:{file}");
+                }
+            }
+            last = file;
+        }
+        std::thread::sleep(SCHEDULE_CHECK_INTERVAL);
+    }
+}
+
+// 注册配置文件中的布局快捷键，返回快捷键id和对应的布局文件
+#[cfg(feature = "tray")]
+fn register_layout_hotkeys(manager: &global_hotkey::GlobalHotKeyManager) -> Vec<(u32, String)> {
+    let mut layouts = vec![];
+    for (key, file) in config::layout_hotkeys() {
+        let hotkey = match key.parse::<global_hotkey::hotkey::HotKey>() {
+            Ok(hotkey) => hotkey,
+            Err(err) => {
+                error!("快捷键格式错误:{key} {err:?}");
+                continue;
+            }
+        };
+        if !Path::new(&file).exists() {
+            error!("快捷键{key}对应的布局文件不存在:{file}");
+            continue;
+        }
+        match manager.register(hotkey) {
+            Ok(()) => layouts.push((hotkey.id(), file)),
+            Err(err) => error!("快捷键注册失败:{key} {err:?}"),
+        }
+    }
+    layouts
+}
+
 fn read_screen_file() -> Option<String> {
     // #[cfg(debug_assertions)]
     // {
     //     return None;
     // }
-    //在当前目录下查找.screen文件
+    list_screen_files().into_iter().next()
+}
+
+//在当前目录下查找所有.screen文件
+fn list_screen_files() -> Vec<String> {
+    let mut files = vec![];
     let path = Path::new("./"); // 这里以当前目录为例，你可以替换为任何你想要列出的目录路径
                                 // 使用read_dir函数读取目录条目
     if let Ok(entries) = std::fs::read_dir(path) {
@@ -216,7 +1099,7 @@ fn read_screen_file() -> Option<String> {
                     if let Some(extension) = path.extension() {
                         if extension == "screen" {
                             if let Some(str) = path.to_str() {
-                                return Some(str.to_string());
+                                files.push(str.to_string());
                             }
                         }
                     }
@@ -224,7 +1107,8 @@ fn read_screen_file() -> Option<String> {
             }
         }
     }
-    None
+    files.sort();
+    files
 }
 
 #[cfg(windows)]
@@ -1,6 +1,6 @@
 use anyhow::{anyhow, Result};
 use chinese_number::{ChineseCase, ChineseCountMethod, ChineseVariant, NumberToChinese};
-use chrono::{Datelike, Local};
+use chrono::{Datelike, Local, Timelike};
 use fast_image_resize::{images::Image, Resizer};
 use human_repr::HumanDuration;
 use image::{DynamicImage, RgbImage};
@@ -10,17 +10,32 @@ use nokhwa::{pixel_format::RgbFormat, utils::{CameraFormat, CameraIndex, FrameFo
 use once_cell::sync::Lazy;
 use rust_ephemeris::lunnar::SolorDate;
 use serde::{Deserialize, Serialize};
+use bincode::{Decode, Encode};
 
 use std::{
-    collections::HashMap, process::Child, sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard}, time::{Duration, Instant, SystemTime}
+    collections::{HashMap, HashSet}, process::Child, sync::{atomic::{AtomicBool, Ordering}, Arc, Condvar, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard}, time::{Duration, Instant, SystemTime}
 };
 use sysinfo::Networks;
 
-use crate::nmc::{query_weather, City, RealWeather};
+use crate::nmc::{self, query_weather_data, City, PredictDay, RealWeather};
+use crate::phone::{self, PhoneBattery};
+use crate::ping;
+use crate::calendar::{self, CalendarEvent};
+use crate::rss;
+use crate::ticker::{self, Quote};
 
 const UPDATE_WEATHER_DELAY: u128 = 1000 * 60 * 5;
 const UPDATE_NET_IP_DELAY: u128 = 1000 * 60 * 5;
+const UPDATE_PING_DELAY: u128 = 1000 * 5;
+const UPDATE_TICKER_DELAY: u128 = 1000 * 60;
+const UPDATE_PHONE_DELAY: u128 = 1000 * 30;
+//Linux需要启动xprop，不跟随帧率刷新
+const UPDATE_FOREGROUND_WINDOW_DELAY: u128 = 1000;
+const UPDATE_CALENDAR_DELAY: u128 = 1000 * 60 * 15;
 pub const EMPTY_STRING: &str = "N/A";
+//默认的磁盘最大速度(MB/s)和网络带宽(Mbps)
+pub const DEFAULT_DISK_MAX_SPEED: f32 = 500.;
+pub const DEFAULT_NETWORK_MAX_SPEED: f32 = 1000.;
 
 #[cfg(windows)]
 const OHMS_EXE_FILE: &[u8] =
@@ -43,9 +58,14 @@ pub struct NetIpInfo {
     pub query: String,
 }
 
-#[cfg(windows)]
-#[derive(Debug, Serialize, Deserialize)]
+#[cfg(any(windows, target_os = "linux"))]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct HardwareInfo {
+    //显卡名称和稳定的标识(例如PCI地址)，旧版本的监控服务没有上报
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub identifier: String,
     pub fans: Vec<f32>,
     pub temperatures: Vec<f32>,
     pub loads: Vec<f32>,
@@ -58,13 +78,142 @@ pub struct HardwareInfo {
     pub memory_total: f32,
 }
 
-#[cfg(windows)]
-#[derive(Debug, Serialize, Deserialize)]
+#[cfg(any(windows, target_os = "linux"))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HardwareData {
     pub cpu_infos: Vec<HardwareInfo>,
     pub gpu_infos: Vec<HardwareInfo>,
 }
 
+#[cfg(any(windows, target_os = "linux"))]
+impl HardwareInfo {
+    // 按照校准值修正读数，kind为 "cpu" 或者 "gpu"
+    fn apply_corrections(&mut self, kind: &str, corrections: &[Correction]) {
+        for c in corrections {
+            match c.type_name.strip_prefix(kind) {
+                Some("_temp.") => {
+                    self.temperatures.iter_mut().for_each(|v| *v = c.apply(*v));
+                    self.total_temperature = c.apply(self.total_temperature);
+                }
+                Some("_fan") => self.fans.iter_mut().for_each(|v| *v = c.apply(*v)),
+                Some("_clock") => self.clocks.iter_mut().for_each(|v| *v = c.apply(*v)),
+                Some("_load") => {
+                    self.loads.iter_mut().for_each(|v| *v = c.apply(*v));
+                    self.total_load = c.apply(self.total_load);
+                }
+                Some("_memory_load") => self.memory_load = c.apply(self.memory_load),
+                Some("_package_power") => self.package_power = c.apply(self.package_power),
+                Some("_cores_power") => self.cores_power = c.apply(self.cores_power),
+                _ => (),
+            }
+        }
+    }
+}
+
+// 不同厂商的显卡统一补全名称和标识，并按标识排序，索引不受上报顺序影响
+#[cfg(any(windows, target_os = "linux"))]
+fn normalize_gpus(gpu_infos: &mut [HardwareInfo]) {
+    for (i, gpu) in gpu_infos.iter_mut().enumerate() {
+        if gpu.name.is_empty() {
+            gpu.name = format!("GPU {}", i + 1);
+        }
+        //没有标识时使用名称和上报顺序
+        if gpu.identifier.is_empty() {
+            gpu.identifier = format!("{}#{i}", gpu.name);
+        }
+    }
+    gpu_infos.sort_by(|a, b| a.identifier.cmp(&b.identifier));
+}
+
+// 校准后写入传感器数据
+#[cfg(any(windows, target_os = "linux"))]
+fn update_hardware_data(ctx: &mut SystemInfo, mut info: HardwareData) {
+    normalize_gpus(&mut info.gpu_infos);
+    for cpu_info in info.cpu_infos.iter_mut() {
+        cpu_info.apply_corrections("cpu", &ctx.corrections);
+    }
+    for gpu_info in info.gpu_infos.iter_mut() {
+        gpu_info.apply_corrections("gpu", &ctx.corrections);
+    }
+    if info.cpu_infos.len() > 0 {
+        ctx.cpu_temperatures = info.cpu_infos[0].temperatures.clone();
+        ctx.cpu_fans = info.cpu_infos[0].fans.clone();
+        ctx.cpu_temperature_total = info.cpu_infos[0].total_temperature;
+        ctx.cpu_cores_power = info.cpu_infos[0].cores_power;
+        ctx.cpu_package_power = info.cpu_infos[0].package_power;
+    }
+    ctx.gpu_clocks.clear();
+    ctx.gpu_fans.clear();
+    ctx.gpu_load.clear();
+    ctx.gpu_temperatures.clear();
+    ctx.gpu_temperature_total.clear();
+    ctx.gpu_load_total.clear();
+    ctx.gpu_memory_load.clear();
+    ctx.gpu_memory_total.clear();
+    ctx.gpu_devices = info.gpu_infos.iter().map(|gpu| (gpu.identifier.clone(), gpu.name.clone())).collect();
+    for gpu_info in info.gpu_infos {
+        ctx.gpu_clocks.push(gpu_info.clocks.clone());
+        ctx.gpu_temperatures.push(gpu_info.temperatures.clone());
+        ctx.gpu_fans.push(gpu_info.fans.clone());
+        ctx.gpu_load.push(gpu_info.loads.clone());
+        ctx.gpu_temperature_total.push(gpu_info.total_temperature);
+        ctx.gpu_load_total.push(gpu_info.total_load);
+        ctx.gpu_cores_power = gpu_info.cores_power;
+        ctx.gpu_package_power = gpu_info.package_power;
+        ctx.gpu_memory_load.push(gpu_info.memory_load);
+        ctx.gpu_memory_total.push(gpu_info.memory_total);
+    }
+    accumulate_energy(ctx);
+}
+
+// 两次读数间隔过长时(停止监听后重新开始)不累加，避免一次加上很长时间的耗电
+#[cfg(any(windows, target_os = "linux"))]
+const MAX_ENERGY_INTERVAL: Duration = Duration::from_secs(10);
+
+#[cfg(any(windows, target_os = "linux"))]
+fn accumulate_energy(ctx: &mut SystemInfo) {
+    let now = Instant::now();
+    if let Some(last) = ctx.last_power_time {
+        let elapsed = now.duration_since(last);
+        if elapsed <= MAX_ENERGY_INTERVAL {
+            let hours = elapsed.as_secs_f64() / 3600.;
+            ctx.cpu_energy_wh += ctx.cpu_package_power.max(0.) as f64 * hours;
+            ctx.gpu_energy_wh += ctx.gpu_package_power.max(0.) as f64 * hours;
+        }
+    }
+    ctx.last_power_time = Some(now);
+}
+
+// 可以校准的传感器数值
+pub const CORRECTION_TYPES: [&str; 11] = [
+    "cpu_temp.",
+    "cpu_fan",
+    "cpu_package_power",
+    "cpu_cores_power",
+    "gpu_temp.",
+    "gpu_fan",
+    "gpu_clock",
+    "gpu_load",
+    "gpu_memory_load",
+    "gpu_package_power",
+    "gpu_cores_power",
+];
+
+// 传感器读数校准: value * scale + offset
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Serialize, Deserialize)]
+pub struct Correction {
+    pub type_name: String,
+    pub scale: f32,
+    pub offset: f32,
+}
+
+impl Correction {
+    #[allow(unused)]
+    pub fn apply(&self, value: f32) -> f32 {
+        value * self.scale + self.offset
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebcamInfo{
     pub index: u32,
@@ -73,6 +222,58 @@ pub struct WebcamInfo{
     pub height: u32
 }
 
+// 截取主显示器的区域(x, y, 宽, 高)，None截取整个显示器，缩放到不超过width x height
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(not(feature = "desktop-capture"), allow(dead_code))]
+pub struct ScreenCaptureInfo{
+    pub region: Option<(i32, i32, u32, u32)>,
+    pub fps: u32,
+    pub width: u32,
+    pub height: u32
+}
+
+//统计类型，对应TextWidget的tag1: 2最小值 3最大值 4平均值
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetricStat {
+    Min,
+    Max,
+    Avg,
+}
+
+impl MetricStat {
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "2" => Some(MetricStat::Min),
+            "3" => Some(MetricStat::Max),
+            "4" => Some(MetricStat::Avg),
+            _ => None,
+        }
+    }
+}
+
+//数值的最小、最大、平均值
+#[derive(Debug, Clone, Default)]
+struct MetricStats {
+    min: f32,
+    max: f32,
+    sum: f64,
+    count: u64,
+}
+
+impl MetricStats {
+    fn record(&mut self, value: f32) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.sum += value as f64;
+        self.count += 1;
+    }
+}
+
 pub struct SystemInfo {
     update_delay: u128,
     watch_memory: bool,
@@ -88,9 +289,26 @@ pub struct SystemInfo {
     watch_gpu_fan: bool,
     watch_gpu_load: bool,
     watch_process: bool,
-    watch_weather: Option<City>,
+    //系统负载(Unix)和线程总数
+    watch_load: bool,
+    //前台窗口的标题
+    watch_foreground_window: bool,
+    //监听天气的城市，城市名称对应城市信息
+    watch_weather: HashMap<String, City>,
+    //订阅地址以及刷新间隔(分钟)
+    watch_feeds: HashMap<String, u64>,
+    //日历订阅地址
+    watch_calendars: HashSet<String>,
+    //检测是否在线的主机
+    watch_ping: HashSet<String>,
+    //行情(接口地址, 价格和涨跌幅的路径)
+    watch_tickers: HashSet<(String, String)>,
+    //手机的adb序列号或配套程序地址
+    watch_phones: HashSet<String>,
     watch_network_speed: bool,
     watch_net_ip: bool,
+    //按公网IP定位天气城市
+    watch_auto_weather: bool,
 
     memory_info: String,
     memory_percent: String,
@@ -111,31 +329,72 @@ pub struct SystemInfo {
     gpu_temperature_total: Vec<f32>,
     gpu_package_power: f32,
     gpu_cores_power: f32,
+    //本次运行累计的耗电量(Wh)，按照封装功耗和上报间隔累加
+    cpu_energy_wh: f64,
+    gpu_energy_wh: f64,
+    last_power_time: Option<Instant>,
     gpu_fans: Vec<Vec<f32>>,
     gpu_load: Vec<Vec<f32>>,
     gpu_memory_load: Vec<f32>,
     gpu_memory_total: Vec<f32>,
     gpu_load_total: Vec<f32>,
+    //检测到的显卡(标识, 名称)，与gpu_*的索引对应
+    gpu_devices: Vec<(String, String)>,
     num_process: String,
+    load_average: String,
+    num_threads: String,
+    //None为无法获取
+    foreground_window_title: Option<String>,
     disk_usage: HashMap<usize, String>,
+    //剩余空间最少的磁盘的剩余百分比
+    disk_free_percent: Option<f32>,
     disk_speed_per_sec: (String, String),
     network_speed_per_sec: (String, String),
+    //磁盘读写、网络收发的字节/秒，用于计算占用百分比
+    disk_bytes_per_sec: (f64, f64),
+    network_bytes_per_sec: (f64, f64),
+    //本次运行累计的网络接收、发送字节数
+    network_total: (u64, u64),
+    //磁盘最大速度(MB/s)和网络带宽(Mbps)
+    disk_max_speed: f32,
+    network_max_speed: f32,
     system_name: String,
     kernel_version: String,
     os_version: String,
     host_name: String,
     local_ip: String,
     net_ip: Option<NetIpInfo>,
-    weather_info: Option<RealWeather>,
+    //各城市的实时天气和天气预报，天气预报第一个为今天
+    weather: HashMap<String, (RealWeather, Vec<PredictDay>)>,
+    //订阅地址对应的标题
+    feed_titles: HashMap<String, String>,
+    //日历订阅地址对应的未过去的事件，按开始时间排序
+    calendar_events: HashMap<String, Vec<CalendarEvent>>,
+    //主机的往返时间(毫秒)，None为离线
+    ping: HashMap<String, Option<f32>>,
+    tickers: HashMap<(String, String), Quote>,
+    //手机的电池状态，None为未连接
+    phones: HashMap<String, Option<PhoneBattery>>,
     cpu_freq_query_task: Option<std::thread::JoinHandle<()>>,
     watch_disk_speed_task: Option<std::thread::JoinHandle<()>>,
     watch_network_speed_task: Option<std::thread::JoinHandle<()>>,
-    watch_webcam_task: Option<std::thread::JoinHandle<()>>,
+    //每个相机一个拍照线程
+    watch_webcam_tasks: HashMap<u32, std::thread::JoinHandle<()>>,
     hardware_monitor_service: Option<Child>,
-    //缓存最新的相机图像
-    webcam_frame: Option<RgbImage>,
+    //按相机编号缓存最新的相机图像
+    webcam_frames: HashMap<u32, RgbImage>,
     //监控的相机编号以及帧率
-    webcam_info: Option<WebcamInfo>
+    webcams: HashMap<u32, WebcamInfo>,
+    //桌面截图的区域和帧率，截图线程缓存最新的一帧
+    screen_capture: Option<ScreenCaptureInfo>,
+    screen_capture_frame: Option<RgbImage>,
+    #[cfg_attr(not(feature = "desktop-capture"), allow(dead_code))]
+    watch_screen_capture_task: Option<std::thread::JoinHandle<()>>,
+    //数值统计
+    stats: HashMap<&'static str, MetricStats>,
+    corrections: Vec<Correction>,
+    //外部程序通过http推送的自定义数值
+    custom_values: HashMap<String, String>,
 }
 
 impl SystemInfo {
@@ -154,9 +413,12 @@ impl SystemInfo {
             watch_gpu_temperatures: false,
             watch_gpu_load: false,
             watch_process: false,
+            watch_load: false,
+            watch_foreground_window: false,
             watch_disk_speed: false,
             watch_network_speed: false,
             watch_net_ip: false,
+            watch_auto_weather: false,
 
             memory_info: EMPTY_STRING.to_string(),
             swap_info: EMPTY_STRING.to_string(),
@@ -179,38 +441,129 @@ impl SystemInfo {
             gpu_temperatures: vec![],
             gpu_cores_power: 0.,
             gpu_package_power: 0.,
+            cpu_energy_wh: 0.,
+            gpu_energy_wh: 0.,
+            last_power_time: None,
             gpu_temperature_total: vec![],
+            gpu_devices: vec![],
             num_process: EMPTY_STRING.to_string(),
+            load_average: EMPTY_STRING.to_string(),
+            num_threads: EMPTY_STRING.to_string(),
+            foreground_window_title: None,
             disk_usage: HashMap::new(),
+            disk_free_percent: None,
             system_name: EMPTY_STRING.to_string(),
             kernel_version: sysinfo::System::kernel_version().unwrap_or(String::from("N/A")),
             os_version: sysinfo::System::os_version().unwrap_or(String::from("N/A")),
             host_name: sysinfo::System::host_name().unwrap_or(String::from("N/A")),
-            watch_weather: None,
-            weather_info: None,
+            watch_weather: HashMap::new(),
+            weather: HashMap::new(),
+            watch_feeds: HashMap::new(),
+            feed_titles: HashMap::new(),
+            watch_calendars: HashSet::new(),
+            calendar_events: HashMap::new(),
+            watch_ping: HashSet::new(),
+            ping: HashMap::new(),
+            watch_tickers: HashSet::new(),
+            tickers: HashMap::new(),
+            watch_phones: HashSet::new(),
+            phones: HashMap::new(),
             cpu_freq_query_task: None,
             disk_speed_per_sec: (EMPTY_STRING.to_string(), EMPTY_STRING.to_string()),
             watch_disk_speed_task: None,
             watch_network_speed_task: None,
             network_speed_per_sec: (EMPTY_STRING.to_string(), EMPTY_STRING.to_string()),
+            disk_bytes_per_sec: (0., 0.),
+            network_bytes_per_sec: (0., 0.),
+            network_total: (0, 0),
+            disk_max_speed: DEFAULT_DISK_MAX_SPEED,
+            network_max_speed: DEFAULT_NETWORK_MAX_SPEED,
             memory_percent: EMPTY_STRING.to_string(),
             swap_percent: EMPTY_STRING.to_string(),
             hardware_monitor_service: None,
             local_ip: EMPTY_STRING.to_string(),
             net_ip: None,
-            webcam_frame: None,
-            webcam_info: None,
-            watch_webcam_task: None,
+            webcam_frames: HashMap::new(),
+            webcams: HashMap::new(),
+            watch_webcam_tasks: HashMap::new(),
+            screen_capture: None,
+            screen_capture_frame: None,
+            watch_screen_capture_task: None,
+            stats: HashMap::new(),
+            custom_values: HashMap::new(),
+            corrections: vec![],
         }
     }
 }
 
+//需要硬件传感器的组件类型，Windows由OpenHardwareMonitorService提供，Linux读取hwmon
+#[cfg(not(any(windows, target_os = "linux")))]
+const SENSOR_METRICS: &[&str] = &["cpu_temp.", "cpu_fan", "cooling", "cpu_package_power", "cpu_cores_power"];
+#[cfg(not(windows))]
+const GPU_METRICS: &[&str] = &[
+    "gpu_temp.", "gpu_load", "gpu_memory_load", "gpu_memory_total_mb", "gpu_memory_total_gb", "gpu_memory_used_gb",
+    "gpu_clock", "gpu_fan", "gpu_package_power", "gpu_cores_power",
+];
+
+//当前系统读取不到数据的组件类型，第一次使用时检测
+static UNSUPPORTED_METRICS: Lazy<Vec<&'static str>> = Lazy::new(detect_unsupported_metrics);
+
+//时间使用24小时制，布局设置
+static TIME_24H: AtomicBool = AtomicBool::new(true);
+
 static SYSTEM_INFO: Lazy<Arc<RwLock<SystemInfo>>> = Lazy::new(|| {
     let ctx = Arc::new(RwLock::new(SystemInfo::new()));
     start_refresh_task(ctx.clone());
     ctx
 });
 
+//相机和桌面截图的新帧计数，渲染循环跟随画面源刷新时等待新帧
+static SOURCE_FRAME: Lazy<(Mutex<u64>, Condvar)> = Lazy::new(|| (Mutex::new(0), Condvar::new()));
+
+//正在后台线程中更新的数据源，上一次还没有结束时跳过，避免卡住的请求越积越多
+static UPDATING: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+// 在新线程中更新数据，同一个key上一次的更新还没有结束时不启动
+fn spawn_update(key: String, update: impl FnOnce() + Send + 'static) {
+    match UPDATING.lock() {
+        Ok(mut updating) if updating.insert(key.clone()) => (),
+        _ => return,
+    }
+    std::thread::spawn(move || {
+        //线程panic时也要清除标记
+        struct Done(String);
+        impl Drop for Done {
+            fn drop(&mut self) {
+                if let Ok(mut updating) = UPDATING.lock() {
+                    updating.remove(&self.0);
+                }
+            }
+        }
+        let _done = Done(key);
+        update();
+    });
+}
+
+fn notify_source_frame() {
+    let (count, condvar) = &*SOURCE_FRAME;
+    if let Ok(mut count) = count.lock() {
+        *count += 1;
+        condvar.notify_all();
+    }
+}
+
+// 等待相机或截图的新帧，last为上一次的计数，超时后返回当前计数
+pub fn wait_source_frame(last: u64, timeout: Duration) -> u64 {
+    let (count, condvar) = &*SOURCE_FRAME;
+    let Ok(count) = count.lock() else {
+        return last;
+    };
+    match condvar.wait_timeout_while(count, timeout, |count| *count == last) {
+        Ok((count, _)) => *count,
+        Err(_) => last,
+    }
+}
+
 fn try_write<'a, F: Fn(RwLockWriteGuard<'a, SystemInfo>)>(callback: F) {
     if let Ok(ctx) = SYSTEM_INFO.try_write() {
         callback(ctx);
@@ -231,13 +584,22 @@ fn start_refresh_task(ctx: Arc<RwLock<SystemInfo>>) {
 
         let mut sysinfo_system = sysinfo::System::new_all();
         let mut sysinfo_disks = sysinfo::Disks::new();
+        #[cfg(target_os = "linux")]
+        let mut linux_sensors = crate::hwmon::LinuxSensors::new();
 
         let mut last_update_time = 0;
         let mut last_update_net_ip_time = 0;
+        let mut last_ping_time = 0;
+        let mut last_ticker_time = 0;
+        let mut last_phone_time = 0;
+        let mut last_foreground_window_time = 0;
 
         //(city, time)
-        let last_weather_update_store: Arc<Mutex<(Option<City>, u128)>> =
-            Arc::new(Mutex::new((None, 0)));
+        let mut last_weather_update_time: HashMap<String, u128> = HashMap::new();
+        //(url, time)
+        let mut last_feed_update_time: HashMap<String, u128> = HashMap::new();
+        //(url, time)
+        let mut last_calendar_update_time: HashMap<String, u128> = HashMap::new();
 
         loop {
             let current_time = current_timestamp();
@@ -248,40 +610,181 @@ fn start_refresh_task(ctx: Arc<RwLock<SystemInfo>>) {
             };
 
             //相机根据帧率刷新
-            let watch_webcam = match ctx.read() {
+            let watch_webcams = match ctx.read() {
                 Err(_err) => return,
-                Ok(ctx) => ctx.webcam_info.is_some(),
+                Ok(ctx) => ctx.webcams.keys().cloned().collect::<Vec<u32>>(),
             };
 
             //天气30分钟更新一次
-            let watch_weather_data = match ctx.read() {
+            let watch_weather = match ctx.read() {
                 Err(_err) => return,
                 Ok(ctx) => ctx.watch_weather.clone(),
             };
+            //按城市编号计时，自动定位的城市变化后立即更新
+            for (name, city) in watch_weather {
+                let last_time = last_weather_update_time.get(&city.code).cloned().unwrap_or(0);
+                if current_time - last_time <= UPDATE_WEATHER_DELAY {
+                    continue;
+                }
+                last_weather_update_time.insert(city.code.clone(), current_time);
+                spawn_update(format!("weather:{}", city.code), move || {
+                    info!("开始更新天气 {:?}", city);
+                    let weather = match query_weather_data(&city.code) {
+                        Err(err) => {
+                            error!("天气更新失败:{:?}", err);
+                            return;
+                        }
+                        Ok(info) => info,
+                    };
+                    info!("天气已更新:{:?}", weather);
+                    if let Ok(mut ctx) = SYSTEM_INFO.write() {
+                        let forecast = weather.predict.map(|p| p.detail).unwrap_or_default();
+                        ctx.weather.insert(name, (weather.real, forecast));
+                    }
+                });
+            }
+
+            //订阅按照各自的间隔更新
+            let watch_feeds = match ctx.read() {
+                Err(_err) => return,
+                Ok(ctx) => ctx.watch_feeds.clone(),
+            };
+            for (url, interval) in watch_feeds {
+                let last_time = last_feed_update_time.get(&url).cloned().unwrap_or(0);
+                if current_time - last_time <= interval as u128 * 60 * 1000 {
+                    continue;
+                }
+                last_feed_update_time.insert(url.clone(), current_time);
+                spawn_update(format!("feed:{url}"), move || {
+                    info!("开始更新订阅 {url}");
+                    let titles = match rss::query_titles(&url) {
+                        Err(err) => {
+                            error!("订阅更新失败:{:?}", err);
+                            return;
+                        }
+                        Ok(titles) => titles,
+                    };
+                    info!("订阅已更新:{}条", titles.len());
+                    if let Ok(mut ctx) = SYSTEM_INFO.write() {
+                        ctx.feed_titles.insert(url, titles.join(rss::TITLE_SEPARATOR));
+                    }
+                });
+            }
 
-            if let (Ok(mut last_weather_time), Some(city)) =
-                (last_weather_update_store.lock(), watch_weather_data)
-            {
-                if let Some(last_city) = last_weather_time.0.as_ref() {
-                    if last_city.code != city.code {
-                        last_weather_time.1 = 0;
+            //日历15分钟更新一次
+            let watch_calendars = match ctx.read() {
+                Err(_err) => return,
+                Ok(ctx) => ctx.watch_calendars.clone(),
+            };
+            for url in watch_calendars {
+                let last_time = last_calendar_update_time.get(&url).cloned().unwrap_or(0);
+                if current_time - last_time <= UPDATE_CALENDAR_DELAY {
+                    continue;
+                }
+                last_calendar_update_time.insert(url.clone(), current_time);
+                spawn_update(format!("calendar:{url}"), move || {
+                    info!("开始更新日历 {url}");
+                    let events = match calendar::query_events(&url) {
+                        Err(err) => {
+                            error!("日历更新失败:{:?}", err);
+                            return;
+                        }
+                        Ok(events) => events,
+                    };
+                    info!("日历已更新:{}个事件", events.len());
+                    if let Ok(mut ctx) = SYSTEM_INFO.write() {
+                        ctx.calendar_events.insert(url, events);
                     }
+                });
+            }
+
+            //在线检测5秒一次
+            if current_time - last_ping_time > UPDATE_PING_DELAY {
+                let watch_ping = match ctx.read() {
+                    Err(_err) => return,
+                    Ok(ctx) => ctx.watch_ping.clone(),
+                };
+                if watch_ping.len() > 0 {
+                    last_ping_time = current_time;
+                }
+                for host in watch_ping {
+                    spawn_update(format!("ping:{host}"), move || {
+                        let latency = match ping::ping(&host) {
+                            Err(err) => {
+                                error!("ping失败 {host}:{:?}", err);
+                                None
+                            }
+                            Ok(latency) => latency,
+                        };
+                        if let Ok(mut ctx) = SYSTEM_INFO.write() {
+                            ctx.ping.insert(host, latency);
+                        }
+                    });
+                }
+            }
+
+            //行情1分钟更新一次
+            if current_time - last_ticker_time > UPDATE_TICKER_DELAY {
+                let watch_tickers = match ctx.read() {
+                    Err(_err) => return,
+                    Ok(ctx) => ctx.watch_tickers.clone(),
+                };
+                if watch_tickers.len() > 0 {
+                    last_ticker_time = current_time;
                 }
-                last_weather_time.0.replace(city.clone());
-                if current_time - last_weather_time.1 > UPDATE_WEATHER_DELAY {
-                    last_weather_time.1 = current_time;
-                    std::thread::spawn(move || {
-                        info!("开始更新天气 {:?}", city);
-                        let weather = match query_weather(&city.code) {
+                for (url, paths) in watch_tickers {
+                    spawn_update(format!("ticker:{url} {paths}"), move || {
+                        let quote = match ticker::query_quote(&url, &paths) {
                             Err(err) => {
-                                error!("天气更新失败:{:?}", err);
+                                error!("行情更新失败 {url}:{:?}", err);
                                 return;
                             }
-                            Ok(info) => info,
+                            Ok(quote) => quote,
+                        };
+                        if let Ok(mut ctx) = SYSTEM_INFO.write() {
+                            ctx.tickers.insert((url, paths), quote);
+                        }
+                    });
+                }
+            }
+
+            //手机电池30秒更新一次
+            if current_time - last_phone_time > UPDATE_PHONE_DELAY {
+                let watch_phones = match ctx.read() {
+                    Err(_err) => return,
+                    Ok(ctx) => ctx.watch_phones.clone(),
+                };
+                if watch_phones.len() > 0 {
+                    last_phone_time = current_time;
+                }
+                for source in watch_phones {
+                    spawn_update(format!("phone:{source}"), move || {
+                        let battery = match phone::query_battery(&source) {
+                            Err(err) => {
+                                error!("手机电池更新失败 {source}:{:?}", err);
+                                None
+                            }
+                            Ok(battery) => battery,
                         };
-                        info!("天气已更新:{:?}", weather);
                         if let Ok(mut ctx) = SYSTEM_INFO.write() {
-                            ctx.weather_info = Some(weather);
+                            ctx.phones.insert(source, battery);
+                        }
+                    });
+                }
+            }
+
+            //前台窗口1秒更新一次，在单独的线程中读取，上一次没有返回时跳过
+            if current_time - last_foreground_window_time > UPDATE_FOREGROUND_WINDOW_DELAY {
+                let watch_foreground_window = match ctx.read() {
+                    Err(_err) => return,
+                    Ok(ctx) => ctx.watch_foreground_window,
+                };
+                if watch_foreground_window {
+                    last_foreground_window_time = current_time;
+                    spawn_update("foreground_window".to_string(), || {
+                        let title = read_foreground_window_title();
+                        if let Ok(mut ctx) = SYSTEM_INFO.write() {
+                            ctx.foreground_window_title = title;
                         }
                     });
                 }
@@ -300,6 +803,14 @@ fn start_refresh_task(ctx: Arc<RwLock<SystemInfo>>) {
                     std::thread::spawn(|| {
                         if let Ok(net_ip_info) = query_net_ip() {
                             if let Ok(mut ctx) = SYSTEM_INFO.write() {
+                                if ctx.watch_auto_weather {
+                                    match nmc::find_city(&net_ip_info.region_name, &net_ip_info.city) {
+                                        Some(city) => {
+                                            ctx.watch_weather.insert(nmc::AUTO_CITY.to_string(), city);
+                                        }
+                                        None => warn!("没有找到定位的城市:{} {}", net_ip_info.region_name, net_ip_info.city),
+                                    }
+                                }
                                 ctx.net_ip = Some(net_ip_info);
                             }
                         }
@@ -330,12 +841,14 @@ fn start_refresh_task(ctx: Arc<RwLock<SystemInfo>>) {
                 let mut watch_memory = false;
                 let mut watch_disk = false;
                 let mut watch_process = false;
+                let mut watch_load = false;
                 let mut watch_cpu_clock_speed = false;
                 let mut watch_disk_speed = false;
                 let mut watch_network_speed = false;
 
-                #[cfg(target_os = "linux")]
-                let mut watch_cpu_temperature = false;
+                //CPU温度、风扇、功耗和显卡
+                let mut watch_sensors = false;
+                let mut watch_gpu = false;
 
                 if let Ok(ctx) = SYSTEM_INFO.read() {
                     watch_cpu = ctx.watch_cpu;
@@ -343,11 +856,27 @@ fn start_refresh_task(ctx: Arc<RwLock<SystemInfo>>) {
                     watch_memory = ctx.watch_memory;
                     watch_disk = ctx.watch_disk;
                     watch_process = ctx.watch_process;
+                    watch_load = ctx.watch_load;
                     watch_disk_speed = ctx.watch_disk_speed;
                     watch_network_speed = ctx.watch_network_speed;
+                    watch_sensors = ctx.watch_cpu_temperatures || ctx.watch_cpu_fan || ctx.watch_cpu_power;
+                    watch_gpu = ctx.watch_gpu_clock_speed
+                        || ctx.watch_gpu_fan
+                        || ctx.watch_gpu_load
+                        || ctx.watch_gpu_temperatures
+                        || ctx.watch_cpu_power;
                     drop(ctx);
                 }
 
+                //Linux直接读取传感器，Windows由OpenHardwareMonitorService上传
+                #[cfg(target_os = "linux")]
+                if watch_sensors || watch_gpu {
+                    let data = linux_sensors.read(watch_gpu);
+                    try_write(|mut ctx| update_hardware_data(&mut ctx, data.clone()));
+                }
+                #[cfg(not(target_os = "linux"))]
+                let _ = (watch_sensors, watch_gpu);
+
                 if watch_cpu {
                     sysinfo_system.refresh_cpu();
                     let cpus = sysinfo_system.cpus();
@@ -396,6 +925,11 @@ fn start_refresh_task(ctx: Arc<RwLock<SystemInfo>>) {
                 if watch_disk {
                     sysinfo_disks.refresh_list();
                     try_write(|mut ctx| {
+                        ctx.disk_free_percent = sysinfo_disks
+                            .iter()
+                            .filter(|disk| disk.total_space() > 0)
+                            .map(|disk| disk.available_space() as f32 / disk.total_space() as f32 * 100.)
+                            .reduce(f32::min);
                         for (disk_idx, disk) in sysinfo_disks.iter().enumerate() {
                             let path = disk.mount_point().to_str().unwrap_or("").replace("\\", "");
                             ctx.disk_usage.insert(
@@ -420,17 +954,24 @@ fn start_refresh_task(ctx: Arc<RwLock<SystemInfo>>) {
                 }
 
                 #[cfg(any(feature = "nokhwa-webcam", feature = "v4l-webcam"))]
-                if watch_webcam {
+                for index in watch_webcams.iter().cloned() {
                     try_write(|mut ctx| {
-                        if ctx.watch_webcam_task.is_none() {
+                        if !ctx.watch_webcam_tasks.contains_key(&index) {
                             #[cfg(any(feature = "nokhwa-webcam", all(not(windows),feature = "v4l-webcam")))]
                             {
-                                ctx.watch_webcam_task = Some(start_webcam_capture_thread());
+                                ctx.watch_webcam_tasks.insert(index, start_webcam_capture_thread(index));
                             }
                         }
                     });
                 }
 
+                #[cfg(feature = "desktop-capture")]
+                try_write(|mut ctx| {
+                    if ctx.screen_capture.is_some() && ctx.watch_screen_capture_task.is_none() {
+                        ctx.watch_screen_capture_task = Some(start_screen_capture_thread());
+                    }
+                });
+
                 if watch_network_speed {
                     try_write(|mut ctx| {
                         if ctx.watch_network_speed_task.is_none() {
@@ -446,6 +987,14 @@ fn start_refresh_task(ctx: Arc<RwLock<SystemInfo>>) {
                     });
                 }
 
+                if watch_load {
+                    let (load_average, num_threads) = (read_load_average(), read_num_threads());
+                    try_write(|mut ctx| {
+                        ctx.load_average = load_average.clone();
+                        ctx.num_threads = num_threads.map(|n| n.to_string()).unwrap_or(String::from("N/A"));
+                    });
+                }
+
                 if let Some(system) = precord_core_system.as_mut() {
                     if watch_cpu_clock_speed {
                         system.update(Instant::now());
@@ -473,6 +1022,13 @@ fn start_refresh_task(ctx: Arc<RwLock<SystemInfo>>) {
                         });
                     }
                 }
+
+                //记录统计值
+                try_write(|mut ctx| {
+                    for (type_name, value) in metric_values(&ctx) {
+                        ctx.stats.entry(type_name).or_default().record(value);
+                    }
+                });
             }
 
             std::thread::sleep(Duration::from_millis(10));
@@ -480,7 +1036,143 @@ fn start_refresh_task(ctx: Arc<RwLock<SystemInfo>>) {
     });
 }
 
-fn current_timestamp() -> u128 {
+fn parse_number(s: &str) -> Option<f32> {
+    s.trim_end_matches(|c: char| !c.is_ascii_digit()).parse().ok()
+}
+
+//当前监控中的数值，用于统计
+fn metric_values(ctx: &SystemInfo) -> Vec<(&'static str, f32)> {
+    let mut values = vec![];
+    if ctx.watch_cpu {
+        if let Some(v) = parse_number(&ctx.cpu_usage) {
+            values.push(("cpu_usage", v));
+        }
+    }
+    if ctx.watch_memory {
+        if let Some(v) = parse_number(&ctx.memory_percent) {
+            values.push(("memory_percent", v));
+        }
+        if let Some(v) = parse_number(&ctx.swap_percent) {
+            values.push(("swap_percent", v));
+        }
+    }
+    if ctx.watch_cpu_clock_speed {
+        if let Some(v) = ctx.cpu_clock_speed.iter().cloned().reduce(f32::max) {
+            values.push(("cpu_freq", v));
+        }
+    }
+    if ctx.watch_cpu_temperatures && ctx.cpu_temperature_total > 0. {
+        values.push(("cpu_temp.", ctx.cpu_temperature_total));
+    }
+    if ctx.watch_cpu_power {
+        values.push(("cpu_package_power", ctx.cpu_package_power));
+        values.push(("cpu_cores_power", ctx.cpu_cores_power));
+        values.push(("gpu_package_power", ctx.gpu_package_power));
+        values.push(("gpu_cores_power", ctx.gpu_cores_power));
+    }
+    if ctx.watch_cpu_fan {
+        if let Some(v) = ctx.cpu_fans.get(0) {
+            values.push(("cpu_fan", *v));
+        }
+    }
+    if ctx.watch_gpu_temperatures {
+        if let Some(v) = ctx.gpu_temperatures.get(0).and_then(|t| t.get(0)) {
+            values.push(("gpu_temp.", *v));
+        }
+    }
+    if ctx.watch_gpu_load {
+        if let Some(v) = ctx.gpu_load_total.get(0).or(ctx.gpu_load.get(0).and_then(|l| l.get(0))) {
+            values.push(("gpu_load", *v));
+        }
+        if let Some(v) = ctx.gpu_memory_load.get(0) {
+            values.push(("gpu_memory_load", *v));
+        }
+    }
+    if ctx.watch_disk {
+        if let Some(v) = ctx.disk_free_percent {
+            values.push(("disk_free_percent", v));
+        }
+    }
+    if ctx.watch_disk_speed {
+        values.push(("disk_activity", disk_activity(ctx)));
+    }
+    if ctx.watch_network_speed {
+        values.push(("network_activity", network_activity(ctx)));
+    }
+    values
+}
+
+fn format_metric(type_name: &str, value: f32) -> String {
+    match type_name {
+        "cpu_temp." | "gpu_temp." => format!("{:.1}°C", value),
+        "cpu_package_power" | "cpu_cores_power" | "gpu_package_power" | "gpu_cores_power" => {
+            format!("{:.1}W", value)
+        }
+        "cpu_fan" => format!("{}RPM", value as i32),
+        "cpu_freq" => format!("{:.2} GHz", value / 1000.),
+        _ => format!("{:.1}%", value),
+    }
+}
+
+// 读取统计值
+pub fn metric_stat(type_name: &str, stat: MetricStat) -> Option<String> {
+    let ctx = try_read_ctx()?;
+    let stats = ctx.stats.get(type_name)?;
+    if stats.count == 0 {
+        return None;
+    }
+    let value = match stat {
+        MetricStat::Min => stats.min,
+        MetricStat::Max => stats.max,
+        MetricStat::Avg => (stats.sum / stats.count as f64) as f32,
+    };
+    Some(format_metric(type_name, value))
+}
+
+// 读取当前数值，用于组件的显示条件
+pub fn metric_value(type_name: &str) -> Option<f32> {
+    let ctx = try_read_ctx()?;
+    metric_values(&ctx)
+        .into_iter()
+        .find(|(name, _)| *name == type_name)
+        .map(|(_, v)| v)
+}
+
+// 开启数值对应的监听
+pub fn watch_metric(type_name: &str) -> Result<()> {
+    match type_name {
+        "cpu_usage" => watch_cpu(true),
+        "memory_percent" | "swap_percent" => watch_memory(true),
+        "cpu_freq" => watch_cpu_clock_speed(true),
+        "cpu_temp." => watch_cpu_temperatures(true),
+        "cpu_package_power" | "cpu_cores_power" | "gpu_package_power" | "gpu_cores_power" => {
+            watch_cpu_power(true)
+        }
+        "cpu_fan" => watch_cpu_fan(true),
+        "gpu_temp." => watch_gpu_temperatures(true),
+        "gpu_load" | "gpu_memory_load" => watch_gpu_load(true),
+        "disk_activity" => watch_disk_speed(true),
+        "disk_free_percent" => watch_disk(true),
+        "network_activity" => watch_network_speed(true),
+        _ => Ok(()),
+    }
+}
+
+// 设置传感器校准值，在接收数据时生效
+pub fn set_corrections(corrections: Vec<Correction>) -> Result<()> {
+    let mut sys_info = SYSTEM_INFO.write().map_err(|err| anyhow!("{:?}", err))?;
+    sys_info.corrections = corrections;
+    Ok(())
+}
+
+// 清空统计值
+pub fn reset_stats() -> Result<()> {
+    let mut sys_info = SYSTEM_INFO.write().map_err(|err| anyhow!("{:?}", err))?;
+    sys_info.stats.clear();
+    Ok(())
+}
+
+pub fn current_timestamp() -> u128 {
     let now = SystemTime::now();
     // 转换为UNIX纪元以来的纳秒数
     let since_the_epoch = now.duration_since(SystemTime::UNIX_EPOCH).unwrap();
@@ -636,9 +1328,101 @@ pub fn watch_process(watch_process: bool) -> Result<()> {
     Ok(())
 }
 
-pub fn watch_weather(watch_weather: Option<City>) -> Result<()> {
+pub fn watch_load(watch_load: bool) -> Result<()> {
+    let mut sys_info = SYSTEM_INFO.write().map_err(|err| anyhow!("{:?}", err))?;
+    sys_info.watch_load = watch_load;
+    Ok(())
+}
+
+pub fn watch_foreground_window(watch_foreground_window: bool) -> Result<()> {
+    let mut sys_info = SYSTEM_INFO.write().map_err(|err| anyhow!("{:?}", err))?;
+    sys_info.watch_foreground_window = watch_foreground_window;
+    Ok(())
+}
+
+// 添加监听天气的城市，可以同时监听多个城市
+pub fn watch_weather(city: City) -> Result<()> {
+    let mut sys_info = SYSTEM_INFO.write().map_err(|err| anyhow!("{:?}", err))?;
+    sys_info.watch_weather.insert(city.city.clone(), city);
+    Ok(())
+}
+
+pub fn watch_feed(url: &str, interval_minutes: u64) -> Result<()> {
+    let mut sys_info = SYSTEM_INFO.write().map_err(|err| anyhow!("{:?}", err))?;
+    sys_info.watch_feeds.insert(url.to_string(), interval_minutes.max(1));
+    Ok(())
+}
+
+pub fn watch_calendar(url: &str) -> Result<()> {
+    let mut sys_info = SYSTEM_INFO.write().map_err(|err| anyhow!("{:?}", err))?;
+    sys_info.watch_calendars.insert(url.to_string());
+    Ok(())
+}
+
+pub fn watch_ticker(url: &str, paths: &str) -> Result<()> {
+    let mut sys_info = SYSTEM_INFO.write().map_err(|err| anyhow!("{:?}", err))?;
+    sys_info.watch_tickers.insert((url.to_string(), paths.to_string()));
+    Ok(())
+}
+
+// source为adb设备序列号或手机配套程序的地址，为空时使用唯一连接的设备
+pub fn watch_phone(source: &str) -> Result<()> {
+    let mut sys_info = SYSTEM_INFO.write().map_err(|err| anyhow!("{:?}", err))?;
+    sys_info.watch_phones.insert(source.to_string());
+    Ok(())
+}
+
+pub fn watch_ping(host: &str) -> Result<()> {
+    let mut sys_info = SYSTEM_INFO.write().map_err(|err| anyhow!("{:?}", err))?;
+    sys_info.watch_ping.insert(host.to_string());
+    Ok(())
+}
+
+// 按公网IP定位城市，定位结果以 nmc::AUTO_CITY 为名称监听天气
+pub fn watch_auto_weather() -> Result<()> {
+    let mut sys_info = SYSTEM_INFO.write().map_err(|err| anyhow!("{:?}", err))?;
+    sys_info.watch_auto_weather = true;
+    sys_info.watch_net_ip = true;
+    //已经定位过时直接监听，不用等下一次公网地址更新
+    let city = sys_info
+        .net_ip
+        .as_ref()
+        .and_then(|ip| nmc::find_city(&ip.region_name, &ip.city));
+    if let Some(city) = city {
+        sys_info.watch_weather.insert(nmc::AUTO_CITY.to_string(), city);
+    }
+    Ok(())
+}
+
+// 取消所有监听，各个线程在下一次检查时停止查询，已经取到的数据保留
+pub fn unwatch_all() -> Result<()> {
     let mut sys_info = SYSTEM_INFO.write().map_err(|err| anyhow!("{:?}", err))?;
-    sys_info.watch_weather = watch_weather;
+    sys_info.watch_memory = false;
+    sys_info.watch_disk = false;
+    sys_info.watch_disk_speed = false;
+    sys_info.watch_cpu = false;
+    sys_info.watch_cpu_clock_speed = false;
+    sys_info.watch_cpu_temperatures = false;
+    sys_info.watch_cpu_power = false;
+    sys_info.watch_cpu_fan = false;
+    sys_info.watch_gpu_clock_speed = false;
+    sys_info.watch_gpu_temperatures = false;
+    sys_info.watch_gpu_fan = false;
+    sys_info.watch_gpu_load = false;
+    sys_info.watch_process = false;
+    sys_info.watch_load = false;
+    sys_info.watch_foreground_window = false;
+    sys_info.watch_network_speed = false;
+    sys_info.watch_net_ip = false;
+    sys_info.watch_auto_weather = false;
+    sys_info.watch_weather.clear();
+    sys_info.watch_feeds.clear();
+    sys_info.watch_calendars.clear();
+    sys_info.watch_ping.clear();
+    sys_info.watch_tickers.clear();
+    sys_info.watch_phones.clear();
+    sys_info.webcams.clear();
+    sys_info.screen_capture = None;
     Ok(())
 }
 
@@ -648,9 +1432,17 @@ pub fn watch_net_ip(v: bool) -> Result<()> {
     Ok(())
 }
 
-pub fn watch_webcam(webcam_info: Option<WebcamInfo>) -> Result<()> {
+// 添加监控的相机，可以同时监控多个相机，同一个相机按编号只保留一份
+pub fn watch_webcam(webcam_info: WebcamInfo) -> Result<()> {
     let mut sys_info = SYSTEM_INFO.write().map_err(|err| anyhow!("{:?}", err))?;
-    sys_info.webcam_info = webcam_info;
+    sys_info.webcams.insert(webcam_info.index, webcam_info);
+    Ok(())
+}
+
+// 截取桌面区域，帧率和屏幕帧率分开设置，同时只截取一个区域
+pub fn watch_screen_capture(info: ScreenCaptureInfo) -> Result<()> {
+    let mut sys_info = SYSTEM_INFO.write().map_err(|err| anyhow!("{:?}", err))?;
+    sys_info.screen_capture = Some(info);
     Ok(())
 }
 
@@ -692,12 +1484,31 @@ pub fn cpu_usage_percpu(index: usize) -> Option<String> {
     try_read_ctx()?.cpu_usage_percpu.clone().remove(&index)
 }
 
+// 所有逻辑核心的使用率(0~100)，按核心的顺序
+pub fn cpu_usage_all() -> Vec<f32> {
+    let ctx = match try_read_ctx() {
+        Some(ctx) => ctx,
+        None => return vec![],
+    };
+    let mut cores: Vec<(usize, f32)> = ctx
+        .cpu_usage_percpu
+        .iter()
+        .map(|(index, usage)| (*index, usage.trim_end_matches('%').parse().unwrap_or(0.)))
+        .collect();
+    cores.sort_by_key(|(index, _)| *index);
+    cores.into_iter().map(|(_, usage)| usage).collect()
+}
+
 pub fn cpu_usage() -> Option<String> {
     Some(try_read_ctx()?.cpu_usage.clone())
 }
 
-pub fn webcam_frame() -> Option<RgbImage> {
-    try_read_ctx()?.webcam_frame.clone()
+pub fn webcam_frame(index: u32) -> Option<RgbImage> {
+    try_read_ctx()?.webcam_frames.get(&index).cloned()
+}
+
+pub fn screen_capture_frame() -> Option<RgbImage> {
+    try_read_ctx()?.screen_capture_frame.clone()
 }
 
 pub fn cpu_clock_speed(index: Option<usize>) -> Option<String> {
@@ -718,6 +1529,12 @@ pub fn cpu_temperature() -> Option<String> {
     Some(format!("{:.1}°C", ctx.cpu_temperature_total))
 }
 
+// 单个核心的温度，index为传感器顺序
+pub fn cpu_temperature_core(index: usize) -> Option<String> {
+    let ctx = try_read_ctx()?;
+    ctx.cpu_temperatures.get(index).map(|t| format!("{:.1}°C", t))
+}
+
 pub fn cpu_cores_power() -> Option<String> {
     let ctx = try_read_ctx()?;
     Some(format!("{:.1}W", ctx.cpu_cores_power))
@@ -728,12 +1545,37 @@ pub fn cpu_package_power() -> Option<String> {
     Some(format!("{:.1}W", ctx.cpu_package_power))
 }
 
-pub fn cpu_fan() -> Option<String> {
+// 第index个风扇的转速，index为传感器顺序
+pub fn cpu_fan(index: usize) -> Option<String> {
     let ctx = try_read_ctx()?;
-    if ctx.cpu_fans.len() == 0 {
-        return None;
-    }
-    Some(format!("{}RPM", ctx.cpu_fans[0]))
+    ctx.cpu_fans.get(index).map(|v| format!("{}RPM", v))
+}
+
+// 主板上的风扇数量
+pub fn fan_count() -> usize {
+    try_read_ctx().map(|ctx| ctx.cpu_fans.len()).unwrap_or(0)
+}
+
+// 散热: CPU温度和第index个风扇的转速
+pub fn cooling(index: usize) -> Option<String> {
+    let ctx = try_read_ctx()?;
+    let fan = ctx.cpu_fans.get(index)?;
+    Some(format!("{:.1}°C {}RPM", ctx.cpu_temperature_total, fan))
+}
+
+// 检测到的显卡名称，顺序与组件的显卡索引相同
+pub fn list_gpus() -> Vec<String> {
+    gpu_devices().into_iter().map(|(_, name)| name).collect()
+}
+
+// 检测到的显卡(标识, 名称)
+pub fn gpu_devices() -> Vec<(String, String)> {
+    try_read_ctx().map(|ctx| ctx.gpu_devices.clone()).unwrap_or_default()
+}
+
+// 显卡标识对应的索引，显卡不存在时返回None
+pub fn gpu_index(identifier: &str) -> Option<usize> {
+    try_read_ctx()?.gpu_devices.iter().position(|(id, _)| id == identifier)
 }
 
 pub fn gpu_load(index: usize) -> Option<String> {
@@ -765,6 +1607,14 @@ pub fn gpu_memory_load(index: usize) -> Option<String> {
     });
 }
 
+// 显存 已用/总量
+pub fn gpu_memory_info(index: usize) -> Option<String> {
+    let ctx = try_read_ctx()?;
+    let total = ctx.gpu_memory_total.get(index)? / 1024.;
+    let load = ctx.gpu_memory_load.get(index).cloned().unwrap_or(0.);
+    Some(format!("{:.1}/{:.1}GB", total * load / 100., total))
+}
+
 pub fn gpu_memory_total_mb(index: usize) -> Option<String> {
     let ctx = try_read_ctx()?;
     return ctx.gpu_memory_total.get(index).map(|total|{
@@ -780,6 +1630,14 @@ pub fn gpu_memory_total_gb(index: usize) -> Option<String> {
     });
 }
 
+// 已用显存(GB)，由显存占用百分比和总量计算
+pub fn gpu_memory_used_gb(index: usize) -> Option<String> {
+    let ctx = try_read_ctx()?;
+    let total = ctx.gpu_memory_total.get(index)? / 1024.;
+    let load = ctx.gpu_memory_load.get(index)?;
+    Some(format!("{:.1}", total * load / 100.))
+}
+
 pub fn gpu_clocks(index: usize) -> Option<String> {
     let gpu_clocks = try_read_ctx()?.gpu_clocks.clone();
     if gpu_clocks.len() == 0 {
@@ -810,22 +1668,134 @@ pub fn gpu_package_power() -> Option<String> {
     Some(format!("{:.1}W", ctx.gpu_package_power))
 }
 
+// 所有显卡的风扇依次编号
 pub fn gpu_fan(index: usize) -> Option<String> {
     let ctx = try_read_ctx()?;
-    if ctx.gpu_fans.len() == 0 {
-        return None;
+    ctx.gpu_fans
+        .iter()
+        .flatten()
+        .nth(index)
+        .map(|v| format!("{}RPM", v))
+}
+
+pub fn num_process() -> Option<String> {
+    Some(try_read_ctx()?.num_process.clone())
+}
+
+// 1/5/15分钟平均负载，Windows没有负载显示N/A
+pub fn load_average() -> Option<String> {
+    Some(try_read_ctx()?.load_average.clone())
+}
+
+pub fn num_threads() -> Option<String> {
+    Some(try_read_ctx()?.num_threads.clone())
+}
+
+// 前台窗口的标题，无法获取时为N/A
+pub fn foreground_window_title() -> Option<String> {
+    Some(try_read_ctx()?.foreground_window_title.clone().unwrap_or(EMPTY_STRING.to_string()))
+}
+
+#[cfg(windows)]
+fn read_load_average() -> String {
+    String::from("N/A")
+}
+
+#[cfg(not(windows))]
+fn read_load_average() -> String {
+    let load = sysinfo::System::load_average();
+    format!("{:.2} {:.2} {:.2}", load.one, load.five, load.fifteen)
+}
+
+// 前台窗口覆盖了所在的整个显示器时认为是全屏程序(游戏)，桌面除外
+#[cfg(windows)]
+pub fn is_fullscreen_app_active() -> bool {
+    use windows::Win32::{
+        Foundation::RECT,
+        Graphics::Gdi::{GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTONEAREST},
+        UI::WindowsAndMessaging::{GetDesktopWindow, GetForegroundWindow, GetShellWindow, GetWindowRect},
+    };
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0 == 0 || hwnd == GetDesktopWindow() || hwnd == GetShellWindow() {
+            return false;
+        }
+        let mut rect = RECT::default();
+        if GetWindowRect(hwnd, &mut rect).is_err() {
+            return false;
+        }
+        let monitor = MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST);
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if !GetMonitorInfoW(monitor, &mut info).as_bool() {
+            return false;
+        }
+        let screen = info.rcMonitor;
+        rect.left <= screen.left && rect.top <= screen.top && rect.right >= screen.right && rect.bottom >= screen.bottom
     }
-    if ctx.gpu_fans.len() <= index {
-        return None;
+}
+
+#[cfg(not(windows))]
+pub fn is_fullscreen_app_active() -> bool {
+    false
+}
+
+#[cfg(windows)]
+fn read_foreground_window_title() -> Option<String> {
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowTextW};
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0 == 0 {
+            return None;
+        }
+        let mut buf = [0u16; 512];
+        let len = GetWindowTextW(hwnd, &mut buf);
+        if len <= 0 {
+            return None;
+        }
+        Some(String::from_utf16_lossy(&buf[..len as usize]))
     }
-    if ctx.gpu_fans[index].len() == 0 {
+}
+
+// X11(包括XWayland)通过xprop读取_NET_ACTIVE_WINDOW，纯Wayland下没有通用的接口，返回None
+#[cfg(not(windows))]
+fn read_foreground_window_title() -> Option<String> {
+    let xprop = |args: &[&str]| {
+        let output = std::process::Command::new("xprop").args(args).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).to_string())
+    };
+    //_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007
+    let active = xprop(&["-root", "_NET_ACTIVE_WINDOW"])?;
+    let id = active.split('#').nth(1)?.split(',').next()?.trim().to_string();
+    if id.is_empty() || id == "0x0" {
         return None;
     }
-    Some(format!("{}RPM", ctx.gpu_fans[index][0]))
+    //_NET_WM_NAME(UTF8_STRING) = "标题"
+    let name = xprop(&["-id", &id, "_NET_WM_NAME"])?;
+    let (_, title) = name.split_once(" = ")?;
+    let title = title.trim().trim_matches('"').replace("\\\"", "\"");
+    if title.is_empty() {
+        None
+    } else {
+        Some(title)
+    }
 }
 
-pub fn num_process() -> Option<String> {
-    Some(try_read_ctx()?.num_process.clone())
+// /proc/loadavg 第4列为 运行中/总数 的调度实体(线程)数量
+#[cfg(target_os = "linux")]
+fn read_num_threads() -> Option<usize> {
+    let loadavg = std::fs::read_to_string("/proc/loadavg").ok()?;
+    loadavg.split_whitespace().nth(3)?.split('/').nth(1)?.parse().ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_num_threads() -> Option<usize> {
+    None
 }
 
 pub fn disk_usage(index: usize) -> Option<String> {
@@ -840,6 +1810,90 @@ pub fn network_speed_per_sec() -> Option<(String, String)> {
     Some(try_read_ctx()?.network_speed_per_sec.clone())
 }
 
+// 磁盘读写速度占最大速度的百分比
+pub fn disk_activity_percent() -> Option<String> {
+    let ctx = try_read_ctx()?;
+    Some(format!("{:.1}%", disk_activity(&ctx)))
+}
+
+// 网络收发速度占带宽的百分比
+pub fn network_activity_percent() -> Option<String> {
+    let ctx = try_read_ctx()?;
+    Some(format!("{:.1}%", network_activity(&ctx)))
+}
+
+fn disk_activity(ctx: &SystemInfo) -> f32 {
+    let (read, write) = ctx.disk_bytes_per_sec;
+    let mb = (read + write) / 1024. / 1024.;
+    (mb as f32 / ctx.disk_max_speed.max(0.1) * 100.).min(100.)
+}
+
+fn network_activity(ctx: &SystemInfo) -> f32 {
+    let (received, transmitted) = ctx.network_bytes_per_sec;
+    let mbps = (received + transmitted) * 8. / 1_000_000.;
+    (mbps as f32 / ctx.network_max_speed.max(0.1) * 100.).min(100.)
+}
+
+// 累计接收流量
+pub fn network_total_received() -> Option<String> {
+    Some(format_bytes(try_read_ctx()?.network_total.0))
+}
+
+// 累计发送流量
+pub fn network_total_transmitted() -> Option<String> {
+    Some(format_bytes(try_read_ctx()?.network_total.1))
+}
+
+// 本次运行CPU累计耗电量
+pub fn cpu_energy_wh() -> Option<String> {
+    Some(format_energy(try_read_ctx()?.cpu_energy_wh))
+}
+
+// 本次运行GPU累计耗电量
+pub fn gpu_energy_wh() -> Option<String> {
+    Some(format_energy(try_read_ctx()?.gpu_energy_wh))
+}
+
+// 清空累计耗电量
+pub fn reset_energy() -> Result<()> {
+    let mut sys_info = SYSTEM_INFO.write().map_err(|err| anyhow!("{:?}", err))?;
+    sys_info.cpu_energy_wh = 0.;
+    sys_info.gpu_energy_wh = 0.;
+    Ok(())
+}
+
+fn format_energy(wh: f64) -> String {
+    if wh >= 1000. {
+        format!("{:.2}kWh", wh / 1000.)
+    } else {
+        format!("{:.2}Wh", wh)
+    }
+}
+
+// 清空累计流量
+pub fn reset_network_total() -> Result<()> {
+    let mut sys_info = SYSTEM_INFO.write().map_err(|err| anyhow!("{:?}", err))?;
+    sys_info.network_total = (0, 0);
+    Ok(())
+}
+
+fn format_bytes(bytes: u64) -> String {
+    let mb = bytes as f64 / 1024. / 1024.;
+    if mb >= 1024. {
+        format!("{:.2}GB", mb / 1024.)
+    } else {
+        format!("{:.1}MB", mb)
+    }
+}
+
+// 设置计算占用百分比用的磁盘最大速度(MB/s)和网络带宽(Mbps)
+pub fn set_activity_limits(disk_max_speed: f32, network_max_speed: f32) -> Result<()> {
+    let mut sys_info = SYSTEM_INFO.write().map_err(|err| anyhow!("{:?}", err))?;
+    sys_info.disk_max_speed = disk_max_speed;
+    sys_info.network_max_speed = network_max_speed;
+    Ok(())
+}
+
 pub fn system_name() -> Option<String> {
     Some(try_read_ctx()?.system_name.clone())
 }
@@ -860,12 +1914,220 @@ pub fn date() -> String {
     Local::now().format("%Y/%m/%d").to_string()
 }
 
+//日期的各个部分，用于自定义日期的显示
+pub fn year() -> String {
+    Local::now().year().to_string()
+}
+
+pub fn month() -> String {
+    Local::now().month().to_string()
+}
+
+pub fn day() -> String {
+    Local::now().day().to_string()
+}
+
+//星期几的数字，星期一为1，星期日为7
+pub fn weekday_num() -> String {
+    Local::now().weekday().number_from_monday().to_string()
+}
+
+// 按照平台和硬件检测读取不到数据的组件类型
+fn detect_unsupported_metrics() -> Vec<&'static str> {
+    let mut unsupported = vec![];
+    #[cfg(target_os = "linux")]
+    {
+        let data = crate::hwmon::LinuxSensors::new().read(true);
+        let cpu = data.cpu_infos.first().cloned().unwrap_or_default();
+        if cpu.temperatures.is_empty() && cpu.total_temperature == 0. {
+            unsupported.push("cpu_temp.");
+        }
+        if cpu.fans.is_empty() {
+            unsupported.extend(["cpu_fan", "cooling"]);
+        }
+        if !crate::hwmon::rapl_available() {
+            unsupported.extend(["cpu_package_power", "cpu_cores_power", "cpu_energy"]);
+        }
+        if data.gpu_infos.is_empty() {
+            unsupported.extend(GPU_METRICS);
+            unsupported.push("gpu_energy");
+        }
+    }
+    #[cfg(not(any(windows, target_os = "linux")))]
+    {
+        unsupported.extend(SENSOR_METRICS);
+        unsupported.extend(GPU_METRICS);
+        unsupported.extend(["cpu_energy", "gpu_energy"]);
+    }
+    #[cfg(windows)]
+    unsupported.push("load_avg");
+    #[cfg(not(target_os = "linux"))]
+    unsupported.push("num_threads");
+    #[cfg(not(windows))]
+    unsupported.push("custom");
+    #[cfg(not(any(feature = "nokhwa-webcam", all(not(windows), feature = "v4l-webcam"))))]
+    unsupported.push("webcam");
+    #[cfg(not(feature = "desktop-capture"))]
+    unsupported.push("desktop");
+    info!("当前系统不支持的组件:{:?}", unsupported);
+    unsupported
+}
+
+// 组件类型在当前系统是否有数据
+pub fn is_supported(type_name: &str) -> bool {
+    !UNSUPPORTED_METRICS.contains(&type_name)
+}
+
+pub fn set_time_24h(time_24h: bool) {
+    TIME_24H.store(time_24h, Ordering::Relaxed);
+}
+
 pub fn time() -> String {
-    Local::now().format("%H:%M:%S").to_string()
+    if TIME_24H.load(Ordering::Relaxed) {
+        Local::now().format("%H:%M:%S").to_string()
+    } else {
+        Local::now().format("%I:%M:%S").to_string()
+    }
+}
+
+// 上午/下午标记: AM 或 PM
+pub fn ampm() -> String {
+    Local::now().format("%p").to_string()
+}
+
+// 当前分钟已经过去的比例(0~1)，精确到毫秒
+pub fn minute_progress() -> f32 {
+    let now = Local::now();
+    (now.second() as f32 + now.timestamp_subsec_millis() as f32 / 1000.) / 60.
+}
+
+pub fn weather_info(city: &str) -> Option<RealWeather> {
+    Some(try_read_ctx()?.weather.get(city)?.0.clone())
+}
+
+fn weather_forecast(city: &str, day: usize) -> Option<PredictDay> {
+    try_read_ctx()?.weather.get(city)?.1.get(day).cloned()
+}
+
+// 今天最高气温，白天过去后没有数据
+pub fn weather_high(city: &str) -> Option<String> {
+    Some(format!("{}℃", weather_forecast(city, 0)?.high()?))
+}
+
+// 今天最低气温
+pub fn weather_low(city: &str) -> Option<String> {
+    Some(format!("{}℃", weather_forecast(city, 0)?.low()?))
+}
+
+// 今天 高/低 气温
+pub fn weather_high_low(city: &str) -> Option<String> {
+    let today = weather_forecast(city, 0)?;
+    Some(match (today.high(), today.low()) {
+        (Some(high), Some(low)) => format!("{high}/{low}℃"),
+        (None, Some(low)) => format!("{low}℃"),
+        (Some(high), None) => format!("{high}℃"),
+        (None, None) => return None,
+    })
+}
+
+// 明天的天气和气温
+pub fn weather_tomorrow(city: &str) -> Option<String> {
+    let tomorrow = weather_forecast(city, 1)?;
+    Some(format!(
+        "{} {}/{}℃",
+        tomorrow.info(),
+        tomorrow.high()?,
+        tomorrow.low()?
+    ))
+}
+
+pub fn feed_titles(url: &str) -> Option<String> {
+    try_read_ctx()?.feed_titles.get(url).cloned()
+}
+
+// 日历中下一个还没有过去的事件
+fn next_event(url: &str) -> Option<CalendarEvent> {
+    let now = Local::now();
+    try_read_ctx()?.calendar_events.get(url)?.iter().find(|e| e.is_upcoming(now)).cloned()
+}
+
+pub fn next_event_title(url: &str) -> Option<String> {
+    next_event(url).map(|e| e.title)
+}
+
+// 今天的事件只显示时间，全天事件只显示日期
+pub fn next_event_time(url: &str) -> Option<String> {
+    let event = next_event(url)?;
+    let today = event.start.date_naive() == Local::now().date_naive();
+    Some(match (event.all_day, today) {
+        (true, true) => "今天".to_string(),
+        (true, false) => event.start.format("%m-%d").to_string(),
+        (false, true) => event.start.format("%H:%M").to_string(),
+        (false, false) => event.start.format("%m-%d %H:%M").to_string(),
+    })
 }
 
-pub fn weather_info() -> Option<RealWeather> {
-    try_read_ctx()?.weather_info.clone()
+pub fn ticker_quote(url: &str, paths: &str) -> Option<Quote> {
+    try_read_ctx()?.tickers.get(&(url.to_string(), paths.to_string())).cloned()
+}
+
+// 手机电量，未连接时返回 EMPTY_STRING
+pub fn phone_battery(source: &str) -> Option<String> {
+    Some(match try_read_ctx()?.phones.get(source)? {
+        Some(battery) => format!("{:.0}%", battery.level),
+        None => EMPTY_STRING.to_string(),
+    })
+}
+
+// 手机的充电状态和电池温度
+pub fn phone_status(source: &str) -> Option<String> {
+    Some(match try_read_ctx()?.phones.get(source)? {
+        Some(battery) => battery.status(),
+        None => "未连接".to_string(),
+    })
+}
+
+// 主机的往返时间，还没有检测结果时返回None
+pub fn ping_latency(host: &str) -> Option<Option<f32>> {
+    try_read_ctx()?.ping.get(host).cloned()
+}
+
+pub fn custom_value(name: &str) -> Option<String> {
+    try_read_ctx()?.custom_values.get(name).cloned()
+}
+
+// 自定义数值通过http服务推送，只有Windows启动了http服务
+pub fn watch_custom() {
+    #[cfg(windows)]
+    info!("自定义数值推送地址: http://127.0.0.1:{}/custom", *HTTP_PORT);
+    #[cfg(not(windows))]
+    warn!("当前系统不支持推送自定义数值");
+}
+
+// 推送的JSON对象: {"fps":"144","kills":12}，字符串直接显示，其他值转为字符串，null删除
+#[cfg(windows)]
+fn update_custom_values(ctx: &mut SystemInfo, values: HashMap<String, serde_json::Value>) {
+    for (name, value) in values {
+        match value {
+            serde_json::Value::Null => {
+                ctx.custom_values.remove(&name);
+            }
+            serde_json::Value::String(s) => {
+                ctx.custom_values.insert(name, s);
+            }
+            value => {
+                ctx.custom_values.insert(name, value.to_string());
+            }
+        }
+    }
+}
+
+pub fn ping_info(host: &str) -> Option<String> {
+    Some(match ping_latency(host)? {
+        Some(ms) if ms.is_nan() => "在线".to_string(),
+        Some(ms) => format!("{:.0}ms", ms),
+        None => "离线".to_string(),
+    })
 }
 
 pub fn chinese_weekday() -> String {
@@ -1114,47 +2376,53 @@ pub fn start_network_counter_thread() -> std::thread::JoinHandle<()> {
             );
             try_write(move |mut ctx| {
                 ctx.network_speed_per_sec = (received_str.to_owned(), transmitted_str.to_owned());
+                ctx.network_bytes_per_sec = (received as f64, transmitted as f64);
+                ctx.network_total.0 += received;
+                ctx.network_total.1 += transmitted;
             });
         }
     })
 }
 
 #[cfg(any(feature = "nokhwa-webcam", all(not(windows),feature = "v4l-webcam")))]
-pub fn start_webcam_capture_thread() -> std::thread::JoinHandle<()> {
-    debug!("start_webcam_capture_thread...");
+pub fn start_webcam_capture_thread(camera_index: u32) -> std::thread::JoinHandle<()> {
+    debug!("start_webcam_capture_thread {camera_index}...");
     std::thread::spawn(move || {
 
         #[cfg(feature = "nokhwa-webcam")]
         let mut camera:Option<Camera> = None;
         #[cfg(all(not(windows),feature = "v4l-webcam", ))]
         let mut camera:Option<(v4l::Device, v4l::format::Format, v4l::prelude::MmapStream)> = None;
-        
-        let mut camera_index:i32 = -1;
-        
+
         loop {
             let mut watch_webcam = None;
             if let Ok(ctx) = SYSTEM_INFO.read() {
-                watch_webcam = ctx.webcam_info.clone();
+                watch_webcam = ctx.webcams.get(&camera_index).cloned();
                 drop(ctx);
             }
 
             if watch_webcam.is_none() {
+                //取消监控后关闭相机并结束线程，在写锁内移除任务，避免与重新监控时启动线程冲突
+                if let Ok(mut ctx) = SYSTEM_INFO.write() {
+                    if !ctx.webcams.contains_key(&camera_index) {
+                        ctx.watch_webcam_tasks.remove(&camera_index);
+                        ctx.webcam_frames.remove(&camera_index);
+                        drop(ctx);
+                        drop(camera);
+                        info!("关闭相机 camera_index={camera_index}");
+                        return;
+                    }
+                }
                 std::thread::sleep(Duration::from_millis(100));
                 continue;
             }else if let Some(webcam_info) = watch_webcam{
-                if camera.is_none() || camera_index != webcam_info.index as i32{
-                    camera_index = webcam_info.index as i32;
-                    //相机需要重新打开
-                    if camera.is_some(){
-                        let cam = camera.take();
-                        drop(cam);
-                    }
+                if camera.is_none() {
                     info!("打开相机 camera_index={camera_index}");
 
                     #[cfg(feature = "nokhwa-webcam")]
                     {
                         let requested = RequestedFormat::new::<RgbFormat>(RequestedFormatType::AbsoluteHighestFrameRate);
-                        match Camera::new(CameraIndex::Index(camera_index as u32), requested){
+                        match Camera::new(CameraIndex::Index(camera_index), requested){
                             Ok(cam) => camera = Some(cam),
                             Err(err) =>{
                                 error!("相机打开失败:{err:?}");
@@ -1165,7 +2433,7 @@ pub fn start_webcam_capture_thread() -> std::thread::JoinHandle<()> {
                     }
                     #[cfg(all(not(windows),feature = "v4l-webcam", ))]
                     {
-                        match open_v4l_webcam(camera_index){
+                        match open_v4l_webcam(camera_index as i32){
                             Ok(cam) => camera = Some(cam),
                             Err(err) =>{
                                 error!("相机打开失败:{err:?}");
@@ -1253,7 +2521,8 @@ pub fn start_webcam_capture_thread() -> std::thread::JoinHandle<()> {
                         //写入缓存
                         try_write(move |mut ctx| {
                             if let Some(img) = RgbImage::from_raw(dst_image.width(), dst_image.height(), dst_image.buffer().to_vec()){
-                                ctx.webcam_frame = Some(img);
+                                ctx.webcam_frames.insert(camera_index, img);
+                                notify_source_frame();
                             }
                         });
                     }
@@ -1272,6 +2541,77 @@ pub fn start_webcam_capture_thread() -> std::thread::JoinHandle<()> {
     })
 }
 
+// 截图使用xcap，Windows使用系统的截图接口，Linux支持X11和Wayland(PipeWire)
+#[cfg(feature = "desktop-capture")]
+pub fn start_screen_capture_thread() -> std::thread::JoinHandle<()> {
+    debug!("start_screen_capture_thread...");
+    std::thread::spawn(move || {
+        let mut monitor: Option<xcap::Monitor> = None;
+        loop {
+            let capture_info = match SYSTEM_INFO.read() {
+                Ok(ctx) => ctx.screen_capture.clone(),
+                Err(_) => None,
+            };
+            let capture_info = match capture_info {
+                None => {
+                    std::thread::sleep(Duration::from_millis(100));
+                    continue;
+                }
+                Some(info) => info,
+            };
+            if monitor.is_none() {
+                info!("打开主显示器截图");
+                monitor = xcap::Monitor::all()
+                    .ok()
+                    .and_then(|all| {
+                        let primary = all.iter().position(|m| m.is_primary()).unwrap_or(0);
+                        all.into_iter().nth(primary)
+                    });
+                if monitor.is_none() {
+                    error!("没有找到可以截图的显示器");
+                    std::thread::sleep(Duration::from_millis(3000));
+                    continue;
+                }
+            }
+            let t = Instant::now();
+            match monitor.as_ref().map(|m| m.capture_image()) {
+                Some(Ok(image)) => {
+                    let image = match capture_info.region {
+                        Some((x, y, width, height)) => {
+                            let x = (x.max(0) as u32).min(image.width().saturating_sub(1));
+                            let y = (y.max(0) as u32).min(image.height().saturating_sub(1));
+                            let width = width.min(image.width() - x).max(1);
+                            let height = height.min(image.height() - y).max(1);
+                            image::imageops::crop_imm(&image, x, y, width, height).to_image()
+                        }
+                        None => image,
+                    };
+                    //保持比例缩放，最大不超过屏幕大小
+                    let frame = DynamicImage::ImageRgba8(image)
+                        .resize(capture_info.width, capture_info.height, image::imageops::FilterType::Triangle)
+                        .to_rgb8();
+                    try_write(move |mut ctx| {
+                        ctx.screen_capture_frame = Some(frame);
+                        notify_source_frame();
+                    });
+                }
+                Some(Err(err)) => {
+                    error!("截图失败:{err:?}");
+                    //显示器可能已经断开，重新查找
+                    monitor = None;
+                    std::thread::sleep(Duration::from_millis(1000));
+                    continue;
+                }
+                None => (),
+            }
+            //延迟，减去截图花费的时间
+            let delay = 1000 / capture_info.fps.max(1) as u64;
+            let dur = t.elapsed().as_millis() as u64;
+            std::thread::sleep(Duration::from_millis(delay.saturating_sub(dur).max(1)));
+        }
+    })
+}
+
 #[cfg(windows)]
 pub fn start_disk_counter_thread() -> std::thread::JoinHandle<()> {
     debug!("start_disk_counter_thread...");
@@ -1373,6 +2713,7 @@ pub fn start_disk_counter_thread() -> std::thread::JoinHandle<()> {
                 let write_str = format!("{:.1} MB/s", write_bytes_per_sec / 1024. / 1024.);
                 try_write(move |mut ctx| {
                     ctx.disk_speed_per_sec = (read_str.to_owned(), write_str.to_owned());
+                    ctx.disk_bytes_per_sec = (read_bytes_per_sec, write_bytes_per_sec);
                 });
             }
         }
@@ -1417,8 +2758,10 @@ pub fn start_disk_counter_thread() -> std::thread::JoinHandle<()> {
 
             let read_str = format!("{:.1} MB/s", counter.read_bytes() as f64 / 1024. / 1024.);
             let write_str = format!("{:.1} MB/s", counter.write_bytes() as f64 / 1024. / 1024.);
+            let bytes = (counter.read_bytes() as f64, counter.write_bytes() as f64);
             try_write(move |mut ctx| {
                 ctx.disk_speed_per_sec = (read_str.to_owned(), write_str.to_owned());
+                ctx.disk_bytes_per_sec = bytes;
             });
         }
     })
@@ -1427,7 +2770,8 @@ pub fn start_disk_counter_thread() -> std::thread::JoinHandle<()> {
 #[cfg(windows)]
 pub static HTTP_PORT: Lazy<u16> = Lazy::new(|| {
     use tiny_http::{Response, Server};
-    let server = Server::http("0.0.0.0:0").unwrap();
+    //只监听本机，硬件监控服务和推送自定义数值的程序都在本机运行，不对局域网开放
+    let server = Server::http("127.0.0.1:0").unwrap();
     let port = server.server_addr().to_ip().unwrap().port();
     std::thread::spawn(move || {
         for mut request in server.incoming_requests() {
@@ -1466,38 +2810,26 @@ pub static HTTP_PORT: Lazy<u16> = Lazy::new(|| {
                         info!("接收到:{json}");
                         if let Ok(info) = serde_json::from_str::<HardwareData>(&json) {
                             if let Ok(mut ctx) = SYSTEM_INFO.write() {
-                                if info.cpu_infos.len() > 0 {
-                                    ctx.cpu_temperatures = info.cpu_infos[0].temperatures.clone();
-                                    ctx.cpu_fans = info.cpu_infos[0].fans.clone();
-                                    ctx.cpu_temperature_total = info.cpu_infos[0].total_temperature;
-                                    ctx.cpu_cores_power = info.cpu_infos[0].cores_power;
-                                    ctx.cpu_package_power = info.cpu_infos[0].package_power;
-                                }
-                                ctx.gpu_clocks.clear();
-                                ctx.gpu_fans.clear();
-                                ctx.gpu_load.clear();
-                                ctx.gpu_temperatures.clear();
-                                ctx.gpu_temperature_total.clear();
-                                ctx.gpu_load_total.clear();
-                                ctx.gpu_memory_load.clear();
-                                ctx.gpu_memory_total.clear();
-                                for gpu_info in info.gpu_infos {
-                                    ctx.gpu_clocks.push(gpu_info.clocks.clone());
-                                    ctx.gpu_temperatures.push(gpu_info.temperatures.clone());
-                                    ctx.gpu_fans.push(gpu_info.fans.clone());
-                                    ctx.gpu_load.push(gpu_info.loads.clone());
-                                    ctx.gpu_temperature_total.push(gpu_info.total_temperature);
-                                    ctx.gpu_load_total.push(gpu_info.total_load);
-                                    ctx.gpu_cores_power = gpu_info.cores_power;
-                                    ctx.gpu_package_power = gpu_info.package_power;
-                                    ctx.gpu_memory_load.push(gpu_info.memory_load);
-                                    ctx.gpu_memory_total.push(gpu_info.memory_total);
-                                }
+                                update_hardware_data(&mut ctx, info);
                             }
                         }
                     }
                 }
                 let _ = request.respond(Response::from_string("OK"));
+            } else if url.contains("custom") {
+                let mut buf = vec![];
+                let _ = request.as_reader().read_to_end(&mut buf);
+                match serde_json::from_slice::<HashMap<String, serde_json::Value>>(&buf) {
+                    Ok(values) => {
+                        if let Ok(mut ctx) = SYSTEM_INFO.write() {
+                            update_custom_values(&mut ctx, values);
+                        }
+                        let _ = request.respond(Response::from_string("OK"));
+                    }
+                    Err(err) => {
+                        let _ = request.respond(Response::from_string(format!("{err}")).with_status_code(400));
+                    }
+                }
             }
         }
     });
@@ -0,0 +1,61 @@
+use image::RgbImage;
+
+// 单色OLED(SSD1306/SH1107)使用的1bit格式
+// 每8行为一页，每个字节是一列中的8个像素，最低位在最上面
+pub fn rgb_to_mono1(img: &RgbImage) -> Vec<u8> {
+    let (width, height) = (img.width() as usize, img.height() as usize);
+    let pixels = dither(img);
+    let pages = (height + 7) / 8;
+    let mut data = vec![0u8; width * pages];
+    for y in 0..height {
+        for x in 0..width {
+            if pixels[y * width + x] {
+                data[(y / 8) * width + x] |= 1 << (y % 8);
+            }
+        }
+    }
+    data
+}
+
+// Floyd–Steinberg抖动，返回每个像素是否点亮
+fn dither(img: &RgbImage) -> Vec<bool> {
+    let (width, height) = (img.width() as usize, img.height() as usize);
+    let mut luma: Vec<f32> = img
+        .pixels()
+        .map(|p| 0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32)
+        .collect();
+    let mut pixels = vec![false; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let idx = y * width + x;
+            let on = luma[idx] >= 128.;
+            pixels[idx] = on;
+            let error = luma[idx] - if on { 255. } else { 0. };
+            if x + 1 < width {
+                luma[idx + 1] += error * 7. / 16.;
+            }
+            if y + 1 < height {
+                if x > 0 {
+                    luma[idx + width - 1] += error * 3. / 16.;
+                }
+                luma[idx + width] += error * 5. / 16.;
+                if x + 1 < width {
+                    luma[idx + width + 1] += error * 1. / 16.;
+                }
+            }
+        }
+    }
+    pixels
+}
+
+#[test]
+fn test_rgb_to_mono1() -> anyhow::Result<()> {
+    //左半白色，右半黑色，第9行点亮第二页
+    let mut img = RgbImage::from_fn(4, 16, |x, _| {
+        if x < 2 { image::Rgb([255, 255, 255]) } else { image::Rgb([0, 0, 0]) }
+    });
+    img.put_pixel(3, 8, image::Rgb([255, 255, 255]));
+    let data = rgb_to_mono1(&img);
+    assert_eq!(data, vec![0xff, 0xff, 0, 0, 0xff, 0xff, 0, 0x01]);
+    Ok(())
+}
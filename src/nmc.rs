@@ -1,8 +1,11 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
 
-use anyhow::Result;
-use image::RgbaImage;
+use anyhow::{anyhow, Result};
+use image::{Rgba, RgbaImage};
 use log::info;
+use offscreen_canvas::{Font, FontSettings};
 use once_cell::sync::Lazy;
 use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
 use serde::{Deserialize, Serialize};
@@ -10,7 +13,54 @@ use serde::{Deserialize, Serialize};
 pub const CITIES: Lazy<Vec<City>> =
     Lazy::new(|| serde_json::from_str(include_str!("../cities.json")).unwrap());
 
-pub const ICONS: Lazy<Vec<RgbaImage>> = Lazy::new(|| {
+// 天气组件的城市为"自动"时，按公网IP定位的城市显示天气
+pub const AUTO_CITY: &str = "自动";
+
+// 天气图标样式(保存的名称, 显示的名称)，字体图标没有加载字体时使用彩色图标
+pub const WEATHER_ICON_SETS: [(&str, &str); 3] = [("color", "彩色"), ("mono", "单色"), ("font", "字体")];
+// 当前布局使用的图标样式
+static WEATHER_ICON_SET: AtomicUsize = AtomicUsize::new(0);
+// 当前布局的图标字体
+static ICON_FONT: RwLock<Option<Font>> = RwLock::new(None);
+
+// 天气现象编码(与图片序号相同)对应的图标字体字符，按 Weather Icons 字体的编码
+const WEATHER_GLYPHS: [char; 33] = [
+    '\u{f00d}', //0 晴
+    '\u{f002}', //1 多云
+    '\u{f013}', //2 阴
+    '\u{f01a}', //3 阵雨
+    '\u{f01e}', //4 雷阵雨
+    '\u{f015}', //5 雷阵雨伴有冰雹
+    '\u{f0b5}', //6 雨夹雪
+    '\u{f01c}', //7 小雨
+    '\u{f019}', //8 中雨
+    '\u{f019}', //9 大雨
+    '\u{f019}', //10 暴雨
+    '\u{f019}', //11 大暴雨
+    '\u{f019}', //12 特大暴雨
+    '\u{f01b}', //13 阵雪
+    '\u{f01b}', //14 小雪
+    '\u{f01b}', //15 中雪
+    '\u{f01b}', //16 大雪
+    '\u{f064}', //17 暴雪
+    '\u{f014}', //18 雾
+    '\u{f017}', //19 冻雨
+    '\u{f082}', //20 沙尘暴
+    '\u{f019}', //21 小到中雨
+    '\u{f019}', //22 中到大雨
+    '\u{f019}', //23 大到暴雨
+    '\u{f019}', //24 暴雨到大暴雨
+    '\u{f019}', //25 大暴雨到特大暴雨
+    '\u{f01b}', //26 小到中雪
+    '\u{f01b}', //27 中到大雪
+    '\u{f064}', //28 大到暴雪
+    '\u{f063}', //29 浮尘
+    '\u{f063}', //30 扬沙
+    '\u{f082}', //31 强沙尘暴
+    '\u{f074}', //32 霾
+];
+
+static ICONS: Lazy<Vec<RgbaImage>> = Lazy::new(|| {
     vec![
         image::load_from_memory(include_bytes!("../images/0.png"))
             .unwrap()
@@ -114,6 +164,96 @@ pub const ICONS: Lazy<Vec<RgbaImage>> = Lazy::new(|| {
     ]
 });
 
+// 单色图标，保留透明度，颜色换成白色，适合深色背景
+static MONO_ICONS: Lazy<Vec<RgbaImage>> = Lazy::new(|| {
+    ICONS
+        .iter()
+        .map(|icon| {
+            let mut icon = icon.clone();
+            for p in icon.pixels_mut() {
+                p.0 = [255, 255, 255, p[3]];
+            }
+            icon
+        })
+        .collect()
+});
+
+// 按名称设置图标样式，未知的名称使用默认样式
+pub fn set_weather_icon_set(name: &str) {
+    let index = WEATHER_ICON_SETS.iter().position(|(n, _)| *n == name).unwrap_or(0);
+    WEATHER_ICON_SET.store(index, Ordering::SeqCst);
+}
+
+// 设置图标字体，None清除
+pub fn set_icon_font(font: Option<&[u8]>) -> Result<()> {
+    let font = match font {
+        Some(font) => Some(Font::from_bytes(font, FontSettings::default()).map_err(|err| anyhow!("{err}"))?),
+        None => None,
+    };
+    if let Ok(mut icon_font) = ICON_FONT.write() {
+        *icon_font = font;
+    }
+    Ok(())
+}
+
+// 使用字体图标时，按字号绘制图标字符，居中放在size x size的图像中
+pub fn weather_glyph(index: usize, size: f32, color: [u8; 4]) -> Option<RgbaImage> {
+    if WEATHER_ICON_SETS.get(WEATHER_ICON_SET.load(Ordering::SeqCst))?.0 != "font" {
+        return None;
+    }
+    let glyph = *WEATHER_GLYPHS.get(index)?;
+    let icon_font = ICON_FONT.read().ok()?;
+    let font = icon_font.as_ref()?;
+    let (metrics, coverage) = font.rasterize(glyph, size);
+    let side = size.max(1.) as u32;
+    let mut image = RgbaImage::new(side, side);
+    let left = (side as i32 - metrics.width as i32) / 2;
+    let top = (side as i32 - metrics.height as i32) / 2;
+    for (i, alpha) in coverage.iter().enumerate() {
+        let x = left + (i % metrics.width.max(1)) as i32;
+        let y = top + (i / metrics.width.max(1)) as i32;
+        if *alpha == 0 || x < 0 || y < 0 || x >= side as i32 || y >= side as i32 {
+            continue;
+        }
+        let a = (*alpha as u16 * color[3] as u16 / 255) as u8;
+        image.put_pixel(x as u32, y as u32, Rgba([color[0], color[1], color[2], a]));
+    }
+    Some(image)
+}
+
+pub fn weather_icon(index: usize) -> Option<&'static RgbaImage> {
+    match WEATHER_ICON_SET.load(Ordering::SeqCst) {
+        1 => MONO_ICONS.get(index),
+        _ => ICONS.get(index),
+    }
+}
+
+// 去掉行政区划的后缀，例如 广东省->广东 深圳市->深圳
+fn short_name(name: &str) -> &str {
+    let name = name.trim();
+    for suffix in ["特别行政区", "维吾尔自治区", "壮族自治区", "回族自治区", "自治区", "省", "市", "区", "县"] {
+        if let Some(short) = name.strip_suffix(suffix).filter(|s| !s.is_empty()) {
+            return short;
+        }
+    }
+    name
+}
+
+// 按IP定位的省份和城市查找对应的城市，找不到城市时使用省份的第一个城市(省会)
+// 省份不在列表中时只按城市名称查找
+pub fn find_city(region: &str, city: &str) -> Option<City> {
+    let cities = CITIES;
+    let (region, city) = (short_name(region), short_name(city));
+    let in_region = |c: &&City| short_name(&c.province) == region;
+    cities
+        .iter()
+        .filter(in_region)
+        .find(|c| short_name(&c.city) == city)
+        .or_else(|| cities.iter().find(in_region))
+        .or_else(|| cities.iter().find(|c| short_name(&c.city) == city))
+        .cloned()
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Province {
     code: String,
@@ -138,7 +278,63 @@ pub struct WeatherResp {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WeatherData {
-    real: RealWeather,
+    pub real: RealWeather,
+    //天气预报
+    #[serde(default)]
+    pub predict: Option<Predict>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Predict {
+    pub publish_time: String,
+    pub detail: Vec<PredictDay>,
+}
+
+//每天的预报，第一个为今天
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PredictDay {
+    pub date: String,
+    pub day: PredictPart,
+    pub night: PredictPart,
+}
+
+impl PredictDay {
+    //白天气温，已经过去时为None
+    pub fn high(&self) -> Option<f32> {
+        self.day.weather.temperature()
+    }
+
+    pub fn low(&self) -> Option<f32> {
+        self.night.weather.temperature()
+    }
+
+    //白天已经过去时使用夜间天气
+    pub fn info(&self) -> &str {
+        if self.day.weather.temperature().is_some() {
+            &self.day.weather.info
+        } else {
+            &self.night.weather.info
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PredictPart {
+    pub weather: PredictWeather,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PredictWeather {
+    pub info: String,
+    pub img: String,
+    pub temperature: String,
+}
+
+impl PredictWeather {
+    //没有数据时为9999
+    pub fn temperature(&self) -> Option<f32> {
+        self.temperature.parse::<f32>().ok().filter(|t| *t < 9999.)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -164,6 +360,13 @@ pub struct Weather {
     pub feelst: f32,
 }
 
+impl Weather {
+    //没有数据时为9999
+    pub fn valid(value: f32) -> Option<f32> {
+        Some(value).filter(|v| *v < 9999.)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Wind {
     pub direct: String,
@@ -194,7 +397,13 @@ pub fn query_city() -> Result<Vec<City>> {
     Ok(cities)
 }
 
+#[allow(unused)]
 pub fn query_weather(station_id: &str) -> Result<RealWeather> {
+    Ok(query_weather_data(station_id)?.real)
+}
+
+//实时天气和天气预报
+pub fn query_weather_data(station_id: &str) -> Result<WeatherData> {
     let client = reqwest::blocking::Client::new();
     let res = client.get(format!("http://www.nmc.cn/rest/weather?stationid={station_id}"))
         .header(USER_AGENT, "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36 Edg/126.0.0.0")
@@ -205,7 +414,7 @@ pub fn query_weather(station_id: &str) -> Result<RealWeather> {
     
     // info!("天气:{json}");
     let resp = serde_json::from_str::<WeatherResp>(&json)?;
-    Ok(resp.data.real)
+    Ok(resp.data)
 }
 
 #[test]
@@ -221,6 +430,18 @@ fn download_city() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_find_city() {
+    let province = |region: &str, city: &str| find_city(region, city).map(|c| c.province);
+    assert_eq!(province("北京市", "朝阳区").as_deref(), Some("北京市"));
+    assert_eq!(province("辽宁", "朝阳市").as_deref(), Some("辽宁省"));
+    //没有对应的城市时使用省会
+    assert_eq!(find_city("河北省", "雄安新区").map(|c| c.city).as_deref(), Some("石家庄"));
+    assert_eq!(province("", "朝阳").as_deref(), Some("北京市"));
+    assert!(find_city("California", "Los Angeles").is_none());
+    assert!(find_city("", "").is_none());
+}
+
 #[test]
 fn test_weather() -> Result<()> {
     env_logger::builder()
@@ -0,0 +1,157 @@
+use std::{process::{Command, Output, Stdio}, time::{Duration, Instant}};
+
+use anyhow::{anyhow, Result};
+use reqwest::header::USER_AGENT;
+use serde_json::Value;
+
+use crate::config;
+use crate::ticker::json_number;
+
+// usb-screen.json中没有设置adb_path时使用PATH中的adb
+pub const DEFAULT_ADB: &str = "adb";
+// 设备未授权或者adb服务卡住时不会返回，超时后结束进程
+const ADB_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 手机的电池状态
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhoneBattery {
+    //电量百分比
+    pub level: f32,
+    pub charging: bool,
+    pub full: bool,
+    //电池温度(℃)
+    pub temperature: Option<f32>,
+}
+
+impl PhoneBattery {
+    // 充电状态和电池温度，例如 "充电中 31.5℃"
+    pub fn status(&self) -> String {
+        let state = if self.full {
+            "已充满"
+        } else if self.charging {
+            "充电中"
+        } else {
+            "未充电"
+        };
+        match self.temperature {
+            Some(temperature) => format!("{state} {temperature:.1}℃"),
+            None => state.to_string(),
+        }
+    }
+}
+
+// source为http地址时请求手机上的配套程序，否则作为adb的设备序列号，为空时使用唯一连接的设备
+// source来自布局文件，adb程序的路径只从配置文件读取，手机未连接时返回None
+pub fn query_battery(source: &str) -> Result<Option<PhoneBattery>> {
+    let source = source.trim();
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(5))
+            .build()?;
+        //配套程序在手机上，连接失败按未连接处理
+        let res = match client.get(source).header(USER_AGENT, "USB-Screen").send() {
+            Ok(res) => res.error_for_status()?,
+            Err(err) if err.is_connect() || err.is_timeout() => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        return parse_json(&res.json()?).map(Some);
+    }
+    if !is_valid_serial(source) {
+        return Err(anyhow!("设备序列号错误:{source}"));
+    }
+    let mut command = Command::new(config::adb_path().unwrap_or(DEFAULT_ADB.to_string()));
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        //不显示命令行窗口
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    if !source.is_empty() {
+        command.args(["-s", source]);
+    }
+    let output = output_with_timeout(command.args(["shell", "dumpsys", "battery"]), ADB_TIMEOUT)?;
+    //没有连接设备时adb返回错误
+    if !output.status.success() {
+        return Ok(None);
+    }
+    parse_dumpsys(&String::from_utf8_lossy(&output.stdout)).map(Some)
+}
+
+// 运行命令读取标准输出，超时后结束进程并返回错误
+fn output_with_timeout(command: &mut Command, timeout: Duration) -> Result<Output> {
+    let mut child = command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn()?;
+    let start = Instant::now();
+    while child.try_wait()?.is_none() {
+        if start.elapsed() > timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(anyhow!("adb没有响应"));
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    Ok(child.wait_with_output()?)
+}
+
+// adb的设备序列号，例如 R58M12345 或者 192.168.1.5:5555，不能作为命令行选项
+fn is_valid_serial(serial: &str) -> bool {
+    !serial.starts_with('-') && serial.chars().all(|c| c.is_ascii_alphanumeric() || ".:-_".contains(c))
+}
+
+// 配套程序返回的JSON: {"level":85,"charging":true,"full":false,"temperature":31.5}
+pub fn parse_json(json: &Value) -> Result<PhoneBattery> {
+    let level = json_number(json, "level").ok_or(anyhow!("没有电量数据"))?;
+    let flag = |key: &str| match json.get(key) {
+        Some(Value::Bool(v)) => *v,
+        Some(Value::String(s)) => s == "true",
+        _ => false,
+    };
+    Ok(PhoneBattery {
+        level: level.clamp(0., 100.) as f32,
+        charging: flag("charging"),
+        full: flag("full"),
+        temperature: json_number(json, "temperature").map(|t| t as f32),
+    })
+}
+
+// 解析 adb shell dumpsys battery 的输出，温度单位为0.1℃，status为2时充电、5时充满
+pub fn parse_dumpsys(output: &str) -> Result<PhoneBattery> {
+    let value = |key: &str| {
+        output.lines().find_map(|line| {
+            let (name, value) = line.trim().split_once(':')?;
+            if name.trim() == key {
+                Some(value.trim().to_string())
+            } else {
+                None
+            }
+        })
+    };
+    let number = |key: &str| value(key).and_then(|v| v.parse::<f32>().ok());
+    let level = number("level").ok_or(anyhow!("没有电量数据"))?;
+    let scale = number("scale").filter(|scale| *scale > 0.).unwrap_or(100.);
+    let status = number("status").unwrap_or(0.) as u32;
+    Ok(PhoneBattery {
+        level: (level * 100. / scale).clamp(0., 100.),
+        charging: status == 2,
+        full: status == 5,
+        temperature: number("temperature").map(|t| t / 10.),
+    })
+}
+
+#[test]
+fn test_parse_battery() -> Result<()> {
+    let output = "Current Battery Service state:\n  AC powered: false\n  USB powered: true\n  status: 2\n  health: 2\n  present: true\n  level: 85\n  scale: 100\n  voltage: 4200\n  temperature: 315\n";
+    let battery = parse_dumpsys(output)?;
+    assert_eq!(battery, PhoneBattery { level: 85., charging: true, full: false, temperature: Some(31.5) });
+    assert_eq!(battery.status(), "充电中 31.5℃");
+    assert!(parse_dumpsys("error: no devices/emulators found").is_err());
+
+    let json: Value = serde_json::from_str(r#"{"level":"42","charging":false}"#)?;
+    let battery = parse_json(&json)?;
+    assert_eq!(battery.level, 42.);
+    assert_eq!(battery.status(), "未充电");
+
+    assert!(is_valid_serial("") && is_valid_serial("R58M12345") && is_valid_serial("192.168.1.5:5555"));
+    assert!(!is_valid_serial("-P") && !is_valid_serial("C:\\tools\\evil.exe") && !is_valid_serial("a b"));
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::process::Command;
+
+use anyhow::{anyhow, Result};
+
+// 调用系统的ping命令，不需要管理员权限
+// 返回往返时间(毫秒)，主机不可达时返回None，在线但是没有读到时间时为NaN
+pub fn ping(host: &str) -> Result<Option<f32>> {
+    //主机名来自布局文件，不能作为命令行选项
+    if host.is_empty() || host.starts_with('-') || host.contains(char::is_whitespace) {
+        return Err(anyhow!("主机名错误:{host}"));
+    }
+    let mut command = Command::new("ping");
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        //不显示命令行窗口
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.args(["-n", "1", "-w", "1000"]).creation_flags(CREATE_NO_WINDOW);
+    }
+    //macOS的-W单位为毫秒，Linux为秒
+    #[cfg(target_os = "macos")]
+    command.args(["-c", "1", "-W", "1000"]);
+    #[cfg(not(any(windows, target_os = "macos")))]
+    command.args(["-c", "1", "-W", "1"]);
+    let output = command.arg(host).output()?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    //中文Windows的输出为GBK编码，读不到时间时也按在线处理
+    //Windows收到"无法访问目标主机"时也返回成功，这时没有TTL
+    let output = String::from_utf8_lossy(&output.stdout);
+    Ok(match parse_ping_time(&output) {
+        Some(ms) => Some(ms),
+        None if !cfg!(windows) || output.to_ascii_uppercase().contains("TTL=") => Some(f32::NAN),
+        None => None,
+    })
+}
+
+// 从ping的输出中读取 time=12.3 ms / 时间=12ms / time<1ms
+// 关键字无法识别时(例如GBK编码)，读取 =12ms、<1ms 这样的数字
+pub fn parse_ping_time(output: &str) -> Option<f32> {
+    for key in ["time", "时间"] {
+        for line in output.lines() {
+            let rest = match line.find(key) {
+                Some(idx) => &line[idx + key.len()..],
+                None => continue,
+            };
+            let rest = rest.trim_start_matches(['=', '<', ' ']);
+            let number: String = rest
+                .chars()
+                .take_while(|c| c.is_ascii_digit() || *c == '.')
+                .collect();
+            if let Ok(ms) = number.parse::<f32>() {
+                return Some(ms);
+            }
+        }
+    }
+    for line in output.lines() {
+        for (idx, _) in line.match_indices("ms") {
+            let head = line[..idx].trim_end();
+            let start = head.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.').len();
+            if head[..start].ends_with(['=', '<']) {
+                if let Ok(ms) = head[start..].parse::<f32>() {
+                    return Some(ms);
+                }
+            }
+        }
+    }
+    None
+}
+
+#[test]
+fn test_parse_ping_time() -> Result<()> {
+    let linux = "64 bytes from 192.168.1.1: icmp_seq=1 ttl=64 time=0.512 ms";
+    assert_eq!(parse_ping_time(linux), Some(0.512));
+    let windows = "来自 192.168.1.1 的回复: 字节=32 时间<1ms TTL=64";
+    assert_eq!(parse_ping_time(windows), Some(1.));
+    let windows_en = "Reply from 10.0.0.2: bytes=32 time=14ms TTL=117";
+    assert_eq!(parse_ping_time(windows_en), Some(14.));
+    assert_eq!(parse_ping_time("Request timed out."), None);
+    //GBK编码的 "来自 10.0.0.2 的回复: 字节=32 时间=14ms TTL=117"
+    let gbk = b"\xc0\xb4\xd7\xd4 10.0.0.2 \xb5\xc4\xbb\xd8\xb8\xb4: \xd7\xd6\xbd\xda=32 \xca\xb1\xbc\xe4=14ms TTL=117";
+    assert_eq!(parse_ping_time(&String::from_utf8_lossy(gbk)), Some(14.));
+    assert!(ping("-f").is_err());
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use std::io::{ErrorKind, Read};
+
+use anyhow::{anyhow, Result};
+
+// 单条消息的最大长度
+const MAX_MESSAGE_SIZE: usize = 32 * 1024 * 1024;
+
+// 从标准输入或命名管道读取的消息
+// 格式: 1字节类型 + 4字节长度(大端) + 数据
+// 类型 'I' 图片(PNG/JPG/GIF)，'L' 布局(.screen文件或者导出的json)
+#[derive(Debug, PartialEq)]
+pub enum Message {
+    Image(Vec<u8>),
+    Layout(Vec<u8>),
+}
+
+// 读到结尾时返回None
+pub fn read_message(reader: &mut impl Read) -> Result<Option<Message>> {
+    let mut header = [0u8; 5];
+    if let Err(err) = reader.read_exact(&mut header) {
+        if err.kind() == ErrorKind::UnexpectedEof {
+            return Ok(None);
+        }
+        return Err(err.into());
+    }
+    let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    if len > MAX_MESSAGE_SIZE {
+        return Err(anyhow!("消息太大:{len}"));
+    }
+    let mut data = vec![0u8; len];
+    reader.read_exact(&mut data)?;
+    match header[0] {
+        b'I' => Ok(Some(Message::Image(data))),
+        b'L' => Ok(Some(Message::Layout(data))),
+        t => Err(anyhow!("未知的消息类型:{t}")),
+    }
+}
+
+#[test]
+fn test_read_message() -> Result<()> {
+    let mut input = vec![b'I', 0, 0, 0, 3, 1, 2, 3];
+    input.extend_from_slice(&[b'L', 0, 0, 0, 2, b'{', b'}']);
+    let mut reader = input.as_slice();
+    assert_eq!(read_message(&mut reader)?, Some(Message::Image(vec![1, 2, 3])));
+    assert_eq!(read_message(&mut reader)?, Some(Message::Layout(b"{}".to_vec())));
+    assert_eq!(read_message(&mut reader)?, None);
+    //未知类型
+    assert!(read_message(&mut [b'X', 0, 0, 0, 0].as_slice()).is_err());
+    Ok(())
+}
@@ -1,8 +1,38 @@
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+// RGB565字节序，ST7789等驱动使用Big-Endian
+#[derive(Debug, Clone, Copy, Default, PartialEq, Encode, Decode, Deserialize, Serialize)]
+pub enum ByteOrder {
+    #[default]
+    BigEndian,
+    LittleEndian,
+}
+
 #[inline]
 pub fn rgb_to_rgb565(r: u8, g: u8, b: u8) -> u16 {
     ((r as u16 & 0b11111000) << 8) | ((g as u16 & 0b11111100) << 3) | (b as u16 >> 3)
 }
 
+// RGB565还原为RGB888，低位用高位填充
+#[inline]
+pub fn rgb565_to_rgb(pixel: u16) -> (u8, u8, u8) {
+    let r = ((pixel >> 11) & 0x1f) as u8;
+    let g = ((pixel >> 5) & 0x3f) as u8;
+    let b = (pixel & 0x1f) as u8;
+    ((r << 3) | (r >> 2), (g << 2) | (g >> 4), (b << 3) | (b >> 2))
+}
+
+// 将RGBA图像的颜色降为RGB565，用于预览屏幕上的实际效果
+pub fn reduce_rgba_to_rgb565(rgba: &mut [u8]) {
+    for p in rgba.chunks_mut(4) {
+        let (r, g, b) = rgb565_to_rgb(rgb_to_rgb565(p[0], p[1], p[2]));
+        p[0] = r;
+        p[1] = g;
+        p[2] = b;
+    }
+}
+
 pub fn rgb888_to_rgb565_be(img: &[u8], width: usize, height: usize) -> Vec<u8>{
     let mut rgb565 = Vec::with_capacity(width * height * 2);
     for p in img.chunks(3){
@@ -10,4 +40,20 @@ pub fn rgb888_to_rgb565_be(img: &[u8], width: usize, height: usize) -> Vec<u8>{
         rgb565.extend_from_slice(&rgb565_pixel.to_be_bytes());
     }
     rgb565
+}
+
+pub fn rgb888_to_rgb565_le(img: &[u8], width: usize, height: usize) -> Vec<u8>{
+    let mut rgb565 = Vec::with_capacity(width * height * 2);
+    for p in img.chunks(3){
+        let rgb565_pixel = rgb_to_rgb565(p[0], p[1], p[2]);
+        rgb565.extend_from_slice(&rgb565_pixel.to_le_bytes());
+    }
+    rgb565
+}
+
+pub fn rgb888_to_rgb565(img: &[u8], width: usize, height: usize, byte_order: ByteOrder) -> Vec<u8>{
+    match byte_order{
+        ByteOrder::BigEndian => rgb888_to_rgb565_be(img, width, height),
+        ByteOrder::LittleEndian => rgb888_to_rgb565_le(img, width, height),
+    }
 }
\ No newline at end of file
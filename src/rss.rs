@@ -0,0 +1,86 @@
+use anyhow::Result;
+use reqwest::header::USER_AGENT;
+
+// 标题之间的分隔符
+pub const TITLE_SEPARATOR: &str = "  ·  ";
+
+// 下载RSS/Atom订阅，返回所有条目的标题
+pub fn query_titles(url: &str) -> Result<Vec<String>> {
+    let client = reqwest::blocking::Client::new();
+    let res = client.get(url)
+        .header(USER_AGENT, "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36 Edg/126.0.0.0")
+        .send()?
+        .error_for_status()?;
+    Ok(parse_titles(&res.text()?))
+}
+
+// 读取RSS的<item>和Atom的<entry>中的<title>，忽略频道标题
+pub fn parse_titles(xml: &str) -> Vec<String> {
+    let mut titles = vec![];
+    for tag in ["item", "entry"] {
+        let open = format!("<{tag}");
+        let close = format!("</{tag}>");
+        let mut rest = xml;
+        while let Some(start) = find_tag(rest, &open) {
+            rest = &rest[start..];
+            let end = match rest.find(&close) {
+                Some(end) => end,
+                None => break,
+            };
+            if let Some(title) = element_text(&rest[..end], "title") {
+                if !title.is_empty() {
+                    titles.push(title);
+                }
+            }
+            rest = &rest[end + close.len()..];
+        }
+    }
+    titles
+}
+
+// 查找标签开始位置，避免<item>匹配到<itemFoo>
+fn find_tag(xml: &str, open: &str) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(pos) = xml[offset..].find(open) {
+        let start = offset + pos;
+        match xml[start + open.len()..].chars().next() {
+            Some('>') | Some(' ') | Some('\t') | Some('\r') | Some('\n') | Some('/') => return Some(start),
+            _ => offset = start + open.len(),
+        }
+    }
+    None
+}
+
+fn element_text(xml: &str, tag: &str) -> Option<String> {
+    let start = find_tag(xml, &format!("<{tag}"))?;
+    let content_start = start + xml[start..].find('>')? + 1;
+    let content_end = content_start + xml[content_start..].find(&format!("</{tag}>"))?;
+    let text = xml[content_start..content_end].trim();
+    let text = text
+        .strip_prefix("<![CDATA[")
+        .and_then(|t| t.strip_suffix("]]>"))
+        .map(|t| t.to_string())
+        .unwrap_or_else(|| unescape(text));
+    Some(text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[test]
+fn test_parse_titles() -> Result<()> {
+    let rss = r#"<rss><channel><title>频道</title>
+        <item><title>第一条 &amp; 新闻</title></item>
+        <item><title><![CDATA[第二条 <b>新闻</b>]]></title></item>
+    </channel></rss>"#;
+    assert_eq!(parse_titles(rss), vec!["第一条 & 新闻", "第二条 <b>新闻</b>"]);
+    let atom = r#"<feed><title>Feed</title><entry><title type="text">Hello</title></entry></feed>"#;
+    assert_eq!(parse_titles(atom), vec!["Hello"]);
+    Ok(())
+}
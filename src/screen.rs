@@ -1,18 +1,226 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{collections::HashMap, path::{Path, PathBuf}, sync::Mutex, time::{Duration, Instant}};
 
 use crate::{
-    monitor::{self, WebcamInfo},
-    nmc::CITIES,
-    widgets::{ImageWidget, SaveableWidget, TextWidget, Widget},
+    config,
+    lut::Lut,
+    monitor::{self, Correction, ScreenCaptureInfo, WebcamInfo},
+    nmc::{self, CITIES},
+    rgb565::ByteOrder,
+    sensor_spec::{self, SensorSpec},
+    transition::{Animation, Transition, TransitionKind, DEFAULT_TRANSITION_DURATION},
+    utils::crc32_chunks,
+    widgets::{self, CpuCoresWidget, CustomWidgetData, Fit, ImageData, ImageSource, ImageWidget, Rect, SaveableWidget, SummaryWidget, TextWidget, Theme, Widget},
 };
 use anyhow::{anyhow, Result};
+use image::{buffer::ConvertBuffer, RgbImage, Rgba, RgbaImage};
 use bincode::{Decode, Encode};
-use log::info;
+use fast_image_resize::{images::Image, PixelType, Resizer};
+use log::{error, info, warn};
 use lz4_flex::{compress_prepend_size, decompress_size_prepended};
+use once_cell::sync::Lazy;
 use offscreen_canvas::{Font, FontSettings, OffscreenCanvas, BLACK};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 pub static DEFAULT_FONT: &[u8] = include_bytes!("../fonts/VonwaonBitmap-16px.ttf");
+//订阅默认刷新间隔(分钟)
+pub const DEFAULT_FEED_INTERVAL: u64 = 10;
+//最大超采样倍数
+pub const MAX_SUPERSAMPLE: u8 = 3;
+//页面轮播默认间隔(秒)
+pub const DEFAULT_PAGE_INTERVAL: f32 = 10.;
+
+// screen文件的压缩方式，LZ4速度快，zstd压缩率高，适合分享包含大量图像的布局
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Compression {
+    #[default]
+    Lz4,
+    Zstd,
+}
+
+impl Compression {
+    // 按照设置中的高压缩率选项选择压缩方式
+    pub fn from_config() -> Self {
+        if config::high_compression() { Compression::Zstd } else { Compression::Lz4 }
+    }
+}
+
+// zstd帧固定以这4个字节开头，LZ4格式开头是4字节的原始长度(这个值约4GB)，加载时据此识别压缩方式
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+const ZSTD_LEVEL: i32 = 19;
+
+pub fn compress_screen_data(data: &[u8], compression: Compression) -> Result<Vec<u8>> {
+    Ok(match compression {
+        Compression::Lz4 => compress_prepend_size(data),
+        Compression::Zstd => zstd::encode_all(data, ZSTD_LEVEL)?,
+    })
+}
+
+pub fn decompress_screen_data(data: &[u8]) -> Result<Vec<u8>> {
+    if data.starts_with(&ZSTD_MAGIC) {
+        Ok(zstd::decode_all(data)?)
+    } else {
+        Ok(decompress_size_prepended(data)?)
+    }
+}
+
+// 在(x,y)处创建自定义组件
+pub type WidgetFactory = fn(x: i32, y: i32) -> Box<dyn Widget>;
+
+// 自定义组件的保存和加载
+#[derive(Clone, Copy)]
+pub struct WidgetSerializer {
+    pub save: fn(widget: &mut dyn Widget) -> Result<String>,
+    pub load: fn(data: &str) -> Result<Box<dyn Widget>>,
+}
+
+// 组合组件的类型，add_widget和加载文件时使用注册的函数创建组件
+static WIDGET_TYPES: Lazy<HashMap<String, (WidgetFactory, WidgetSerializer)>> = Lazy::new(|| {
+    let mut types: HashMap<String, (WidgetFactory, WidgetSerializer)> = HashMap::new();
+    types.insert(
+        SummaryWidget::TYPE_NAME.to_string(),
+        (SummaryWidget::create, WidgetSerializer { save: SummaryWidget::save, load: SummaryWidget::load }),
+    );
+    types.insert(
+        CpuCoresWidget::TYPE_NAME.to_string(),
+        (CpuCoresWidget::create, WidgetSerializer { save: CpuCoresWidget::save, load: CpuCoresWidget::load }),
+    );
+    types
+});
+
+fn registered_widget_type(type_name: &str) -> Option<(WidgetFactory, WidgetSerializer)> {
+    WIDGET_TYPES.get(type_name).cloned()
+}
+
+// 导出的未压缩布局文件
+pub fn is_json_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.eq_ignore_ascii_case("json"))
+        .unwrap_or(false)
+}
+
+// 和目标画布同样大小的透明临时画布，大小变化时重新创建
+fn scratch_canvas<'a>(scratch: &'a mut Option<OffscreenCanvas>, target: &OffscreenCanvas) -> &'a mut OffscreenCanvas {
+    let (width, height) = (target.width(), target.height());
+    if scratch.as_ref().map(|c| c.width() != width || c.height() != height).unwrap_or(false) {
+        *scratch = None;
+    }
+    let canvas = scratch.get_or_insert_with(|| OffscreenCanvas::new(width, height, target.font().clone()));
+    canvas.clear(Rgba([0, 0, 0, 0]));
+    canvas
+}
+
+// 组件不透明时直接绘制，否则先画在透明的临时画布上，只把绘制的区域按不透明度叠加
+fn draw_widget(widget: &mut dyn Widget, canvas: &mut OffscreenCanvas, scratch: &mut Option<OffscreenCanvas>, tick: u128) {
+    let opacity = widget.opacity().clamp(0., 1.);
+    if opacity >= 1. {
+        widget.draw(canvas, tick);
+        return;
+    }
+    let layer = scratch_canvas(scratch, canvas);
+    widget.draw(layer, tick);
+    if let Some((image, left, top)) = crop_transparent(layer.image_data(), opacity) {
+        canvas.draw_image_at(&image, left, top, None, None);
+    }
+}
+
+// 组件上次绘制的图像和位置，没有绘制任何内容时image为None
+struct CachedRaster {
+    image: Option<RgbaImage>,
+    left: i32,
+    top: i32,
+    time: u128,
+}
+
+// 设置了最短重绘间隔的组件在透明画布上单独绘制，间隔内直接贴上次的图像
+#[derive(Default)]
+struct RasterCache {
+    canvas: Option<OffscreenCanvas>,
+    rasters: HashMap<String, CachedRaster>,
+}
+
+impl RasterCache {
+    fn clear(&mut self) {
+        self.rasters.clear();
+    }
+
+    fn draw(&mut self, widget: &mut dyn Widget, target: &mut OffscreenCanvas, tick: u128) {
+        let interval = widget.min_refresh_ms() as u128;
+        if interval == 0 {
+            draw_widget(widget, target, &mut self.canvas, tick);
+            return;
+        }
+        if let Some(raster) = self.rasters.get(widget.id()).filter(|r| tick.saturating_sub(r.time) < interval) {
+            if let Some(image) = raster.image.as_ref() {
+                target.draw_image_at(image, raster.left, raster.top, None, None);
+            }
+            return;
+        }
+        let canvas = scratch_canvas(&mut self.canvas, target);
+        widget.draw(canvas, tick);
+        let (image, left, top) = match crop_transparent(canvas.image_data(), widget.opacity().clamp(0., 1.)) {
+            Some((image, left, top)) => (Some(image), left, top),
+            None => (None, 0, 0),
+        };
+        if let Some(image) = image.as_ref() {
+            target.draw_image_at(image, left, top, None, None);
+        }
+        self.rasters.insert(widget.id().to_string(), CachedRaster { image, left, top, time: tick });
+    }
+}
+
+// 裁剪掉四周透明的部分并乘以不透明度，返回图像和左上角位置
+fn crop_transparent(image: &RgbaImage, opacity: f32) -> Option<(RgbaImage, i32, i32)> {
+    let (mut left, mut top, mut right, mut bottom) = (image.width(), image.height(), 0, 0);
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if pixel[3] > 0 {
+            left = left.min(x);
+            top = top.min(y);
+            right = right.max(x + 1);
+            bottom = bottom.max(y + 1);
+        }
+    }
+    if left >= right || top >= bottom {
+        return None;
+    }
+    let mut cropped = image::imageops::crop_imm(image, left, top, right - left, bottom - top).to_image();
+    if opacity < 1. {
+        for pixel in cropped.pixels_mut() {
+            pixel[3] = (pixel[3] as f32 * opacity).round() as u8;
+        }
+    }
+    Some((cropped, left as i32, top as i32))
+}
+
+// 性能分析：每帧和每个组件的绘制耗时(毫秒，滑动平均)
+#[derive(Debug, Default)]
+pub struct RenderProfile {
+    pub render_ms: f32,
+    widget_ms: HashMap<String, f32>,
+}
+
+impl RenderProfile {
+    fn smooth(value: &mut f32, ms: f32) {
+        *value = if *value == 0. { ms } else { *value * 0.9 + ms * 0.1 };
+    }
+
+    fn record_widget(&mut self, id: &str, elapsed: Duration) {
+        let value = self.widget_ms.entry(id.to_string()).or_default();
+        Self::smooth(value, elapsed.as_secs_f32() * 1000.);
+    }
+
+    fn record_render(&mut self, elapsed: Duration) {
+        Self::smooth(&mut self.render_ms, elapsed.as_secs_f32() * 1000.);
+    }
+
+    // 最慢的count个组件
+    pub fn slowest(&self, count: usize) -> Vec<(&str, f32)> {
+        let mut widgets: Vec<(&str, f32)> = self.widget_ms.iter().map(|(id, ms)| (id.as_str(), *ms)).collect();
+        widgets.sort_by(|a, b| b.1.total_cmp(&a.1));
+        widgets.truncate(count);
+        widgets
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct ScreenSize {
@@ -33,7 +241,202 @@ pub struct SaveableScreen {
     pub widgets: Vec<SaveableWidget>,
     pub font: Option<Vec<u8>>,
     pub font_name: String,
-    pub rotate_degree: Option<i32>
+    pub rotate_degree: Option<i32>,
+    //屏幕的RGB565字节序，不指定时使用设备串号中的配置
+    pub byte_order: Option<ByteOrder>,
+    //传感器读数校准
+    pub corrections: Option<Vec<Correction>>,
+    //退出时屏幕的显示
+    pub shutdown: Option<ShutdownAction>,
+    //夜间模式(反色)和亮度(0.1~1.0)
+    pub night_mode: Option<bool>,
+    pub brightness: Option<f32>,
+    //整个画面的颜色查找表(.cube)，在夜间模式和亮度之前应用
+    pub lut: Option<Lut>,
+    //指定链接设备的串号，重新插拔后地址会变化，串号不变
+    pub device_serial: Option<String>,
+    //磁盘最大速度(MB/s)和网络带宽(Mbps)，用于计算占用百分比
+    pub disk_max_speed: Option<f32>,
+    pub network_max_speed: Option<f32>,
+    //超采样倍数，按倍数放大绘制后缩小，文字边缘更平滑
+    pub supersample: Option<u8>,
+    //同时输出到所有连接的屏幕，大小不同时缩放
+    pub mirror: Option<bool>,
+    //镜像输出到大小不同的屏幕时的缩放方式
+    pub mirror_fit: Option<Fit>,
+    //有相机或桌面截图时，收到新的一帧才刷新，帧率作为上限
+    pub sync_source: Option<bool>,
+    //第2页开始的组件，widgets为第1页
+    pub pages: Option<Vec<Vec<SaveableWidget>>>,
+    //页面轮播间隔(秒)，0不轮播
+    pub page_interval: Option<f32>,
+    //天气图标样式，见 nmc::WEATHER_ICON_SETS
+    pub weather_icon_set: Option<String>,
+    //字体图标样式使用的图标字体
+    pub icon_font: Option<Vec<u8>>,
+    pub icon_font_name: Option<String>,
+    //页面和布局切换时的过渡效果和时长(秒)
+    pub transition: Option<TransitionKind>,
+    pub transition_duration: Option<f32>,
+    //推入方向和缓动
+    pub animation: Option<Animation>,
+    //像素模式，不做平滑处理，适合像素画
+    pub pixel_perfect: Option<bool>,
+    //屏幕边框遮挡的像素数，四周填充黑色
+    pub overscan: Option<u32>,
+    //所有文字组件的字号缩放倍数
+    pub font_scale: Option<f32>,
+    //颜色主题，引用角色的组件使用主题中的颜色
+    pub theme: Option<Theme>,
+    //时间使用24小时制，旧文件没有该字段时为24小时制
+    pub time_24h: Option<bool>,
+    //分享的布局包清单，打开时校验
+    pub bundle: Option<BundleManifest>,
+}
+
+//分享的布局包: 图像和字体都嵌入到文件中，checksum为嵌入的图像和字体数据的CRC32
+//只校验资源数据，以后给布局增加字段也不影响旧的布局包
+#[derive(Clone, Debug, PartialEq, Encode, Decode, Deserialize, Serialize)]
+pub struct BundleManifest {
+    //导出时的程序版本
+    pub version: String,
+    pub images: usize,
+    pub font_name: String,
+    pub checksum: u32,
+}
+
+//程序退出时屏幕显示的内容
+#[derive(Clone, Default, Encode, Decode, Deserialize, Serialize)]
+pub enum ShutdownAction {
+    //保持最后一帧
+    #[default]
+    Keep,
+    //黑屏
+    Clear,
+    //显示待机图像
+    Image(ImageData),
+}
+
+impl ShutdownAction {
+    pub fn index(&self) -> i32 {
+        match self {
+            ShutdownAction::Keep => 0,
+            ShutdownAction::Clear => 1,
+            ShutdownAction::Image(_) => 2,
+        }
+    }
+}
+
+//自动生成的布局模板
+#[derive(Clone, Copy, Debug)]
+pub enum LayoutPreset {
+    //大号时钟和日期
+    Clock,
+    //时钟、日期、CPU温度和内存
+    Monitor,
+}
+
+impl LayoutPreset {
+    pub fn from_index(index: i32) -> Option<Self> {
+        match index {
+            0 => Some(LayoutPreset::Clock),
+            1 => Some(LayoutPreset::Monitor),
+            _ => None,
+        }
+    }
+
+    // 每一行的(类型, 前缀, 高度比例, 颜色)
+    fn rows(&self) -> Vec<(&'static str, &'static str, f32, [u8; 4])> {
+        match self {
+            LayoutPreset::Clock => vec![
+                ("time", "", 3., [255, 255, 255, 255]),
+                ("date", "", 1., [255, 255, 0, 255]),
+                ("weekday", "", 1., [0, 255, 255, 255]),
+            ],
+            LayoutPreset::Monitor => vec![
+                ("time", "", 3., [255, 255, 255, 255]),
+                ("date", "", 1., [255, 255, 0, 255]),
+                ("cpu_temp.", "CPU ", 1.5, [0, 255, 255, 255]),
+                ("memory_percent", "内存 ", 1.5, [0, 255, 0, 255]),
+            ],
+        }
+    }
+}
+
+//常用场景的监控指标组合，应用后在指定位置添加一组组件
+pub struct MetricPreset {
+    pub name: &'static str,
+    pub label: &'static str,
+    // 每一行的(类型, 前缀, 颜色)
+    rows: &'static [(&'static str, &'static str, [u8; 4])],
+}
+
+pub const METRIC_PRESETS: &[MetricPreset] = &[
+    MetricPreset {
+        name: "gaming",
+        label: "游戏",
+        rows: &[
+            ("gpu_load", "GPU ", [0, 255, 0, 255]),
+            ("gpu_temp.", "GPU ", [255, 128, 0, 255]),
+            ("gpu_memory_used_gb", "显存 ", [0, 255, 255, 255]),
+            ("cpu_usage", "CPU ", [0, 255, 0, 255]),
+            ("cpu_temp.", "CPU ", [255, 128, 0, 255]),
+        ],
+    },
+    MetricPreset {
+        name: "server",
+        label: "服务器",
+        rows: &[
+            ("load_avg", "负载 ", [255, 255, 255, 255]),
+            ("received_speed", "下载 ", [0, 255, 0, 255]),
+            ("transmitted_speed", "上传 ", [255, 255, 0, 255]),
+            ("disk_usage", "磁盘 ", [0, 255, 255, 255]),
+            ("uptime", "运行 ", [200, 200, 200, 255]),
+        ],
+    },
+    MetricPreset {
+        name: "system",
+        label: "系统",
+        rows: &[
+            ("cpu_usage", "CPU ", [0, 255, 0, 255]),
+            ("memory_percent", "内存 ", [0, 255, 255, 255]),
+            ("disk_usage", "磁盘 ", [255, 255, 0, 255]),
+            ("local_ip", "IP ", [255, 255, 255, 255]),
+        ],
+    },
+];
+
+//预设中每一行的高度
+const PRESET_ROW_HEIGHT: i32 = 18;
+
+//检查坏点、颜色和几何形状的测试图案
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TestPattern {
+    Solid([u8; 3]),
+    //八色竖条
+    ColorBars,
+    //从左到右由黑到白，上下分为灰、红、绿、蓝四段
+    Gradient,
+    //黑底白色1像素网格，每隔8像素一条线，最外圈为边框
+    Grid,
+}
+
+impl TestPattern {
+    pub const NAMES: [&'static str; 8] = ["red", "green", "blue", "white", "black", "bars", "gradient", "grid"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "red" => Some(TestPattern::Solid([255, 0, 0])),
+            "green" => Some(TestPattern::Solid([0, 255, 0])),
+            "blue" => Some(TestPattern::Solid([0, 0, 255])),
+            "white" => Some(TestPattern::Solid([255, 255, 255])),
+            "black" => Some(TestPattern::Solid([0, 0, 0])),
+            "bars" => Some(TestPattern::ColorBars),
+            "gradient" => Some(TestPattern::Gradient),
+            "grid" => Some(TestPattern::Grid),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Encode, Decode, Deserialize, Serialize)]
@@ -46,6 +449,12 @@ pub struct SaveableScreenV10 {
     pub font_name: String,
 }
 
+//可复用的组件组合(.component文件)
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SaveableComponent {
+    pub widgets: Vec<SaveableWidget>,
+}
+
 pub struct ScreenRender {
     pub width: u32,
     pub height: u32,
@@ -56,7 +465,59 @@ pub struct ScreenRender {
     pub font: Option<Vec<u8>>,
     pub fps: f32,
     pub rotate_degree: i32,
-    pub device_address: Option<String>
+    pub device_address: Option<String>,
+    pub byte_order: Option<ByteOrder>,
+    pub corrections: Vec<Correction>,
+    pub shutdown: ShutdownAction,
+    pub night_mode: bool,
+    pub brightness: f32,
+    pub lut: Option<Lut>,
+    pub device_serial: Option<String>,
+    pub disk_max_speed: f32,
+    pub network_max_speed: f32,
+    pub supersample: u8,
+    //超采样时使用的放大画布
+    supersample_canvas: Option<OffscreenCanvas>,
+    //绘制半透明组件的临时画布
+    opacity_canvas: Option<OffscreenCanvas>,
+    //本次渲染开始的时间(毫秒)，传给每个组件绘制，同一帧的动画使用相同的时间
+    pub tick: u128,
+    pub mirror: bool,
+    pub mirror_fit: Fit,
+    pub sync_source: bool,
+    pub weather_icon_set: String,
+    pub icon_font: Option<Vec<u8>>,
+    pub icon_font_name: String,
+    //所有页面的组件，当前页的组件移到widgets中，对应位置为空
+    pages: Vec<Vec<Box<dyn Widget>>>,
+    page_index: usize,
+    //页面轮播间隔(秒)
+    pub page_interval: f32,
+    //是否自动轮播，运行时开启，编辑器中可以预览
+    pub auto_page: bool,
+    page_start_time: u128,
+    //页面和布局切换时的过渡效果和时长(秒)
+    pub transition: TransitionKind,
+    pub transition_duration: f32,
+    pub animation: Animation,
+    active_transition: Option<Transition>,
+    //最后输出的一帧，作为下一次过渡的起点
+    last_frame: Option<RgbImage>,
+    //像素模式：字号取整，最近邻缩放，不超采样
+    pub pixel_perfect: bool,
+    //四周被边框遮挡的像素数，绘制后填充黑色
+    pub overscan: u32,
+    //文字组件绘制时的字号倍数，不修改保存的字号
+    pub font_scale: f32,
+    pub theme: Theme,
+    //时间组件使用24小时制，否则为12小时制
+    pub time_24h: bool,
+    //组件图像缓存，运行时开启，编辑器中每帧都重新绘制
+    raster_cache: Option<RasterCache>,
+    //开启性能分析时记录绘制耗时
+    pub profile: Option<RenderProfile>,
+    //.screen文件所在目录，用于加载链接的图像
+    pub base_dir: Option<PathBuf>,
 }
 
 impl ScreenRender {
@@ -82,6 +543,43 @@ impl ScreenRender {
             widgets: vec![],
             fps: 10.,
             device_address: None,
+            byte_order: None,
+            corrections: vec![],
+            shutdown: ShutdownAction::Keep,
+            night_mode: false,
+            brightness: 1.,
+            lut: None,
+            device_serial: None,
+            disk_max_speed: monitor::DEFAULT_DISK_MAX_SPEED,
+            network_max_speed: monitor::DEFAULT_NETWORK_MAX_SPEED,
+            supersample: 1,
+            supersample_canvas: None,
+            opacity_canvas: None,
+            tick: 0,
+            mirror: false,
+            mirror_fit: Fit::Stretch,
+            sync_source: false,
+            weather_icon_set: nmc::WEATHER_ICON_SETS[0].0.to_string(),
+            icon_font: None,
+            icon_font_name: String::new(),
+            pages: vec![vec![]],
+            page_index: 0,
+            page_interval: DEFAULT_PAGE_INTERVAL,
+            auto_page: false,
+            page_start_time: 0,
+            transition: TransitionKind::None,
+            transition_duration: DEFAULT_TRANSITION_DURATION,
+            animation: Animation::default(),
+            active_transition: None,
+            last_frame: None,
+            pixel_perfect: false,
+            overscan: 0,
+            font_scale: 1.,
+            theme: Theme::default(),
+            time_24h: true,
+            raster_cache: None,
+            profile: None,
+            base_dir: None,
         })
     }
 
@@ -99,14 +597,24 @@ impl ScreenRender {
         let font =
             Font::from_bytes(font_file, FontSettings::default()).map_err(|err| anyhow!("{err}"))?;
         self.canvas = OffscreenCanvas::new(self.width, self.height, font);
+        self.supersample_canvas = None;
+        self.opacity_canvas = None;
         self.font = font_file_clone.map(|v| v.to_vec());
         self.font_name = font_name;
         Ok(())
     }
 
     pub fn setup_monitor(&mut self) -> Result<()> {
-        //在点击的地方添加一个对象
-        for widget in &mut self.widgets {
+        monitor::set_corrections(self.corrections.clone())?;
+        monitor::set_activity_limits(self.disk_max_speed, self.network_max_speed)?;
+        nmc::set_weather_icon_set(&self.weather_icon_set);
+        if let Err(err) = nmc::set_icon_font(self.icon_font.as_deref()) {
+            error!("图标字体加载失败:{err:?}");
+        }
+        //先取消所有监听，删除的组件不再查询
+        monitor::unwatch_all()?;
+        //所有页面的组件都需要监听
+        for widget in self.widgets.iter_mut().chain(self.pages.iter_mut().flatten()) {
             info!("setup_monitor:{}", widget.type_name());
             match widget.type_name() {
                 "memory" | "memory_total" | "memory_percent" | "swap" | "swap_percent" => {
@@ -114,45 +622,117 @@ impl ScreenRender {
                 }
                 "webcam" =>{
                     if let Some(widget) = widget.as_any_mut().downcast_mut::<ImageWidget>() {
-                        info!("webcam: tag1={:?}", widget.tag1);
-                        monitor::watch_webcam(Some(WebcamInfo{
+                        info!("webcam: tag1={:?} tag2={:?}", widget.tag1, widget.tag2);
+                        //tag2为相机帧率，未设置时和屏幕帧率相同
+                        let fps = widget.tag2.as_ref().and_then(|fps| fps.trim().parse::<u32>().ok()).unwrap_or(self.fps as u32);
+                        monitor::watch_webcam(WebcamInfo{
+                            width: self.width,
+                            height: self.height,
+                            index: widget.webcam_index(),
+                            fps: fps.max(1)
+                        })?
+                    }
+                }
+                "desktop" => {
+                    if let Some(widget) = widget.as_any_mut().downcast_mut::<ImageWidget>() {
+                        //tag2为截图帧率，未设置时和屏幕帧率相同
+                        let fps = widget.tag2.as_ref().and_then(|fps| fps.trim().parse::<u32>().ok()).unwrap_or(self.fps as u32);
+                        monitor::watch_screen_capture(ScreenCaptureInfo{
+                            region: widget.capture_region(),
+                            fps: fps.max(1),
                             width: self.width,
                             height: self.height,
-                            index: widget.tag1.as_ref().unwrap_or(&String::new()).parse().unwrap_or(0),
-                            fps: self.fps as u32
-                        }))?
+                        })?
                     }
                 }
-                "cpu" | "cpu_usage" => monitor::watch_cpu(true)?,
+                "cpu" | "cpu_usage" | "cpu_cores" => monitor::watch_cpu(true)?,
+                "summary" => {
+                    monitor::watch_cpu(true)?;
+                    monitor::watch_memory(true)?;
+                    monitor::watch_cpu_temperatures(true)?;
+                    monitor::watch_network_speed(true)?;
+                }
                 "cpu_freq" => monitor::watch_cpu_clock_speed(true)?,
                 "cpu_temp." => monitor::watch_cpu_temperatures(true)?,
                 "cpu_cores_power" | "gpu_cores_power" => monitor::watch_cpu_power(true)?,
                 "cpu_package_power" | "gpu_package_power" => monitor::watch_cpu_power(true)?,
+                "cpu_energy" | "gpu_energy" => monitor::watch_cpu_power(true)?,
                 "cpu_fan" => monitor::watch_cpu_fan(true)?,
+                "cooling" => {
+                    monitor::watch_cpu_temperatures(true)?;
+                    monitor::watch_cpu_fan(true)?
+                }
                 "gpu_fan" => monitor::watch_gpu_fan(true)?,
                 "gpu_clock" => monitor::watch_gpu_clock_speed(true)?,
-                "gpu_load" | "gpu_memory_load" | "gpu_memory_total_mb" | "gpu_memory_total_gb" => monitor::watch_gpu_load(true)?,
+                "gpu_load" | "gpu_memory_load" | "gpu_memory_total_mb" | "gpu_memory_total_gb" | "gpu_memory_used_gb" => monitor::watch_gpu_load(true)?,
                 "gpu_temp." => monitor::watch_gpu_temperatures(true)?,
                 "num_process" => monitor::watch_process(true)?,
+                "load_avg" | "num_threads" => monitor::watch_load(true)?,
+                "foreground_window" => monitor::watch_foreground_window(true)?,
                 "disk_usage" => monitor::watch_disk(true)?,
                 "net_ip" | "net_ip_info" => monitor::watch_net_ip(true)?,
-                "disk_read_speed" => monitor::watch_disk_speed(true)?,
+                "disk_read_speed" | "disk_activity" => monitor::watch_disk_speed(true)?,
                 "disk_write_speed" => monitor::watch_disk_speed(true)?,
-                "received_speed" => monitor::watch_network_speed(true)?,
-                "transmitted_speed" => monitor::watch_network_speed(true)?,
+                "received_speed" | "network_activity" => monitor::watch_network_speed(true)?,
+                "transmitted_speed" | "network_total_received" | "network_total_transmitted" => monitor::watch_network_speed(true)?,
+                "custom" => monitor::watch_custom(),
+                "ping" => {
+                    //tag1为主机名或IP
+                    if let Some(widget) = widget.as_any_mut().downcast_mut::<TextWidget>() {
+                        if widget.tag1.trim().len() > 0 {
+                            monitor::watch_ping(widget.tag1.trim())?
+                        }
+                    }
+                }
+                "ticker" => {
+                    //tag1为接口地址，tag2为价格和涨跌幅的路径
+                    if let Some(widget) = widget.as_any_mut().downcast_mut::<TextWidget>() {
+                        if widget.tag1.trim().len() > 0 && widget.tag2.trim().len() > 0 {
+                            monitor::watch_ticker(widget.tag1.trim(), widget.tag2.trim())?
+                        }
+                    }
+                }
+                "phone_battery" | "phone_status" => {
+                    //tag1为adb设备序列号或手机配套程序的地址，为空时使用唯一连接的设备
+                    if let Some(widget) = widget.as_any_mut().downcast_mut::<TextWidget>() {
+                        monitor::watch_phone(widget.tag1.trim())?
+                    }
+                }
+                "rss" => {
+                    //tag1为订阅地址，tag2为刷新间隔(分钟)
+                    if let Some(widget) = widget.as_any_mut().downcast_mut::<TextWidget>() {
+                        if widget.tag1.trim().len() > 0 {
+                            monitor::watch_feed(widget.tag1.trim(), widget.tag2.parse().unwrap_or(DEFAULT_FEED_INTERVAL))?
+                        }
+                    }
+                }
+                "next_event_title" | "next_event_time" => {
+                    //tag1为iCal订阅地址
+                    if let Some(widget) = widget.as_any_mut().downcast_mut::<TextWidget>() {
+                        if widget.tag1.trim().len() > 0 {
+                            monitor::watch_calendar(widget.tag1.trim())?
+                        }
+                    }
+                }
                 "weather" => {
                     if let Some(widget) = widget.as_any_mut().downcast_mut::<TextWidget>() {
-                        if widget.tag2.len() > 0 {
-                            //查询对应的城市
-                            info!("更新天气，查询对应的城市: tag2={}", widget.tag2);
-                            if let Some(city) = CITIES.iter().find(|c| c.city == widget.tag2) {
-                                monitor::watch_weather(Some(city.clone()))?
+                        //查询对应的城市，多个城市用逗号分隔
+                        info!("更新天气，查询对应的城市: tag2={}", widget.tag2);
+                        for name in widget.weather_cities() {
+                            if name == nmc::AUTO_CITY {
+                                monitor::watch_auto_weather()?;
+                            } else if let Some(city) = CITIES.iter().find(|c| c.city == name) {
+                                monitor::watch_weather(city.clone())?
                             }
                         }
                     }
                 }
                 _ => (),
             }
+            //显示条件用到的数值
+            if let Some(condition) = widget.show_when() {
+                monitor::watch_metric(&condition.type_name)?;
+            }
         }
         Ok(())
     }
@@ -168,12 +748,409 @@ impl ScreenRender {
             }
             w.set_index(*map.get_mut(w.type_name()).unwrap());
         }
+        //map中是最后一个组件的索引，组件数量为索引+1
         for w in self.widgets.iter_mut() {
-            w.set_num_widget(*map.get_mut(w.type_name()).unwrap());
+            w.set_num_widget(*map.get_mut(w.type_name()).unwrap() + 1);
         }
+        let start = Instant::now();
+        if self.auto_page {
+            self.next_page_if_due();
+        }
+        widgets::set_pixel_perfect(self.pixel_perfect);
+        widgets::set_font_scale(self.font_scale);
+        widgets::set_theme(self.theme);
+        self.tick = monitor::current_timestamp();
+        monitor::set_time_24h(self.time_24h);
+        self.load_image_sources();
         self.canvas.clear(BLACK);
-        for widget in &mut self.widgets {
-            widget.draw(&mut self.canvas);
+        //超采样缩小时会插值，像素模式下不使用
+        if self.supersample > 1 && !self.pixel_perfect {
+            if let Err(err) = self.render_supersampled(self.tick) {
+                error!("超采样绘制失败:{err:?}");
+            }
+        } else {
+            for idx in self.draw_order() {
+                let widget = &mut self.widgets[idx];
+                if widget.is_visible(self.tick) {
+                    let widget_start = Instant::now();
+                    match self.raster_cache.as_mut() {
+                        Some(cache) => cache.draw(widget.as_mut(), &mut self.canvas, self.tick),
+                        None => draw_widget(widget.as_mut(), &mut self.canvas, &mut self.opacity_canvas, self.tick),
+                    }
+                    if let Some(profile) = self.profile.as_mut() {
+                        profile.record_widget(widget.id(), widget_start.elapsed());
+                    }
+                }
+            }
+        }
+        self.fill_overscan();
+        if let Some(profile) = self.profile.as_mut() {
+            profile.record_render(start.elapsed());
+        }
+    }
+
+    // 四周被边框遮挡的部分填充黑色，布局时可以看到实际显示的区域
+    fn fill_overscan(&mut self) {
+        let (width, height) = (self.canvas.width() as i32, self.canvas.height() as i32);
+        let margin = (self.overscan as i32).min(width / 2).min(height / 2);
+        if margin <= 0 {
+            return;
+        }
+        for (x, y, w, h) in [
+            (0, 0, width, margin),
+            (0, height - margin, width, margin),
+            (0, margin, margin, height - margin * 2),
+            (width - margin, margin, margin, height - margin * 2),
+        ] {
+            self.canvas.fill_rect(offscreen_canvas::Rect::from(x, y, w, h), BLACK);
+        }
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn page_index(&self) -> usize {
+        self.page_index
+    }
+
+    // 切换页面，当前页的组件放回pages，新页面的组件移到widgets
+    pub fn switch_page(&mut self, index: usize) {
+        if index >= self.pages.len() {
+            return;
+        }
+        self.page_start_time = monitor::current_timestamp();
+        if index == self.page_index {
+            return;
+        }
+        std::mem::swap(&mut self.widgets, &mut self.pages[self.page_index]);
+        std::mem::swap(&mut self.widgets, &mut self.pages[index]);
+        self.page_index = index;
+        if let Some(cache) = self.raster_cache.as_mut() {
+            cache.clear();
+        }
+        let from = self.last_frame.take();
+        self.start_transition(from);
+    }
+
+    // 按组件的最短重绘间隔缓存绘制结果
+    pub fn enable_raster_cache(&mut self) {
+        self.raster_cache.get_or_insert_with(RasterCache::default);
+    }
+
+    // 从切换前的画面开始过渡
+    pub fn start_transition(&mut self, from: Option<RgbImage>) {
+        if self.transition == TransitionKind::None {
+            return;
+        }
+        if let Some(from) = from {
+            self.active_transition = Some(Transition::new(self.transition, from, self.transition_duration, self.animation));
+        }
+    }
+
+    // 切换布局时取出旧布局的最后一帧
+    pub fn take_last_frame(&mut self) -> Option<RgbImage> {
+        self.last_frame.take()
+    }
+
+    // 在输出的画面上叠加过渡效果，并记录这一帧
+    pub fn apply_transition(&mut self, frame: &mut RgbImage) {
+        if self.transition == TransitionKind::None {
+            self.active_transition = None;
+            self.last_frame = None;
+            return;
+        }
+        if let Some(transition) = self.active_transition.as_ref() {
+            if !transition.apply(frame) {
+                self.active_transition = None;
+            }
+        }
+        self.last_frame = Some(frame.clone());
+    }
+
+    // 在最后添加空白页面并切换过去
+    pub fn add_page(&mut self) {
+        self.pages.push(vec![]);
+        self.switch_page(self.pages.len() - 1);
+    }
+
+    // 删除当前页面，至少保留一页
+    pub fn remove_page(&mut self) {
+        if self.pages.len() <= 1 {
+            return;
+        }
+        self.widgets.clear();
+        self.pages.remove(self.page_index);
+        self.page_index = self.page_index.min(self.pages.len() - 1);
+        std::mem::swap(&mut self.widgets, &mut self.pages[self.page_index]);
+        self.page_start_time = monitor::current_timestamp();
+    }
+
+    fn next_page_if_due(&mut self) {
+        if self.pages.len() <= 1 || self.page_interval <= 0. {
+            return;
+        }
+        let now = monitor::current_timestamp();
+        if self.page_start_time == 0 {
+            self.page_start_time = now;
+        }
+        if now.saturating_sub(self.page_start_time) >= (self.page_interval * 1000.) as u128 {
+            self.switch_page((self.page_index + 1) % self.pages.len());
+        }
+    }
+
+    // 加载文件中的页面，显示第1页
+    fn load_pages(&mut self, widgets: Vec<SaveableWidget>, pages: Option<Vec<Vec<SaveableWidget>>>) {
+        self.widgets.clear();
+        self.widgets
+            .extend(widgets.into_iter().filter_map(Self::load_widget));
+        self.pages = vec![vec![]];
+        for page in pages.unwrap_or_default() {
+            self.pages.push(page.into_iter().filter_map(Self::load_widget).collect());
+        }
+        //各页面的组件按层级排序
+        self.sort_by_z_index();
+        for page in self.pages.iter_mut() {
+            page.sort_by_key(|w| w.z_index());
+        }
+        self.page_index = 0;
+        self.page_start_time = 0;
+    }
+
+    // 按页面顺序转换为保存的组件，第1页之外的页面放在pages中
+    fn saveable_pages(&mut self) -> Result<(Vec<SaveableWidget>, Option<Vec<Vec<SaveableWidget>>>)> {
+        let mut pages = vec![];
+        for idx in 0..self.pages.len() {
+            let widgets = if idx == self.page_index {
+                &mut self.widgets
+            } else {
+                &mut self.pages[idx]
+            };
+            let mut page = vec![];
+            for widget in widgets.iter_mut() {
+                if let Some(widget) = Self::saveable_widget(widget)? {
+                    page.push(widget);
+                }
+            }
+            pages.push(page);
+        }
+        let first = pages.remove(0);
+        Ok((first, Some(pages).filter(|p| p.len() > 0)))
+    }
+
+    // 绘制顺序，背景图层在最前面，其余按层级从低到高
+    fn draw_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.widgets.len()).collect();
+        //稳定排序，层级相同的组件保持数组顺序
+        order.sort_by_key(|idx| (!self.widgets[*idx].is_background(), self.widgets[*idx].z_index()));
+        order
+    }
+
+    // 按层级稳定排序组件数组，编辑器列表的顺序和绘制顺序一致
+    pub fn sort_by_z_index(&mut self) {
+        self.widgets.sort_by_key(|w| w.z_index());
+    }
+
+    // 将组件设置为铺满屏幕的背景图层，或者取消背景
+    pub fn set_background(&mut self, uuid: &str, is_background: bool) -> bool {
+        let (width, height) = (self.width as i32, self.height as i32);
+        let widget = match self
+            .find_widget(uuid)
+            .and_then(|(_, w)| w.as_any_mut().downcast_mut::<ImageWidget>())
+        {
+            None => return false,
+            Some(w) => w,
+        };
+        widget.is_background = is_background;
+        if is_background {
+            widget.position = Rect::new(0, 0, width, height);
+        }
+        true
+    }
+
+    // 在放大的画布上绘制，再缩小到屏幕大小
+    fn render_supersampled(&mut self, tick: u128) -> Result<()> {
+        let factor = self.supersample as u32;
+        let (width, height) = (self.canvas.width(), self.canvas.height());
+        let order = self.draw_order();
+        let canvas = match self.supersample_canvas.as_mut() {
+            Some(canvas) if canvas.width() == width * factor && canvas.height() == height * factor => canvas,
+            _ => self.supersample_canvas.insert(OffscreenCanvas::new(
+                width * factor,
+                height * factor,
+                self.canvas.font().clone(),
+            )),
+        };
+        canvas.clear(BLACK);
+        for idx in order {
+            let widget = &mut self.widgets[idx];
+            if widget.is_visible(tick) {
+                let widget_start = Instant::now();
+                widget.scale(factor as f32);
+                match self.raster_cache.as_mut() {
+                    Some(cache) => cache.draw(widget.as_mut(), canvas, tick),
+                    None => draw_widget(widget.as_mut(), canvas, &mut self.opacity_canvas, tick),
+                }
+                widget.scale(1. / factor as f32);
+                if let Some(profile) = self.profile.as_mut() {
+                    profile.record_widget(widget.id(), widget_start.elapsed());
+                }
+            }
+        }
+        let src = Image::from_vec_u8(
+            canvas.width(),
+            canvas.height(),
+            canvas.image_data().to_vec(),
+            PixelType::U8x4,
+        )
+        .map_err(|err| anyhow!("{err:?}"))?;
+        let mut dst = Image::new(width, height, PixelType::U8x4);
+        Resizer::new()
+            .resize(&src, &mut dst, None)
+            .map_err(|err| anyhow!("{err:?}"))?;
+        let image = RgbaImage::from_raw(width, height, dst.into_vec())
+            .ok_or(anyhow!("超采样图像大小错误"))?;
+        self.canvas.draw_image_at(&image, 0, 0, None, None);
+        Ok(())
+    }
+
+    // 对整个画面应用LUT、反色和调整亮度，channels为每个像素的字节数(RGB为3，RGBA为4)
+    pub fn apply_color_filter(&self, pixels: &mut [u8], channels: usize) {
+        if let Some(lut) = self.lut.as_ref() {
+            lut.apply(pixels, channels);
+        }
+        let brightness = self.brightness.clamp(0.1, 1.);
+        if !self.night_mode && brightness >= 1. {
+            return;
+        }
+        for p in pixels.chunks_mut(channels) {
+            for v in p.iter_mut().take(3) {
+                let c = if self.night_mode { 255 - *v } else { *v };
+                *v = (c as f32 * brightness) as u8;
+            }
+        }
+    }
+
+    // 退出时发送到屏幕的图像，保持最后一帧时返回None
+    pub fn shutdown_frame(&self) -> Option<RgbImage> {
+        let mut frame = RgbaImage::from_pixel(self.width, self.height, Rgba([0, 0, 0, 255]));
+        match &self.shutdown {
+            ShutdownAction::Keep => return None,
+            ShutdownAction::Clear => (),
+            ShutdownAction::Image(image_data) => {
+                if let Some(image) = image_data.frames.first().and_then(|data| {
+                    RgbaImage::from_raw(image_data.width, image_data.height, data.clone())
+                }) {
+                    //保持比例居中显示
+                    let scale = (self.width as f32 / image.width() as f32)
+                        .min(self.height as f32 / image.height() as f32);
+                    let (w, h) = ((image.width() as f32 * scale) as u32, (image.height() as f32 * scale) as u32);
+                    let image = image::imageops::resize(&image, w.max(1), h.max(1), widgets::resize_filter());
+                    let (x, y) = ((self.width - image.width()) / 2, (self.height - image.height()) / 2);
+                    image::imageops::overlay(&mut frame, &image, x as i64, y as i64);
+                }
+            }
+        }
+        Some(frame.convert())
+    }
+
+    // 加载链接的图像，加载后缓存在image_data中
+    pub fn load_image_sources(&mut self) {
+        let max_size = (self.width, self.height);
+        for widget in self.widgets.iter_mut() {
+            if let Some(widget) = widget.as_any_mut().downcast_mut::<ImageWidget>() {
+                if widget.need_load_source() {
+                    if let Err(err) = widget.load_source(self.base_dir.as_deref(), max_size) {
+                        error!("图像加载失败:{err:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    // 编辑时使用绝对路径，避免另存到其他目录后找不到图像
+    pub fn absolute_image_paths(&mut self) {
+        let base_dir = match &self.base_dir {
+            Some(dir) => dir.clone(),
+            None => return,
+        };
+        for widget in self.widgets.iter_mut().chain(self.pages.iter_mut().flatten()) {
+            if let Some(widget) = widget.as_any_mut().downcast_mut::<ImageWidget>() {
+                if let ImageSource::Path(path) = &widget.source {
+                    if Path::new(path).is_relative() {
+                        widget.source = ImageSource::Path(base_dir.join(path).to_string_lossy().to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    // 保存到dir目录时，将dir下的图像路径改为相对路径
+    pub fn relative_image_paths(saveable: &mut SaveableScreen, dir: &Path) {
+        let pages = saveable.pages.iter_mut().flatten().flatten();
+        for widget in saveable.widgets.iter_mut().chain(pages) {
+            if let SaveableWidget::ImageWidget(widget) = widget {
+                if let ImageSource::Path(path) = &widget.source {
+                    if let Ok(relative) = Path::new(path).strip_prefix(dir) {
+                        widget.source = ImageSource::Path(relative.to_string_lossy().to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    // 当前页面有相机或桌面截图
+    pub fn has_frame_source(&self) -> bool {
+        self.widgets.iter().any(|w| w.type_name() == "webcam" || w.type_name() == "desktop")
+    }
+
+    // 当前系统读取不到数据的组件类型(不重复)，加载其他系统制作的布局时提示
+    pub fn unsupported_widgets(&self) -> Vec<String> {
+        let mut names: Vec<String> = vec![];
+        for widget in self.widgets.iter().chain(self.pages.iter().flatten()) {
+            let type_name = widget.type_name();
+            if !monitor::is_supported(type_name) && !names.iter().any(|n| n == type_name) {
+                names.push(type_name.to_string());
+            }
+        }
+        names
+    }
+
+    // 转换为保存的组件，未注册的自定义组件返回None
+    fn saveable_widget(widget: &mut Box<dyn Widget>) -> Result<Option<SaveableWidget>> {
+        if let Some(widget) = widget.as_any_mut().downcast_mut::<TextWidget>() {
+            return Ok(Some(SaveableWidget::TextWidget(widget.clone())));
+        }
+        if let Some(widget) = widget.as_any_mut().downcast_mut::<ImageWidget>() {
+            return Ok(Some(SaveableWidget::ImageWidget(widget.to_saveable())));
+        }
+        let type_name = widget.type_name().to_string();
+        match registered_widget_type(&type_name) {
+            Some((_, serializer)) => Ok(Some(SaveableWidget::CustomWidget(CustomWidgetData {
+                data: (serializer.save)(widget.as_mut())?,
+                type_name,
+            }))),
+            None => Ok(None),
+        }
+    }
+
+    // 加载保存的组件，自定义组件未注册时忽略
+    fn load_widget(widget: SaveableWidget) -> Option<Box<dyn Widget>> {
+        match widget {
+            SaveableWidget::TextWidget(txt) => Some(Box::new(txt)),
+            SaveableWidget::ImageWidget(img) => Some(Box::new(img)),
+            SaveableWidget::CustomWidget(custom) => {
+                let result = registered_widget_type(&custom.type_name)
+                    .ok_or(anyhow!("未注册的组件类型:{}", custom.type_name))
+                    .and_then(|(_, serializer)| (serializer.load)(&custom.data));
+                match result {
+                    Ok(widget) => Some(widget),
+                    Err(err) => {
+                        error!("组件加载失败:{err:?}");
+                        None
+                    }
+                }
+            }
         }
     }
 
@@ -188,8 +1165,10 @@ impl ScreenRender {
             return None;
         }
 
-        let widget: Box<dyn Widget> = if type_name == "images" || type_name == "webcam" {
+        let mut widget: Box<dyn Widget> = if type_name == "images" || type_name == "webcam" || type_name == "desktop" {
             Box::new(ImageWidget::new(x, y, &type_name))
+        } else if let Some((factory, _)) = registered_widget_type(type_name) {
+            factory(x, y)
         } else {
             let mut text_index = 1;
             for w in self.widgets.iter_mut() {
@@ -197,19 +1176,163 @@ impl ScreenRender {
                     text_index += 1;
                 }
             }
-            Box::new(TextWidget::new_with_text(
+            let mut widget = TextWidget::new_with_text(
                 x,
                 y,
                 &type_name,
                 &type_label,
                 &format!("文本{text_index}"),
-            ))
+            );
+            //订阅默认滚动显示到屏幕右侧
+            if type_name == "rss" {
+                widget.text = "订阅加载中...".to_string();
+                widget.tag2 = format!("{DEFAULT_FEED_INTERVAL}");
+                widget.scroll_speed = 30.;
+                widget.position.set_position(x, y);
+                widget.position.right = (self.width as i32).max(x + 20);
+                widget.position.bottom = y + 20;
+            }
+            if type_name == "ticker" {
+                widget.text = "行情加载中...".to_string();
+            }
+            if type_name == "next_event_title" {
+                widget.text = "日历加载中...".to_string();
+            }
+            Box::new(widget)
         };
+        //新组件放在最上层
+        widget.set_z_index(self.top_z_index());
         let id = widget.id().to_string();
         self.widgets.push(widget);
         Some(id)
     }
 
+    // 在origin附近逐行添加预设中的组件，超出屏幕时向左上移动，返回新组件的id
+    // 同一次添加的组件放在一个组合中，可以一起移动
+    pub fn apply_preset(&mut self, name: &str, origin: (i32, i32)) -> Result<Vec<String>> {
+        let preset = METRIC_PRESETS
+            .iter()
+            .find(|preset| preset.name == name)
+            .ok_or(anyhow!("没有这个预设:{name}"))?;
+        let block_height = PRESET_ROW_HEIGHT * preset.rows.len() as i32;
+        let x = origin.0.clamp(0, (self.width as i32 - PRESET_ROW_HEIGHT * 4).max(0));
+        let y = origin.1.clamp(0, (self.height as i32 - block_height).max(0));
+        let group = Uuid::new_v4().to_string();
+        let mut ids = vec![];
+        for (i, (type_name, prefix, color)) in preset.rows.iter().enumerate() {
+            let id = self
+                .add_widget(type_name, "", x, y + i as i32 * PRESET_ROW_HEIGHT)
+                .ok_or(anyhow!("组件添加失败:{type_name}"))?;
+            if let Some((_, widget)) = self.find_widget(&id) {
+                widget.set_group(Some(group.clone()));
+                if let Some(text) = widget.as_any_mut().downcast_mut::<TextWidget>() {
+                    text.prefix = prefix.to_string();
+                    text.color = *color;
+                }
+            }
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
+    fn top_z_index(&self) -> i32 {
+        self.widgets.iter().map(|w| w.z_index()).max().unwrap_or(0)
+    }
+
+    // 按屏幕分辨率生成测试图案
+    pub fn test_pattern(kind: TestPattern, width: u32, height: u32) -> RgbImage {
+        const BARS: [[u8; 3]; 8] = [
+            [255, 255, 255], [255, 255, 0], [0, 255, 255], [0, 255, 0],
+            [255, 0, 255], [255, 0, 0], [0, 0, 255], [0, 0, 0],
+        ];
+        RgbImage::from_fn(width, height, |x, y| {
+            let color = match kind {
+                TestPattern::Solid(color) => color,
+                TestPattern::ColorBars => BARS[(x * 8 / width.max(1)) as usize],
+                TestPattern::Gradient => {
+                    let v = (x * 255 / width.saturating_sub(1).max(1)) as u8;
+                    match y * 4 / height.max(1) {
+                        0 => [v, v, v],
+                        1 => [v, 0, 0],
+                        2 => [0, v, 0],
+                        _ => [0, 0, v],
+                    }
+                }
+                TestPattern::Grid => {
+                    let line = x % 8 == 0 || y % 8 == 0 || x + 1 == width || y + 1 == height;
+                    if line { [255, 255, 255] } else { [0, 0, 0] }
+                }
+            };
+            image::Rgb(color)
+        })
+    }
+
+    // 按屏幕大小生成黑底亮色的大字布局，作为一个组合添加到当前页面最上层，不删除现有的组件
+    pub fn generate_default_layout(&mut self, preset: LayoutPreset) -> Vec<String> {
+        let group = Uuid::new_v4().to_string();
+        let z_index = self.top_z_index() + 1;
+        let rows = preset.rows();
+        let (width, height) = (self.width as i32, self.height as i32);
+        let margin = (height.min(width) / 20).max(2);
+        let total: f32 = rows.iter().map(|row| row.2).sum();
+        let available = (height - margin * 2) as f32;
+        let mut ids = vec![];
+        let mut y = margin;
+        for (type_name, prefix, weight, color) in rows {
+            let row_height = ((available * weight / total) as i32).max(1);
+            let mut widget = TextWidget::new_with_text(margin, y, type_name, "", "");
+            widget.prefix = prefix.to_string();
+            widget.color = color;
+            widget.font_size = row_height as f32;
+            //自动字号，文字充满整行
+            widget.auto_size = true;
+            widget.position = Rect::from(margin, y, width - margin * 2, row_height);
+            widget.group = Some(group.clone());
+            widget.z_index = z_index;
+            ids.push(widget.id.clone());
+            self.widgets.push(Box::new(widget));
+            y += row_height;
+        }
+        self.sort_by_z_index();
+        ids
+    }
+
+    // 按AIDA64/Rainmeter等软件的传感器名称生成文本组件，按网格排列，识别不了的名称跳过
+    pub fn from_sensor_spec(spec: &SensorSpec) -> Result<ScreenRender> {
+        let sensors: Vec<(&'static str, String)> = spec
+            .sensors
+            .iter()
+            .filter_map(|sensor| match sensor_spec::type_name_of(&sensor.name) {
+                Some(type_name) => Some((type_name, sensor.label.clone().unwrap_or(sensor.name.clone()))),
+                None => {
+                    warn!("未知的传感器名称:{}", sensor.name);
+                    None
+                }
+            })
+            .collect();
+        if sensors.is_empty() {
+            return Err(anyhow!("没有可以识别的传感器名称"));
+        }
+        let mut render = ScreenRender::new(spec.model.clone(), spec.width, spec.height, None, String::new())?;
+        let (width, height) = (spec.width as i32, spec.height as i32);
+        let margin = (height.min(width) / 20).max(2);
+        //每行至少16像素高，放不下时分多列
+        let max_rows = ((height - margin * 2) / 16).max(1) as usize;
+        let cols = (sensors.len() + max_rows - 1) / max_rows;
+        let rows = (sensors.len() + cols - 1) / cols;
+        let cell_width = ((width - margin * 2) / cols as i32).max(1);
+        let cell_height = ((height - margin * 2) / rows as i32).max(1);
+        for (i, (type_name, label)) in sensors.iter().enumerate() {
+            let (x, y) = (margin + (i / rows) as i32 * cell_width, margin + (i % rows) as i32 * cell_height);
+            let mut widget = TextWidget::new_with_text(x, y, type_name, label, "");
+            widget.font_size = cell_height as f32;
+            widget.auto_size = true;
+            widget.position = Rect::from(x, y, cell_width, cell_height);
+            render.widgets.push(Box::new(widget));
+        }
+        Ok(render)
+    }
+
     pub fn find_widget(&mut self, uuid: &str) -> Option<(usize, &mut Box<dyn Widget>)> {
         self.widgets
             .iter_mut()
@@ -234,8 +1357,12 @@ impl ScreenRender {
     }
 
     pub fn decompress_screen_file(file: PathBuf) -> Result<Vec<u8>>{
-        let compressed = std::fs::read(file)?;
-        Ok(decompress_size_prepended(&compressed)?)
+        let compressed = std::fs::read(&file)?;
+        //导出的json文件没有压缩
+        if is_json_file(&file){
+            return Ok(compressed);
+        }
+        decompress_screen_data(&compressed)
     }
 
     //尝试使用bindcode解析老版本screen文件
@@ -251,6 +1378,10 @@ impl ScreenRender {
         }
         
         let (saveable, _) = saveable?;
+        self.load_saveable_v10(saveable)
+    }
+
+    fn load_saveable_v10(&mut self, saveable: SaveableScreenV10) -> Result<()> {
         self.width = saveable.width;
         self.height = saveable.height;
         self.canvas =
@@ -258,11 +1389,13 @@ impl ScreenRender {
         if let Some(font) = saveable.font {
             self.set_font(Some(&font), saveable.font_name)?;
         }
+        self.pages = vec![vec![]];
+        self.page_index = 0;
         self.widgets.clear();
         for w in saveable.widgets {
             match w {
                 crate::widgets::v10::SaveableWidget::TextWidget(txt) => {
-                    self.widgets.push(Box::new(txt));
+                    self.widgets.push(Box::new(TextWidget::from_v10(txt)));
                 }
                 crate::widgets::v10::SaveableWidget::ImageWidget(img) => {
                     self.widgets.push(Box::new(ImageWidget::from_v10(img)));
@@ -274,89 +1407,96 @@ impl ScreenRender {
 
     //使用json解析screen文件
     pub fn load_from_file_v2(&mut self, uncompressed: &[u8]) -> Result<()> {
+        let saveable = Self::parse_saveable(uncompressed)?;
+        self.load_saveable(saveable)
+    }
+
+    fn parse_saveable(uncompressed: &[u8]) -> Result<SaveableScreen> {
         let saveable:SaveableScreen = serde_json::from_str(&String::from_utf8(uncompressed.to_vec())?)?;
-        // let saveable: Result<(SaveableScreen, usize), bincode::error::DecodeError> =
-        //     bincode::decode_from_slice(&uncompressed, bincode::config::standard());
-        // let (saveable, _) = saveable?;
+        Self::validate_bundle(&saveable)?;
+        Ok(saveable)
+    }
+
+    fn load_saveable(&mut self, saveable: SaveableScreen) -> Result<()> {
         self.width = saveable.width;
         self.height = saveable.height;
         self.fps = saveable.fps;
         self.rotate_degree = saveable.rotate_degree.unwrap_or(0);
         self.device_address = saveable.device_address;
+        self.byte_order = saveable.byte_order;
+        self.corrections = saveable.corrections.unwrap_or_default();
+        self.shutdown = saveable.shutdown.unwrap_or_default();
+        self.night_mode = saveable.night_mode.unwrap_or(false);
+        self.brightness = saveable.brightness.unwrap_or(1.);
+        self.lut = saveable.lut;
+        self.device_serial = saveable.device_serial;
+        self.disk_max_speed = saveable.disk_max_speed.unwrap_or(monitor::DEFAULT_DISK_MAX_SPEED);
+        self.network_max_speed = saveable.network_max_speed.unwrap_or(monitor::DEFAULT_NETWORK_MAX_SPEED);
+        self.supersample = saveable.supersample.unwrap_or(1).clamp(1, MAX_SUPERSAMPLE);
+        self.mirror = saveable.mirror.unwrap_or(false);
+        self.mirror_fit = saveable.mirror_fit.unwrap_or_default();
+        self.sync_source = saveable.sync_source.unwrap_or(false);
+        self.weather_icon_set = saveable.weather_icon_set.unwrap_or(nmc::WEATHER_ICON_SETS[0].0.to_string());
+        self.icon_font = saveable.icon_font;
+        self.icon_font_name = saveable.icon_font_name.unwrap_or_default();
+        self.page_interval = saveable.page_interval.unwrap_or(DEFAULT_PAGE_INTERVAL);
+        self.transition = saveable.transition.unwrap_or_default();
+        self.transition_duration = saveable.transition_duration.unwrap_or(DEFAULT_TRANSITION_DURATION);
+        self.animation = saveable.animation.unwrap_or_default();
+        self.pixel_perfect = saveable.pixel_perfect.unwrap_or(false);
+        self.overscan = saveable.overscan.unwrap_or(0);
+        self.font_scale = saveable.font_scale.unwrap_or(1.);
+        self.theme = saveable.theme.unwrap_or_default();
+        self.time_24h = saveable.time_24h.unwrap_or(true);
         self.canvas =
             OffscreenCanvas::new(saveable.width, saveable.height, self.canvas.font().clone());
         if let Some(font) = saveable.font {
             self.set_font(Some(&font), saveable.font_name)?;
         }
-        self.widgets.clear();
-        for w in saveable.widgets {
-            match w {
-                SaveableWidget::TextWidget(txt) => {
-                    self.widgets.push(Box::new(txt));
-                }
-                SaveableWidget::ImageWidget(img) => {
-                    self.widgets.push(Box::new(img));
-                }
-            }
-        }
+        self.load_pages(saveable.widgets, saveable.pages);
         Ok(())
     }
 
+    // 导出的json没有压缩(可以带BOM和前导空白)，其他为压缩后的screen文件
+    pub fn from_bytes(data: &[u8]) -> Result<ScreenRender> {
+        let data = data.strip_prefix(b"\xef\xbb\xbf".as_slice()).unwrap_or(data);
+        if data.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'{') {
+            Self::new_from_file_v2(data)
+        } else {
+            Self::new_from_file(data)
+        }
+    }
+
     pub fn new_from_file(file: &[u8]) -> Result<ScreenRender> {
-        let uncompressed = decompress_size_prepended(&file)?;
+        let uncompressed = decompress_screen_data(file)?;
         let saveable: Result<(SaveableScreenV10, usize), bincode::error::DecodeError> =
             bincode::decode_from_slice(&uncompressed, bincode::config::standard());
         if saveable.is_err(){
             return Self::new_from_file_v2(&uncompressed);
         }
         let (saveable, _) = saveable?;
-        let model = saveable.model;
         let mut render =
-            ScreenRender::new(model, saveable.width, saveable.height, None, String::new())?;
-        if let Some(font) = saveable.font {
-            render.set_font(Some(&font), saveable.font_name)?;
-        }
-        render.widgets.clear();
-        for w in saveable.widgets {
-            match w {
-                crate::widgets::v10::SaveableWidget::TextWidget(txt) => {
-                    render.widgets.push(Box::new(txt));
-                }
-                crate::widgets::v10::SaveableWidget::ImageWidget(img) => {
-                    render.widgets.push(Box::new(ImageWidget::from_v10(img)));
-                }
-            }
-        }
+            ScreenRender::new(saveable.model.clone(), saveable.width, saveable.height, None, String::new())?;
+        render.load_saveable_v10(saveable)?;
         Ok(render)
     }
 
     pub fn new_from_file_v2(uncompressed: &[u8]) -> Result<ScreenRender> {
-        let saveable:SaveableScreen = serde_json::from_str(&String::from_utf8(uncompressed.to_vec())?)?;
-
-        let model = saveable.model;
+        let saveable = Self::parse_saveable(uncompressed)?;
         let mut render =
-            ScreenRender::new(model, saveable.width, saveable.height, None, String::new())?;
-        if let Some(font) = saveable.font {
-            render.set_font(Some(&font), saveable.font_name)?;
-        }
-        render.fps = saveable.fps;
-        render.device_address = saveable.device_address;
-        render.widgets.clear();
-        for w in saveable.widgets {
-            match w {
-                SaveableWidget::TextWidget(txt) => {
-                    render.widgets.push(Box::new(txt));
-                }
-                SaveableWidget::ImageWidget(img) => {
-                    render.widgets.push(Box::new(img));
-                }
-            }
-        }
+            ScreenRender::new(saveable.model.clone(), saveable.width, saveable.height, None, String::new())?;
+        render.load_saveable(saveable)?;
         Ok(render)
     }
 
-    //改为json格式存储，这样添加了新的字段不影响解析原有格式的screen文件
+    // 按照设置的压缩方式保存为screen文件
     pub fn to_json(&mut self) -> Result<Vec<u8>> {
+        let saveable = self.to_savable()?;
+        Self::saveable_to_compressed_json(&saveable, Compression::from_config())
+    }
+
+    //改为json格式存储，这样添加了新的字段不影响解析原有格式的screen文件
+    pub fn to_savable(&mut self) -> Result<SaveableScreen> {
         let mut font = self.font.clone();
         let font_name = self.font_name.clone();
         if font_name == "凤凰点阵"{
@@ -371,69 +1511,367 @@ impl ScreenRender {
             font_name,
             widgets: vec![],
             fps: self.fps,
-            device_address: self.device_address.clone()
+            device_address: self.device_address.clone(),
+            byte_order: self.byte_order,
+            corrections: Some(self.corrections.clone()),
+            shutdown: Some(self.shutdown.clone()),
+            night_mode: Some(self.night_mode),
+            brightness: Some(self.brightness),
+            lut: self.lut.clone(),
+            device_serial: self.device_serial.clone(),
+            disk_max_speed: Some(self.disk_max_speed),
+            network_max_speed: Some(self.network_max_speed),
+            supersample: Some(self.supersample),
+            mirror: Some(self.mirror),
+            mirror_fit: Some(self.mirror_fit),
+            sync_source: Some(self.sync_source),
+            weather_icon_set: Some(self.weather_icon_set.clone()),
+            icon_font: self.icon_font.clone(),
+            icon_font_name: Some(self.icon_font_name.clone()).filter(|name| !name.is_empty()),
+            pages: None,
+            page_interval: Some(self.page_interval),
+            transition: Some(self.transition),
+            transition_duration: Some(self.transition_duration),
+            animation: Some(self.animation),
+            pixel_perfect: Some(self.pixel_perfect),
+            overscan: Some(self.overscan),
+            font_scale: Some(self.font_scale),
+            theme: Some(self.theme),
+            time_24h: Some(self.time_24h),
+            bundle: None,
         };
-        for idx in 0..self.widgets.len() {
-            if let Some(widget) = self.widgets[idx].as_any_mut().downcast_mut::<TextWidget>() {
-                saveable
-                    .widgets
-                    .push(SaveableWidget::TextWidget(widget.clone()));
+        let (widgets, pages) = self.saveable_pages()?;
+        saveable.widgets = widgets;
+        saveable.pages = pages;
+        Ok(saveable)
+    }
+
+    // 导出分享用的布局包，链接的图像读取后嵌入，并生成清单和校验和
+    pub fn to_bundle(&mut self) -> Result<SaveableScreen> {
+        let mut saveable = self.to_savable()?;
+        let max_size = (self.width, self.height);
+        let mut images = 0;
+        let pages = saveable.pages.iter_mut().flatten().flatten();
+        for widget in saveable.widgets.iter_mut().chain(pages) {
+            if let SaveableWidget::ImageWidget(widget) = widget {
+                if widget.need_load_source() {
+                    widget.load_source(self.base_dir.as_deref(), max_size)?;
+                    widget.source = ImageSource::Embedded;
+                }
+                images += 1;
             }
-            if let Some(widget) = self.widgets[idx].as_any_mut().downcast_mut::<ImageWidget>() {
-                saveable
-                    .widgets
-                    .push(SaveableWidget::ImageWidget(widget.clone()));
+        }
+        let checksum = bundle_checksum(&saveable);
+        saveable.bundle = Some(BundleManifest {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            images,
+            font_name: saveable.font_name.clone(),
+            checksum,
+        });
+        Ok(saveable)
+    }
+
+    // 校验分享的布局包，普通的布局文件不校验
+    // 图像或字体缺失时返回错误，校验和不一致只提示，返回false
+    pub fn validate_bundle(saveable: &SaveableScreen) -> Result<bool> {
+        let manifest = match saveable.bundle.as_ref() {
+            Some(manifest) => manifest,
+            None => return Ok(true),
+        };
+        let mut images = 0;
+        for widget in saveable.widgets.iter().chain(saveable.pages.iter().flatten().flatten()) {
+            if let SaveableWidget::ImageWidget(widget) = widget {
+                let data = &widget.image_data;
+                let size = data.width as usize * data.height as usize * 4;
+                if widget.source != ImageSource::Embedded || data.frames.is_empty() || data.frames.iter().any(|f| f.len() != size) {
+                    return Err(anyhow!("布局包中的图像不完整:{}", widget.id));
+                }
+                images += 1;
             }
         }
-        let json = serde_json::to_string(&saveable)?;
-        let contents = json.as_bytes();
-        info!("压缩前:{}k", contents.len() / 1024);
-        //压缩
-        let compressed = compress_prepend_size(contents);
-        info!("压缩后:{}k", compressed.len() / 1024);
-        Ok(compressed)
+        if images != manifest.images {
+            return Err(anyhow!("布局包中的图像数量错误:{images}，应为{}", manifest.images));
+        }
+        for font in [saveable.font.as_ref(), saveable.icon_font.as_ref()].into_iter().flatten() {
+            Font::from_bytes(font.as_slice(), FontSettings::default()).map_err(|err| anyhow!("布局包中的字体错误:{err}"))?;
+        }
+        if bundle_checksum(saveable) != manifest.checksum {
+            warn!("布局包的校验和不一致，图像或字体可能已损坏");
+            return Ok(false);
+        }
+        Ok(true)
     }
 
-    //改为json格式存储，这样添加了新的字段不影响解析原有格式的screen文件
-    pub fn to_savable(&mut self) -> Result<SaveableScreen> {
-        let mut font = self.font.clone();
-        let font_name = self.font_name.clone();
-        if font_name == "凤凰点阵"{
-            font = None;
+    //将指定的组件保存为组合
+    pub fn to_component(&mut self, ids: &[String]) -> Result<Vec<u8>> {
+        let mut component = SaveableComponent { widgets: vec![] };
+        for widget in self.widgets.iter_mut() {
+            if !ids.iter().any(|id| id == widget.id()) {
+                continue;
+            }
+            //和保存文件一样转换，链接的图像只保存路径
+            match Self::saveable_widget(widget)? {
+                //自定义组件没有办法重新生成id，不保存到组合中
+                Some(SaveableWidget::CustomWidget(_)) | None => (),
+                Some(saveable) => component.widgets.push(saveable),
+            }
         }
-        let mut saveable = SaveableScreen {
-            rotate_degree: Some(self.rotate_degree),
-            width: self.width,
-            height: self.height,
-            model: self.model.clone(),
-            font,
-            font_name,
-            widgets: vec![],
-            fps: self.fps,
-            device_address: self.device_address.clone()
+        if component.widgets.len() == 0 {
+            return Err(anyhow!("没有选中的组件"));
+        }
+        let json = serde_json::to_string(&component)?;
+        Ok(compress_prepend_size(json.as_bytes()))
+    }
+
+    //在(x,y)处插入组合，组合的左上角对齐到该位置，返回新组件的id
+    pub fn add_component(&mut self, file: &[u8], x: i32, y: i32) -> Result<Vec<String>> {
+        let uncompressed = decompress_size_prepended(file)?;
+        let component: SaveableComponent = serde_json::from_slice(&uncompressed)?;
+        let positions = component.widgets.iter().filter_map(|w| match w {
+            SaveableWidget::TextWidget(txt) => Some((txt.position.left, txt.position.top)),
+            SaveableWidget::ImageWidget(img) => Some((img.position.left, img.position.top)),
+            SaveableWidget::CustomWidget(_) => None,
+        });
+        let left = positions.clone().map(|p| p.0).min().unwrap_or(0);
+        let top = positions.map(|p| p.1).min().unwrap_or(0);
+        let mut ids = vec![];
+        //多次插入时组合不能共用
+        let mut groups: HashMap<String, String> = HashMap::new();
+        let mut new_group = |group: Option<String>| {
+            group.map(|g| groups.entry(g).or_insert_with(|| Uuid::new_v4().to_string()).clone())
         };
-        for idx in 0..self.widgets.len() {
-            if let Some(widget) = self.widgets[idx].as_any_mut().downcast_mut::<TextWidget>() {
-                saveable
-                    .widgets
-                    .push(SaveableWidget::TextWidget(widget.clone()));
-            }
-            if let Some(widget) = self.widgets[idx].as_any_mut().downcast_mut::<ImageWidget>() {
-                saveable
-                    .widgets
-                    .push(SaveableWidget::ImageWidget(widget.clone()));
+        //组合放在最上层，保持组合内部的层级
+        let top_z_index = self.top_z_index();
+        for w in component.widgets {
+            //重新生成id
+            let id = Uuid::new_v4().to_string();
+            match w {
+                SaveableWidget::TextWidget(mut txt) => {
+                    txt.id = id.clone();
+                    txt.group = new_group(txt.group.take());
+                    txt.position.offset(x - left, y - top);
+                    txt.z_index += top_z_index;
+                    self.widgets.push(Box::new(txt));
+                }
+                SaveableWidget::ImageWidget(mut img) => {
+                    img.id = id.clone();
+                    img.group = new_group(img.group.take());
+                    img.position.offset(x - left, y - top);
+                    img.z_index += top_z_index;
+                    self.widgets.push(Box::new(img));
+                }
+                SaveableWidget::CustomWidget(_) => continue,
             }
+            ids.push(id);
         }
-        Ok(saveable)
+        self.sort_by_z_index();
+        Ok(ids)
+    }
+
+    //不压缩的格式化json，方便对比和手动修改
+    pub fn saveable_to_pretty_json(saveable: &SaveableScreen) -> Result<Vec<u8>>{
+        Ok(serde_json::to_string_pretty(saveable)?.into_bytes())
     }
 
-    pub fn saveable_to_compressed_json(saveable: &SaveableScreen) -> Result<Vec<u8>>{
+    pub fn saveable_to_compressed_json(saveable: &SaveableScreen, compression: Compression) -> Result<Vec<u8>>{
         let json = serde_json::to_string(&saveable)?;
         let contents = json.as_bytes();
         info!("压缩前:{}k", contents.len() / 1024);
         //压缩
-        let compressed = compress_prepend_size(contents);
+        let compressed = compress_screen_data(contents, compression)?;
         info!("压缩后:{}k", compressed.len() / 1024);
         Ok(compressed)
     }
 }
+
+// 按布局的旋转角度旋转输出的画面
+pub fn rotate_frame(frame: RgbImage, rotate_degree: i32) -> RgbImage {
+    if rotate_degree == 90 {
+        image::imageops::rotate90(&frame)
+    }else if rotate_degree == 180{
+        image::imageops::rotate180(&frame)
+    }else if rotate_degree == 270{
+        image::imageops::rotate270(&frame)
+    }else{
+        frame
+    }
+}
+
+// 按顺序计算所有嵌入图像的每一帧和字体数据的校验和
+fn bundle_checksum(saveable: &SaveableScreen) -> u32 {
+    let mut chunks: Vec<&[u8]> = vec![];
+    for widget in saveable.widgets.iter().chain(saveable.pages.iter().flatten().flatten()) {
+        if let SaveableWidget::ImageWidget(widget) = widget {
+            chunks.extend(widget.image_data.frames.iter().map(|frame| frame.as_slice()));
+        }
+    }
+    chunks.extend([saveable.font.as_ref(), saveable.icon_font.as_ref()].into_iter().flatten().map(|font| font.as_slice()));
+    crc32_chunks(chunks)
+}
+
+// 把布局的画面缩放到屏幕大小，一个布局可以同时输出到大小不同的屏幕，Contain空白部分为黑色
+pub fn scale_frame(frame: &RgbImage, width: u32, height: u32, fit: Fit) -> RgbImage {
+    if frame.dimensions() == (width, height) || width == 0 || height == 0 || frame.width() == 0 || frame.height() == 0 {
+        return frame.clone();
+    }
+    let filter = widgets::resize_filter();
+    let (frame_width, frame_height) = (frame.width() as f32, frame.height() as f32);
+    match fit {
+        Fit::Stretch => image::imageops::resize(frame, width, height, filter),
+        Fit::Contain => {
+            let scale = (width as f32 / frame_width).min(height as f32 / frame_height);
+            let scaled_width = ((frame_width * scale).round() as u32).clamp(1, width);
+            let scaled_height = ((frame_height * scale).round() as u32).clamp(1, height);
+            let scaled = image::imageops::resize(frame, scaled_width, scaled_height, filter);
+            let mut output = RgbImage::new(width, height);
+            let (x, y) = ((width - scaled_width) / 2, (height - scaled_height) / 2);
+            image::imageops::replace(&mut output, &scaled, x as i64, y as i64);
+            output
+        }
+        Fit::Cover => {
+            //按屏幕比例居中裁剪
+            let scale = (width as f32 / frame_width).max(height as f32 / frame_height);
+            let crop_width = ((width as f32 / scale).round() as u32).clamp(1, frame.width());
+            let crop_height = ((height as f32 / scale).round() as u32).clamp(1, frame.height());
+            let (x, y) = ((frame.width() - crop_width) / 2, (frame.height() - crop_height) / 2);
+            let cropped = image::imageops::crop_imm(frame, x, y, crop_width, crop_height).to_image();
+            image::imageops::resize(&cropped, width, height, filter)
+        }
+    }
+}
+
+// 上一次渲染单帧时设置过监控的布局校验值，同一个布局重复渲染时不再重新设置
+static SINGLE_FRAME_LAYOUT: Lazy<Mutex<Option<u32>>> = Lazy::new(|| Mutex::new(None));
+
+// 不需要屏幕和编辑器，加载布局渲染一帧，用于命令行或服务端生成图片
+// 系统信息在后台线程中更新，第一帧可能还没有数据
+pub fn render_single_frame(screen_bytes: &[u8], base_dir: Option<&Path>) -> Result<RgbImage> {
+    let mut render = ScreenRender::from_bytes(screen_bytes)?;
+    render.base_dir = base_dir.map(|p| p.to_path_buf());
+    let checksum = crc32_chunks([screen_bytes]);
+    let mut layout = SINGLE_FRAME_LAYOUT.lock().map_err(|err| anyhow!("{:?}", err))?;
+    if *layout != Some(checksum) {
+        render.setup_monitor()?;
+        *layout = Some(checksum);
+    }
+    drop(layout);
+    render.render();
+    let mut frame: RgbImage = render.canvas.image_data().convert();
+    render.apply_color_filter(&mut frame, 3);
+    Ok(rotate_frame(frame, render.rotate_degree))
+}
+
+#[test]
+fn test_num_widget() -> Result<()> {
+    let mut render = ScreenRender::new("ST7735".to_string(), 160, 128, None, String::new())?;
+    render.add_widget("cpu_temp.", "CPU温度", 0, 0);
+    render.render();
+    //只有一个组件时显示总温度
+    assert_eq!(render.widgets[0].num_widget(), 1);
+    render.add_widget("cpu_temp.", "CPU温度", 0, 20);
+    render.add_widget("cpu_temp.", "CPU温度", 0, 40);
+    render.render();
+    //多个组件时依次显示每个核心
+    for (i, widget) in render.widgets.iter().enumerate() {
+        assert_eq!(widget.num_widget(), 3);
+        assert_eq!(widget.index(), i);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_render_single_frame() -> Result<()> {
+    let mut render = ScreenRender::new("ST7735".to_string(), 160, 128, None, String::new())?;
+    render.add_widget("text", "文本", 10, 10);
+    let data = render.to_json()?;
+    let frame = render_single_frame(&data, None)?;
+    assert_eq!(frame.dimensions(), (160, 128));
+    //同一个布局再次渲染不重新设置监控
+    assert_eq!(render_single_frame(&data, None)?.dimensions(), (160, 128));
+    assert_eq!(*SINGLE_FRAME_LAYOUT.lock().unwrap(), Some(crc32_chunks([data.as_slice()])));
+    Ok(())
+}
+
+#[test]
+fn test_from_bytes() -> Result<()> {
+    let mut render = ScreenRender::new("ST7735".to_string(), 160, 128, None, String::new())?;
+    render.add_widget("text", "文本", 10, 10);
+    //压缩的screen文件
+    assert_eq!(ScreenRender::from_bytes(&render.to_json()?)?.widgets.len(), 1);
+    //外部编辑的json，带BOM和换行
+    let json = serde_json::to_vec(&render.to_savable()?)?;
+    let edited = [b"\xef\xbb\xbf\n".as_slice(), &json].concat();
+    assert_eq!(ScreenRender::from_bytes(&edited)?.widgets.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_load_same_as_new() -> Result<()> {
+    let mut render = ScreenRender::new("ST7735".to_string(), 160, 128, None, String::new())?;
+    render.add_widget("text", "文本", 10, 10);
+    render.rotate_degree = 90;
+    render.fps = 5.;
+    let json = serde_json::to_vec(&render.to_savable()?)?;
+    //新建和在已有的布局中加载，结果相同
+    let created = ScreenRender::new_from_file_v2(&json)?;
+    let mut loaded = ScreenRender::new("ST7735".to_string(), 320, 240, None, String::new())?;
+    loaded.load_from_file_v2(&json)?;
+    for r in [&created, &loaded] {
+        assert_eq!((r.width, r.height, r.rotate_degree, r.fps, r.widgets.len()), (160, 128, 90, 5., 1));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_screen_compression() -> Result<()> {
+    let json = br#"{"width":160,"height":128,"widgets":[]}"#;
+    for compression in [Compression::Lz4, Compression::Zstd] {
+        let compressed = compress_screen_data(json, compression)?;
+        assert_eq!(compressed.starts_with(&ZSTD_MAGIC), compression == Compression::Zstd);
+        assert_eq!(decompress_screen_data(&compressed)?, json);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_scale_frame() {
+    use image::Rgb;
+    //左半边红色，右半边蓝色
+    let frame = RgbImage::from_fn(4, 2, |x, _| if x < 2 { Rgb([255, 0, 0]) } else { Rgb([0, 0, 255]) });
+    let stretched = scale_frame(&frame, 2, 2, Fit::Stretch);
+    assert_eq!(stretched.dimensions(), (2, 2));
+
+    //完整显示，上下留黑边
+    let contained = scale_frame(&frame, 4, 4, Fit::Contain);
+    assert_eq!(contained.dimensions(), (4, 4));
+    assert_eq!(contained.get_pixel(0, 0), &Rgb([0, 0, 0]));
+    assert_eq!(contained.get_pixel(0, 1), &Rgb([255, 0, 0]));
+    assert_eq!(contained.get_pixel(3, 2), &Rgb([0, 0, 255]));
+    assert_eq!(contained.get_pixel(0, 3), &Rgb([0, 0, 0]));
+
+    //裁剪中间部分
+    let covered = scale_frame(&frame, 2, 2, Fit::Cover);
+    assert_eq!(covered.get_pixel(0, 0), &Rgb([255, 0, 0]));
+    assert_eq!(covered.get_pixel(1, 1), &Rgb([0, 0, 255]));
+}
+
+#[test]
+fn test_bundle() -> Result<()> {
+    let mut render = ScreenRender::new("ST7735".to_string(), 160, 128, None, String::new())?;
+    render.add_widget("images", "图像", 20, 20);
+    let saveable = render.to_bundle()?;
+    assert_eq!(saveable.bundle.as_ref().map(|b| b.images), Some(1));
+    assert!(ScreenRender::validate_bundle(&saveable)?);
+
+    //布局的其他设置不影响校验和
+    let mut modified = saveable.clone();
+    modified.fps = saveable.fps + 1.;
+    assert!(ScreenRender::validate_bundle(&modified)?);
+    //图像数据修改后校验和不一致，但仍然可以打开
+    let mut modified = saveable.clone();
+    if let Some(SaveableWidget::ImageWidget(widget)) = modified.widgets.first_mut() {
+        widget.image_data.frames[0][0] ^= 0xff;
+    }
+    assert!(!ScreenRender::validate_bundle(&modified)?);
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+// AIDA64/Rainmeter/HWiNFO中常见的传感器名称(小写)对应的组件类型
+const SENSOR_NAMES: &[(&str, &str)] = &[
+    ("cpu utilization", "cpu_usage"),
+    ("cpu usage", "cpu_usage"),
+    ("cpu load", "cpu_usage"),
+    ("total cpu usage", "cpu_usage"),
+    ("cpu type", "cpu"),
+    ("cpu name", "cpu"),
+    ("cpu clock", "cpu_freq"),
+    ("cpu core clock", "cpu_freq"),
+    ("cpu frequency", "cpu_freq"),
+    ("cpu package", "cpu_temp."),
+    ("cpu temperature", "cpu_temp."),
+    ("cpu diode", "cpu_temp."),
+    ("cpu tctl/tdie", "cpu_temp."),
+    ("cpu package power", "cpu_package_power"),
+    ("cpu power", "cpu_package_power"),
+    ("cpu cores power", "cpu_cores_power"),
+    ("cpu core power", "cpu_cores_power"),
+    ("cpu ia cores", "cpu_cores_power"),
+    ("cpu fan", "cpu_fan"),
+    ("cpu fan speed", "cpu_fan"),
+    ("cpu cores", "num_cpu"),
+    ("logical processors", "num_cpu"),
+    ("chassis fan", "cooling"),
+    ("pump", "cooling"),
+    ("gpu hot spot", "gpu_temp."),
+    ("gpu hotspot", "gpu_temp."),
+    ("gpu temperature", "gpu_temp."),
+    ("gpu diode", "gpu_temp."),
+    ("gpu utilization", "gpu_load"),
+    ("gpu usage", "gpu_load"),
+    ("gpu core load", "gpu_load"),
+    ("gpu memory utilization", "gpu_memory_load"),
+    ("gpu memory usage", "gpu_memory_load"),
+    ("gpu memory load", "gpu_memory_load"),
+    ("gpu used memory", "gpu_memory_used_gb"),
+    ("gpu dedicated memory", "gpu_memory_total_mb"),
+    ("gpu clock", "gpu_clock"),
+    ("gpu core clock", "gpu_clock"),
+    ("gpu fan", "gpu_fan"),
+    ("gpu fan speed", "gpu_fan"),
+    ("gpu power", "gpu_package_power"),
+    ("gpu package power", "gpu_package_power"),
+    ("gpu core power", "gpu_cores_power"),
+    ("memory utilization", "memory_percent"),
+    ("memory usage", "memory_percent"),
+    ("memory load", "memory_percent"),
+    ("used memory", "memory"),
+    ("physicalmemory", "memory"),
+    ("total memory", "memory_total"),
+    ("used virtual memory", "swap"),
+    ("swapmemory", "swap"),
+    ("virtual memory utilization", "swap_percent"),
+    ("disk read rate", "disk_read_speed"),
+    ("disk read speed", "disk_read_speed"),
+    ("disk write rate", "disk_write_speed"),
+    ("disk write speed", "disk_write_speed"),
+    ("disk activity", "disk_activity"),
+    ("freediskspace", "disk_usage"),
+    ("free space", "disk_usage"),
+    ("nic download rate", "received_speed"),
+    ("download rate", "received_speed"),
+    ("netin", "received_speed"),
+    ("nic upload rate", "transmitted_speed"),
+    ("upload rate", "transmitted_speed"),
+    ("netout", "transmitted_speed"),
+    ("nic total download", "network_total_received"),
+    ("total download", "network_total_received"),
+    ("nic total upload", "network_total_transmitted"),
+    ("total upload", "network_total_transmitted"),
+    ("processes", "num_process"),
+    ("process count", "num_process"),
+    ("threads", "num_threads"),
+    ("thread count", "num_threads"),
+    ("load average", "load_avg"),
+    ("system uptime", "uptime"),
+    ("day of week", "weekday"),
+    ("primary ip address", "local_ip"),
+    ("ip address", "local_ip"),
+    ("external ip address", "net_ip"),
+    ("computer name", "host"),
+    ("operating system", "system"),
+    ("os version", "version"),
+    ("kernel version", "kernel"),
+];
+
+// 可以直接使用的组件类型，外部名称匹配不上时按类型名处理
+const TYPE_NAMES: &[&str] = &[
+    "cpu", "cpu_usage", "cpu_freq", "cpu_temp.", "cpu_cores_power", "cpu_package_power", "cpu_energy", "cpu_fan", "cooling", "num_cpu",
+    "gpu_temp.", "gpu_cores_power", "gpu_package_power", "gpu_energy", "gpu_load", "gpu_memory_load", "gpu_memory_total_mb",
+    "gpu_memory_total_gb", "gpu_memory_used_gb", "gpu_clock", "gpu_fan", "memory", "memory_total", "memory_percent",
+    "swap", "swap_percent", "num_process", "num_threads", "load_avg", "disk_usage", "disk_read_speed", "disk_write_speed",
+    "received_speed", "transmitted_speed", "network_total_received", "network_total_transmitted", "disk_activity", "network_activity",
+    "local_ip", "net_ip", "net_ip_info", "system", "uptime", "kernel", "version", "host", "date", "year", "month", "day", "weekday_num", "time", "ampm", "weekday",
+    "lunar_year", "lunar_date",
+];
+
+// 导入的一项传感器，label为空时显示原来的名称
+#[derive(Debug, Clone, Deserialize)]
+pub struct SensorEntry {
+    pub name: String,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+// 传感器列表和屏幕大小，CSV格式使用默认的160x128
+#[derive(Debug, Clone, Deserialize)]
+pub struct SensorSpec {
+    #[serde(default = "default_model")]
+    pub model: String,
+    #[serde(default = "default_width")]
+    pub width: u32,
+    #[serde(default = "default_height")]
+    pub height: u32,
+    pub sensors: Vec<SensorEntry>,
+}
+
+fn default_model() -> String {
+    "ST7735".to_string()
+}
+
+fn default_width() -> u32 {
+    160
+}
+
+fn default_height() -> u32 {
+    128
+}
+
+impl SensorSpec {
+    // JSON: {"width":320,"height":240,"sensors":[{"name":"CPU Package","label":"CPU"}]} 或者只有sensors数组
+    // CSV: 每行 名称[,显示名称]，#开头的行忽略
+    pub fn parse(text: &str) -> Result<Self> {
+        let trimmed = text.trim_start_matches('\u{feff}').trim();
+        let spec = if trimmed.starts_with('{') {
+            serde_json::from_str(trimmed)?
+        } else if trimmed.starts_with('[') {
+            let sensors: Vec<SensorEntry> = serde_json::from_str(trimmed)?;
+            SensorSpec { model: default_model(), width: default_width(), height: default_height(), sensors }
+        } else {
+            let sensors = trimmed
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(|line| {
+                    let mut arr = line.splitn(2, ',');
+                    let name = arr.next().unwrap_or("").trim().trim_matches('"').to_string();
+                    let label = arr.next().map(|l| l.trim().trim_matches('"').to_string()).filter(|l| !l.is_empty());
+                    SensorEntry { name, label }
+                })
+                .collect();
+            SensorSpec { model: default_model(), width: default_width(), height: default_height(), sensors }
+        };
+        if spec.width == 0 || spec.height == 0 {
+            return Err(anyhow!("屏幕大小错误:{}x{}", spec.width, spec.height));
+        }
+        Ok(spec)
+    }
+}
+
+// 外部传感器名称转换为组件类型，忽略大小写和名称后面的单位，例如 "CPU Package [°C]"
+pub fn type_name_of(name: &str) -> Option<&'static str> {
+    let name = name.split(['[', '(']).next().unwrap_or(name);
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    SENSOR_NAMES
+        .iter()
+        .find(|(external, _)| *external == name)
+        .map(|(_, type_name)| *type_name)
+        .or_else(|| TYPE_NAMES.iter().find(|t| **t == name).copied())
+}
@@ -0,0 +1,93 @@
+use anyhow::{anyhow, Result};
+use reqwest::header::USER_AGENT;
+use serde_json::Value;
+
+// 行情: 价格和涨跌幅
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quote {
+    pub price: f64,
+    pub change: f64,
+}
+
+impl Quote {
+    // 上涨显示绿色，下跌显示红色
+    pub fn color(&self, alpha: u8) -> [u8; 4] {
+        if self.change > 0. {
+            [60, 200, 90, alpha]
+        } else if self.change < 0. {
+            [255, 60, 60, alpha]
+        } else {
+            [200, 200, 200, alpha]
+        }
+    }
+}
+
+impl std::fmt::Display for Quote {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let arrow = if self.change > 0. {
+            "▲"
+        } else if self.change < 0. {
+            "▼"
+        } else {
+            ""
+        };
+        write!(f, "{:.2} {arrow}{:.2}%", self.price, self.change.abs())
+    }
+}
+
+// 价格和涨跌幅的路径用逗号分隔，例如 data.price,data.change_percent
+pub fn split_paths(paths: &str) -> (&str, &str) {
+    let mut arr = paths.splitn(2, ',');
+    let price = arr.next().unwrap_or("").trim();
+    let change = arr.next().unwrap_or("").trim();
+    (price, change)
+}
+
+// 请求行情接口，按路径读取价格和涨跌幅
+pub fn query_quote(url: &str, paths: &str) -> Result<Quote> {
+    let client = reqwest::blocking::Client::new();
+    let res = client.get(url)
+        .header(USER_AGENT, "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36 Edg/126.0.0.0")
+        .send()?
+        .error_for_status()?;
+    parse_quote(&res.json()?, paths)
+}
+
+pub fn parse_quote(json: &Value, paths: &str) -> Result<Quote> {
+    let (price_path, change_path) = split_paths(paths);
+    let price = json_number(json, price_path).ok_or(anyhow!("没有找到价格:{price_path}"))?;
+    //没有涨跌幅时按持平显示
+    let change = json_number(json, change_path).unwrap_or(0.);
+    Ok(Quote { price, change })
+}
+
+// 按 a.b.0.c 格式的路径读取数字，数字也可以是字符串
+pub fn json_number(json: &Value, path: &str) -> Option<f64> {
+    if path.is_empty() {
+        return None;
+    }
+    let mut value = json;
+    for key in path.split('.') {
+        value = match value {
+            Value::Array(arr) => arr.get(key.parse::<usize>().ok()?)?,
+            _ => value.get(key)?,
+        };
+    }
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().trim_end_matches('%').parse().ok(),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_parse_quote() -> Result<()> {
+    let json: Value = serde_json::from_str(
+        r#"{"data":[{"symbol":"BTC","price":"64000.5","change":-2.31}]}"#,
+    )?;
+    let quote = parse_quote(&json, "data.0.price, data.0.change")?;
+    assert_eq!(quote, Quote { price: 64000.5, change: -2.31 });
+    assert_eq!(quote.to_string(), "64000.50 ▼2.31%");
+    assert!(parse_quote(&json, "data.1.price").is_err());
+    Ok(())
+}
@@ -0,0 +1,228 @@
+use std::time::{Duration, Instant};
+
+use bincode::{Decode, Encode};
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+
+//过渡默认时长(秒)
+pub const DEFAULT_TRANSITION_DURATION: f32 = 0.5;
+
+//切换页面或布局时的过渡效果
+#[derive(Clone, Copy, Debug, Default, PartialEq, Encode, Decode, Deserialize, Serialize)]
+pub enum TransitionKind {
+    //直接切换
+    #[default]
+    None,
+    //淡入淡出
+    Fade,
+    //新画面从右侧推入
+    Slide,
+}
+
+impl TransitionKind {
+    pub fn index(&self) -> i32 {
+        match self {
+            TransitionKind::None => 0,
+            TransitionKind::Fade => 1,
+            TransitionKind::Slide => 2,
+        }
+    }
+
+    pub fn from_index(index: i32) -> Self {
+        match index {
+            1 => TransitionKind::Fade,
+            2 => TransitionKind::Slide,
+            _ => TransitionKind::None,
+        }
+    }
+}
+
+//滚动和推入的方向
+#[derive(Clone, Copy, Debug, Default, PartialEq, Encode, Decode, Deserialize, Serialize)]
+pub enum Direction {
+    //向左移动
+    #[default]
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    pub fn index(&self) -> i32 {
+        match self {
+            Direction::Left => 0,
+            Direction::Right => 1,
+            Direction::Up => 2,
+            Direction::Down => 3,
+        }
+    }
+
+    pub fn from_index(index: i32) -> Self {
+        match index {
+            1 => Direction::Right,
+            2 => Direction::Up,
+            3 => Direction::Down,
+            _ => Direction::Left,
+        }
+    }
+
+    pub fn is_vertical(&self) -> bool {
+        matches!(self, Direction::Up | Direction::Down)
+    }
+}
+
+//缓动曲线
+#[derive(Clone, Copy, Debug, Default, PartialEq, Encode, Decode, Deserialize, Serialize)]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseInOut,
+}
+
+impl Easing {
+    pub fn index(&self) -> i32 {
+        match self {
+            Easing::Linear => 0,
+            Easing::EaseInOut => 1,
+        }
+    }
+
+    pub fn from_index(index: i32) -> Self {
+        match index {
+            1 => Easing::EaseInOut,
+            _ => Easing::Linear,
+        }
+    }
+
+    // t从0到1，返回缓动后的进度
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => t * t * (3. - 2. * t),
+        }
+    }
+}
+
+//滚动文字、城市轮播和页面过渡共用的动画参数
+#[derive(Clone, Copy, Debug, Default, PartialEq, Encode, Decode, Deserialize, Serialize)]
+pub struct Animation {
+    #[serde(default)]
+    pub direction: Direction,
+    #[serde(default)]
+    pub easing: Easing,
+    //到达两端后停顿的时长(秒)，轮播时为每一项显示的时长，0使用默认值
+    #[serde(default)]
+    pub pause: f32,
+}
+
+impl Animation {
+    // 停顿时长(毫秒)，未设置时返回default_ms
+    pub fn pause_ms(&self, default_ms: u128) -> u128 {
+        if self.pause > 0. {
+            (self.pause * 1000.) as u128
+        } else {
+            default_ms
+        }
+    }
+}
+
+// 正在进行的过渡，保存切换前的最后一帧
+pub struct Transition {
+    kind: TransitionKind,
+    from: RgbImage,
+    start: Instant,
+    duration: Duration,
+    animation: Animation,
+}
+
+impl Transition {
+    pub fn new(kind: TransitionKind, from: RgbImage, duration: f32, animation: Animation) -> Self {
+        Self {
+            kind,
+            from,
+            start: Instant::now(),
+            duration: Duration::from_secs_f32(duration.max(0.)),
+            animation,
+        }
+    }
+
+    // 把旧画面混合到新画面上，过渡结束后返回false
+    pub fn apply(&self, frame: &mut RgbImage) -> bool {
+        if self.from.dimensions() != frame.dimensions() || self.duration.is_zero() {
+            return false;
+        }
+        let progress = self.start.elapsed().as_secs_f32() / self.duration.as_secs_f32();
+        if progress >= 1. {
+            return false;
+        }
+        blend(self.kind, self.animation.direction, &self.from, frame, self.animation.easing.apply(progress));
+        true
+    }
+}
+
+// progress从0到1，0时为旧画面，1时为新画面
+pub fn blend(kind: TransitionKind, direction: Direction, from: &RgbImage, to: &mut RgbImage, progress: f32) {
+    let progress = progress.clamp(0., 1.);
+    match kind {
+        TransitionKind::None => (),
+        TransitionKind::Fade => fade(from, to, progress),
+        TransitionKind::Slide => {
+            let span = if direction.is_vertical() { to.height() } else { to.width() };
+            let shift = (span as f32 * progress) as u32;
+            let new_frame = to.clone();
+            for (x, y, pixel) in to.enumerate_pixels_mut() {
+                //沿移动方向的坐标，向右、向下时翻转后按向左处理
+                let pos = match direction {
+                    Direction::Left => x,
+                    Direction::Up => y,
+                    Direction::Right => span - 1 - x,
+                    Direction::Down => span - 1 - y,
+                };
+                let (src, pos) = if pos + shift < span { (from, pos + shift) } else { (&new_frame, pos + shift - span) };
+                *pixel = *match direction {
+                    Direction::Left => src.get_pixel(pos, y),
+                    Direction::Up => src.get_pixel(x, pos),
+                    Direction::Right => src.get_pixel(span - 1 - pos, y),
+                    Direction::Down => src.get_pixel(x, span - 1 - pos),
+                };
+            }
+        }
+    }
+}
+
+// 按像素通道淡入淡出，页面过渡和图像切换共用
+pub fn fade(from: &[u8], to: &mut [u8], progress: f32) {
+    let progress = progress.clamp(0., 1.);
+    for (dst, src) in to.iter_mut().zip(from.iter()) {
+        *dst = (*src as f32 + (*dst as f32 - *src as f32) * progress) as u8;
+    }
+}
+
+#[test]
+fn test_blend() {
+    use image::Rgb;
+    let from = RgbImage::from_pixel(4, 1, Rgb([0, 0, 0]));
+    let mut to = RgbImage::from_pixel(4, 1, Rgb([200, 100, 50]));
+    blend(TransitionKind::Fade, Direction::Left, &from, &mut to, 0.5);
+    assert_eq!(to.get_pixel(0, 0), &Rgb([100, 50, 25]));
+
+    let mut to = RgbImage::from_pixel(4, 1, Rgb([255, 255, 255]));
+    blend(TransitionKind::Slide, Direction::Left, &from, &mut to, 0.5);
+    let row: Vec<u8> = to.pixels().map(|p| p[0]).collect();
+    assert_eq!(row, vec![0, 0, 255, 255]);
+
+    let mut to = RgbImage::from_pixel(4, 1, Rgb([255, 255, 255]));
+    blend(TransitionKind::Slide, Direction::Right, &from, &mut to, 0.5);
+    let row: Vec<u8> = to.pixels().map(|p| p[0]).collect();
+    assert_eq!(row, vec![255, 255, 0, 0]);
+}
+
+#[test]
+fn test_easing() {
+    assert_eq!(Easing::Linear.apply(0.25), 0.25);
+    assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+    assert!(Easing::EaseInOut.apply(0.1) < 0.1);
+    assert_eq!(Easing::EaseInOut.apply(2.), 1.);
+}
@@ -1,78 +1,647 @@
-use std::time::Duration;
+use std::{collections::HashMap, sync::Mutex, time::{Duration, Instant}};
 
 use futures_lite::future::block_on;
 use image::{Rgb, RgbImage};
-use log::{info, warn};
-use nusb::Interface;
-use anyhow::{anyhow, Result};
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use nusb::{transfer::{RequestBuffer, TransferError}, Interface};
+use anyhow::Result;
 #[cfg(feature = "usb-serial")]
 use serialport::{SerialPort, SerialPortInfo, SerialPortType};
 
-use crate::rgb565::rgb888_to_rgb565_be;
-
-// use crate::rgb565::rgb888_to_rgb565_be;
+use crate::mono;
+use crate::rgb565::{rgb888_to_rgb565, ByteOrder};
 
 const BULK_OUT_EP: u8 = 0x01;
 const BULK_IN_EP: u8 = 0x81;
+//图像开始和结束的标记
+const IMAGE_AA: u64 = 7596835243154170209;
+const IMAGE_BB: u64 = 7596835243154170466;
+//开机画面，固件保存后在上电时显示
+const BOOT_USB: u64 = 7093010483740242786;
+//双缓冲: 一帧开始和提交的标记，固件收到提交后再交换缓冲区，避免撕裂
+const FRAME_AA: u64 = 7382069861693743457;
+const FRAME_BB: u64 = 7382069861693743714;
+//查询固件版本和功能，固件通过BULK_IN_EP回传
+const INFO_USB: u64 = 7597122249949541218;
+//每个设备只查询一次固件信息，旧固件不回应时也记录下来，定时重新打开设备时不再等待查询超时
+//按串号和地址区分，重新插拔后地址改变会重新查询
+static FIRMWARE_INFOS: Lazy<Mutex<HashMap<String, Option<FirmwareInfo>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+//串口写入的超时，设备卡住时返回错误，发送线程释放串口后再重新打开
+#[cfg(feature = "usb-serial")]
+const SERIAL_TIMEOUT: Duration = Duration::from_millis(500);
+
+pub type ScreenResult<T> = std::result::Result<T, ScreenError>;
+
+// 屏幕设备操作的错误，调用者可以根据类型判断是否重试或者重新打开设备
+#[derive(Debug)]
+pub enum ScreenError{
+    //没有找到设备
+    DeviceNotFound,
+    //压缩后的图像超过设备缓冲区大小(字节数)
+    ImageTooLarge(usize),
+    //传输超时
+    Timeout,
+    //设备已断开
+    Disconnected,
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for ScreenError{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result{
+        match self{
+            ScreenError::DeviceNotFound => write!(f, "设备未找到"),
+            ScreenError::ImageTooLarge(size) => write!(f, "图像太大了!({}k)", size / 1024),
+            ScreenError::Timeout => write!(f, "传输超时"),
+            ScreenError::Disconnected => write!(f, "设备已断开"),
+            ScreenError::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for ScreenError{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)>{
+        match self{
+            ScreenError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ScreenError{
+    fn from(err: std::io::Error) -> Self{
+        match err.kind(){
+            std::io::ErrorKind::BrokenPipe | std::io::ErrorKind::NotConnected | std::io::ErrorKind::NotFound => ScreenError::Disconnected,
+            std::io::ErrorKind::TimedOut => ScreenError::Timeout,
+            _ => ScreenError::Io(err),
+        }
+    }
+}
+
+impl From<TransferError> for ScreenError{
+    fn from(err: TransferError) -> Self{
+        match err{
+            TransferError::Disconnected => ScreenError::Disconnected,
+            err => ScreenError::Io(std::io::Error::new(std::io::ErrorKind::Other, err)),
+        }
+    }
+}
+
+impl From<async_std::future::TimeoutError> for ScreenError{
+    fn from(_err: async_std::future::TimeoutError) -> Self{
+        ScreenError::Timeout
+    }
+}
+
+impl From<image::ImageError> for ScreenError{
+    fn from(err: image::ImageError) -> Self{
+        match err{
+            image::ImageError::IoError(err) => err.into(),
+            err => ScreenError::Io(std::io::Error::new(std::io::ErrorKind::Other, err)),
+        }
+    }
+}
+
+#[cfg(feature = "usb-serial")]
+impl From<serialport::Error> for ScreenError{
+    fn from(err: serialport::Error) -> Self{
+        match err.kind(){
+            serialport::ErrorKind::NoDevice => ScreenError::Disconnected,
+            _ => std::io::Error::from(err).into(),
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct UsbScreenInfo{
     pub label: String,
     pub address: String,
+    //设备串号，例如 USBSCR160x128;LE
+    pub serial: String,
     pub width: u16,
     pub height: u16,
+    pub byte_order: ByteOrder,
+    //压缩后一帧的最大字节数，串号中带有 ";BUF64" 时为64KB
+    pub max_frame_size: usize,
+    pub color_mode: ColorMode,
+    //固件支持双缓冲，串号中带有 ";SYNC"
+    pub double_buffer: bool,
+    //固件回传的版本和功能，旧固件不回传时为None
+    pub firmware: Option<FirmwareInfo>,
+}
+
+impl UsbScreenInfo{
+    // 固件回传的功能优先于串号中的配置
+    fn apply_firmware(&mut self, firmware: FirmwareInfo){
+        if let Some(color_mode) = firmware.color_mode{
+            self.color_mode = color_mode;
+        }
+        if let Some(byte_order) = firmware.byte_order{
+            self.byte_order = byte_order;
+        }
+        if let Some(max_frame_size) = firmware.max_frame_size{
+            self.max_frame_size = max_frame_size;
+        }
+        self.double_buffer |= firmware.double_buffer;
+        self.firmware = Some(firmware);
+    }
+}
+
+// 固件的版本和功能，格式和串号相同，例如 v1.2;RGB565;LE;BUF64;BRIGHT;PARTIAL;SYNC
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FirmwareInfo{
+    pub version: String,
+    pub color_mode: Option<ColorMode>,
+    //RGB565的字节序，不回传时使用串号中的配置
+    pub byte_order: Option<ByteOrder>,
+    //压缩后一帧的最大字节数
+    pub max_frame_size: Option<usize>,
+    //支持调节亮度
+    pub brightness: bool,
+    //支持局部刷新
+    pub partial_update: bool,
+    pub double_buffer: bool,
+}
+
+impl FirmwareInfo{
+    // 第一项必须是版本号，避免把渲染耗时等其他回传数据当作版本信息
+    pub fn parse(msg: &str) -> Option<Self>{
+        let msg = msg.trim_matches(char::from(0)).trim();
+        let mut arr = msg.split(';');
+        let version = arr.next()?.trim();
+        if !version.starts_with(['v', 'V']) || version.len() < 2{
+            return None;
+        }
+        let mut info = FirmwareInfo{ version: version.to_string(), ..Default::default() };
+        for item in arr{
+            let item = item.trim().to_ascii_uppercase();
+            match item.as_str(){
+                "RGB565" => info.color_mode = Some(ColorMode::Rgb565),
+                "RGB888" => info.color_mode = Some(ColorMode::Rgb888),
+                "MONO" => info.color_mode = Some(ColorMode::Mono1),
+                "BE" => info.byte_order = Some(ByteOrder::BigEndian),
+                "LE" => info.byte_order = Some(ByteOrder::LittleEndian),
+                "BRIGHT" => info.brightness = true,
+                "PARTIAL" => info.partial_update = true,
+                "SYNC" => info.double_buffer = true,
+                _ => {
+                    if let Some(kb) = item.strip_prefix("BUF").and_then(|kb| kb.parse::<usize>().ok()).filter(|kb| *kb > 0){
+                        info.max_frame_size = Some(kb * 1024);
+                    }
+                }
+            }
+        }
+        Some(info)
+    }
+}
+
+impl std::fmt::Display for FirmwareInfo{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result{
+        write!(f, "{}", self.version)?;
+        if let Some(color_mode) = self.color_mode{
+            write!(f, " {color_mode:?}")?;
+        }
+        if let Some(byte_order) = self.byte_order{
+            write!(f, " {byte_order:?}")?;
+        }
+        if let Some(max_frame_size) = self.max_frame_size{
+            write!(f, " {}KB", max_frame_size / 1024)?;
+        }
+        if self.brightness{
+            write!(f, " 亮度")?;
+        }
+        if self.partial_update{
+            write!(f, " 局部刷新")?;
+        }
+        if self.double_buffer{
+            write!(f, " 双缓冲")?;
+        }
+        Ok(())
+    }
+}
+
+// 屏幕的像素格式
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ColorMode{
+    #[default]
+    Rgb565,
+    //每个像素3字节，不降色
+    Rgb888,
+    //单色OLED，每个像素1bit
+    Mono1,
+}
+
+// 固件回传的每帧渲染耗时(滑动平均)
+#[derive(Clone, Debug)]
+pub struct RenderTiming{
+    // 固件不回传耗时的情况下关闭读取
+    enabled: bool,
+    avg_ms: Option<f32>,
+    // 连续读取超时的次数
+    timeouts: u32,
+}
+
+impl Default for RenderTiming{
+    fn default() -> Self {
+        Self { enabled: true, avg_ms: None, timeouts: 0 }
+    }
+}
+
+impl RenderTiming{
+    fn update(&mut self, ms: f32){
+        self.avg_ms = Some(match self.avg_ms{
+            Some(avg) => avg * 0.8 + ms * 0.2,
+            None => ms
+        });
+    }
+}
+
+// 等待固件回传渲染耗时的时间，大屏幕一帧的绘制可能超过100ms
+const RENDER_TIME_TIMEOUT: Duration = Duration::from_millis(500);
+// 连续超时多少次后停止读取渲染耗时
+const RENDER_TIME_MAX_TIMEOUTS: u32 = 5;
+
+// RP2040一帧压缩后默认的最大字节数，超过后会死机
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 1024 * 28;
+// 压缩后大小超过上限的85%时提示复杂度过高
+const COMPLEXITY_WARN_RATIO: f32 = 0.85;
+
+// 最近的帧压缩后大小(滑动平均)，持续接近上限时降低帧率
+#[derive(Clone, Debug)]
+pub struct FrameComplexity{
+    avg_size: Option<f32>,
+    //当前设备的大小上限
+    max_size: usize,
+}
+
+impl Default for FrameComplexity{
+    fn default() -> Self {
+        Self { avg_size: None, max_size: DEFAULT_MAX_FRAME_SIZE }
+    }
+}
+
+impl FrameComplexity{
+    pub fn set_max_size(&mut self, max_size: usize){
+        self.max_size = max_size.max(1);
+    }
+
+
+    pub fn update(&mut self, size: usize){
+        let was_high = self.is_high();
+        self.avg_size = Some(match self.avg_size{
+            Some(avg) => avg * 0.8 + size as f32 * 0.2,
+            None => size as f32
+        });
+        if self.is_high() && !was_high{
+            warn!("画面过于复杂:{}%，降低帧率", self.percent());
+        }
+    }
+
+    // 压缩后的平均字节数
+    pub fn size(&self) -> usize{
+        self.avg_size.unwrap_or(0.) as usize
+    }
+
+    // 相对上限的百分比
+    pub fn percent(&self) -> u32{
+        (self.avg_size.unwrap_or(0.) / self.max_size as f32 * 100.) as u32
+    }
+
+    pub fn is_high(&self) -> bool{
+        self.avg_size.map(|avg| avg >= self.max_size as f32 * COMPLEXITY_WARN_RATIO).unwrap_or(false)
+    }
+}
+
+// 估算一帧发送到USB屏幕时压缩后的大小
+pub fn compressed_frame_size(img: &RgbImage) -> usize{
+    let rgb565 = rgb888_to_rgb565(img, img.width() as usize, img.height() as usize, ByteOrder::BigEndian);
+    lz4_flex::compress_prepend_size(&rgb565).len()
+}
+
+// 测速时逐级增加画面复杂度
+pub const BENCHMARK_LEVELS: [&str; 4] = ["纯色", "色块", "渐变", "噪点"];
+
+// 一个复杂度等级的测速结果
+#[derive(Clone, Debug)]
+pub struct BenchmarkResult{
+    pub level: usize,
+    pub frames: u32,
+    pub fps: f32,
+    //每帧实际发送的平均字节数
+    pub avg_bytes: usize,
+}
+
+impl std::fmt::Display for BenchmarkResult{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result{
+        write!(f, "{} {:.1}帧 {:.1}k", BENCHMARK_LEVELS[self.level], self.fps, self.avg_bytes as f32 / 1024.)
+    }
+}
+
+// 测速画面，每帧内容都不同，避免固件跳过相同的画面
+pub fn benchmark_frame(width: u32, height: u32, level: usize, frame: u32) -> RgbImage{
+    let shift = (frame * 8) as u8;
+    match level{
+        0 => RgbImage::from_pixel(width, height, Rgb([shift, 255 - shift, 128])),
+        1 => RgbImage::from_fn(width, height, |x, y| {
+            let block = ((x / 16 + y / 16) as u8).wrapping_mul(40).wrapping_add(shift);
+            Rgb([block, block.wrapping_mul(3), 255 - block])
+        }),
+        2 => RgbImage::from_fn(width, height, |x, y| {
+            Rgb([(x * 255 / width.max(1)) as u8 ^ shift, (y * 255 / height.max(1)) as u8, shift])
+        }),
+        _ => {
+            //xorshift伪随机数
+            let mut seed = (0x9E37_79B9u32 ^ frame.wrapping_mul(0x85EB_CA6B)) | 1;
+            RgbImage::from_fn(width, height, |_, _| {
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                let [r, g, b, _] = seed.to_le_bytes();
+                Rgb([r, g, b])
+            })
+        }
+    }
+}
+
+// 环境变量 USB_SCREEN_MOCK=160x128 在没有屏幕时使用模拟设备
+pub const MOCK_ENV: &str = "USB_SCREEN_MOCK";
+// 环境变量 USB_SCREEN_MOCK_FILE=frame.png 将模拟设备的每一帧保存到文件
+pub const MOCK_FILE_ENV: &str = "USB_SCREEN_MOCK_FILE";
+const MOCK_LABEL: &str = "Mock";
+
+// 模拟设备，帧数据保存在内存中
+pub struct MockScreen{
+    pub frame: RgbImage,
+    pub frame_count: u64,
+    pub out_file: Option<String>,
 }
 
 pub enum UsbScreen{
-    USBRaw((UsbScreenInfo, Interface)),
+    USBRaw((UsbScreenInfo, Interface, RenderTiming)),
     #[cfg(feature = "usb-serial")]
-    USBSerial((UsbScreenInfo, Box<dyn SerialPort>))
+    USBSerial((UsbScreenInfo, Box<dyn SerialPort>)),
+    Mock((UsbScreenInfo, MockScreen)),
 }
 
 impl UsbScreen{
-    pub fn draw_rgb_image(&mut self, x: u16, y: u16, img:&RgbImage) -> anyhow::Result<()>{
+    pub fn draw_rgb_image(&mut self, x: u16, y: u16, img:&RgbImage) -> ScreenResult<()>{
         //如果图像比屏幕大， 不绘制，否则会RP2040死机导致卡住
         match self{
-            UsbScreen::USBRaw((info, interface)) => {
+            UsbScreen::USBRaw((info, interface, timing)) => {
                 if img.width() <= info.width as u32 && img.height() <= info.height as u32{
-                    draw_rgb_image(x, y, img, interface)?;
+                    if info.double_buffer{
+                        send_marker(interface, FRAME_AA)?;
+                    }
+                    //压缩后超过上限时拆分成多个条带发送，固件按照帧头中的位置绘制
+                    let tiles = split_frame(img, info)?;
+                    let mut render_ms = Some(0.);
+                    for (tile_y, height, data) in tiles{
+                        send_compressed(IMAGE_AA, data, x, y + tile_y as u16, img.width() as u16, height as u16, interface)?;
+                        //每个条带都会回传一次渲染耗时
+                        if timing.enabled{
+                            match read_render_time(interface){
+                                Ok(ms) => {
+                                    timing.timeouts = 0;
+                                    render_ms = render_ms.map(|sum| sum + ms);
+                                }
+                                //偶尔超时可能是固件忙，连续多次超时才认为固件不支持
+                                Err(ScreenError::Timeout) => {
+                                    timing.timeouts += 1;
+                                    if timing.timeouts >= RENDER_TIME_MAX_TIMEOUTS{
+                                        info!("固件连续{}次未回传渲染耗时，停止读取", timing.timeouts);
+                                        timing.enabled = false;
+                                    }
+                                    render_ms = None;
+                                }
+                                Err(err) => {
+                                    info!("读取渲染耗时失败，停止读取:{err:?}");
+                                    timing.enabled = false;
+                                    render_ms = None;
+                                }
+                            }
+                        }
+                    }
+                    if info.double_buffer{
+                        send_marker(interface, FRAME_BB)?;
+                    }
+                    if let (true, Some(ms)) = (timing.enabled, render_ms){
+                        timing.update(ms);
+                    }
                 }
             }
 
             #[cfg(feature = "usb-serial")]
             UsbScreen::USBSerial((info, port)) => {
                 if img.width() <= info.width as u32 && img.height() <= info.height as u32{
-                    draw_rgb_image_serial(x, y, img, port.as_mut())?;
+                    if info.double_buffer{
+                        send_marker_serial(port.as_mut(), FRAME_AA)?;
+                    }
+                    let data = encode_frame(img, info);
+                    draw_rgb565_serial(&data, x, y, img.width() as u16, img.height() as u16, port.as_mut())?;
+                    if info.double_buffer{
+                        send_marker_serial(port.as_mut(), FRAME_BB)?;
+                    }
+                }
+            }
+
+            UsbScreen::Mock((info, mock)) => {
+                if img.width() <= info.width as u32 && img.height() <= info.height as u32{
+                    image::imageops::replace(&mut mock.frame, img, x as i64, y as i64);
+                    mock.frame_count += 1;
+                    if let Some(file) = mock.out_file.as_ref(){
+                        mock.frame.save(file)?;
+                    }
                 }
             }
         }
         Ok(())
     }
 
-    pub fn open(info: UsbScreenInfo) -> Result<Self>{
+    pub fn open(info: UsbScreenInfo) -> ScreenResult<Self>{
         info!("打开屏幕:label={} addr={} {}x{}", info.label, info.address, info.width, info.height);
-        let addr = info.address.clone();
-        if info.label.contains("Screen"){
+        let (addr, serial) = (info.address.clone(), info.serial.clone());
+        if info.label == MOCK_LABEL{
+            let frame = RgbImage::new(info.width as u32, info.height as u32);
+            let out_file = std::env::var(MOCK_FILE_ENV).ok();
+            Ok(Self::Mock((info, MockScreen { frame, frame_count: 0, out_file })))
+        }else if info.label.contains("Screen"){
             //USB Raw设备, addr是device_address
-            Ok(Self::USBRaw((info, open_usb_raw_device(&addr)?)))
+            let mut screen = Self::USBRaw((info, open_usb_raw_device(&serial, &addr)?, RenderTiming::default()));
+            screen.query_info();
+            Ok(screen)
         }else{
             #[cfg(feature = "usb-serial")]
             {
                 //USB串口设备, addr是串口名称
-                let screen =  serialport::new(&info.address, 115_200).open()?;
+                let screen =  serialport::new(&info.address, 115_200).timeout(SERIAL_TIMEOUT).open()?;
                 Ok(Self::USBSerial((info, screen)))
             }
             #[cfg(not(feature = "usb-serial"))]
             {
-                Err(anyhow!("此平台不支持 USB串口设备"))
+                Err(ScreenError::Io(std::io::Error::new(std::io::ErrorKind::Unsupported, "此平台不支持 USB串口设备")))
             }
         }
     }
+
+    // 设置开机画面，图像大小必须和屏幕相同
+    pub fn set_boot_splash(&mut self, img:&RgbImage) -> ScreenResult<()>{
+        match self{
+            UsbScreen::USBRaw((info, interface, _)) => {
+                if img.width() != info.width as u32 || img.height() != info.height as u32{
+                    return Err(ScreenError::Io(std::io::Error::new(std::io::ErrorKind::InvalidInput, "开机画面和屏幕大小不同")));
+                }
+                let data = encode_frame(img, info);
+                send_rgb565(BOOT_USB, &data, 0, 0, info.width, info.height, interface, info.max_frame_size)?;
+            }
+
+            #[cfg(feature = "usb-serial")]
+            UsbScreen::USBSerial((info, port)) => {
+                if img.width() != info.width as u32 || img.height() != info.height as u32{
+                    return Err(ScreenError::Io(std::io::Error::new(std::io::ErrorKind::InvalidInput, "开机画面和屏幕大小不同")));
+                }
+                let data = encode_frame(img, info);
+                send_rgb565_serial(BOOT_USB, &data, 0, 0, info.width, info.height, port.as_mut())?;
+            }
+
+            UsbScreen::Mock(_) => info!("模拟设备不支持开机画面"),
+        }
+        Ok(())
+    }
+
+    // 查询固件版本和功能并缓存到UsbScreenInfo中，旧固件不回应时返回None
+    pub fn query_info(&mut self) -> Option<&FirmwareInfo>{
+        if let UsbScreen::USBRaw((info, interface, _)) = self{
+            if info.firmware.is_none(){
+                let key = format!("{} {}", info.serial, info.address);
+                let cached = FIRMWARE_INFOS.lock().ok().and_then(|infos| infos.get(&key).cloned());
+                let firmware = match cached{
+                    Some(firmware) => firmware,
+                    None => {
+                        let firmware = match read_firmware_info(interface){
+                            Ok(firmware) => {
+                                info!("固件信息:{firmware}");
+                                Some(firmware)
+                            }
+                            Err(err) => {
+                                info!("固件不支持查询版本:{err:?}");
+                                None
+                            }
+                        };
+                        if let Ok(mut infos) = FIRMWARE_INFOS.lock(){
+                            infos.insert(key, firmware.clone());
+                        }
+                        firmware
+                    }
+                };
+                if let Some(firmware) = firmware{
+                    info.apply_firmware(firmware);
+                }
+            }
+        }
+        self.info().firmware.as_ref()
+    }
+
+    // 屏幕文件中指定了字节序时，覆盖设备的字节序
+    pub fn set_byte_order(&mut self, byte_order: ByteOrder){
+        match self{
+            UsbScreen::USBRaw((info, _, _)) => info.byte_order = byte_order,
+            #[cfg(feature = "usb-serial")]
+            UsbScreen::USBSerial((info, _)) => info.byte_order = byte_order,
+            UsbScreen::Mock((info, _)) => info.byte_order = byte_order,
+        }
+    }
+
+    pub fn info(&self) -> &UsbScreenInfo{
+        match self{
+            UsbScreen::USBRaw((info, _, _)) => info,
+            #[cfg(feature = "usb-serial")]
+            UsbScreen::USBSerial((info, _)) => info,
+            UsbScreen::Mock((info, _)) => info,
+        }
+    }
+
+    // USB直连的RP2040限制每帧压缩后的大小，其他设备返回None
+    pub fn frame_size_limit(&self) -> Option<usize>{
+        match self{
+            UsbScreen::USBRaw((info, _, _)) => Some(info.max_frame_size),
+            _ => None,
+        }
+    }
+
+    // 以最快速度发送测速画面，每个复杂度等级持续level_duration
+    // 画面超过设备缓冲区时停止，返回已经完成的等级
+    pub fn benchmark(&mut self, level_duration: Duration) -> ScreenResult<Vec<BenchmarkResult>>{
+        let info = self.info().clone();
+        let (width, height) = (info.width as u32, info.height as u32);
+        let mut results = vec![];
+        for level in 0..BENCHMARK_LEVELS.len(){
+            let (mut frames, mut bytes, mut elapsed) = (0u32, 0usize, Duration::ZERO);
+            while elapsed < level_duration{
+                let frame = benchmark_frame(width, height, level, frames);
+                bytes += encode_frame(&frame, &info).len();
+                let start = Instant::now();
+                match self.draw_rgb_image(0, 0, &frame){
+                    Ok(()) => (),
+                    Err(ScreenError::ImageTooLarge(size)) => {
+                        info!("测速画面超过设备缓冲区:{size}，停止测速");
+                        return Ok(results);
+                    }
+                    Err(err) => return Err(err),
+                }
+                elapsed += start.elapsed();
+                frames += 1;
+            }
+            results.push(BenchmarkResult{
+                level,
+                frames,
+                fps: frames as f32 / elapsed.as_secs_f32().max(0.001),
+                avg_bytes: bytes / frames.max(1) as usize,
+            });
+        }
+        Ok(results)
+    }
+
+    // 设备端每帧渲染耗时(毫秒)，固件未回传时返回None
+    pub fn device_render_time(&self) -> Option<f32>{
+        match self{
+            UsbScreen::USBRaw((_, _, timing)) => timing.avg_ms,
+            #[cfg(feature = "usb-serial")]
+            UsbScreen::USBSerial(_) => None,
+            UsbScreen::Mock(_) => None,
+        }
+    }
+}
+
+// 打开所有可用的屏幕，跳过opened中已经打开的地址
+pub fn open_all_screens(opened: &[String]) -> Vec<UsbScreen>{
+    find_all_device()
+        .into_iter()
+        .filter(|info| !opened.contains(&info.address))
+        .filter_map(|info| UsbScreen::open(info).ok())
+        .collect()
 }
 
-pub fn find_and_open_a_screen() -> Option<UsbScreen>{
-    //先查找串口设备
-    let devices = find_all_device();
+// 所有已连接屏幕的地址，用于判断是否有屏幕插拔
+pub fn device_addresses() -> Vec<String>{
+    let mut addresses: Vec<String> = find_all_device().into_iter().map(|info| info.address).collect();
+    addresses.sort();
+    addresses
+}
+
+// 优先打开布局文件中指定的设备：先按串号，其次按地址，都找不到时打开第一个可用的设备
+pub fn find_and_open_screen(serial: Option<&str>, address: Option<&str>) -> Option<UsbScreen>{
+    let mut devices = find_all_device();
+    //串号相同的多个屏幕再按地址区分
+    devices.sort_by_key(|info| {
+        let serial_match = serial.map(|s| s == info.serial).unwrap_or(false);
+        let address_match = address.map(|a| a == info.address).unwrap_or(false);
+        match (serial_match, address_match) {
+            (true, true) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (false, false) => 3,
+        }
+    });
     for info in devices{
         if let Ok(screen) = UsbScreen::open(info){
             return Some(screen);
@@ -81,16 +650,33 @@ pub fn find_and_open_a_screen() -> Option<UsbScreen>{
     None
 }
 
-pub fn open_usb_raw_device(device_address: &str) -> Result<Interface>{
-    let di = nusb::list_devices()?;
-    for d in di{
-        if d.serial_number().unwrap_or("").starts_with("USBSCR") && d.device_address() == device_address.parse::<u8>()?{
+// 按串号打开USB Raw设备，串号为空或者有多个相同串号的设备时用地址区分
+pub fn open_usb_raw_device(serial: &str, device_address: &str) -> ScreenResult<Interface>{
+    let address = device_address.parse::<u8>().ok();
+    let mut found = None;
+    for d in nusb::list_devices()?{
+        let serial_number = d.serial_number().unwrap_or("");
+        if !serial_number.starts_with("USBSCR"){
+            continue;
+        }
+        let serial_match = !serial.is_empty() && serial_number == serial;
+        let address_match = address == Some(d.device_address());
+        if serial_match && address_match{
+            found = Some(d);
+            break;
+        }
+        if (serial_match || address_match) && found.is_none(){
+            found = Some(d);
+        }
+    }
+    match found{
+        Some(d) => {
             let device = d.open()?;
             let interface = device.claim_interface(0)?;
-            return Ok(interface);
+            Ok(interface)
         }
+        None => Err(ScreenError::DeviceNotFound)
     }
-    Err(anyhow!("设备地址未找到"))
 }
 
 fn get_screen_size_from_serial_number(serial_number:&str) -> (u16, u16){
@@ -103,6 +689,78 @@ fn get_screen_size_from_serial_number(serial_number:&str) -> (u16, u16){
     (width, height)
 }
 
+// 压缩后的图像超过设备缓冲区，需要简化画面
+pub fn is_image_too_large(err: &anyhow::Error) -> bool{
+    matches!(err.downcast_ref::<ScreenError>(), Some(ScreenError::ImageTooLarge(_)))
+}
+
+// 设备被拔出等无法恢复的错误，超时等其他错误可以重试
+pub fn is_disconnected(err: &anyhow::Error) -> bool{
+    if let Some(err) = err.downcast_ref::<ScreenError>(){
+        return matches!(err, ScreenError::Disconnected);
+    }
+    if let Some(err) = err.downcast_ref::<TransferError>(){
+        return matches!(err, TransferError::Disconnected);
+    }
+    if let Some(err) = err.downcast_ref::<std::io::Error>(){
+        return matches!(err.kind(), std::io::ErrorKind::BrokenPipe | std::io::ErrorKind::NotConnected | std::io::ErrorKind::NotFound);
+    }
+    #[cfg(feature = "usb-serial")]
+    if let Some(err) = err.downcast_ref::<serialport::Error>(){
+        return matches!(err.kind(), serialport::ErrorKind::NoDevice);
+    }
+    false
+}
+
+// 在打开的屏幕上绘制，设备断开时将其移除，以便重新打开
+pub fn draw_or_release<T>(screen: &mut Option<T>, draw: impl FnOnce(&mut T) -> Result<()>) -> Result<()>{
+    if let Some(device) = screen.as_mut(){
+        if let Err(err) = draw(device){
+            if is_disconnected(&err){
+                warn!("屏幕已断开:{err:?}");
+                screen.take();
+            }
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+// 串号中带有 ";BUF64" 的屏幕每帧最大64KB，例如 USBSCR320x240;LE;BUF64
+fn get_max_frame_size_from_serial_number(serial_number:&str) -> usize{
+    serial_number.split(";").skip(1)
+        .find_map(|s| s.to_ascii_uppercase().strip_prefix("BUF")?.parse::<usize>().ok())
+        .filter(|kb| *kb > 0)
+        .map(|kb| kb * 1024)
+        .unwrap_or(DEFAULT_MAX_FRAME_SIZE)
+}
+
+// 串号中带有 ";MONO" 的是单色屏，例如 USBSCR128x64;MONO，带有 ";RGB888" 的不降色
+fn get_color_mode_from_serial_number(serial_number:&str) -> ColorMode{
+    let has = |flag: &str| serial_number.split(";").skip(1).any(|s| s.eq_ignore_ascii_case(flag));
+    if has("MONO"){
+        ColorMode::Mono1
+    }else if has("RGB888"){
+        ColorMode::Rgb888
+    }else{
+        ColorMode::Rgb565
+    }
+}
+
+// 串号中带有 ";SYNC" 的屏幕支持双缓冲，例如 USBSCR320x240;LE;SYNC
+fn get_double_buffer_from_serial_number(serial_number:&str) -> bool{
+    serial_number.split(";").skip(1).any(|s| s.eq_ignore_ascii_case("SYNC"))
+}
+
+// 串号中带有 ";LE" 的屏幕使用Little-Endian, 例如 USBSCR160x128;LE
+fn get_byte_order_from_serial_number(serial_number:&str) -> ByteOrder{
+    if serial_number.split(";").skip(1).any(|s| s.eq_ignore_ascii_case("LE")){
+        ByteOrder::LittleEndian
+    }else{
+        ByteOrder::BigEndian
+    }
+}
+
 // 查询所有USB屏幕设备
 // 对于USB Raw返回的第2个参数是 device_address
 // 对于USB Serial, 返回的第2个参数是串口名称
@@ -111,7 +769,7 @@ pub fn find_all_device() -> Vec<UsbScreenInfo>{
     if let Ok(di) = nusb::list_devices(){
         for d in di{
             #[cfg(not(windows))]
-            info!("USB Raw设备:{:?}", d);
+            debug!("USB Raw设备:{:?}", d);
             let serial_number = d.serial_number().unwrap_or("");
             if  d.product_string().unwrap_or("") == "USB Screen" && serial_number.starts_with("USBSCR"){
                 let label = format!("USB Screen({})", d.device_address());
@@ -120,8 +778,14 @@ pub fn find_all_device() -> Vec<UsbScreenInfo>{
                 devices.push(UsbScreenInfo{
                     label,
                     address,
+                    serial: serial_number.to_string(),
                     width,
                     height,
+                    byte_order: get_byte_order_from_serial_number(serial_number),
+                    max_frame_size: get_max_frame_size_from_serial_number(serial_number),
+                    color_mode: get_color_mode_from_serial_number(serial_number),
+                    double_buffer: get_double_buffer_from_serial_number(serial_number),
+                    firmware: None,
                 });
             }
         }
@@ -130,15 +794,43 @@ pub fn find_all_device() -> Vec<UsbScreenInfo>{
     #[cfg(feature = "usb-serial")]
     devices.extend_from_slice(&find_usb_serial_device());
     #[cfg(not(windows))]
-    info!("所有usb 设备:{:?}", devices);
+    debug!("所有usb 设备:{:?}", devices);
 
     if devices.len() == 0{
-        warn!("no available device!");
+        match mock_device(){
+            Some(mock) => devices.push(mock),
+            None => warn!("no available device!"),
+        }
     }
 
     devices
 }
 
+// 没有真实设备时，根据环境变量创建模拟设备
+fn mock_device() -> Option<UsbScreenInfo>{
+    Some(mock_device_with_size(&std::env::var(MOCK_ENV).ok()?))
+}
+
+// 按照 宽x高 创建模拟设备，测试中直接调用，不修改环境变量
+fn mock_device_with_size(size: &str) -> UsbScreenInfo{
+    let mut arr = size.split(['x', 'X']);
+    let width = arr.next().and_then(|w| w.trim().parse::<u16>().ok()).unwrap_or(160);
+    let height = arr.next().and_then(|h| h.trim().parse::<u16>().ok()).unwrap_or(128);
+    info!("使用模拟设备:{width}x{height}");
+    UsbScreenInfo{
+        label: MOCK_LABEL.to_string(),
+        address: String::new(),
+        serial: String::new(),
+        width,
+        height,
+        byte_order: ByteOrder::BigEndian,
+        max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+        color_mode: ColorMode::Rgb565,
+        double_buffer: false,
+        firmware: None,
+    }
+}
+
 #[cfg(feature = "usb-serial")]
 pub fn find_usb_serial_device() -> Vec<UsbScreenInfo>{
     let ports: Vec<SerialPortInfo> = serialport::available_ports().unwrap_or(vec![]);
@@ -154,8 +846,14 @@ pub fn find_usb_serial_device() -> Vec<UsbScreenInfo>{
                     let (width, height) = get_screen_size_from_serial_number(&serial_number);
                     devices.push(UsbScreenInfo{
                         label: format!("USB {port_name}"), address: port_name.to_string(),
+                        serial: serial_number.clone(),
                         width,
                         height,
+                        byte_order: get_byte_order_from_serial_number(&serial_number),
+                        max_frame_size: get_max_frame_size_from_serial_number(&serial_number),
+                        color_mode: get_color_mode_from_serial_number(&serial_number),
+                        double_buffer: get_double_buffer_from_serial_number(&serial_number),
+                        firmware: None,
                     });
                     continue;
                 }
@@ -166,46 +864,107 @@ pub fn find_usb_serial_device() -> Vec<UsbScreenInfo>{
     devices
 }
 
-pub fn clear_screen(color: Rgb<u8>, interface:&Interface, width: u16, height: u16) -> anyhow::Result<()>{
-    let mut img = RgbImage::new(width as u32, height as u32);
-    for p in img.pixels_mut(){
-        *p = color;
-    }
-    draw_rgb_image(0, 0, &img, interface)
+// 按照设备的像素格式清屏
+pub fn clear_screen(color: Rgb<u8>, interface:&Interface, info:&UsbScreenInfo) -> ScreenResult<()>{
+    let img = RgbImage::from_pixel(info.width as u32, info.height as u32, color);
+    let data = encode_frame(&img, info);
+    send_rgb565(IMAGE_AA, &data, 0, 0, info.width, info.height, interface, info.max_frame_size)
 }
 
 #[cfg(feature = "usb-serial")]
-pub fn clear_screen_serial(color: Rgb<u8>, port:&mut dyn SerialPort, width: u16, height: u16) -> anyhow::Result<()>{
-    let mut img = RgbImage::new(width as u32, height as u32);
-    for p in img.pixels_mut(){
-        *p = color;
-    }
-    draw_rgb_image_serial(0, 0, &img, port)
+pub fn clear_screen_serial(color: Rgb<u8>, port:&mut dyn SerialPort, info:&UsbScreenInfo) -> ScreenResult<()>{
+    let img = RgbImage::from_pixel(info.width as u32, info.height as u32, color);
+    let data = encode_frame(&img, info);
+    send_rgb565_serial(IMAGE_AA, &data, 0, 0, info.width, info.height, port)
 }
 
-pub fn draw_rgb_image(x: u16, y: u16, img:&RgbImage, interface:&Interface) -> anyhow::Result<()>{
+pub fn draw_rgb_image(x: u16, y: u16, img:&RgbImage, interface:&Interface, byte_order: ByteOrder, max_frame_size: usize) -> ScreenResult<()>{
     //ST7789驱动使用的是Big-Endian
-    let rgb565 = rgb888_to_rgb565_be(&img, img.width() as usize, img.height() as usize);
-    draw_rgb565(&rgb565, x, y, img.width() as u16, img.height() as u16, interface)
+    let rgb565 = rgb888_to_rgb565(&img, img.width() as usize, img.height() as usize, byte_order);
+    draw_rgb565(&rgb565, x, y, img.width() as u16, img.height() as u16, interface, max_frame_size)
+}
+
+// 按照屏幕的像素格式编码，单色屏抖动后按页打包，都使用和RGB565相同的传输协议
+fn encode_frame(img:&RgbImage, info:&UsbScreenInfo) -> Vec<u8>{
+    encode_pixels(img, info.color_mode, info.byte_order)
+}
+
+fn encode_pixels(img:&RgbImage, color_mode: ColorMode, byte_order: ByteOrder) -> Vec<u8>{
+    match color_mode{
+        ColorMode::Mono1 => mono::rgb_to_mono1(img),
+        ColorMode::Rgb565 => rgb888_to_rgb565(img, img.width() as usize, img.height() as usize, byte_order),
+        ColorMode::Rgb888 => img.as_raw().clone(),
+    }
+}
+
+// 压缩后超过上限的帧从中间拆分成上下两个条带，直到每个条带都不超过上限
+// 返回按从上到下排列的(y, 高度, 压缩后的数据)，单色屏按页打包，条带高度对齐到8行
+fn split_frame(img:&RgbImage, info:&UsbScreenInfo) -> ScreenResult<Vec<(u32, u32, Vec<u8>)>>{
+    let align = if info.color_mode == ColorMode::Mono1{ 8 }else{ 1 };
+    let mut tiles = vec![];
+    let mut bands = vec![(0, img.height())];
+    while let Some((y, height)) = bands.pop(){
+        let data = if height == img.height(){
+            encode_frame(img, info)
+        }else{
+            encode_frame(&image::imageops::crop_imm(img, 0, y, img.width(), height).to_image(), info)
+        };
+        let compressed = lz4_flex::compress_prepend_size(&data);
+        if compressed.len() <= info.max_frame_size{
+            tiles.push((y, height, compressed));
+        }else if height <= align{
+            //单行仍然超过上限，无法继续拆分
+            return Err(ScreenError::ImageTooLarge(compressed.len()));
+        }else{
+            let top = (height / 2).div_ceil(align) * align;
+            //先处理上半部分
+            bands.push((y + top, height - top));
+            bands.push((y, top));
+        }
+    }
+    Ok(tiles)
+}
+
+// 16字节的帧头: 开始标记、宽、高、x、y，都是Big-Endian
+fn frame_header(magic: u64, x: u16, y: u16, width: u16, height: u16) -> [u8; 16]{
+    let mut header = [0u8; 16];
+    header[0..8].copy_from_slice(&magic.to_be_bytes());
+    header[8..10].copy_from_slice(&width.to_be_bytes());
+    header[10..12].copy_from_slice(&height.to_be_bytes());
+    header[12..14].copy_from_slice(&x.to_be_bytes());
+    header[14..16].copy_from_slice(&y.to_be_bytes());
+    header
+}
+
+// 一帧完整的传输数据: 帧头 + LZ4压缩的像素 + IMAGE_BB，和draw_rgb_image发送的内容完全一致，用于导出给设备离线回放
+// 像素按照设备的格式编码，和屏幕绘制时相同
+pub fn encode_wire_frame(img:&RgbImage, color_mode: ColorMode, byte_order: ByteOrder) -> Vec<u8>{
+    let compressed = lz4_flex::compress_prepend_size(&encode_pixels(img, color_mode, byte_order));
+    let mut data = Vec::with_capacity(16 + compressed.len() + 8);
+    data.extend_from_slice(&frame_header(IMAGE_AA, 0, 0, img.width() as u16, img.height() as u16));
+    data.extend_from_slice(&compressed);
+    data.extend_from_slice(&IMAGE_BB.to_be_bytes());
+    data
 }
 
-pub fn draw_rgb565(rgb565:&[u8], x: u16, y: u16, width: u16, height: u16, interface:&Interface) -> anyhow::Result<()>{
+pub fn draw_rgb565(rgb565:&[u8], x: u16, y: u16, width: u16, height: u16, interface:&Interface, max_frame_size: usize) -> ScreenResult<()>{
+    send_rgb565(IMAGE_AA, rgb565, x, y, width, height, interface, max_frame_size)
+}
+
+// 发送图像数据，magic为开始标记: IMAGE_AA绘制，BOOT_USB保存为开机画面
+fn send_rgb565(magic: u64, rgb565:&[u8], x: u16, y: u16, width: u16, height: u16, interface:&Interface, max_frame_size: usize) -> ScreenResult<()>{
     // info!("压缩前大小:{}", rgb565.len());
     let rgb565_u8_slice = lz4_flex::compress_prepend_size(rgb565);
     // info!("压缩后大小:{}", rgb565_u8_slice.len());
-    if rgb565_u8_slice.len() >1024*28 {
-        return Err(anyhow!("图像太大了!"));
-    }
-    const IMAGE_AA:u64 = 7596835243154170209;
-    const BOOT_USB:u64 = 7093010483740242786;
-    const IMAGE_BB:u64 = 7596835243154170466;
-
-    let img_begin = &mut [0u8; 16];
-    img_begin[0..8].copy_from_slice(&IMAGE_AA.to_be_bytes());
-    img_begin[8..10].copy_from_slice(&width.to_be_bytes());
-    img_begin[10..12].copy_from_slice(&height.to_be_bytes());
-    img_begin[12..14].copy_from_slice(&x.to_be_bytes());
-    img_begin[14..16].copy_from_slice(&y.to_be_bytes());
+    if rgb565_u8_slice.len() > max_frame_size {
+        return Err(ScreenError::ImageTooLarge(rgb565_u8_slice.len()));
+    }
+    send_compressed(magic, rgb565_u8_slice, x, y, width, height, interface)
+}
+
+// 发送已经压缩的图像数据: 帧头 + 数据 + IMAGE_BB
+fn send_compressed(magic: u64, rgb565_u8_slice: Vec<u8>, x: u16, y: u16, width: u16, height: u16, interface:&Interface) -> ScreenResult<()>{
+    let img_begin = &mut frame_header(magic, x, y, width, height);
     // info!("绘制:{x}x{y} {width}x{height}");
     // block_on(interface.bulk_out(BULK_OUT_EP, img_begin.into())).status?;
     block_on(async {
@@ -230,32 +989,71 @@ pub fn draw_rgb565(rgb565:&[u8], x: u16, y: u16, width: u16, height: u16, interf
     Ok(())
 }
 
+// 发送8字节的命令标记
+fn send_marker(interface:&Interface, marker: u64) -> ScreenResult<()>{
+    block_on(async {
+        async_std::future::timeout(Duration::from_millis(100), interface.bulk_out(BULK_OUT_EP, marker.to_be_bytes().into()))
+            .await
+    })?.status?;
+    Ok(())
+}
+
+// 发送查询命令，读取固件回传的版本和功能
+fn read_firmware_info(interface:&Interface) -> ScreenResult<FirmwareInfo>{
+    send_marker(interface, INFO_USB)?;
+    let result = block_on(async {
+        async_std::future::timeout(Duration::from_millis(100), interface.bulk_in(BULK_IN_EP, RequestBuffer::new(64)))
+            .await
+    })?;
+    result.status?;
+    let msg = String::from_utf8_lossy(&result.data).to_string();
+    FirmwareInfo::parse(&msg)
+        .ok_or(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("固件信息格式错误:{msg}")).into())
+}
+
+// 读取固件回传的渲染耗时，格式为 "{n}ms"
+pub fn read_render_time(interface:&Interface) -> ScreenResult<f32>{
+    let result = block_on(async {
+        async_std::future::timeout(RENDER_TIME_TIMEOUT, interface.bulk_in(BULK_IN_EP, RequestBuffer::new(64)))
+            .await
+    })?;
+    result.status?;
+    let msg = String::from_utf8(result.data)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    let ms = msg.trim_matches(char::from(0)).trim().trim_end_matches("ms").parse::<f32>()
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    Ok(ms)
+}
+
 #[cfg(feature = "usb-serial")]
-pub fn draw_rgb_image_serial(x: u16, y: u16, img:&RgbImage, port:&mut dyn SerialPort) -> anyhow::Result<()>{
+pub fn draw_rgb_image_serial(x: u16, y: u16, img:&RgbImage, port:&mut dyn SerialPort, byte_order: ByteOrder) -> ScreenResult<()>{
     //ST7789驱动使用的是Big-Endian
-    let rgb565 = rgb888_to_rgb565_be(&img, img.width() as usize, img.height() as usize);
+    let rgb565 = rgb888_to_rgb565(&img, img.width() as usize, img.height() as usize, byte_order);
     draw_rgb565_serial(&rgb565, x, y, img.width() as u16, img.height() as u16, port)
 }
 
+#[cfg(feature = "usb-serial")]
+fn send_marker_serial(port:&mut dyn SerialPort, marker: u64) -> ScreenResult<()>{
+    port.write(&marker.to_be_bytes())?;
+    port.flush()?;
+    Ok(())
+}
+
 // 320x240屏幕连接到usb，然后在编辑器中一边添加多张gif，一边保存时，有时候rp2040会死机，同时编辑器也会卡死。
 //第一：首先解决usb死机后，软件卡死问题
 //第二：找到硬件代码死机问题，增加判断逻辑
 
 #[cfg(feature = "usb-serial")]
-pub fn draw_rgb565_serial(rgb565:&[u8], x: u16, y: u16, width: u16, height: u16, port:&mut dyn SerialPort) -> anyhow::Result<()>{
+pub fn draw_rgb565_serial(rgb565:&[u8], x: u16, y: u16, width: u16, height: u16, port:&mut dyn SerialPort) -> ScreenResult<()>{
+    send_rgb565_serial(IMAGE_AA, rgb565, x, y, width, height, port)
+}
+
+#[cfg(feature = "usb-serial")]
+fn send_rgb565_serial(magic: u64, rgb565:&[u8], x: u16, y: u16, width: u16, height: u16, port:&mut dyn SerialPort) -> ScreenResult<()>{
     
     let rgb565_u8_slice = lz4_flex::compress_prepend_size(rgb565);
 
-    const IMAGE_AA:u64 = 7596835243154170209;
-    const BOOT_USB:u64 = 7093010483740242786;
-    const IMAGE_BB:u64 = 7596835243154170466;
-
-    let img_begin = &mut [0u8; 16];
-    img_begin[0..8].copy_from_slice(&IMAGE_AA.to_be_bytes());
-    img_begin[8..10].copy_from_slice(&width.to_be_bytes());
-    img_begin[10..12].copy_from_slice(&height.to_be_bytes());
-    img_begin[12..14].copy_from_slice(&x.to_be_bytes());
-    img_begin[14..16].copy_from_slice(&y.to_be_bytes());
+    let img_begin = &mut frame_header(magic, x, y, width, height);
     // println!("draw:{x}x{y} {width}x{height} len={}", rgb565_u8_slice.len());
     
     port.write(img_begin)?;
@@ -290,4 +1088,119 @@ fn list_acm_devices() -> Vec<String> {
             None
         })
     }).collect()
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_release_disconnected_screen() -> Result<()> {
+    let mut screen = Some(0);
+    //超时等错误保留设备
+    let result = draw_or_release(&mut screen, |_| Err(ScreenError::ImageTooLarge(DEFAULT_MAX_FRAME_SIZE + 1).into()));
+    assert!(result.as_ref().is_err_and(is_image_too_large) && screen.is_some());
+    let result = draw_or_release(&mut screen, |_| Err(ScreenError::Timeout.into()));
+    assert!(result.is_err() && screen.is_some());
+    //设备拔出后移除设备
+    let result = draw_or_release(&mut screen, |_| Err(ScreenError::from(TransferError::Disconnected).into()));
+    assert!(result.is_err() && screen.is_none());
+    //移除后不再绘制
+    draw_or_release(&mut screen, |_| Err(TransferError::Disconnected.into()))?;
+    //重新打开后可以继续绘制
+    screen.replace(1);
+    draw_or_release(&mut screen, |n| { *n += 1; Ok(()) })?;
+    assert_eq!(screen, Some(2));
+    Ok(())
+}
+
+#[test]
+fn test_split_frame() -> Result<()> {
+    let mut info = mock_device_with_size("32x16");
+    //简单的画面不拆分
+    let tiles = split_frame(&benchmark_frame(160, 128, 0, 0), &info)?;
+    assert_eq!(tiles.len(), 1);
+    //噪点画面拆分成多个条带，每个都不超过上限，并且覆盖整个画面
+    info.max_frame_size = 8 * 1024;
+    let tiles = split_frame(&benchmark_frame(160, 128, 3, 0), &info)?;
+    assert!(tiles.len() > 1);
+    assert!(tiles.iter().all(|(_, _, data)| data.len() <= info.max_frame_size));
+    let mut next_y = 0;
+    for (y, height, _) in &tiles{
+        assert_eq!(*y, next_y);
+        next_y += height;
+    }
+    assert_eq!(next_y, 128);
+    //单行也超过上限时返回错误
+    info.max_frame_size = 64;
+    assert!(matches!(split_frame(&benchmark_frame(160, 128, 3, 0), &info), Err(ScreenError::ImageTooLarge(_))));
+    Ok(())
+}
+
+#[test]
+fn test_encode_wire_frame() -> Result<()> {
+    let img = RgbImage::from_pixel(16, 8, Rgb([255, 255, 255]));
+    let payload = |data: &[u8]| lz4_flex::decompress_size_prepended(&data[16..data.len() - 8]);
+    //按照设备的像素格式编码
+    let data = encode_wire_frame(&img, ColorMode::Rgb565, ByteOrder::BigEndian);
+    assert_eq!(&data[0..8], &IMAGE_AA.to_be_bytes());
+    assert_eq!(&data[data.len() - 8..], &IMAGE_BB.to_be_bytes());
+    assert_eq!(payload(&data)?.len(), 16 * 8 * 2);
+    assert_eq!(payload(&encode_wire_frame(&img, ColorMode::Rgb888, ByteOrder::BigEndian))?, img.as_raw().clone());
+    assert_eq!(payload(&encode_wire_frame(&img, ColorMode::Mono1, ByteOrder::BigEndian))?, mono::rgb_to_mono1(&img));
+    Ok(())
+}
+
+#[test]
+fn test_max_frame_size_from_serial() -> Result<()> {
+    assert_eq!(get_max_frame_size_from_serial_number("USBSCR320x240;LE;BUF64"), 64 * 1024);
+    assert_eq!(get_max_frame_size_from_serial_number("USBSCR160x128;buf40"), 40 * 1024);
+    assert_eq!(get_max_frame_size_from_serial_number("USBSCR160x128;LE"), DEFAULT_MAX_FRAME_SIZE);
+    assert_eq!(get_max_frame_size_from_serial_number("USBSCR160x128;BUF0"), DEFAULT_MAX_FRAME_SIZE);
+    Ok(())
+}
+
+#[test]
+fn test_parse_firmware_info() -> Result<()> {
+    let info = FirmwareInfo::parse("v1.2;RGB565;BUF64;bright;PARTIAL\0\0").ok_or(ScreenError::DeviceNotFound)?;
+    assert_eq!(info.version, "v1.2");
+    assert_eq!(info.color_mode, Some(ColorMode::Rgb565));
+    assert_eq!(info.max_frame_size, Some(64 * 1024));
+    assert!(info.brightness && info.partial_update && !info.double_buffer);
+    assert_eq!(info.byte_order, None);
+    let info = FirmwareInfo::parse("v2.0;RGB888;LE;SYNC").ok_or(ScreenError::DeviceNotFound)?;
+    assert_eq!(info.color_mode, Some(ColorMode::Rgb888));
+    assert_eq!(info.byte_order, Some(ByteOrder::LittleEndian));
+    assert!(info.double_buffer);
+    //渲染耗时等其他回传数据
+    assert_eq!(FirmwareInfo::parse("12ms"), None);
+    assert_eq!(FirmwareInfo::parse(""), None);
+    Ok(())
+}
+
+#[test]
+fn test_mock_screen() -> Result<()> {
+    let info = mock_device_with_size("32x16");
+    let mut screen = UsbScreen::open(info)?;
+    let img = RgbImage::from_pixel(8, 8, Rgb([255, 0, 0]));
+    screen.draw_rgb_image(4, 2, &img)?;
+    //比屏幕大的图像不绘制
+    screen.draw_rgb_image(0, 0, &RgbImage::new(64, 64))?;
+    if let UsbScreen::Mock((_, mock)) = screen {
+        assert_eq!(mock.frame_count, 1);
+        assert_eq!(mock.frame.get_pixel(4, 2), &Rgb([255, 0, 0]));
+        assert_eq!(mock.frame.get_pixel(0, 0), &Rgb([0, 0, 0]));
+    } else {
+        panic!("不是模拟设备");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_benchmark_mock_screen() -> Result<()> {
+    let info = mock_device_with_size("32x16");
+    let mut screen = UsbScreen::open(info)?;
+    let results = screen.benchmark(Duration::from_millis(20))?;
+    assert_eq!(results.len(), BENCHMARK_LEVELS.len());
+    assert!(results.iter().all(|r| r.frames > 0 && r.fps > 0.));
+    //纯色压缩后比噪点小
+    assert!(results[0].avg_bytes < results[3].avg_bytes);
+    assert_ne!(benchmark_frame(8, 8, 3, 0), benchmark_frame(8, 8, 3, 1));
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::{Condvar, Mutex}, time::Duration};
 
 use image::{imageops::FilterType, RgbaImage};
 
@@ -136,4 +136,96 @@ IconFile=--
         std::fs::remove_file(format!("{}\\{}.url", path, app_name))?;
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+// 多段数据连在一起的CRC32(IEEE)校验和，用于检查分享的布局包是否完整
+pub fn crc32_chunks<'a>(chunks: impl IntoIterator<Item = &'a [u8]>) -> u32 {
+    let mut crc = !0u32;
+    for byte in chunks.into_iter().flatten() {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+// 图标着色，每个像素的RGB乘以着色颜色，透明度不变
+pub fn tint_image(image: &mut RgbaImage, tint: [u8; 3]) {
+    for pixel in image.pixels_mut() {
+        for (channel, t) in pixel.0.iter_mut().zip(tint) {
+            *channel = (*channel as u16 * t as u16 / 255) as u8;
+        }
+    }
+}
+
+// 抠色时透明边缘的过渡范围，避免抠图边缘出现锯齿
+const CHROMA_KEY_FEATHER: u16 = 16;
+
+// 抠色，与key的差值(各通道差值的最大值)不超过tolerance的像素变为透明，稍大的差值按比例半透明
+pub fn chroma_key_image(image: &mut RgbaImage, key: [u8; 3], tolerance: u8) {
+    for pixel in image.pixels_mut() {
+        let diff = pixel.0[..3]
+            .iter()
+            .zip(key)
+            .map(|(c, k)| c.abs_diff(k))
+            .max()
+            .unwrap_or(0) as u16;
+        let tolerance = tolerance as u16;
+        if diff <= tolerance {
+            pixel.0[3] = 0;
+        } else if diff < tolerance + CHROMA_KEY_FEATHER {
+            pixel.0[3] = (pixel.0[3] as u16 * (diff - tolerance) / CHROMA_KEY_FEATHER) as u8;
+        }
+    }
+}
+
+// 只保存最新一个值的通道，放入时覆盖还没有取走的旧值
+pub struct LatestSlot<T> {
+    state: Mutex<(Option<T>, bool)>,
+    ready: Condvar,
+}
+
+impl<T> Default for LatestSlot<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> LatestSlot<T> {
+    pub fn new() -> Self {
+        Self { state: Mutex::new((None, false)), ready: Condvar::new() }
+    }
+
+    // 返回被覆盖的旧值，关闭之后放入的值直接丢弃
+    pub fn put(&self, value: T) -> Option<T> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.1 {
+            return None;
+        }
+        let old = state.0.replace(value);
+        self.ready.notify_one();
+        old
+    }
+
+    // 等待新的值，超时或者已关闭并且没有剩余的值时返回None
+    pub fn take(&self, timeout: Duration) -> Option<T> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let (mut state, _) = self
+            .ready
+            .wait_timeout_while(state, timeout, |(value, closed)| value.is_none() && !*closed)
+            .unwrap_or_else(|e| e.into_inner());
+        state.0.take()
+    }
+
+    pub fn close(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.1 = true;
+        self.ready.notify_all();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state.lock().map(|s| s.1).unwrap_or(true)
+    }
+}
@@ -1,20 +1,211 @@
 use crate::{
+    bidi,
+    lut::Lut,
     monitor::{self, system_uptime, webcam_frame},
-    nmc::ICONS,
-    utils::{degrees_to_radians, resize_image, test_resize_image},
+    nmc,
+    transition::{self, Animation, Direction},
+    utils::{chroma_key_image, degrees_to_radians, resize_image, test_resize_image, tint_image},
 };
-use anyhow::Result;
-use bincode::{Decode, Encode};
+use anyhow::{anyhow, Result};
+use bincode::{de::{BorrowDecoder, Decoder}, enc::Encoder, error::{DecodeError, EncodeError}, BorrowDecode, Decode, Encode};
 use image::{
     buffer::ConvertBuffer, imageops::{resize, FilterType}, Rgba, RgbaImage
 };
-use offscreen_canvas::{OffscreenCanvas, ResizeOption, RotateOption, WHITE};
+use offscreen_canvas::{Font, OffscreenCanvas, ResizeOption, RotateOption, WHITE};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use core::prelude::v1;
-use std::any::Any;
+use std::{any::Any, collections::HashMap, path::Path, sync::{atomic::{AtomicBool, AtomicU32, Ordering}, RwLock}};
 use uuid::Uuid;
 
 static DEFAULT_IMAGE: &[u8] = include_bytes!("../images/icon_photo.png");
+pub const MIN_FONT_SIZE: f32 = 4.;
+// 自动字号的上限
+const MAX_AUTO_FONT_SIZE: f32 = 200.;
+//天气组件设置多个城市时，每个城市显示的时间(毫秒)
+const WEATHER_CYCLE_INTERVAL: u128 = 5000;
+//占位为转圈时显示的动画帧和每帧时间(毫秒)
+pub const PLACEHOLDER_SPINNER: &str = "@spinner";
+const SPINNER_FRAMES: [&str; 4] = ["|", "/", "-", "\\"];
+const SPINNER_INTERVAL: u128 = 150;
+//进度条峰值保持的时长(毫秒)和之后每秒回落的百分比
+const PEAK_HOLD_MS: u128 = 1000;
+const PEAK_DECAY: f32 = 20.;
+//数值跳动的动画时长(毫秒)，跳动时文字最多变亮的比例和放大的比例
+const PULSE_MS: u128 = 400;
+const PULSE_BRIGHTEN: f32 = 0.6;
+const PULSE_SCALE: f32 = 0.15;
+//没有数据时可选的占位文字，None保持原来的显示
+pub const PLACEHOLDERS: [Option<&str>; 5] = [None, Some(""), Some("--"), Some("..."), Some(PLACEHOLDER_SPINNER)];
+//像素模式：字号取整，缩放时不做插值
+static PIXEL_PERFECT: AtomicBool = AtomicBool::new(false);
+
+pub fn set_pixel_perfect(pixel_perfect: bool) {
+    PIXEL_PERFECT.store(pixel_perfect, Ordering::SeqCst);
+}
+
+pub fn pixel_perfect() -> bool {
+    PIXEL_PERFECT.load(Ordering::SeqCst)
+}
+
+//布局的字号缩放倍数(f32的二进制)，绘制时乘以文字的字号，不修改保存的字号
+static FONT_SCALE: AtomicU32 = AtomicU32::new(0x3f80_0000);
+
+pub fn set_font_scale(scale: f32) {
+    FONT_SCALE.store(scale.to_bits(), Ordering::SeqCst);
+}
+
+pub fn font_scale() -> f32 {
+    f32::from_bits(FONT_SCALE.load(Ordering::SeqCst))
+}
+
+//设计预览：编辑器中没有传感器数据时，组件显示设置的示例值，示例值不保存到布局文件
+static DESIGN_PREVIEW: AtomicBool = AtomicBool::new(false);
+//组件id对应的示例值
+static PREVIEW_VALUES: Lazy<RwLock<HashMap<String, String>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+pub fn set_design_preview(design_preview: bool) {
+    DESIGN_PREVIEW.store(design_preview, Ordering::SeqCst);
+}
+
+// 示例值为空时删除
+pub fn set_preview_value(id: &str, value: &str) {
+    if let Ok(mut values) = PREVIEW_VALUES.write() {
+        if value.is_empty() {
+            values.remove(id);
+        } else {
+            values.insert(id.to_string(), value.to_string());
+        }
+    }
+}
+
+// 编辑器中设置的示例值，不区分是否开启了设计预览
+pub fn preview_value_of(id: &str) -> Option<String> {
+    PREVIEW_VALUES.read().ok()?.get(id).cloned()
+}
+
+// 开启设计预览时组件的示例值
+pub fn preview_value(id: &str) -> Option<String> {
+    if !DESIGN_PREVIEW.load(Ordering::SeqCst) {
+        return None;
+    }
+    preview_value_of(id)
+}
+
+//主题中的颜色角色，组件引用角色时绘制使用主题中的颜色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, Deserialize, Serialize)]
+pub enum Role {
+    Accent,
+    Text,
+    Background,
+    Warning,
+}
+
+impl Role {
+    // 0为不使用角色
+    pub fn from_index(index: i32) -> Option<Self> {
+        match index {
+            1 => Some(Role::Accent),
+            2 => Some(Role::Text),
+            3 => Some(Role::Background),
+            4 => Some(Role::Warning),
+            _ => None,
+        }
+    }
+
+    pub fn index(role: Option<Role>) -> i32 {
+        match role {
+            None => 0,
+            Some(Role::Accent) => 1,
+            Some(Role::Text) => 2,
+            Some(Role::Background) => 3,
+            Some(Role::Warning) => 4,
+        }
+    }
+}
+
+//布局的颜色主题，修改后所有引用角色的组件一起改变颜色
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode, Deserialize, Serialize)]
+pub struct Theme {
+    pub accent: [u8; 3],
+    pub text: [u8; 3],
+    pub background: [u8; 3],
+    pub warning: [u8; 3],
+}
+
+impl Theme {
+    pub const DEFAULT: Theme = Theme {
+        accent: [0, 200, 255],
+        text: [255, 255, 255],
+        background: [0, 0, 0],
+        warning: [255, 60, 60],
+    };
+
+    pub fn color(&self, role: Role) -> [u8; 3] {
+        match role {
+            Role::Accent => self.accent,
+            Role::Text => self.text,
+            Role::Background => self.background,
+            Role::Warning => self.warning,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+//当前布局的主题，渲染前由Render设置
+static THEME: RwLock<Theme> = RwLock::new(Theme::DEFAULT);
+
+pub fn set_theme(theme: Theme) {
+    if let Ok(mut current) = THEME.write() {
+        *current = theme;
+    }
+}
+
+pub fn theme() -> Theme {
+    THEME.read().map(|theme| *theme).unwrap_or_default()
+}
+
+// 有角色时使用主题颜色，透明度保持组件自己的设置
+pub fn themed_color(role: Option<Role>, color: [u8; 4]) -> [u8; 4] {
+    match role {
+        Some(role) => {
+            let [r, g, b] = theme().color(role);
+            [r, g, b, color[3]]
+        }
+        None => color,
+    }
+}
+
+// 像素模式下使用最近邻缩放，避免像素画变模糊
+pub fn resize_filter() -> FilterType {
+    if pixel_perfect() {
+        FilterType::Nearest
+    } else {
+        FilterType::Triangle
+    }
+}
+
+// 像素模式下尺寸取整
+pub fn snap(value: f32) -> f32 {
+    if pixel_perfect() {
+        value.round()
+    } else {
+        value
+    }
+}
+
+// 多个城市用逗号分隔
+pub fn split_cities(text: &str) -> Vec<&str> {
+    text.split(|c| c == ',' || c == '，')
+        .map(|c| c.trim())
+        .filter(|c| !c.is_empty())
+        .collect()
+}
 
 #[derive(Debug, Clone, Default, Encode, Decode, Deserialize, Serialize)]
 pub struct Rect {
@@ -66,6 +257,12 @@ impl Rect {
         self.bottom -= dy;
     }
 
+    // 按比例缩放坐标
+    pub fn scaled(&self, factor: f32) -> Rect {
+        let scale = |v: i32| (v as f32 * factor).round() as i32;
+        Rect::new(scale(self.left), scale(self.top), scale(self.right), scale(self.bottom))
+    }
+
     // 平移矩形
     pub fn offset(&mut self, dx: i32, dy: i32) {
         self.left += dx;
@@ -102,6 +299,12 @@ impl Rect {
         self.bottom = top + height;
     }
 
+    // 改变矩形的宽高，左上角不动，宽高最小为1
+    pub fn resize(&mut self, dw: i32, dh: i32) {
+        self.right = (self.right + dw).max(self.left + 1);
+        self.bottom = (self.bottom + dh).max(self.top + 1);
+    }
+
     // 设置矩形的尺寸（宽高）
     pub fn set_size(&mut self, width: i32, height: i32) {
         let center_x = (self.left + self.right) / 2;
@@ -113,8 +316,227 @@ impl Rect {
     }
 }
 
+// 比较方式
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode, Deserialize, Serialize)]
+pub enum Compare {
+    Greater,
+    Less,
+}
+
+// 显示条件，例如 gpu_load > 0
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Deserialize, Serialize)]
+pub struct Condition {
+    pub type_name: String,
+    pub compare: Compare,
+    pub value: f32,
+    //满足条件时闪烁显示，用于警告图标
+    #[serde(default)]
+    pub blink: bool,
+}
+
+//闪烁的间隔(毫秒)
+const BLINK_INTERVAL: u128 = 500;
+
+impl Condition {
+    // 0总是显示 1大于 2小于
+    pub fn from_index(index: i32, type_name: &str, value: &str, blink: bool) -> Option<Self> {
+        let compare = match index {
+            1 => Compare::Greater,
+            2 => Compare::Less,
+            _ => return None,
+        };
+        Some(Condition {
+            type_name: type_name.trim().to_string(),
+            compare,
+            value: value.parse().unwrap_or(0.),
+            blink,
+        })
+    }
+
+    pub fn index(condition: Option<&Condition>) -> i32 {
+        match condition.map(|c| c.compare) {
+            None => 0,
+            Some(Compare::Greater) => 1,
+            Some(Compare::Less) => 2,
+        }
+    }
+
+    // 读取不到数值时不满足条件
+    pub fn is_met(&self) -> bool {
+        match monitor::metric_value(&self.type_name) {
+            None => false,
+            Some(v) => match self.compare {
+                Compare::Greater => v > self.value,
+                Compare::Less => v < self.value,
+            },
+        }
+    }
+
+    // 满足条件时显示，闪烁时每隔BLINK_INTERVAL隐藏一次
+    pub fn is_shown(&self, tick: u128) -> bool {
+        self.is_met() && (!self.blink || tick / BLINK_INTERVAL % 2 == 0)
+    }
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+fn default_opacity() -> f32 {
+    1.
+}
+
+fn default_value_max() -> f32 {
+    100.
+}
+
+fn default_multiplier() -> f64 {
+    1.
+}
+
+// 自定义数值的显示格式，例如字节数乘以0.000000001、后缀" GB"显示为"1.2 GB"
+#[derive(Clone, Debug, PartialEq, Encode, Decode, Deserialize, Serialize)]
+pub struct ValueFormat {
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f64,
+    //保留的小数位数，None按原样显示
+    #[serde(default)]
+    pub decimals: Option<u8>,
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(default)]
+    pub suffix: String,
+}
+
+impl ValueFormat {
+    // 不是数字时显示原来的文字
+    pub fn apply(&self, raw: &str) -> String {
+        let value = match raw.trim().parse::<f64>() {
+            Ok(v) if v.is_finite() => v * self.multiplier,
+            _ => return raw.to_string(),
+        };
+        match self.decimals {
+            Some(decimals) => format!("{}{:.*}{}", self.prefix, decimals as usize, value, self.suffix),
+            None => format!("{}{}{}", self.prefix, value, self.suffix),
+        }
+    }
+}
+
+// 按色相插值，绿到红会经过黄色而不是暗褐色
+pub fn gradient_color(low: [u8; 4], high: [u8; 4], t: f32) -> [u8; 4] {
+    let t = t.clamp(0., 1.);
+    let (h1, s1, v1) = rgb_to_hsv(low);
+    let (h2, s2, v2) = rgb_to_hsv(high);
+    let mut dh = h2 - h1;
+    if dh > 180. {
+        dh -= 360.;
+    } else if dh < -180. {
+        dh += 360.;
+    }
+    let h = (h1 + dh * t).rem_euclid(360.);
+    let [r, g, b] = hsv_to_rgb(h, s1 + (s2 - s1) * t, v1 + (v2 - v1) * t);
+    let a = low[3] as f32 + (high[3] as f32 - low[3] as f32) * t;
+    [r, g, b, a.round() as u8]
+}
+
+fn rgb_to_hsv(c: [u8; 4]) -> (f32, f32, f32) {
+    let (r, g, b) = (c[0] as f32 / 255., c[1] as f32 / 255., c[2] as f32 / 255.);
+    let max = r.max(g).max(b);
+    let delta = max - r.min(g).min(b);
+    let h = if delta == 0. {
+        0.
+    } else if max == r {
+        60. * ((g - b) / delta).rem_euclid(6.)
+    } else if max == g {
+        60. * ((b - r) / delta + 2.)
+    } else {
+        60. * ((r - g) / delta + 4.)
+    };
+    let s = if max == 0. { 0. } else { delta / max };
+    (h, s, max)
+}
+
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [u8; 3] {
+    let c = v * s;
+    let x = c * (1. - ((h / 60.) % 2. - 1.).abs());
+    let m = v - c;
+    let (r, g, b) = match (h / 60.) as i32 {
+        0 => (c, x, 0.),
+        1 => (x, c, 0.),
+        2 => (0., c, x),
+        3 => (0., x, c),
+        4 => (x, 0., c),
+        _ => (c, 0., x),
+    };
+    [((r + m) * 255.).round() as u8, ((g + m) * 255.).round() as u8, ((b + m) * 255.).round() as u8]
+}
+
+// 以组件中心为圆心反向旋转坐标，得到组件未旋转时对应的点
+fn unrotate_point(position: &Rect, rotation: f32, x: i32, y: i32) -> (i32, i32) {
+    if rotation % 360. == 0. {
+        return (x, y);
+    }
+    let (cx, cy) = position.center();
+    let (sin, cos) = degrees_to_radians(-rotation).sin_cos();
+    let (dx, dy) = ((x - cx) as f32, (y - cy) as f32);
+    (cx + (dx * cos - dy * sin).round() as i32, cy + (dx * sin + dy * cos).round() as i32)
+}
+
 pub trait Widget {
-    fn draw(&mut self, context: &mut OffscreenCanvas);
+    //tick为本次渲染的时间(毫秒)，同一帧中所有组件的动画使用相同的时间
+    //滚动、闪烁、轮播和GIF都按实际经过的时间推进，与帧率无关
+    fn draw(&mut self, context: &mut OffscreenCanvas, tick: u128);
+    fn visible(&self) -> bool;
+    fn set_visible(&mut self, visible: bool);
+    fn show_when(&self) -> Option<&Condition>;
+    fn set_show_when(&mut self, condition: Option<Condition>);
+    //所属的组合，同一组合的组件一起移动和缩放
+    fn group(&self) -> Option<&str> {
+        None
+    }
+    fn set_group(&mut self, _group: Option<String>) {}
+    //锁定位置后不能拖动和用方向键移动，仍然可以选中
+    fn locked(&self) -> bool {
+        false
+    }
+    fn set_locked(&mut self, _locked: bool) {}
+    //整个组件的不透明度(0~1)，与颜色的透明度分开
+    fn opacity(&self) -> f32 {
+        1.
+    }
+    fn set_opacity(&mut self, _opacity: f32) {}
+    //最短重绘间隔(毫秒)，间隔内使用上次绘制的图像，0每帧重绘
+    fn min_refresh_ms(&self) -> u32 {
+        0
+    }
+    fn set_min_refresh_ms(&mut self, _ms: u32) {}
+    //背景图层总是最先绘制，编辑器中默认锁定
+    fn is_background(&self) -> bool {
+        false
+    }
+    //绘制层级，数值大的绘制在上层，相同层级按数组顺序绘制
+    fn z_index(&self) -> i32 {
+        0
+    }
+    fn set_z_index(&mut self, _z_index: i32) {}
+    //颜色引用的主题角色，None使用组件自己的颜色
+    fn color_role(&self) -> Option<Role> {
+        None
+    }
+    fn set_color_role(&mut self, _role: Option<Role>) {}
+    //按比例缩放位置和大小，超采样绘制时使用
+    fn scale(&mut self, factor: f32) {
+        let pos = self.position_mut();
+        *pos = pos.scaled(factor);
+    }
+    //隐藏或者不满足显示条件时不绘制
+    fn is_visible(&self, tick: u128) -> bool {
+        self.visible() && self.show_when().map(|c| c.is_shown(tick)).unwrap_or(true)
+    }
+    //编辑器点击选中时的命中检测，默认按矩形区域
+    fn contains_point(&self, x: i32, y: i32) -> bool {
+        self.position().contain(x, y)
+    }
     fn id(&self) -> &str;
     fn index(&self) -> usize;
     fn set_index(&mut self, idx: usize);
@@ -125,7 +547,7 @@ pub trait Widget {
     fn type_name(&self) -> &str;
     fn as_any_mut(&mut self) -> &mut dyn Any;
     fn is_text(&self) -> bool{
-        self.type_name() != "images" && self.type_name() != "webcam"
+        self.type_name() != "images" && self.type_name() != "webcam" && self.type_name() != "desktop"
     }
     fn is_image(&self) -> bool{
         self.type_name() == "images"
@@ -133,11 +555,16 @@ pub trait Widget {
     fn is_webcam(&self) -> bool{
         self.type_name() == "webcam"
     }
+    fn is_desktop(&self) -> bool{
+        self.type_name() == "desktop"
+    }
     fn get_label(&self) -> &str{
         if self.is_image() {
             "图像"
         }else if self.is_webcam() {
             "摄像头"
+        }else if self.is_desktop() {
+            "桌面"
         } else {
             "文本"
         }
@@ -159,9 +586,138 @@ pub struct TextWidget {
     pub num_widget: usize,
     pub tag1: String,
     pub tag2: String,
+    //根据position的宽高自动选择字号
+    #[serde(default)]
+    pub auto_size: bool,
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+    pub show_when: Option<Condition>,
+    //横向滚动速度(像素/秒)，0不滚动，滚动时position为固定的显示区域
+    #[serde(default)]
+    pub scroll_speed: f32,
+    #[serde(skip)]
+    scroll_offset: f32,
+    #[serde(skip)]
+    scroll_time: u128,
+    //滚动停顿结束的时间
+    #[serde(skip)]
+    scroll_pause_until: u128,
+    //滚动文字绘制用的画布，显示区域大小不变时重复使用
+    #[serde(skip)]
+    scroll_canvas: CanvasCache,
+    //旋转绘制用的画布，和屏幕一样大
+    #[serde(skip)]
+    rotate_canvas: CanvasCache,
+    //圆角进度条两端使用的圆形图像(直径, 颜色, 图像)，底色和填充各一个
+    #[serde(skip)]
+    bar_caps: RuntimeCache<Vec<(i32, [u8; 4], RgbaImage)>>,
+    //滚动方向、缓动和停顿，多城市天气使用停顿作为每个城市的显示时长
+    #[serde(default)]
+    pub animation: Animation,
+    //进度条渐变色(低值颜色, 高值颜色)，None使用color纯色
+    #[serde(default)]
+    pub bar_gradient: Option<([u8; 4], [u8; 4])>,
+    //进度条、圆环未填充部分的颜色，None不绘制
+    #[serde(default)]
+    pub track_color: Option<[u8; 4]>,
+    //竖向进度条从下往上填充，字号为宽度，tag2为高度
+    #[serde(default)]
+    pub bar_vertical: bool,
+    //进度条两端画成半圆
+    #[serde(default)]
+    pub bar_rounded: bool,
+    //进度条和圆环的数值范围，数值按范围换算为百分比，默认0~100
+    #[serde(default)]
+    pub value_min: f32,
+    #[serde(default = "default_value_max")]
+    pub value_max: f32,
+    //进度条显示峰值标记，峰值保持一段时间后慢慢回落
+    #[serde(default)]
+    pub bar_peak_hold: bool,
+    //峰值百分比和最后一次达到峰值的时间
+    #[serde(skip)]
+    bar_peak: f32,
+    #[serde(skip)]
+    bar_peak_time: u128,
+    //数值变化超过该幅度时文字短暂变亮放大，0关闭
+    #[serde(default)]
+    pub pulse_delta: f32,
+    //自定义数值的显示格式，None显示原始值
+    #[serde(default)]
+    pub value_format: Option<ValueFormat>,
+    //上一次的数值和最后一次跳动的时间
+    #[serde(skip)]
+    pulse_value: Option<f32>,
+    #[serde(skip)]
+    pulse_time: u128,
+    //本次绘制的渲染时间(毫秒)，由draw传入，动画和跳动按这个时间计算
+    #[serde(skip)]
+    tick: u128,
+    //文字背景色(支持透明度)和背景的内边距
+    #[serde(default)]
+    pub background: Option<[u8; 4]>,
+    #[serde(default)]
+    pub padding: i32,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+    //旋转角度，以组件中心为圆心
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default)]
+    pub locked: bool,
+    //字间距(像素)和行高(字号的倍数，0为字体默认行高)
+    #[serde(default)]
+    pub letter_spacing: f32,
+    #[serde(default)]
+    pub line_height: f32,
+    //没有数据时显示的占位文字，None不替换
+    #[serde(default)]
+    pub placeholder: Option<String>,
+    //没有数据时保留最后一次的值，从未取到数据时才显示占位
+    #[serde(default)]
+    pub keep_last: bool,
+    #[serde(skip)]
+    has_value: bool,
+    #[serde(default)]
+    pub min_refresh_ms: u32,
+    //天气图标的着色颜色，None按原图绘制
+    #[serde(default)]
+    pub tint: Option<[u8; 3]>,
+    #[serde(default)]
+    pub z_index: i32,
+    //颜色使用主题中的角色
+    #[serde(default)]
+    pub color_role: Option<Role>,
+    //显卡的标识，None按照同类组件的顺序选择显卡
+    #[serde(default)]
+    pub gpu: Option<String>,
 }
 
 impl TextWidget {
+    pub fn from_v10(txt: v10::TextWidget) -> Self {
+        Self { id: txt.id, text: txt.text, prefix: txt.prefix, color: txt.color, font_size: txt.font_size, position: txt.position, type_name: txt.type_name,
+            num_widget_index: txt.num_widget_index, num_widget: txt.num_widget, tag1: txt.tag1, tag2: txt.tag2, auto_size: false, visible: true, show_when: None,
+            scroll_speed: 0., scroll_offset: 0., scroll_time: 0, scroll_pause_until: 0, scroll_canvas: CanvasCache::default(), rotate_canvas: CanvasCache::default(), bar_caps: RuntimeCache::default(), animation: Animation::default(), bar_gradient: None, track_color: None, bar_vertical: false, bar_rounded: false, value_min: 0., value_max: 100., bar_peak_hold: false, bar_peak: 0., bar_peak_time: 0, pulse_delta: 0., pulse_value: None, pulse_time: 0, tick: 0, value_format: None, background: None, padding: 0, group: None, opacity: 1., rotation: 0., locked: false, letter_spacing: 0., line_height: 0., placeholder: None, keep_last: false, has_value: false, min_refresh_ms: 0, tint: None, z_index: 0, color_role: None, gpu: None }
+    }
+
+    // 天气组件的城市列表
+    pub fn weather_cities(&self) -> Vec<&str> {
+        split_cities(&self.tag2)
+    }
+
+    // 当前显示的城市，多个城市时轮流显示
+    fn weather_city(&self) -> String {
+        let cities = self.weather_cities();
+        if cities.is_empty() {
+            return String::new();
+        }
+        let interval = self.animation.pause_ms(WEATHER_CYCLE_INTERVAL);
+        let idx = (self.tick / interval) as usize % cities.len();
+        cities[idx].to_string()
+    }
+
     #[allow(unused)]
     pub fn new(x: i32, y: i32, type_name: &str, type_label: &str) -> Self {
         Self::new_with_text(x, y, type_name, type_label, "文本")
@@ -184,14 +740,541 @@ impl TextWidget {
             num_widget: 1,
             tag1: "".to_string(),
             tag2: "".to_string(),
+            auto_size: false,
+            visible: true,
+            show_when: None,
+            scroll_speed: 0.,
+            scroll_offset: 0.,
+            scroll_time: 0,
+            scroll_pause_until: 0,
+            scroll_canvas: CanvasCache::default(),
+            rotate_canvas: CanvasCache::default(),
+            bar_caps: RuntimeCache::default(),
+            animation: Animation::default(),
+            bar_gradient: None,
+            track_color: None,
+            bar_vertical: false,
+            bar_rounded: false,
+            value_min: 0.,
+            value_max: 100.,
+            bar_peak_hold: false,
+            bar_peak: 0.,
+            bar_peak_time: 0,
+            pulse_delta: 0.,
+            pulse_value: None,
+            pulse_time: 0,
+            tick: 0,
+            value_format: None,
+            background: None,
+            padding: 0,
+            group: None,
+            opacity: 1.,
+            rotation: 0.,
+            locked: false,
+            letter_spacing: 0.,
+            line_height: 0.,
+            placeholder: None,
+            keep_last: false,
+            has_value: false,
+            min_refresh_ms: 0,
+            tint: None,
+            z_index: 0,
+            color_role: None,
+            gpu: None,
+        }
+    }
+
+    // 选择了显卡时按标识查找索引，显卡不存在时没有数据
+    fn gpu_index(&self) -> usize {
+        match self.gpu.as_deref() {
+            Some(id) => monitor::gpu_index(id).unwrap_or(usize::MAX),
+            None => self.num_widget_index,
+        }
+    }
+
+    // 没有数据时显示占位文字
+    fn show_placeholder(&mut self) {
+        let placeholder = match self.placeholder.as_deref() {
+            None => return,
+            Some(_) if self.keep_last && self.has_value => return,
+            Some(p) => p,
+        };
+        self.text = if placeholder == PLACEHOLDER_SPINNER {
+            let idx = (self.tick / SPINNER_INTERVAL) as usize % SPINNER_FRAMES.len();
+            SPINNER_FRAMES[idx].to_string()
+        } else {
+            placeholder.to_string()
+        };
+    }
+
+    // 每一行的文字和相对于顶部的偏移，以及宽高
+    fn layout_lines<'a>(&self, context: &OffscreenCanvas, text: &'a str, font_size: f32) -> Vec<(&'a str, i32, i32, i32)> {
+        let font_size = snap(font_size);
+        let mut lines = vec![];
+        let mut y = 0;
+        for line in text.split('\n') {
+            let rect = context.measure_text(line, font_size);
+            let mut width = rect.width();
+            if self.letter_spacing != 0. {
+                let count = line.chars().count() as i32;
+                width = line.chars().map(|c| context.measure_text(&c.to_string(), font_size).width()).sum::<i32>()
+                    + self.letter_spacing as i32 * (count - 1).max(0);
+            }
+            let height = rect.height().max(font_size as i32);
+            lines.push((line, y, width, height));
+            y += if self.line_height > 0. { (font_size * self.line_height) as i32 } else { height };
+        }
+        lines
+    }
+
+    // 前缀和文字，从右到左的文字转换为绘制顺序
+    fn display_text(&self) -> String {
+        bidi::visual_text(&format!("{}{}", self.prefix, self.text))
+    }
+
+    // 按字间距和行高测量文字大小
+    fn measure_lines(&self, context: &OffscreenCanvas, text: &str, font_size: f32) -> (i32, i32) {
+        if self.letter_spacing == 0. && !text.contains('\n') {
+            let rect = context.measure_text(text, snap(font_size));
+            return (rect.width(), rect.height());
+        }
+        self.layout_lines(context, text, font_size)
+            .iter()
+            .fold((0, 0), |(w, h), (_, y, width, height)| (w.max(*width), h.max(y + height)))
+    }
+
+    // 有字间距时逐字绘制，有换行时逐行绘制
+    fn draw_lines(&self, context: &mut OffscreenCanvas, text: &str, font_size: f32, x: i32, y: i32) {
+        let color = self.text_color();
+        let font_size = snap(font_size);
+        if self.letter_spacing == 0. && !text.contains('\n') {
+            context.draw_text(text, color, font_size, x, y);
+            return;
+        }
+        //从右到左的文字每行右对齐
+        let rtl = bidi::is_rtl(&self.text);
+        let lines = self.layout_lines(context, text, font_size);
+        let block_width = lines.iter().map(|(_, _, width, _)| *width).max().unwrap_or(0);
+        for (line, offset, width, _) in lines {
+            let x = if rtl { x + block_width - width } else { x };
+            if self.letter_spacing == 0. {
+                context.draw_text(line, color, font_size, x, y + offset);
+                continue;
+            }
+            let mut char_x = x;
+            for c in line.chars() {
+                let c = c.to_string();
+                context.draw_text(&c, color, font_size, char_x, y + offset);
+                char_x += context.measure_text(&c, font_size).width() + self.letter_spacing as i32;
+            }
+        }
+    }
+
+    // 检测的主机离线时显示红色，行情按涨跌显示绿色或红色，数值跳动时变亮
+    fn text_color(&self) -> Rgba<u8> {
+        let quote = match self.type_name.as_str() {
+            "ticker" => monitor::ticker_quote(self.tag1.trim(), self.tag2.trim()),
+            _ => None,
+        };
+        let color = if self.type_name == "ping" && monitor::ping_latency(self.tag1.trim()) == Some(None) {
+            [255, 60, 60, self.color[3]]
+        } else if let Some(quote) = quote {
+            quote.color(self.color[3])
+        } else {
+            themed_color(self.color_role, self.color)
+        };
+        let t = self.pulse() * PULSE_BRIGHTEN;
+        let [r, g, b, a] = color;
+        let brighten = |c: u8| (c as f32 + (255. - c as f32) * t) as u8;
+        Rgba([brighten(r), brighten(g), brighten(b), a])
+    }
+
+    // 文字开头的数字，忽略后面的单位(%、°C、RPM等)
+    fn leading_number(&self) -> Option<f32> {
+        let text = self.text.trim();
+        let end = text
+            .char_indices()
+            .find(|(i, c)| !(c.is_ascii_digit() || *c == '.' || (*i == 0 && *c == '-')))
+            .map(|(i, _)| i)
+            .unwrap_or(text.len());
+        text[..end].parse::<f32>().ok()
+    }
+
+    // 数值变化超过pulse_delta时开始跳动
+    fn update_pulse(&mut self) {
+        if self.pulse_delta <= 0. {
+            self.pulse_value = None;
+            return;
+        }
+        let Some(value) = self.leading_number() else {
+            return;
+        };
+        if self.pulse_value.is_some_and(|last| (value - last).abs() >= self.pulse_delta) {
+            self.pulse_time = self.tick;
+        }
+        self.pulse_value = Some(value);
+    }
+
+    // 跳动的强度0~1，先增强再回落
+    fn pulse(&self) -> f32 {
+        if self.pulse_delta <= 0. || self.pulse_time == 0 {
+            return 0.;
+        }
+        let elapsed = self.tick.saturating_sub(self.pulse_time);
+        if elapsed >= PULSE_MS {
+            return 0.;
+        }
+        (elapsed as f32 / PULSE_MS as f32 * std::f32::consts::PI).sin()
+    }
+
+    // 在文字区域外扩padding绘制背景，用图像叠加以支持半透明
+    fn draw_background(&self, context: &mut OffscreenCanvas, width: i32, height: i32) {
+        let background = match self.background {
+            None => return,
+            Some(c) => c,
+        };
+        //从文件加载的padding可能很大，不超过画布大小
+        let padding = self.padding.clamp(0, context.width().max(context.height()) as i32);
+        let (width, height) = (width + padding * 2, height + padding * 2);
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        let image = RgbaImage::from_pixel(width as u32, height as u32, Rgba(background));
+        context.draw_image_at(&image, self.position.left - padding, self.position.top - padding, None, None);
+    }
+
+    // 进度条和圆环显示的百分比，时间按秒针走一圈
+    // 按数值范围换算为百分比，读取文字开头的数字，忽略后面的单位(%、°C、RPM等)
+    fn percent_value(&self) -> f32 {
+        if self.type_name == "time" {
+            return monitor::minute_progress() * 100.;
+        }
+        let value = self.leading_number().unwrap_or(0.);
+        if self.value_max > self.value_min {
+            (value - self.value_min) / (self.value_max - self.value_min) * 100.
+        } else {
+            value
+        }
+    }
+
+    fn fill_color(&self, percent: f32) -> [u8; 4] {
+        match self.bar_gradient {
+            Some((low, high)) => gradient_color(low, high, percent / 100.),
+            None => themed_color(self.color_role, self.color),
+        }
+    }
+
+    // 进度条(tag1为1，或者6带标注)，tag2为宽度
+    pub fn is_bar(&self) -> bool {
+        self.type_name != "weather" && self.type_name != "uptime" && (self.tag1 == "1" || self.tag1 == "6")
+    }
+
+    // 圆环(tag1为5)，tag2为直径
+    pub fn is_ring(&self) -> bool {
+        self.type_name != "weather" && self.type_name != "uptime" && self.tag1 == "5"
+    }
+
+    // 圆环的直径，tag2过大时不超过屏幕大小，避免分配过大的图像
+    pub fn ring_size(&self, max_size: i32) -> i32 {
+        self.tag2
+            .parse::<i32>()
+            .unwrap_or(self.font_size as i32 * 5)
+            .min(max_size)
+            .max(4)
+    }
+
+    // 进度条的宽高，横向为tag2 x 字号，竖向为字号 x tag2
+    // tag2和字号过大时不超过屏幕大小
+    pub fn bar_size(&self, max_size: i32) -> (i32, i32) {
+        let length = self.tag2.parse::<i32>().unwrap_or(self.font_size as i32 * 5).min(max_size);
+        let thickness = (self.font_size as i32).min(max_size);
+        if self.bar_vertical {
+            (thickness, length)
+        } else {
+            (length, thickness)
+        }
+    }
+
+    // 绘制进度条，返回进度条宽高
+    fn draw_bar(&mut self, context: &mut OffscreenCanvas) -> (i32, i32) {
+        let percent = self.percent_value().clamp(0., 100.);
+        //字体作为高度
+        if self.font_size <= 2. {
+            self.font_size = 2.;
+        }
+        let (width, height) = self.bar_size(context.width().max(context.height()) as i32);
+        let (left, top) = (self.position.left, self.position.top);
+        if let Some(track_color) = self.track_color {
+            self.fill_bar_rect(context, left, top, width, height, track_color);
+        }
+        let color = self.fill_color(percent);
+        if self.bar_vertical {
+            let rect_height = ((height as f32 * (percent / 100.)) as i32).max(1);
+            self.fill_bar_rect(context, left, top + height - rect_height, width, rect_height, color);
+        } else {
+            let rect_width = ((width as f32 * (percent / 100.)) as i32).max(1);
+            self.fill_bar_rect(context, left, top, rect_width, height, color);
+        }
+        if self.bar_peak_hold {
+            self.draw_bar_peak(context, percent, (width, height), color);
+        }
+        (width, height)
+    }
+
+    // 峰值标记: 超过峰值时更新，保持PEAK_HOLD_MS后按PEAK_DECAY回落，用和填充色对比的颜色画一条线
+    fn draw_bar_peak(&mut self, context: &mut OffscreenCanvas, percent: f32, (width, height): (i32, i32), color: [u8; 4]) {
+        let now = self.tick;
+        let held = now.saturating_sub(self.bar_peak_time);
+        if percent >= self.bar_peak {
+            self.bar_peak = percent;
+            self.bar_peak_time = now;
+        } else if held > PEAK_HOLD_MS {
+            let decay = (held - PEAK_HOLD_MS) as f32 / 1000. * PEAK_DECAY;
+            self.bar_peak = (self.bar_peak - decay).max(percent);
+            //从当前位置继续回落
+            self.bar_peak_time = now - PEAK_HOLD_MS;
+        }
+        let luma = color[0] as f32 * 0.299 + color[1] as f32 * 0.587 + color[2] as f32 * 0.114;
+        let marker = if luma > 160. { Rgba([0, 0, 0, 255]) } else { WHITE };
+        let (left, top) = (self.position.left, self.position.top);
+        let rect = if self.bar_vertical {
+            let y = top + height - (height as f32 * self.bar_peak / 100.) as i32;
+            offscreen_canvas::Rect::from(left, y.clamp(top, top + height - 1), width, 1)
+        } else {
+            let x = left + (width as f32 * self.bar_peak / 100.) as i32;
+            offscreen_canvas::Rect::from(x.clamp(left, left + width - 1), top, 1, height)
+        };
+        context.fill_rect(rect, marker);
+    }
+
+    // 圆角时半径为短边的一半，两端从缓存的圆形图像中各截取一半，中间直接填充
+    fn fill_bar_rect(&mut self, context: &mut OffscreenCanvas, x: i32, y: i32, width: i32, height: i32, color: [u8; 4]) {
+        if !self.bar_rounded || width <= 0 || height <= 0 {
+            context.fill_rect(offscreen_canvas::Rect::from(x, y, width, height), Rgba(color));
+            return;
+        }
+        let diameter = width.min(height);
+        let radius = diameter / 2;
+        if width.max(height) > radius * 2 {
+            //两端之间的部分
+            let middle = if width >= height {
+                offscreen_canvas::Rect::from(x + radius, y, width - radius * 2, height)
+            } else {
+                offscreen_canvas::Rect::from(x, y + radius, width, height - radius * 2)
+            };
+            context.fill_rect(middle, Rgba(color));
+        }
+        if radius == 0 {
+            return;
+        }
+        let circle = self.bar_cap(diameter, color);
+        let (head_src, tail_src, tail_dst) = if width >= height {
+            (
+                offscreen_canvas::Rect::from(0, 0, radius, diameter),
+                offscreen_canvas::Rect::from(diameter - radius, 0, radius, diameter),
+                offscreen_canvas::Rect::from(x + width - radius, y, radius, diameter),
+            )
+        } else {
+            (
+                offscreen_canvas::Rect::from(0, 0, diameter, radius),
+                offscreen_canvas::Rect::from(0, diameter - radius, diameter, radius),
+                offscreen_canvas::Rect::from(x, y + height - radius, diameter, radius),
+            )
+        };
+        let head_dst = offscreen_canvas::Rect::from(x, y, head_src.width(), head_src.height());
+        context.draw_image_with_src_and_dst(circle, &head_src, &head_dst, FilterType::Nearest);
+        context.draw_image_with_src_and_dst(circle, &tail_src, &tail_dst, FilterType::Nearest);
+    }
+
+    // 直径和颜色相同时重复使用圆形图像，边缘抗锯齿
+    fn bar_cap(&mut self, diameter: i32, color: [u8; 4]) -> &RgbaImage {
+        let caps = self.bar_caps.0.get_or_insert_with(Vec::new);
+        let index = match caps.iter().position(|(d, c, _)| *d == diameter && *c == color) {
+            Some(index) => index,
+            None => {
+                let radius = diameter as f32 / 2.;
+                let image = RgbaImage::from_fn(diameter as u32, diameter as u32, |px, py| {
+                    let (dx, dy) = (px as f32 + 0.5 - radius, py as f32 + 0.5 - radius);
+                    let coverage = (radius - (dx * dx + dy * dy).sqrt() + 0.5).clamp(0., 1.);
+                    Rgba([color[0], color[1], color[2], (color[3] as f32 * coverage) as u8])
+                });
+                //只保留底色和填充两个
+                if caps.len() >= 2 {
+                    caps.remove(0);
+                }
+                caps.push((diameter, color, image));
+                caps.len() - 1
+            }
+        };
+        &caps[index].2
+    }
+
+    // 进度条中间显示的文字，内存和显存显示已用/总量，其他显示百分比
+    fn bar_label(&self) -> String {
+        let label = match self.type_name.as_str() {
+            "memory_percent" => monitor::memory_info(),
+            "swap_percent" => monitor::swap_info(),
+            "gpu_memory_load" => monitor::gpu_memory_info(self.gpu_index()),
+            _ => None,
+        };
+        format!("{}{}", self.prefix, label.unwrap_or(self.text.clone()))
+    }
+
+    // 在进度条中间绘制带阴影的白色文字，在填充和底色上都能看清
+    fn draw_bar_label(&self, context: &mut OffscreenCanvas, (width, height): (i32, i32)) {
+        let label = self.bar_label();
+        let font_size = (width.min(height) as f32 * 0.8).max(MIN_FONT_SIZE);
+        let text_rect = context.measure_text(&label, font_size);
+        let x = self.position.left + (width - text_rect.width()) / 2;
+        let y = self.position.top + (height - text_rect.height()) / 2;
+        context.draw_text(&label, Rgba([0, 0, 0, 255]), font_size, x + 1, y + 1);
+        context.draw_text(&label, WHITE, font_size, x, y);
+    }
+
+    // 时间显示在圆环中间
+    fn draw_ring_label(&self, context: &mut OffscreenCanvas, size: i32) {
+        let font_size = (size as f32 / 5.).max(MIN_FONT_SIZE);
+        let text_rect = context.measure_text(&self.text, font_size);
+        let x = self.position.left + (size - text_rect.width()) / 2;
+        let y = self.position.top + (size - text_rect.height()) / 2;
+        context.draw_text(&self.text, self.text_color(), font_size, x, y);
+    }
+
+    // 圆环从顶部开始顺时针填充，tag2为直径，字号为圆环粗细
+    fn draw_ring(&mut self, context: &mut OffscreenCanvas) {
+        let percent = self.percent_value().clamp(0., 100.);
+        let size = self.ring_size(context.width().max(context.height()) as i32);
+        if self.font_size <= 1. {
+            self.font_size = 1.;
+        }
+        let color = self.fill_color(percent);
+        let outer = size as f32 / 2.;
+        let inner = (outer - self.font_size).max(0.);
+        let end_angle = percent / 100. * std::f32::consts::TAU;
+        let mut ring = RgbaImage::new(size as u32, size as u32);
+        for (x, y, pixel) in ring.enumerate_pixels_mut() {
+            let (dx, dy) = (x as f32 + 0.5 - outer, y as f32 + 0.5 - outer);
+            let distance = (dx * dx + dy * dy).sqrt();
+            //边缘抗锯齿
+            let coverage = (outer - distance + 0.5).min(distance - inner + 0.5).clamp(0., 1.);
+            if coverage <= 0. {
+                continue;
+            }
+            let angle = dx.atan2(-dy).rem_euclid(std::f32::consts::TAU);
+            let c = if percent > 0. && angle <= end_angle {
+                color
+            } else {
+                match self.track_color {
+                    Some(c) => c,
+                    None => continue,
+                }
+            };
+            *pixel = Rgba([c[0], c[1], c[2], (c[3] as f32 * coverage) as u8]);
+        }
+        context.draw_image_at(&ring, self.position.left, self.position.top, None, None);
+        if self.type_name == "time" {
+            self.draw_ring_label(context, size);
+        }
+    }
+
+    // 先在透明的临时画布上水平绘制，再把组件区域(包含背景的内边距)以组件中心为圆心旋转绘制到画面上
+    fn draw_rotated(&mut self, context: &mut OffscreenCanvas) {
+        let (width, height) = (context.width() as i32, context.height() as i32);
+        let mut canvas = self.rotate_canvas.take(width as u32, height as u32, context.font());
+        let rotation = self.rotation;
+        self.rotation = 0.;
+        self.draw(&mut canvas, self.tick);
+        self.rotation = rotation;
+        let padding = self.padding.clamp(0, width.max(height));
+        let rect = offscreen_canvas::Rect::new(
+            (self.position.left - padding).max(0),
+            (self.position.top - padding).max(0),
+            (self.position.right + padding).min(width),
+            (self.position.bottom + padding).min(height),
+        );
+        if rect.width() > 0 && rect.height() > 0 {
+            //旋转中心相对于绘制区域的左上角
+            let (x, y) = self.position.center();
+            let option = RotateOption::from(((x - rect.left) as f32, (y - rect.top) as f32), degrees_to_radians(rotation));
+            context.draw_image_with_src_and_dst_and_rotation(canvas.image_data(), &rect, &rect, option);
+        }
+        self.rotate_canvas.put(canvas);
+    }
+
+    // 文字按方向从一侧进入，完全移出另一侧后重新开始，只绘制position内的部分
+    // 文字贴齐起始边时停顿，进入和离开两段分别应用缓动
+    fn draw_scrolling(&mut self, context: &mut OffscreenCanvas, text: &str) {
+        let now = self.tick;
+        let (width, height) = (self.position.width(), self.position.height());
+        let font_size = self.scaled_font_size();
+        let (text_width, text_height) = self.measure_lines(context, text, font_size);
+        let direction = self.animation.direction;
+        let span = if direction.is_vertical() { height } else { width } as f32;
+        let total = span + if direction.is_vertical() { text_height } else { text_width } as f32;
+
+        let since = self.scroll_time.max(self.scroll_pause_until);
+        if self.scroll_time > 0 && now > since {
+            let last = self.scroll_offset;
+            self.scroll_offset += self.scroll_speed * (now - since) as f32 / 1000.;
+            if self.animation.pause > 0. && last < span && self.scroll_offset >= span {
+                self.scroll_offset = span;
+                self.scroll_pause_until = now + self.animation.pause_ms(0);
+            }
         }
+        self.scroll_time = now;
+        if self.scroll_offset >= total {
+            self.scroll_offset %= total;
+        }
+
+        let easing = self.animation.easing;
+        let offset = if self.scroll_offset < span {
+            span * easing.apply(self.scroll_offset / span)
+        } else {
+            span + (total - span) * easing.apply((self.scroll_offset - span) / (total - span).max(1.))
+        } as i32;
+        let (x, y) = match direction {
+            Direction::Left => (width - offset, 0),
+            Direction::Right => (offset - text_width, 0),
+            Direction::Up => (0, height - offset),
+            Direction::Down => (0, offset - text_height),
+        };
+        let mut canvas = self.scroll_canvas.take(width.max(1) as u32, height.max(1) as u32, context.font());
+        self.draw_lines(&mut canvas, text, font_size, x, y);
+        context.draw_image_at(canvas.image_data(), self.position.left, self.position.top, None, None);
+        self.scroll_canvas.put(canvas);
+    }
+
+    // 乘以布局字号缩放后的字号，自动字号、进度条和圆环不缩放
+    fn scaled_font_size(&self) -> f32 {
+        snap(self.font_size * font_scale())
+    }
+
+    // 二分查找能放进position的最大字号
+    fn fit_font_size(&self, context: &OffscreenCanvas, text: &str) -> f32 {
+        let (box_width, box_height) = (self.position.width(), self.position.height());
+        let (mut low, mut high) = (MIN_FONT_SIZE, MAX_AUTO_FONT_SIZE);
+        while high - low > 0.5 {
+            let mid = (low + high) / 2.;
+            let (width, height) = self.measure_lines(context, text, mid);
+            if width <= box_width && height <= box_height {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        low
     }
 }
 
 impl Widget for TextWidget {
-    fn draw(&mut self, context: &mut OffscreenCanvas) {
+    fn draw(&mut self, context: &mut OffscreenCanvas, tick: u128) {
+        self.tick = tick;
+        if self.rotation % 360. != 0. {
+            self.draw_rotated(context);
+            return;
+        }
         if self.type_name != "text" {
-            if let Some(text) = match self.type_name.as_str() {
+            let value = match self.type_name.as_str() {
                 "cpu" => monitor::cpu_brand(),
                 "memory" => monitor::memory_info(),
                 "memory_total" => monitor::memory_total(),
@@ -202,7 +1285,14 @@ impl Widget for TextWidget {
                 "version" => monitor::os_version(),
                 "kernel" => monitor::kernel_version(),
                 "host" => monitor::host_name(),
-                "cpu_freq" => monitor::cpu_clock_speed(None),
+                //放置多个组件时，依次显示每个核心
+                "cpu_freq" => {
+                    if self.num_widget == 1 {
+                        monitor::cpu_clock_speed(None)
+                    } else {
+                        monitor::cpu_clock_speed(Some(self.num_widget_index))
+                    }
+                }
                 "cpu_usage" => {
                     if self.num_widget == 1 {
                         monitor::cpu_usage()
@@ -211,7 +1301,12 @@ impl Widget for TextWidget {
                     }
                 }
                 "cpu_temp." => {
-                    Some(monitor::cpu_temperature().unwrap_or(monitor::EMPTY_STRING.to_string()))
+                    let temperature = if self.num_widget == 1 {
+                        monitor::cpu_temperature()
+                    } else {
+                        monitor::cpu_temperature_core(self.num_widget_index)
+                    };
+                    Some(temperature.unwrap_or(monitor::EMPTY_STRING.to_string()))
                 }
                 "cpu_cores_power" => {
                     Some(monitor::cpu_cores_power().unwrap_or(monitor::EMPTY_STRING.to_string()))
@@ -219,66 +1314,99 @@ impl Widget for TextWidget {
                 "cpu_package_power" => {
                     Some(monitor::cpu_package_power().unwrap_or(monitor::EMPTY_STRING.to_string()))
                 }
-                "cpu_fan" => Some(monitor::cpu_fan().unwrap_or(monitor::EMPTY_STRING.to_string())),
+                //放置多个组件时，依次显示每个风扇
+                "cpu_fan" => Some(
+                    monitor::cpu_fan(self.num_widget_index)
+                        .unwrap_or(monitor::EMPTY_STRING.to_string()),
+                ),
+                "cooling" => Some(
+                    monitor::cooling(self.num_widget_index)
+                        .unwrap_or(monitor::EMPTY_STRING.to_string()),
+                ),
                 "gpu_fan" => Some(
                     monitor::gpu_fan(self.num_widget_index)
                         .unwrap_or(monitor::EMPTY_STRING.to_string()),
                 ),
                 "gpu_clock" => Some(
-                    monitor::gpu_clocks(self.num_widget_index)
+                    monitor::gpu_clocks(self.gpu_index())
                         .unwrap_or(monitor::EMPTY_STRING.to_string()),
                 ),
                 "gpu_load" => Some(
-                    monitor::gpu_load(self.num_widget_index)
+                    monitor::gpu_load(self.gpu_index())
                         .unwrap_or(monitor::EMPTY_STRING.to_string()),
                 ),
                 "gpu_memory_load" => Some(
-                    monitor::gpu_memory_load(self.num_widget_index)
+                    monitor::gpu_memory_load(self.gpu_index())
                         .unwrap_or(monitor::EMPTY_STRING.to_string()),
                 ),
                 "gpu_memory_total_mb" => Some(
-                    monitor::gpu_memory_total_mb(self.num_widget_index)
+                    monitor::gpu_memory_total_mb(self.gpu_index())
+                        .unwrap_or(monitor::EMPTY_STRING.to_string()),
+                ),
+                "gpu_memory_used_gb" => Some(
+                    monitor::gpu_memory_used_gb(self.gpu_index())
                         .unwrap_or(monitor::EMPTY_STRING.to_string()),
                 ),
                 "gpu_memory_total_gb" => Some(
-                    monitor::gpu_memory_total_gb(self.num_widget_index)
+                    monitor::gpu_memory_total_gb(self.gpu_index())
                         .unwrap_or(monitor::EMPTY_STRING.to_string()),
                 ),
                 "gpu_temp." => Some(
-                    monitor::gpu_temperature(self.num_widget_index)
+                    monitor::gpu_temperature(self.gpu_index())
                         .unwrap_or(monitor::EMPTY_STRING.to_string()),
                 ),
                 "gpu_cores_power" => {
                     Some(monitor::gpu_cores_power().unwrap_or(monitor::EMPTY_STRING.to_string()))
                 }
+                "cpu_energy" => monitor::cpu_energy_wh(),
+                "gpu_energy" => monitor::gpu_energy_wh(),
                 "gpu_package_power" => {
                     Some(monitor::gpu_package_power().unwrap_or(monitor::EMPTY_STRING.to_string()))
                 }
                 "num_cpu" => monitor::num_cpus(),
                 "num_process" => monitor::num_process(),
+                "load_avg" => monitor::load_average(),
+                "num_threads" => monitor::num_threads(),
+                "foreground_window" => monitor::foreground_window_title(),
                 "disk_usage" => monitor::disk_usage(self.num_widget_index),
                 "date" => Some(monitor::date()),
+                "year" => Some(monitor::year()),
+                "month" => Some(monitor::month()),
+                "day" => Some(monitor::day()),
+                "weekday_num" => Some(monitor::weekday_num()),
                 "local_ip" => monitor::local_ip_addresses(),
                 "net_ip" => monitor::net_ip_address(),
                 "net_ip_info" => monitor::net_ip_info(),
                 "time" => Some(monitor::time()),
+                "ampm" => Some(monitor::ampm()),
                 "weekday" => Some(monitor::chinese_weekday()),
                 "lunar_year" => Some(monitor::lunar_year()),
                 "lunar_date" => Some(monitor::lunar_date()),
-                "weather" => match monitor::weather_info() {
-                    None => Some(monitor::EMPTY_STRING.to_string()),
-                    Some(w) => {
-                        match self.tag1.as_str() {
-                            "1" => Some(format!("{}", w.station.city)),         //城市
-                            "2" => Some(format!("{}℃", w.weather.temperature)), //气温
-                            "3" => Some(format!("{}℃", w.wind.direct)),         //风向
-                            "4" => Some(format!("{}", w.wind.power)),           //风力
-                            "5" => Some(format!("{}级", w.wind.speed)),         //风级
-                            "6" => Some(format!("{}", w.weather.img)),          //图标
-                            _ => Some(format!("{}", w.weather.info)),
+                "weather" => {
+                    let city = self.weather_city();
+                    match monitor::weather_info(&city) {
+                        None => Some(monitor::EMPTY_STRING.to_string()),
+                        Some(w) => {
+                            match self.tag1.as_str() {
+                                "1" => Some(format!("{}", w.station.city)),         //城市
+                                "2" => Some(format!("{}℃", w.weather.temperature)), //气温
+                                "3" => Some(format!("{}℃", w.wind.direct)),         //风向
+                                "4" => Some(format!("{}", w.wind.power)),           //风力
+                                "5" => Some(format!("{}级", w.wind.speed)),         //风级
+                                "6" => Some(format!("{}", w.weather.img)),          //图标
+                                "7" => monitor::weather_high(&city),                 //最高气温
+                                "8" => monitor::weather_low(&city),                  //最低气温
+                                "9" => monitor::weather_high_low(&city),             //最高/最低气温
+                                "10" => monitor::weather_tomorrow(&city),            //明天
+                                "11" => nmc::Weather::valid(w.weather.feelst).map(|v| format!("{v}℃")),         //体感温度
+                                "12" => nmc::Weather::valid(w.weather.humidity).map(|v| format!("{v}%")),       //湿度
+                                "13" => nmc::Weather::valid(w.weather.airpressure).map(|v| format!("{v}hPa")),  //气压
+                                "14" => nmc::Weather::valid(w.weather.rain).map(|v| format!("{v}mm")),          //降水量
+                                _ => Some(format!("{}", w.weather.info)),
+                            }
                         }
                     }
-                },
+                }
                 "uptime" => {
                     let uptime = system_uptime();
                     let uptime_str = match self.tag1.as_str() {
@@ -297,12 +1425,43 @@ impl Widget for TextWidget {
                 "disk_write_speed" => monitor::disk_speed_per_sec().map(|(_r, w)| w),
                 "received_speed" => monitor::network_speed_per_sec().map(|(r, _t)| r),
                 "transmitted_speed" => monitor::network_speed_per_sec().map(|(_r, t)| t),
+                "disk_activity" => monitor::disk_activity_percent(),
+                "network_total_received" => monitor::network_total_received(),
+                "network_total_transmitted" => monitor::network_total_transmitted(),
+                "network_activity" => monitor::network_activity_percent(),
+                "rss" => monitor::feed_titles(&self.tag1),
+                "next_event_title" => monitor::next_event_title(self.tag1.trim()),
+                "next_event_time" => monitor::next_event_time(self.tag1.trim()),
+                "ping" => monitor::ping_info(self.tag1.trim()),
+                "custom" => monitor::custom_value(self.tag1.trim()).map(|value| match self.value_format.as_ref() {
+                    Some(format) => format.apply(&value),
+                    None => value,
+                }),
+                "ticker" => monitor::ticker_quote(self.tag1.trim(), self.tag2.trim()).map(|q| q.to_string()),
+                "phone_battery" => monitor::phone_battery(self.tag1.trim()),
+                "phone_status" => monitor::phone_status(self.tag1.trim()),
                 _ => None,
-            } {
-                if self.text != text && text != monitor::EMPTY_STRING {
+            }
+            .filter(|text| text != monitor::EMPTY_STRING);
+            //设计预览时使用编辑器中设置的示例值
+            let preview = preview_value(&self.id);
+            let is_preview = preview.is_some();
+            match preview.or(value) {
+                Some(text) => {
                     self.text = text;
+                    self.has_value = true;
                 }
+                None => self.show_placeholder(),
+            }
+
+            //显示统计值(最小/最大/平均)
+            if let Some(text) = monitor::MetricStat::from_tag(&self.tag1)
+                .filter(|_| !is_preview)
+                .and_then(|stat| monitor::metric_stat(&self.type_name, stat))
+            {
+                self.text = text;
             }
+            self.update_pulse();
         }
 
         //天气渲染成图标
@@ -311,54 +1470,78 @@ impl Widget for TextWidget {
             let o = ResizeOption {
                 nwidth: self.font_size as u32,
                 nheight: self.font_size as u32,
-                filter: FilterType::Triangle,
+                filter: resize_filter(),
             };
             let (mut x, mut y) = self.position.center();
             x -= self.font_size as i32 / 2;
             y -= self.font_size as i32 / 2;
-            context.draw_image_at(&ICONS[img_idx], x, y, Some(o), None);
-        } else if self.type_name != "weather" && self.type_name != "uptime" && self.tag1 == "1" {
+            //字体图标按字号绘制，颜色为着色颜色或文字颜色
+            let glyph_color = match self.tint {
+                Some([r, g, b]) => [r, g, b, self.color[3]],
+                None => self.text_color().0,
+            };
+            if let Some(glyph) = nmc::weather_glyph(img_idx, self.font_size, glyph_color) {
+                context.draw_image_at(&glyph, x, y, None, None);
+            } else if let Some(icon) = nmc::weather_icon(img_idx) {
+                match self.tint {
+                    Some(tint) => {
+                        let mut icon = icon.clone();
+                        tint_image(&mut icon, tint);
+                        context.draw_image_at(&icon, x, y, Some(o), None);
+                    }
+                    None => context.draw_image_at(icon, x, y, Some(o), None),
+                }
+            }
+        } else if self.is_ring() {
+            //圆环进度
+            self.draw_ring(context);
+        } else if self.is_bar() {
             //是否渲染成进度条
-            let percent = self
-                .text
-                .replace("%", "")
-                .replace("°C", "")
-                .parse::<f32>()
-                .unwrap_or(0.);
-            let width = self
-                .tag2
-                .parse::<i32>()
-                .unwrap_or(self.font_size as i32 * 5);
-            let mut rect_width = (width as f32 * (percent / 100.)) as i32;
-            if rect_width <= 0 {
-                rect_width = 1;
-            }
-            //字体作为高度
-            if self.font_size <= 2. {
-                self.font_size = 2.;
+            let size = self.draw_bar(context);
+            //进度条上显示已用/总量
+            if self.tag1 == "6" {
+                self.draw_bar_label(context, size);
             }
-            let rect = offscreen_canvas::Rect::from(
-                self.position.left,
-                self.position.top,
-                rect_width,
-                self.font_size as i32,
-            );
-            context.fill_rect(rect, Rgba(self.color));
+        } else if self.scroll_speed > 0. && self.position.width() > 2 && self.position.height() > 2 {
+            let text = self.display_text();
+            self.draw_background(context, self.position.width(), self.position.height());
+            self.draw_scrolling(context, &text);
+        } else if self.auto_size && self.position.width() > 2 && self.position.height() > 2 {
+            //自动字号，保持position大小不变
+            let text = self.display_text();
+            self.font_size = self.fit_font_size(context, &text);
+            self.draw_background(context, self.position.width(), self.position.height());
+            let x = if bidi::is_rtl(&self.text) {
+                self.position.right - self.measure_lines(context, &text, self.font_size).0
+            } else {
+                self.position.left
+            };
+            self.draw_lines(context, &text, self.font_size, x, self.position.top);
         } else {
-            if self.font_size <= 4. {
-                self.font_size = 4.;
-            }
-            let text = format!("{}{}", self.prefix, self.text);
-            let text_rect = context.measure_text(&text, self.font_size);
-            self.position
-                .set_size(text_rect.width(), text_rect.height());
-            context.draw_text(
-                &text,
-                Rgba(self.color),
-                self.font_size,
-                self.position.left,
-                self.position.top,
-            );
+            if self.font_size <= MIN_FONT_SIZE {
+                self.font_size = MIN_FONT_SIZE;
+            }
+            let font_size = self.scaled_font_size().max(MIN_FONT_SIZE);
+            let text = self.display_text();
+            let (width, height) = self.measure_lines(context, &text, font_size);
+            //从右到左的文字保持右边缘不动
+            if bidi::is_rtl(&self.text) {
+                let (right, top) = (self.position.right, self.position.top);
+                self.position = Rect::new(right - width, top, right, top + height);
+            } else {
+                self.position.set_size(width, height);
+            }
+            self.draw_background(context, width, height);
+            //跳动时以原来的区域为中心放大，不改变组件大小
+            let pulse = self.pulse();
+            if pulse > 0. {
+                let pulse_size = font_size * (1. + pulse * PULSE_SCALE);
+                let (pulse_width, pulse_height) = self.measure_lines(context, &text, pulse_size);
+                let (x, y) = (self.position.left - (pulse_width - width) / 2, self.position.top - (pulse_height - height) / 2);
+                self.draw_lines(context, &text, pulse_size, x, y);
+            } else {
+                self.draw_lines(context, &text, font_size, self.position.left, self.position.top);
+            }
         }
     }
 
@@ -366,6 +1549,94 @@ impl Widget for TextWidget {
         &self.id
     }
 
+    fn visible(&self) -> bool {
+        self.visible
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    fn show_when(&self) -> Option<&Condition> {
+        self.show_when.as_ref()
+    }
+
+    fn set_show_when(&mut self, condition: Option<Condition>) {
+        self.show_when = condition;
+    }
+
+    fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    fn set_group(&mut self, group: Option<String>) {
+        self.group = group;
+    }
+
+    fn locked(&self) -> bool {
+        self.locked
+    }
+
+    fn set_locked(&mut self, locked: bool) {
+        self.locked = locked;
+    }
+
+    fn opacity(&self) -> f32 {
+        self.opacity
+    }
+
+    fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity.clamp(0., 1.);
+    }
+
+    fn min_refresh_ms(&self) -> u32 {
+        self.min_refresh_ms
+    }
+
+    fn set_min_refresh_ms(&mut self, ms: u32) {
+        self.min_refresh_ms = ms;
+    }
+
+    fn z_index(&self) -> i32 {
+        self.z_index
+    }
+
+    fn set_z_index(&mut self, z_index: i32) {
+        self.z_index = z_index;
+    }
+
+    fn color_role(&self) -> Option<Role> {
+        self.color_role
+    }
+
+    fn set_color_role(&mut self, role: Option<Role>) {
+        self.color_role = role;
+    }
+
+    //旋转后的点击坐标转换到组件未旋转时的坐标，圆环按圆形判断
+    fn contains_point(&self, x: i32, y: i32) -> bool {
+        let (x, y) = unrotate_point(&self.position, self.rotation, x, y);
+        if self.is_ring() {
+            return self.position.contain(x, y) && Mask::Circle.contains(&self.position, x, y);
+        }
+        self.position.contain(x, y)
+    }
+
+    fn scale(&mut self, factor: f32) {
+        self.position = self.position.scaled(factor);
+        self.font_size *= factor;
+        self.letter_spacing *= factor;
+        self.padding = (self.padding as f32 * factor).round() as i32;
+        self.scroll_speed *= factor;
+        self.scroll_offset *= factor;
+        //进度条和圆环的宽度保存在tag2中
+        if self.is_bar() || self.is_ring() {
+            if let Ok(width) = self.tag2.parse::<f32>() {
+                self.tag2 = format!("{}", (width * factor).round() as i32);
+            }
+        }
+    }
+
     fn position_mut(&mut self) -> &mut Rect {
         &mut self.position
     }
@@ -399,24 +1670,46 @@ impl Widget for TextWidget {
     }
 }
 
+// 图像来源，默认嵌入到.screen文件中；链接文件时只保存路径，相对路径基于.screen文件所在目录
+#[derive(Debug, Clone, Default, PartialEq, Encode, Decode, Deserialize, Serialize)]
+pub enum ImageSource {
+    #[default]
+    Embedded,
+    Path(String),
+}
+
 #[derive(Default, Clone, Encode, Decode, Deserialize, Serialize)]
 pub struct ImageData {
     pub width: u32,
     pub height: u32,
     pub frames: Vec<Vec<u8>>,
+    //GIF每一帧的显示时间(毫秒)，为空时每次渲染切换一帧
+    #[serde(default)]
+    pub delays: Vec<u32>,
 }
 
+//GIF帧延迟小于20ms时，按照浏览器的做法使用100ms
+const MIN_GIF_DELAY: u32 = 20;
+const DEFAULT_GIF_DELAY: u32 = 100;
+//图片宽高、GIF帧数和解码内存的上限，损坏或恶意的图片返回错误，不会耗尽内存
+const MAX_IMAGE_DIMENSION: u32 = 8192;
+const MAX_GIF_FRAMES: usize = 1000;
+const MAX_DECODED_BYTES: u64 = 256 * 1024 * 1024;
+
 impl ImageData {
     pub fn load(data: &[u8], max_size: (u32, u32)) -> Result<Self> {
         let format = image::guess_format(data)?;
         Ok(match format {
             image::ImageFormat::Gif => {
                 let mut frames = vec![];
+                let mut delays = vec![];
 
                 let mut gif_opts = gif::DecodeOptions::new();
                 // Important:
                 gif_opts.set_color_output(gif::ColorOutput::Indexed);
+                gif_opts.set_memory_limit(gif::MemoryLimit(MAX_DECODED_BYTES as u32));
                 let mut decoder = gif_opts.read_info(data)?;
+                check_image_dimensions(decoder.width() as u32, decoder.height() as u32)?;
 
                 //计算最大图像大小
                 let (width, height) = test_resize_image(
@@ -429,7 +1722,14 @@ impl ImageData {
 
                 let mut screen = gif_dispose::Screen::new_decoder(&decoder);
 
+                let mut decoded_bytes = 0u64;
                 while let Some(frame) = decoder.read_next_frame()? {
+                    if frames.len() >= MAX_GIF_FRAMES {
+                        return Err(anyhow!("GIF帧数超过{MAX_GIF_FRAMES}"));
+                    }
+                    //GIF的延迟单位是1/100秒
+                    let delay = frame.delay as u32 * 10;
+                    delays.push(if delay < MIN_GIF_DELAY { DEFAULT_GIF_DELAY } else { delay });
                     screen.blit_frame(&frame)?;
                     let rgba = screen.pixels_rgba();
                     let mut pixels = Vec::with_capacity(rgba.width() * rgba.height() * 4);
@@ -438,40 +1738,213 @@ impl ImageData {
                     }
                     let img =
                         RgbaImage::from_raw(rgba.width() as u32, rgba.height() as u32, pixels)
-                            .unwrap();
+                            .ok_or(anyhow!("GIF帧数据错误"))?;
                     //等比例缩放
                     let nw = img.width() as f32 * scale;
                     let nh = img.height() as f32 * scale;
                     let img: RgbaImage = img;
                     let img =
-                        image::imageops::resize(&img, nw as u32, nh as u32, FilterType::Triangle);
+                        image::imageops::resize(&img, nw as u32, nh as u32, resize_filter());
+                    decoded_bytes += img.as_raw().len() as u64;
+                    if decoded_bytes > MAX_DECODED_BYTES {
+                        return Err(anyhow!("GIF解码后数据过大"));
+                    }
                     frames.push(img.into_raw());
                 }
+                if frames.is_empty() {
+                    return Err(anyhow!("GIF没有图像帧"));
+                }
 
                 Self {
                     width,
                     height,
                     frames,
+                    delays,
                 }
             }
             _ => {
-                let image = image::load_from_memory(data).unwrap().to_rgba8();
+                let mut reader = image::io::Reader::with_format(std::io::Cursor::new(data), format);
+                let mut limits = image::io::Limits::default();
+                limits.max_image_width = Some(MAX_IMAGE_DIMENSION);
+                limits.max_image_height = Some(MAX_IMAGE_DIMENSION);
+                limits.max_alloc = Some(MAX_DECODED_BYTES);
+                reader.limits(limits);
+                let image = reader.decode()?.to_rgba8();
                 let resized = resize_image(
                     &image,
                     max_size.0,
                     max_size.1,
-                    image::imageops::FilterType::Triangle,
+                    resize_filter(),
                 );
                 Self {
                     width: resized.width(),
                     height: resized.height(),
                     frames: vec![resized.to_vec()],
+                    delays: vec![],
                 }
             }
         })
     }
 }
 
+fn check_image_dimensions(width: u32, height: u32) -> Result<()> {
+    if width == 0 || height == 0 || width > MAX_IMAGE_DIMENSION || height > MAX_IMAGE_DIMENSION {
+        return Err(anyhow!("图片大小不支持:{width}x{height}"));
+    }
+    Ok(())
+}
+
+//图片缩放方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Encode, Decode, Deserialize, Serialize)]
+pub enum Fit {
+    //拉伸填满
+    #[default]
+    Stretch,
+    //保持比例，完整显示
+    Contain,
+    //保持比例，填满并裁剪
+    Cover,
+}
+
+impl Fit {
+    pub fn from_index(index: i32) -> Self {
+        match index {
+            1 => Fit::Contain,
+            2 => Fit::Cover,
+            _ => Fit::Stretch,
+        }
+    }
+
+    pub fn index(&self) -> i32 {
+        match self {
+            Fit::Stretch => 0,
+            Fit::Contain => 1,
+            Fit::Cover => 2,
+        }
+    }
+}
+
+//图片遮罩
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode, Deserialize, Serialize)]
+pub enum Mask {
+    //圆角矩形，参数为圆角半径
+    RoundedRect(i32),
+    //圆形
+    Circle,
+}
+
+impl Mask {
+    // 0无遮罩 1圆角 2圆形
+    pub fn from_index(index: i32, radius: i32) -> Option<Self> {
+        match index {
+            1 => Some(Mask::RoundedRect(radius)),
+            2 => Some(Mask::Circle),
+            _ => None,
+        }
+    }
+
+    pub fn index(mask: Option<&Mask>) -> i32 {
+        match mask {
+            None => 0,
+            Some(Mask::RoundedRect(_)) => 1,
+            Some(Mask::Circle) => 2,
+        }
+    }
+
+    //判断点是否在遮罩内
+    pub fn contains(&self, rect: &Rect, x: i32, y: i32) -> bool {
+        let (w, h) = (rect.width() as f32, rect.height() as f32);
+        let (px, py) = ((x - rect.left) as f32 + 0.5, (y - rect.top) as f32 + 0.5);
+        match self {
+            Mask::Circle => {
+                let r = w.min(h) / 2.;
+                let (dx, dy) = (px - w / 2., py - h / 2.);
+                dx * dx + dy * dy <= r * r
+            }
+            Mask::RoundedRect(radius) => {
+                let r = (*radius as f32).min(w / 2.).min(h / 2.).max(0.);
+                let (dx, dy) = (px - px.clamp(r, w - r), py - py.clamp(r, h - r));
+                dx * dx + dy * dy <= r * r
+            }
+        }
+    }
+}
+
+//相机画面上叠加的日期时间水印
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Deserialize, Serialize)]
+pub struct Watermark {
+    pub corner: Corner,
+    pub color: [u8; 4],
+    pub font_size: f32,
+    //显示在时间前面的文字，例如相机名称
+    pub label: String,
+}
+
+impl Default for Watermark {
+    fn default() -> Self {
+        Self { corner: Corner::BottomRight, color: [255, 255, 255, 255], font_size: 12., label: String::new() }
+    }
+}
+
+impl Watermark {
+    pub fn text(&self) -> String {
+        let stamp = format!("{} {}", monitor::date(), monitor::time());
+        if self.label.is_empty() {
+            stamp
+        } else {
+            format!("{} {stamp}", self.label)
+        }
+    }
+
+    // 在画面区域的角落绘制，带阴影，在亮暗画面上都能看清
+    pub fn draw(&self, context: &mut OffscreenCanvas, area: &offscreen_canvas::Rect) {
+        const MARGIN: i32 = 2;
+        let text = self.text();
+        let font_size = self.font_size.max(MIN_FONT_SIZE);
+        let size = context.measure_text(&text, font_size);
+        let x = match self.corner {
+            Corner::TopLeft | Corner::BottomLeft => area.left + MARGIN,
+            Corner::TopRight | Corner::BottomRight => area.right - size.width() - MARGIN,
+        };
+        let y = match self.corner {
+            Corner::TopLeft | Corner::TopRight => area.top + MARGIN,
+            Corner::BottomLeft | Corner::BottomRight => area.bottom - size.height() - MARGIN,
+        };
+        context.draw_text(&text, Rgba([0, 0, 0, self.color[3]]), font_size, x + 1, y + 1);
+        context.draw_text(&text, Rgba(self.color), font_size, x, y);
+    }
+}
+
+//水印的位置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Encode, Decode, Deserialize, Serialize)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+impl Corner {
+    pub fn from_index(index: i32) -> Self {
+        match index {
+            0 => Corner::TopLeft,
+            1 => Corner::TopRight,
+            2 => Corner::BottomLeft,
+            _ => Corner::BottomRight,
+        }
+    }
+
+    pub fn index(&self) -> i32 {
+        match self {
+            Corner::TopLeft => 0,
+            Corner::TopRight => 1,
+            Corner::BottomLeft => 2,
+            Corner::BottomRight => 3,
+        }
+    }
+}
+
 #[derive(Clone, Encode, Decode, Deserialize, Serialize)]
 pub struct ImageWidget {
     pub id: String,
@@ -487,12 +1960,162 @@ pub struct ImageWidget {
     pub num_widget: usize,
     pub tag1: Option<String>,
     pub tag2: Option<String>,
+    #[serde(default)]
+    pub fit: Fit,
+    pub mask: Option<Mask>,
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+    pub show_when: Option<Condition>,
+    //链接文件时，image_data作为缓存，不保存到文件中
+    #[serde(default)]
+    pub source: ImageSource,
+    #[serde(default)]
+    pub group: Option<String>,
+    //背景图层
+    #[serde(default)]
+    pub is_background: bool,
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+    #[serde(default)]
+    pub locked: bool,
+    //当前帧开始显示的时间
+    #[serde(skip)]
+    frame_time: u128,
+    #[serde(default)]
+    pub min_refresh_ms: u32,
+    //单色图标的着色颜色，RGB乘以着色颜色，None按原图绘制
+    #[serde(default)]
+    pub tint: Option<[u8; 3]>,
+    //图像的颜色查找表(.cube)，None不调色
+    #[serde(default)]
+    pub lut: Option<Lut>,
+    #[serde(default)]
+    pub z_index: i32,
+    //颜色使用主题中的角色
+    #[serde(default)]
+    pub color_role: Option<Role>,
+    //相机画面上的日期时间水印，None不显示
+    #[serde(default)]
+    pub watermark: Option<Watermark>,
+    //抠色(颜色, 容差)，接近该颜色的像素绘制时变为透明
+    #[serde(default)]
+    pub chroma_key: Option<([u8; 3], u8)>,
+    //图像切换(多帧切换、相机重连)时淡入淡出的时长(秒)，0直接切换
+    #[serde(default)]
+    pub fade: f32,
+    //上一次绘制的图像，用于判断图像是否切换
+    #[serde(skip)]
+    last_image: Option<CachedFrame>,
+    //正在淡出的图像和开始时间
+    #[serde(skip)]
+    fade_from: Option<(CachedFrame, u128)>,
+}
+
+//运行时缓存，不保存，复制组件时不复制
+struct RuntimeCache<T>(Option<T>);
+
+//缓存的画布和字体的hash
+type CanvasCache = RuntimeCache<(OffscreenCanvas, usize)>;
+
+impl CanvasCache {
+    // 取出大小和字体相同的透明画布，没有时新建，用完后put放回
+    fn take(&mut self, width: u32, height: u32, font: &Font) -> OffscreenCanvas {
+        let font_hash = font.file_hash();
+        let mut canvas = match self.0.take() {
+            Some((canvas, hash)) if canvas.width() == width && canvas.height() == height && hash == font_hash => canvas,
+            _ => OffscreenCanvas::new(width, height, font.clone()),
+        };
+        canvas.clear(Rgba([0, 0, 0, 0]));
+        canvas
+    }
+
+    fn put(&mut self, canvas: OffscreenCanvas) {
+        let font_hash = canvas.font().file_hash();
+        self.0 = Some((canvas, font_hash));
+    }
+}
+
+impl<T> Default for RuntimeCache<T> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<T> Clone for RuntimeCache<T> {
+    fn clone(&self) -> Self {
+        Self(None)
+    }
+}
+
+impl<T> Encode for RuntimeCache<T> {
+    fn encode<E: Encoder>(&self, _encoder: &mut E) -> Result<(), EncodeError> {
+        Ok(())
+    }
+}
+
+impl<T> Decode for RuntimeCache<T> {
+    fn decode<D: Decoder>(_decoder: &mut D) -> Result<Self, DecodeError> {
+        Ok(Self(None))
+    }
+}
+
+impl<'de, T> BorrowDecode<'de> for RuntimeCache<T> {
+    fn borrow_decode<D: BorrowDecoder<'de>>(_decoder: &mut D) -> Result<Self, DecodeError> {
+        Ok(Self(None))
+    }
+}
+
+//绘制时缓存的图像，key为帧序号
+#[derive(Clone, Encode, Decode)]
+struct CachedFrame {
+    key: usize,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl CachedFrame {
+    fn new(key: usize, image: &RgbaImage) -> Self {
+        Self { key, width: image.width(), height: image.height(), pixels: image.to_vec() }
+    }
+
+    fn image(&self) -> Option<RgbaImage> {
+        RgbaImage::from_raw(self.width, self.height, self.pixels.clone())
+    }
 }
 
 impl ImageWidget {
     pub fn from_v10(img:v10::ImageWidget) -> Self{
-        Self { id: img.id, image_data: img.image_data, rotation: img.rotation, position: img.position, type_name: img.type_name, frame_index: img.frame_index, color: img.color,
-            num_widget_index: img.num_widget_index, num_widget: img.num_widget, tag1: None, tag2: None }
+        Self { id: img.id, image_data: ImageData { width: img.image_data.width, height: img.image_data.height, frames: img.image_data.frames, delays: vec![] }, rotation: img.rotation, position: img.position, type_name: img.type_name, frame_index: img.frame_index, color: img.color,
+            num_widget_index: img.num_widget_index, num_widget: img.num_widget, tag1: None, tag2: None, fit: Fit::Stretch, mask: None, visible: true, show_when: None, source: ImageSource::Embedded, group: None, is_background: false, opacity: 1., locked: false, frame_time: 0, min_refresh_ms: 0, tint: None, lut: None, z_index: 0, color_role: None, watermark: None, chroma_key: None, fade: 0., last_image: None, fade_from: None }
+    }
+
+    // 相机编号，tag1为空时使用第一个相机
+    pub fn webcam_index(&self) -> u32 {
+        self.tag1.as_deref().and_then(|index| index.trim().parse().ok()).unwrap_or(0)
+    }
+
+    // 桌面截图区域，tag1为 x,y,宽,高，为空时截取整个显示器
+    pub fn capture_region(&self) -> Option<(i32, i32, u32, u32)> {
+        let values: Vec<&str> = self.tag1.as_deref()?.split(',').map(str::trim).collect();
+        match values[..] {
+            [x, y, width, height] => Some((x.parse().ok()?, y.parse().ok()?, width.parse().ok()?, height.parse().ok()?))
+                .filter(|(_, _, width, height)| *width > 0 && *height > 0),
+            _ => None,
+        }
+    }
+
+    // 按图像原始的宽高比，由宽度计算高度(keep_width)或者由高度计算宽度
+    pub fn fit_aspect(&self, width: i32, height: i32, keep_width: bool) -> (i32, i32) {
+        if self.image_data.width == 0 || self.image_data.height == 0 {
+            return (width, height);
+        }
+        let ratio = self.image_data.height as f32 / self.image_data.width as f32;
+        if keep_width {
+            (width, (width as f32 * ratio).round().max(1.) as i32)
+        } else {
+            ((height as f32 / ratio).round().max(1.) as i32, height)
+        }
     }
     
     pub fn new(x: i32, y: i32, type_name: &str) -> Self {
@@ -505,6 +2128,7 @@ impl ImageWidget {
                 width: w,
                 height: h,
                 frames: vec![image.to_vec()],
+                delays: vec![],
             },
             rotation: 0.,
             position: Rect::from(x - w as i32 / 2, y - h as i32 / 2, w as i32, h as i32),
@@ -515,31 +2139,270 @@ impl ImageWidget {
             num_widget: 1,
             tag1: None,
             tag2: None,
+            fit: Fit::Stretch,
+            mask: None,
+            visible: true,
+            show_when: None,
+            source: ImageSource::Embedded,
+            group: None,
+            is_background: false,
+            opacity: 1.,
+            locked: false,
+            frame_time: 0,
+            min_refresh_ms: 0,
+            tint: None,
+            lut: None,
+            z_index: 0,
+            color_role: None,
+            watermark: None,
+            chroma_key: None,
+            fade: 0.,
+            last_image: None,
+            fade_from: None,
+        }
+    }
+
+    // 图像切换时从上一张图像淡入，返回混合后的图像，key变化表示图像已切换
+    fn crossfade(&mut self, mut image: RgbaImage, key: usize, now: u128) -> RgbaImage {
+        if self.fade <= 0. {
+            self.last_image = None;
+            self.fade_from = None;
+            return image;
+        }
+        if let Some(last) = self.last_image.take().filter(|last| last.key != key) {
+            self.fade_from = Some((last, now));
+        }
+        self.last_image = Some(CachedFrame::new(key, &image));
+        let Some((from, start)) = self.fade_from.as_ref() else {
+            return image;
+        };
+        let progress = now.saturating_sub(*start) as f32 / (self.fade * 1000.);
+        let from = match from.image() {
+            Some(from) if progress < 1. => from,
+            _ => {
+                self.fade_from = None;
+                return image;
+            }
+        };
+        //两张图像大小不同时，旧图像缩放到新图像的大小
+        let from = if from.dimensions() == image.dimensions() {
+            from
+        } else {
+            resize(&from, image.width(), image.height(), FilterType::Nearest)
+        };
+        transition::fade(&from, &mut image, progress);
+        image
+    }
+
+    // 按照渲染时间经过的时间切换帧
+    fn next_frame(&mut self, now: u128) {
+        let count = self.image_data.frames.len();
+        if count <= 1 {
+            return;
+        }
+        if self.frame_time == 0 {
+            self.frame_time = now;
+            return;
+        }
+        //渲染卡顿时跳过多帧，但不超过一轮
+        for _ in 0..count {
+            let delay = self.frame_delay(self.frame_index);
+            if now.saturating_sub(self.frame_time) < delay {
+                break;
+            }
+            self.frame_time += delay;
+            self.frame_index = (self.frame_index + 1) % count;
+        }
+        if now.saturating_sub(self.frame_time) > self.frame_delay(self.frame_index) {
+            self.frame_time = now;
+        }
+    }
+
+    // 帧延迟(毫秒)，旧文件没有延迟信息时使用默认值，按时间切换帧与帧率无关
+    fn frame_delay(&self, index: usize) -> u128 {
+        match self.image_data.delays.get(index) {
+            Some(delay) if *delay > 0 => *delay as u128,
+            _ => DEFAULT_GIF_DELAY as u128,
+        }
+    }
+
+    // 链接的图像还未加载
+    pub fn need_load_source(&self) -> bool {
+        matches!(self.source, ImageSource::Path(_)) && self.image_data.frames.is_empty()
+    }
+
+    // 加载链接的图像文件，加载失败时使用透明图像，避免每帧重复读取
+    pub fn load_source(&mut self, base_dir: Option<&Path>, max_size: (u32, u32)) -> Result<()> {
+        let path = match &self.source {
+            ImageSource::Embedded => return Ok(()),
+            ImageSource::Path(path) => Path::new(path),
+        };
+        let path = match base_dir {
+            Some(dir) if path.is_relative() => dir.join(path),
+            _ => path.to_path_buf(),
+        };
+        let result = std::fs::read(&path)
+            .map_err(|err| anyhow::anyhow!("{:?} {err:?}", path))
+            .and_then(|data| ImageData::load(&data, max_size));
+        match result {
+            Ok(image_data) => {
+                self.image_data = image_data;
+                //重新加载后按新图像淡入
+                if let Some(last) = self.last_image.as_mut() {
+                    last.key = usize::MAX;
+                }
+                Ok(())
+            }
+            Err(err) => {
+                self.image_data = ImageData {
+                    width: 1,
+                    height: 1,
+                    frames: vec![vec![0; 4]],
+                    delays: vec![],
+                };
+                Err(err)
+            }
+        }
+    }
+
+    // 保存时链接的图像不包含图像数据
+    pub fn to_saveable(&self) -> ImageWidget {
+        let mut widget = self.clone();
+        if let ImageSource::Path(_) = widget.source {
+            widget.image_data = ImageData::default();
+            widget.frame_index = 0;
+        }
+        widget
+    }
+
+    //按照遮罩处理图像，返回和目标区域大小一致的图像，遮罩外的像素透明
+    fn masked_image(&self, image: &RgbaImage, src: &offscreen_canvas::Rect, dst: &offscreen_canvas::Rect) -> Option<RgbaImage> {
+        let mask = self.mask.as_ref()?;
+        if src.width() <= 0 || src.height() <= 0 || dst.width() <= 0 || dst.height() <= 0 {
+            return None;
+        }
+        let cropped = image::imageops::crop_imm(
+            image,
+            src.left.max(0) as u32,
+            src.top.max(0) as u32,
+            src.width() as u32,
+            src.height() as u32,
+        )
+        .to_image();
+        let mut masked = resize(&cropped, dst.width() as u32, dst.height() as u32, FilterType::Nearest);
+        for (x, y, pixel) in masked.enumerate_pixels_mut() {
+            if !mask.contains(&self.position, dst.left + x as i32, dst.top + y as i32) {
+                pixel[3] = 0;
+            }
+        }
+        Some(masked)
+    }
+
+    fn draw_image(&self, context: &mut OffscreenCanvas, image: &RgbaImage, src: &offscreen_canvas::Rect, dst: &offscreen_canvas::Rect) {
+        match self.masked_image(image, src, dst) {
+            Some(masked) => {
+                let src = offscreen_canvas::Rect::new(0, 0, masked.width() as i32, masked.height() as i32);
+                context.draw_image_with_src_and_dst(&masked, &src, dst, FilterType::Nearest);
+            }
+            None => context.draw_image_with_src_and_dst(image, src, dst, FilterType::Nearest),
+        }
+    }
+
+    //根据缩放方式计算源区域和目标区域
+    fn fit_rects(&self, image_width: i32, image_height: i32) -> (offscreen_canvas::Rect, offscreen_canvas::Rect) {
+        let (width, height) = (self.position.width(), self.position.height());
+        let full_src = offscreen_canvas::Rect::new(0, 0, image_width, image_height);
+        let full_dst = offscreen_canvas::Rect::from(self.position.left, self.position.top, width, height);
+        if image_width <= 0 || image_height <= 0 || width <= 0 || height <= 0 {
+            return (full_src, full_dst);
+        }
+        let scale_x = width as f32 / image_width as f32;
+        let scale_y = height as f32 / image_height as f32;
+        match self.fit {
+            Fit::Stretch => (full_src, full_dst),
+            Fit::Contain => {
+                //居中显示，空白部分透明
+                let scale = scale_x.min(scale_y);
+                let dw = (image_width as f32 * scale) as i32;
+                let dh = (image_height as f32 * scale) as i32;
+                let dst = offscreen_canvas::Rect::from(
+                    self.position.left + (width - dw) / 2,
+                    self.position.top + (height - dh) / 2,
+                    dw,
+                    dh,
+                );
+                (full_src, dst)
+            }
+            Fit::Cover => {
+                //居中裁剪
+                let scale = scale_x.max(scale_y);
+                let sw = (width as f32 / scale) as i32;
+                let sh = (height as f32 / scale) as i32;
+                let src = offscreen_canvas::Rect::from(
+                    (image_width - sw) / 2,
+                    (image_height - sh) / 2,
+                    sw,
+                    sh,
+                );
+                (src, full_dst)
+            }
         }
     }
 }
 
 impl Widget for ImageWidget {
-    fn draw(&mut self, context: &mut OffscreenCanvas) {
-        if let Some(color) = self.color.as_ref() {
+    fn draw(&mut self, context: &mut OffscreenCanvas, tick: u128) {
+        if let Some(color) = self.color.map(|c| themed_color(self.color_role, c)).as_ref() {
             let rect = offscreen_canvas::Rect::from(
                 self.position.left,
                 self.position.top,
                 self.position.width(),
                 self.position.height(),
             );
-            context.fill_rect(rect, Rgba(*color));
+            //纯色按不透明色绘制，透明度由opacity()在绘制后混合
+            let color = Rgba([color[0], color[1], color[2], 255]);
+            if self.mask.is_some() {
+                let image = RgbaImage::from_pixel(rect.width().max(1) as u32, rect.height().max(1) as u32, color);
+                let src = offscreen_canvas::Rect::new(0, 0, image.width() as i32, image.height() as i32);
+                self.draw_image(context, &image, &src, &rect);
+            } else {
+                context.fill_rect(rect, color);
+            }
         }
-        //是否是相机
-        else if self.type_name == "webcam"{
-            //获取相机图像
-            if let Some(image) = webcam_frame(){
+        //是否是相机或者桌面截图
+        else if self.is_webcam() || self.is_desktop() {
+            //获取相机图像或截图
+            let frame = if self.is_desktop() {
+                monitor::screen_capture_frame()
+            } else {
+                webcam_frame(self.webcam_index())
+            };
+            let frame = frame.map(|image| {
+                let mut image: RgbaImage = image.convert();
+                if let Some((key, tolerance)) = self.chroma_key {
+                    chroma_key_image(&mut image, key, tolerance);
+                }
+                self.crossfade(image, 0, tick)
+            });
+            //相机断开时保留最后一帧，重连后从最后一帧淡入
+            let frame = match frame {
+                None if self.fade > 0. => self.last_image.as_mut().and_then(|last| {
+                    last.key = usize::MAX;
+                    last.image()
+                }),
+                frame => frame,
+            };
+            if let Some(image) = frame {
                 let src =
                     offscreen_canvas::Rect::new(0, 0, image.width() as i32, image.height() as i32);
 
-                //按照宽度比例绘制
-                let width = self.position.width();
-                let height = ((image.height() as f32 / image.width() as f32)*width as f32) as i32;
+                //保持比例，按照宽度绘制，超出高度时按照高度绘制
+                let mut width = self.position.width();
+                let mut height = ((image.height() as f32 / image.width() as f32)*width as f32) as i32;
+                if height > self.position.height() {
+                    height = self.position.height();
+                    width = ((image.width() as f32 / image.height() as f32)*height as f32) as i32;
+                }
                 
                 let pos = offscreen_canvas::Rect::from(
                     self.position.left,
@@ -548,9 +2411,12 @@ impl Widget for ImageWidget {
                     height,
                 );
 
-                context.draw_image_with_src_and_dst(&image.convert(), &src, &pos, FilterType::Nearest);
+                self.draw_image(context, &image, &src, &pos);
+                if let Some(watermark) = self.watermark.as_ref().filter(|_| self.is_webcam()) {
+                    watermark.draw(context, &pos);
+                }
             }else{
-                //未打开相机，显示白色
+                //未打开相机或没有截图，显示白色
                 let rect = offscreen_canvas::Rect::from(
                     self.position.left,
                     self.position.top,
@@ -563,37 +2429,46 @@ impl Widget for ImageWidget {
             if self.frame_index >= self.image_data.frames.len(){
                 self.frame_index = self.image_data.frames.len()-1;
             }
-            let image = RgbaImage::from_raw(
+            let mut image = RgbaImage::from_raw(
                 self.image_data.width,
                 self.image_data.height,
                 self.image_data.frames[self.frame_index].clone(),
             ).unwrap_or(RgbaImage::new(30, 30));
-            let src =
-                offscreen_canvas::Rect::new(0, 0, image.width() as i32, image.height() as i32);
-            let pos = offscreen_canvas::Rect::from(
-                self.position.left,
-                self.position.top,
-                self.position.width(),
-                self.position.height(),
-            );
+            //先抠色再调色，按原图的颜色判断
+            if let Some((key, tolerance)) = self.chroma_key {
+                chroma_key_image(&mut image, key, tolerance);
+            }
+            if let Some(lut) = self.lut.as_ref() {
+                lut.apply(&mut image, 4);
+            }
+            if let Some(tint) = self.tint {
+                tint_image(&mut image, tint);
+            }
+            let image = self.crossfade(image, self.frame_index, tick);
+            let (src, pos) = self.fit_rects(image.width() as i32, image.height() as i32);
 
             if self.rotation == 0.{
                 //不旋转
-                context.draw_image_with_src_and_dst(&image, &src, &pos, FilterType::Nearest);
+                self.draw_image(context, &image, &src, &pos);
             }else{
+                //遮罩跟随图像一起旋转
+                let (image, src) = match self.masked_image(&image, &src, &pos) {
+                    Some(masked) => {
+                        let src = offscreen_canvas::Rect::new(0, 0, masked.width() as i32, masked.height() as i32);
+                        (masked, src)
+                    }
+                    None => (image, src),
+                };
                 let option = RotateOption::from(
                     (
-                        self.position.width() as f32 / 2.,
-                        self.position.height() as f32 / 2.,
+                        pos.width() as f32 / 2.,
+                        pos.height() as f32 / 2.,
                     ),
                     degrees_to_radians(self.rotation),
                 );
                 context.draw_image_with_src_and_dst_and_rotation(&image, &src, &pos, option);
             }
-            self.frame_index += 1;
-            if self.frame_index >= self.image_data.frames.len() {
-                self.frame_index = 0;
-            }
+            self.next_frame(tick);
         }
     }
 
@@ -601,6 +2476,92 @@ impl Widget for ImageWidget {
         &self.id
     }
 
+    fn visible(&self) -> bool {
+        self.visible
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    fn show_when(&self) -> Option<&Condition> {
+        self.show_when.as_ref()
+    }
+
+    fn set_show_when(&mut self, condition: Option<Condition>) {
+        self.show_when = condition;
+    }
+
+    fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    fn set_group(&mut self, group: Option<String>) {
+        self.group = group;
+    }
+
+    fn locked(&self) -> bool {
+        self.locked
+    }
+
+    fn set_locked(&mut self, locked: bool) {
+        self.locked = locked;
+    }
+
+    //纯色的透明度与组件不透明度一起混合
+    fn opacity(&self) -> f32 {
+        let alpha = self.color.map(|c| c[3] as f32 / 255.).unwrap_or(1.);
+        self.opacity * alpha
+    }
+
+    fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity.clamp(0., 1.);
+    }
+
+    fn min_refresh_ms(&self) -> u32 {
+        self.min_refresh_ms
+    }
+
+    fn set_min_refresh_ms(&mut self, ms: u32) {
+        self.min_refresh_ms = ms;
+    }
+
+    fn z_index(&self) -> i32 {
+        self.z_index
+    }
+
+    fn set_z_index(&mut self, z_index: i32) {
+        self.z_index = z_index;
+    }
+
+    fn color_role(&self) -> Option<Role> {
+        self.color_role
+    }
+
+    fn set_color_role(&mut self, role: Option<Role>) {
+        self.color_role = role;
+    }
+
+    //旋转后的点击坐标转换到组件未旋转时的坐标，有遮罩时按遮罩形状判断
+    fn contains_point(&self, x: i32, y: i32) -> bool {
+        let (x, y) = unrotate_point(&self.position, self.rotation, x, y);
+        if !self.position.contain(x, y) {
+            return false;
+        }
+        self.mask.as_ref().map(|mask| mask.contains(&self.position, x, y)).unwrap_or(true)
+    }
+
+    fn is_background(&self) -> bool {
+        self.is_background
+    }
+
+    fn scale(&mut self, factor: f32) {
+        self.position = self.position.scaled(factor);
+        if let Some(Mask::RoundedRect(radius)) = self.mask.as_mut() {
+            *radius = (*radius as f32 * factor).round() as i32;
+        }
+    }
+
     fn position_mut(&mut self) -> &mut Rect {
         &mut self.position
     }
@@ -634,22 +2595,478 @@ impl Widget for ImageWidget {
     }
 }
 
+// 系统概要的排列方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum SummaryLayout {
+    //两列网格，标签在数值上方
+    #[default]
+    Grid,
+    //每行一项，标签在左，数值右对齐
+    List,
+    //三行紧凑显示: CPU和温度、内存、网速
+    Compact,
+}
+
+impl SummaryLayout {
+    pub const ALL: [SummaryLayout; 3] = [SummaryLayout::Grid, SummaryLayout::List, SummaryLayout::Compact];
+
+    pub fn index(&self) -> i32 {
+        Self::ALL.iter().position(|l| l == self).unwrap_or(0) as i32
+    }
+
+    pub fn from_index(index: i32) -> Self {
+        Self::ALL.get(index as usize).copied().unwrap_or_default()
+    }
+}
+
+// 系统概要组件: 在组件区域内按固定排列显示CPU、内存、温度和网速，适合128x128的小屏幕
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SummaryWidget {
+    pub id: String,
+    pub position: Rect,
+    #[serde(default)]
+    pub layout: SummaryLayout,
+    pub color: [u8; 4],
+    pub label_color: [u8; 4],
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+    #[serde(default)]
+    pub show_when: Option<Condition>,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default)]
+    pub z_index: i32,
+    //颜色使用主题中的角色
+    #[serde(default)]
+    pub color_role: Option<Role>,
+}
+
+impl SummaryWidget {
+    pub const TYPE_NAME: &'static str = "summary";
+
+    pub fn new(x: i32, y: i32) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            position: Rect::new(x, y, x + 80, y + 60),
+            layout: SummaryLayout::Grid,
+            color: [255, 255, 255, 255],
+            label_color: [160, 160, 160, 255],
+            visible: true,
+            show_when: None,
+            group: None,
+            locked: false,
+            z_index: 0,
+            color_role: None,
+        }
+    }
+
+    pub fn create(x: i32, y: i32) -> Box<dyn Widget> {
+        Box::new(Self::new(x, y))
+    }
+
+    pub fn save(widget: &mut dyn Widget) -> Result<String> {
+        let widget = widget
+            .as_any_mut()
+            .downcast_mut::<SummaryWidget>()
+            .ok_or(anyhow!("组件类型错误"))?;
+        Ok(serde_json::to_string(widget)?)
+    }
+
+    pub fn load(data: &str) -> Result<Box<dyn Widget>> {
+        Ok(Box::new(serde_json::from_str::<SummaryWidget>(data)?))
+    }
+
+    // 标签和数值，没有数据时显示N/A
+    fn items() -> [(&'static str, String); 5] {
+        let empty = || monitor::EMPTY_STRING.to_string();
+        let (received, transmitted) = monitor::network_speed_per_sec().unwrap_or((empty(), empty()));
+        [
+            ("CPU", monitor::cpu_usage().unwrap_or_else(empty)),
+            ("RAM", monitor::memory_percent().unwrap_or_else(empty)),
+            ("TEMP", monitor::cpu_temperature().unwrap_or_else(empty)),
+            ("UP", transmitted),
+            ("DN", received),
+        ]
+    }
+
+    fn draw_grid(&self, context: &mut OffscreenCanvas, items: &[(&str, String)]) {
+        let rows = (items.len() + 1) / 2;
+        let cell_width = self.position.width() / 2;
+        let cell_height = self.position.height() / rows as i32;
+        let label_size = snap(cell_height as f32 * 0.35).max(MIN_FONT_SIZE);
+        let value_size = snap(cell_height as f32 * 0.55).max(MIN_FONT_SIZE);
+        for (i, (label, value)) in items.iter().enumerate() {
+            let x = self.position.left + (i % 2) as i32 * cell_width;
+            let y = self.position.top + (i / 2) as i32 * cell_height;
+            context.draw_text(label, Rgba(self.label_color), label_size, x, y);
+            context.draw_text(value, Rgba(themed_color(self.color_role, self.color)), value_size, x, y + label_size as i32);
+        }
+    }
+
+    fn draw_list(&self, context: &mut OffscreenCanvas, items: &[(&str, String)]) {
+        let row_height = self.position.height() / items.len() as i32;
+        let font_size = snap(row_height as f32 * 0.85).max(MIN_FONT_SIZE);
+        for (i, (label, value)) in items.iter().enumerate() {
+            let y = self.position.top + i as i32 * row_height;
+            context.draw_text(label, Rgba(self.label_color), font_size, self.position.left, y);
+            let width = context.measure_text(value, font_size).width();
+            context.draw_text(value, Rgba(themed_color(self.color_role, self.color)), font_size, self.position.right - width, y);
+        }
+    }
+
+    fn draw_compact(&self, context: &mut OffscreenCanvas, items: &[(&str, String)]) {
+        let lines = [
+            format!("{} {} {}", items[0].0, items[0].1, items[2].1),
+            format!("{} {}", items[1].0, items[1].1),
+            format!("{}{} {}{}", items[4].0, items[4].1, items[3].0, items[3].1),
+        ];
+        let row_height = self.position.height() / lines.len() as i32;
+        let font_size = snap(row_height as f32 * 0.85).max(MIN_FONT_SIZE);
+        for (i, line) in lines.iter().enumerate() {
+            let y = self.position.top + i as i32 * row_height;
+            context.draw_text(line, Rgba(themed_color(self.color_role, self.color)), font_size, self.position.left, y);
+        }
+    }
+}
+
+impl Widget for SummaryWidget {
+    fn draw(&mut self, context: &mut OffscreenCanvas, _tick: u128) {
+        if self.position.width() <= 2 || self.position.height() <= 2 {
+            return;
+        }
+        let items = Self::items();
+        match self.layout {
+            SummaryLayout::Grid => self.draw_grid(context, &items),
+            SummaryLayout::List => self.draw_list(context, &items),
+            SummaryLayout::Compact => self.draw_compact(context, &items),
+        }
+    }
+
+    fn visible(&self) -> bool {
+        self.visible
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    fn show_when(&self) -> Option<&Condition> {
+        self.show_when.as_ref()
+    }
+
+    fn set_show_when(&mut self, condition: Option<Condition>) {
+        self.show_when = condition;
+    }
+
+    fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    fn set_group(&mut self, group: Option<String>) {
+        self.group = group;
+    }
+
+    fn locked(&self) -> bool {
+        self.locked
+    }
+
+    fn set_locked(&mut self, locked: bool) {
+        self.locked = locked;
+    }
+
+    fn z_index(&self) -> i32 {
+        self.z_index
+    }
+
+    fn set_z_index(&mut self, z_index: i32) {
+        self.z_index = z_index;
+    }
+
+    fn color_role(&self) -> Option<Role> {
+        self.color_role
+    }
+
+    fn set_color_role(&mut self, role: Option<Role>) {
+        self.color_role = role;
+    }
+
+    fn is_text(&self) -> bool {
+        false
+    }
+
+    fn get_label(&self) -> &str {
+        "系统概要"
+    }
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn index(&self) -> usize {
+        0
+    }
+
+    fn set_index(&mut self, _idx: usize) {}
+
+    fn num_widget(&self) -> usize {
+        1
+    }
+
+    fn set_num_widget(&mut self, _num: usize) {}
+
+    fn position(&self) -> &Rect {
+        &self.position
+    }
+
+    fn position_mut(&mut self) -> &mut Rect {
+        &mut self.position
+    }
+
+    fn type_name(&self) -> &str {
+        Self::TYPE_NAME
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// 多核心使用率组件: 每个逻辑核心一个竖向小进度条，按列数排成网格，铺满组件区域
+#[derive(Clone, Deserialize, Serialize)]
+pub struct CpuCoresWidget {
+    pub id: String,
+    pub position: Rect,
+    //每行的进度条数量，0为所有核心排成一行
+    #[serde(default)]
+    pub columns: usize,
+    //进度条之间的间距
+    #[serde(default = "default_cores_gap")]
+    pub gap: i32,
+    pub color: [u8; 4],
+    //未填充部分的颜色，None不绘制
+    #[serde(default)]
+    pub track_color: Option<[u8; 4]>,
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+    #[serde(default)]
+    pub show_when: Option<Condition>,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default)]
+    pub z_index: i32,
+    //颜色使用主题中的角色
+    #[serde(default)]
+    pub color_role: Option<Role>,
+}
+
+fn default_cores_gap() -> i32 {
+    1
+}
+
+impl CpuCoresWidget {
+    pub const TYPE_NAME: &'static str = "cpu_cores";
+
+    pub fn new(x: i32, y: i32) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            position: Rect::new(x, y, x + 64, y + 24),
+            columns: 0,
+            gap: default_cores_gap(),
+            color: [0, 200, 120, 255],
+            track_color: Some([60, 60, 60, 255]),
+            visible: true,
+            show_when: None,
+            group: None,
+            locked: false,
+            z_index: 0,
+            color_role: None,
+        }
+    }
+
+    pub fn create(x: i32, y: i32) -> Box<dyn Widget> {
+        Box::new(Self::new(x, y))
+    }
+
+    pub fn save(widget: &mut dyn Widget) -> Result<String> {
+        let widget = widget
+            .as_any_mut()
+            .downcast_mut::<CpuCoresWidget>()
+            .ok_or(anyhow!("组件类型错误"))?;
+        Ok(serde_json::to_string(widget)?)
+    }
+
+    pub fn load(data: &str) -> Result<Box<dyn Widget>> {
+        Ok(Box::new(serde_json::from_str::<CpuCoresWidget>(data)?))
+    }
+}
+
+impl Widget for CpuCoresWidget {
+    fn draw(&mut self, context: &mut OffscreenCanvas, _tick: u128) {
+        let usages = monitor::cpu_usage_all();
+        if usages.is_empty() || self.position.width() <= 2 || self.position.height() <= 2 {
+            return;
+        }
+        let columns = match self.columns {
+            0 => usages.len(),
+            columns => columns.min(usages.len()),
+        };
+        let rows = usages.len().div_ceil(columns);
+        let gap = self.gap.max(0);
+        let bar_width = ((self.position.width() - gap * (columns as i32 - 1)) / columns as i32).max(1);
+        let bar_height = ((self.position.height() - gap * (rows as i32 - 1)) / rows as i32).max(1);
+        let color = Rgba(themed_color(self.color_role, self.color));
+        for (i, usage) in usages.iter().enumerate() {
+            let x = self.position.left + (i % columns) as i32 * (bar_width + gap);
+            let y = self.position.top + (i / columns) as i32 * (bar_height + gap);
+            if let Some(track_color) = self.track_color {
+                context.fill_rect(offscreen_canvas::Rect::from(x, y, bar_width, bar_height), Rgba(track_color));
+            }
+            //从下往上填充
+            let fill = ((bar_height as f32 * usage.clamp(0., 100.) / 100.).round() as i32).max(1);
+            context.fill_rect(offscreen_canvas::Rect::from(x, y + bar_height - fill, bar_width, fill), color);
+        }
+    }
+
+    fn visible(&self) -> bool {
+        self.visible
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    fn show_when(&self) -> Option<&Condition> {
+        self.show_when.as_ref()
+    }
+
+    fn set_show_when(&mut self, condition: Option<Condition>) {
+        self.show_when = condition;
+    }
+
+    fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    fn set_group(&mut self, group: Option<String>) {
+        self.group = group;
+    }
+
+    fn locked(&self) -> bool {
+        self.locked
+    }
+
+    fn set_locked(&mut self, locked: bool) {
+        self.locked = locked;
+    }
+
+    fn z_index(&self) -> i32 {
+        self.z_index
+    }
+
+    fn set_z_index(&mut self, z_index: i32) {
+        self.z_index = z_index;
+    }
+
+    fn color_role(&self) -> Option<Role> {
+        self.color_role
+    }
+
+    fn set_color_role(&mut self, role: Option<Role>) {
+        self.color_role = role;
+    }
+
+    fn is_text(&self) -> bool {
+        false
+    }
+
+    fn get_label(&self) -> &str {
+        "多核使用率"
+    }
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn index(&self) -> usize {
+        0
+    }
+
+    fn set_index(&mut self, _idx: usize) {}
+
+    fn num_widget(&self) -> usize {
+        1
+    }
+
+    fn set_num_widget(&mut self, _num: usize) {}
+
+    fn position(&self) -> &Rect {
+        &self.position
+    }
+
+    fn position_mut(&mut self) -> &mut Rect {
+        &mut self.position
+    }
+
+    fn type_name(&self) -> &str {
+        Self::TYPE_NAME
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
 #[derive(Clone, Encode, Decode, Deserialize, Serialize)]
 pub enum SaveableWidget {
     TextWidget(TextWidget),
     ImageWidget(ImageWidget),
+    CustomWidget(CustomWidgetData),
+}
+
+//在screen::WIDGET_TYPES中注册的组件，data由注册的序列化函数生成
+#[derive(Clone, Encode, Decode, Deserialize, Serialize)]
+pub struct CustomWidgetData {
+    pub type_name: String,
+    pub data: String,
 }
 
 //老版本
 pub mod v10{
     use super::*;
 
+    //旧版本的图像数据，bincode格式不能增加字段
+    #[derive(Default, Clone, Encode, Decode, Deserialize, Serialize)]
+    pub struct ImageData {
+        pub width: u32,
+        pub height: u32,
+        pub frames: Vec<Vec<u8>>,
+    }
+
     #[derive(Clone, Encode, Decode, Deserialize, Serialize)]
     pub enum SaveableWidget {
-        TextWidget(super::TextWidget),
+        TextWidget(TextWidget),
         ImageWidget(ImageWidget),
     }
 
+    #[derive(Clone, Encode, Decode, Deserialize, Serialize)]
+    pub struct TextWidget {
+        pub id: String,
+        pub text: String,
+        pub prefix: String,
+        pub color: [u8; 4],
+        pub font_size: f32,
+        pub position: Rect,
+        pub type_name: String,
+        pub num_widget_index: usize,
+        pub num_widget: usize,
+        pub tag1: String,
+        pub tag2: String,
+    }
+
     #[derive(Clone, Encode, Decode, Deserialize, Serialize)]
     pub struct ImageWidget {
         pub id: String,
@@ -666,7 +3083,7 @@ pub mod v10{
     }
 
     impl Widget for ImageWidget {
-        fn draw(&mut self, context: &mut OffscreenCanvas) {
+        fn draw(&mut self, context: &mut OffscreenCanvas, _tick: u128) {
             if let Some(color) = self.color.as_ref() {
                 let rect = offscreen_canvas::Rect::from(
                     self.position.left,
@@ -717,6 +3134,19 @@ pub mod v10{
             &self.id
         }
 
+        //旧版本组件总是显示
+        fn visible(&self) -> bool {
+            true
+        }
+
+        fn set_visible(&mut self, _visible: bool) {}
+
+        fn show_when(&self) -> Option<&Condition> {
+            None
+        }
+
+        fn set_show_when(&mut self, _condition: Option<Condition>) {}
+
         fn position_mut(&mut self) -> &mut Rect {
             &mut self.position
         }